@@ -0,0 +1,33 @@
+//! Detects pointer-authentication targets, see the `pac` feature, and reads the
+//! address width the high-bit tags are laid out around
+
+/// Address bits free of tags unless `TAGGED_DISPATCH_ADDRESS_BITS` says otherwise
+const DEFAULT_ADDRESS_BITS: u32 = 56;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=TAGGED_DISPATCH_ADDRESS_BITS");
+    println!("cargo:rustc-check-cfg=cfg(tagged_dispatch_pac)");
+    println!("cargo:rustc-check-cfg=cfg(tagged_dispatch_narrow_tags)");
+
+    // arm64e is the Apple ABI that signs pointers; the feature opts in anywhere else
+    let target = std::env::var("TARGET").unwrap_or_default();
+    if target.starts_with("arm64e-") || std::env::var_os("CARGO_FEATURE_PAC").is_some() {
+        println!("cargo:rustc-cfg=tagged_dispatch_pac");
+    }
+
+    // The tag takes the bits above the address and the spare bit, so 47 bits leave
+    // room for 16-bit tags and 57 bits for 6-bit ones (64 variants)
+    let address_bits = match std::env::var("TAGGED_DISPATCH_ADDRESS_BITS") {
+        Ok(bits) => match bits.trim().parse::<u32>() {
+            Ok(bits) if (47..=57).contains(&bits) => bits,
+            _ => panic!("TAGGED_DISPATCH_ADDRESS_BITS must be a number between 47 and 57, got `{}`", bits),
+        },
+        Err(_) => DEFAULT_ADDRESS_BITS,
+    };
+    println!("cargo:rustc-env=TAGGED_DISPATCH_ADDRESS_BITS={}", address_bits);
+    // Lets the tests skip enums sized for the default 7-bit tag
+    if address_bits > DEFAULT_ADDRESS_BITS {
+        println!("cargo:rustc-cfg=tagged_dispatch_narrow_tags");
+    }
+}
@@ -0,0 +1,266 @@
+//! Atomic storage for tagged pointers.
+//!
+//! [`AtomicTaggedPtr`] keeps the tag and the address in one `AtomicUsize`, so a
+//! single compare-and-swap updates both, e.g. the head of a lock-free queue of
+//! handles taken apart with their `into_raw`. Tagged pointers are never null, so an
+//! empty atomic holds `None`:
+//!
+//! ```rust,ignore
+//! let head = AtomicTaggedPtr::null();
+//!
+//! let node = Some(shape.into_raw());
+//! let mut current = head.load(Ordering::Acquire);
+//! while let Err(actual) = head.compare_exchange_weak(current, node, Ordering::AcqRel, Ordering::Acquire) {
+//!     current = actual;
+//! }
+//! ```
+
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{TaggedPtr, DEFAULT_TAG_BITS};
+
+/// An optional [`TaggedPtr`] that can be shared between threads, read and written
+/// atomically with its tag. Like the pointer, it takes the tag width as `TAG_BITS`.
+///
+/// The empty state is stored as zero, the niche of `Option<TaggedPtr>`.
+#[repr(transparent)]
+pub struct AtomicTaggedPtr<T, const TAG_BITS: u32 = DEFAULT_TAG_BITS> {
+    bits: AtomicUsize,
+    _phantom: PhantomData<*mut T>,
+}
+
+// Safety: like `AtomicPtr`, this only stores the pointer; using the pointee is up to the caller
+unsafe impl<T, const TAG_BITS: u32> Send for AtomicTaggedPtr<T, TAG_BITS> {}
+unsafe impl<T, const TAG_BITS: u32> Sync for AtomicTaggedPtr<T, TAG_BITS> {}
+
+impl<T, const TAG_BITS: u32> AtomicTaggedPtr<T, TAG_BITS> {
+    /// Create an atomic holding `ptr`
+    #[inline]
+    pub const fn new(ptr: TaggedPtr<T, TAG_BITS>) -> Self {
+        Self {
+            bits: AtomicUsize::new(ptr.to_bits()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Create an empty atomic
+    #[inline]
+    pub const fn null() -> Self {
+        Self {
+            bits: AtomicUsize::new(0),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Load the pointer and its tag
+    #[inline]
+    pub fn load(&self, order: Ordering) -> Option<TaggedPtr<T, TAG_BITS>> {
+        from_bits(self.bits.load(order))
+    }
+
+    /// Store a pointer and its tag, or empty the atomic with `None`
+    #[inline]
+    pub fn store(&self, ptr: Option<TaggedPtr<T, TAG_BITS>>, order: Ordering) {
+        self.bits.store(to_bits(ptr), order);
+    }
+
+    /// Store a pointer and its tag, returning the previous ones
+    #[inline]
+    pub fn swap(&self, ptr: Option<TaggedPtr<T, TAG_BITS>>, order: Ordering) -> Option<TaggedPtr<T, TAG_BITS>> {
+        from_bits(self.bits.swap(to_bits(ptr), order))
+    }
+
+    /// Store `new` if the current pointer and tag are both equal to `current`.
+    ///
+    /// Returns the previous value, as `Ok` if it was replaced.
+    #[inline]
+    pub fn compare_exchange(
+        &self,
+        current: Option<TaggedPtr<T, TAG_BITS>>,
+        new: Option<TaggedPtr<T, TAG_BITS>>,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Option<TaggedPtr<T, TAG_BITS>>, Option<TaggedPtr<T, TAG_BITS>>> {
+        self.bits
+            .compare_exchange(to_bits(current), to_bits(new), success, failure)
+            .map(from_bits)
+            .map_err(from_bits)
+    }
+
+    /// Like [`compare_exchange`](Self::compare_exchange), but may fail spuriously,
+    /// which is cheaper on some platforms when called in a loop
+    #[inline]
+    pub fn compare_exchange_weak(
+        &self,
+        current: Option<TaggedPtr<T, TAG_BITS>>,
+        new: Option<TaggedPtr<T, TAG_BITS>>,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Option<TaggedPtr<T, TAG_BITS>>, Option<TaggedPtr<T, TAG_BITS>>> {
+        self.bits
+            .compare_exchange_weak(to_bits(current), to_bits(new), success, failure)
+            .map(from_bits)
+            .map_err(from_bits)
+    }
+
+    /// [`compare_exchange`](Self::compare_exchange) on `(pointer, tag)` pairs
+    #[inline]
+    pub fn compare_exchange_parts(
+        &self,
+        current: (*mut T, u16),
+        new: (*mut T, u16),
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Option<TaggedPtr<T, TAG_BITS>>, Option<TaggedPtr<T, TAG_BITS>>> {
+        self.compare_exchange(
+            Some(TaggedPtr::from_parts(current.0, current.1)),
+            Some(TaggedPtr::from_parts(new.0, new.1)),
+            success,
+            failure,
+        )
+    }
+
+    /// Replace the tag and keep the pointer, returning the previous value; an empty
+    /// atomic stays empty
+    #[inline]
+    pub fn swap_tag(&self, tag: u16, order: Ordering) -> Option<TaggedPtr<T, TAG_BITS>> {
+        let fetch_order = match order {
+            Ordering::Release => Ordering::Relaxed,
+            Ordering::AcqRel => Ordering::Acquire,
+            order => order,
+        };
+        debug_assert!((tag as usize) < TaggedPtr::<T, TAG_BITS>::MAX_VARIANTS, "Tag must be less than {} ({} bits)", TaggedPtr::<T, TAG_BITS>::MAX_VARIANTS, TAG_BITS);
+        let tag_bits = (tag as usize) << TaggedPtr::<T, TAG_BITS>::TAG_SHIFT;
+        let previous = self.bits.fetch_update(order, fetch_order, |bits| {
+            (bits != 0).then_some(bits & !TaggedPtr::<T, TAG_BITS>::TAG_MASK | tag_bits)
+        });
+        // The closure only declines to update an empty atomic
+        from_bits(previous.unwrap_or_else(|bits| bits))
+    }
+
+    /// Get the pointer and tag through exclusive access, without synchronizing
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut Option<TaggedPtr<T, TAG_BITS>> {
+        // Safety: `TaggedPtr` is a transparent `NonZeroUsize`, so `None` is zero and
+        // `Option<TaggedPtr>` has the layout of the atomic's `usize`
+        unsafe { &mut *(self.bits.get_mut() as *mut usize as *mut Option<TaggedPtr<T, TAG_BITS>>) }
+    }
+
+    /// Consume the atomic, returning the pointer and tag
+    #[inline]
+    pub fn into_inner(self) -> Option<TaggedPtr<T, TAG_BITS>> {
+        from_bits(self.bits.into_inner())
+    }
+}
+
+/// The stored bits of an optional pointer, zero for `None`
+#[inline(always)]
+fn to_bits<T, const TAG_BITS: u32>(ptr: Option<TaggedPtr<T, TAG_BITS>>) -> usize {
+    ptr.map_or(0, TaggedPtr::to_bits)
+}
+
+/// The optional pointer stored as `bits`
+#[inline(always)]
+fn from_bits<T, const TAG_BITS: u32>(bits: usize) -> Option<TaggedPtr<T, TAG_BITS>> {
+    (bits != 0).then(|| TaggedPtr::from_bits(bits))
+}
+
+impl<T, const TAG_BITS: u32> Default for AtomicTaggedPtr<T, TAG_BITS> {
+    fn default() -> Self {
+        Self::null()
+    }
+}
+
+impl<T, const TAG_BITS: u32> From<TaggedPtr<T, TAG_BITS>> for AtomicTaggedPtr<T, TAG_BITS> {
+    fn from(ptr: TaggedPtr<T, TAG_BITS>) -> Self {
+        Self::new(ptr)
+    }
+}
+
+impl<T, const TAG_BITS: u32> core::fmt::Debug for AtomicTaggedPtr<T, TAG_BITS> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_store_swap() {
+        let mut a = 1u32;
+        let mut b = 2u32;
+        let atomic = AtomicTaggedPtr::new(TaggedPtr::new(&mut a, 3));
+
+        assert_eq!(atomic.load(Ordering::Relaxed).unwrap().tag(), 3);
+        let previous = atomic.swap(Some(TaggedPtr::new(&mut b, 9)), Ordering::AcqRel).unwrap();
+        assert_eq!(previous.untagged_ptr(), &mut a as *mut u32);
+
+        let current = atomic.load(Ordering::Acquire).unwrap();
+        assert_eq!((current.untagged_ptr(), current.tag()), (&mut b as *mut u32, 9));
+    }
+
+    #[test]
+    fn test_compare_exchange_checks_tag() {
+        let mut value = 5u32;
+        let ptr = &mut value as *mut u32;
+        let atomic = AtomicTaggedPtr::new(TaggedPtr::new(ptr, 1));
+
+        // Same address, different tag
+        assert!(atomic.compare_exchange_parts((ptr, 2), (ptr, 7), Ordering::AcqRel, Ordering::Acquire).is_err());
+        assert!(atomic.compare_exchange_parts((ptr, 1), (ptr, 7), Ordering::AcqRel, Ordering::Acquire).is_ok());
+        assert_eq!(atomic.swap_tag(4, Ordering::AcqRel).unwrap().tag(), 7);
+        assert_eq!(atomic.into_inner(), Some(TaggedPtr::new(ptr, 4)));
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut value = 5u32;
+        let ptr = TaggedPtr::new(&mut value as *mut u32, 2);
+        let atomic = AtomicTaggedPtr::<u32>::default();
+
+        assert_eq!(atomic.load(Ordering::Acquire), None);
+        // Retagging an empty atomic leaves it empty
+        assert_eq!(atomic.swap_tag(5, Ordering::AcqRel), None);
+        assert_eq!(atomic.compare_exchange(None, Some(ptr), Ordering::AcqRel, Ordering::Acquire), Ok(None));
+        assert_eq!(atomic.compare_exchange(None, Some(ptr), Ordering::AcqRel, Ordering::Acquire), Err(Some(ptr)));
+        assert_eq!(atomic.swap(None, Ordering::AcqRel), Some(ptr));
+
+        let mut atomic = AtomicTaggedPtr::<u32>::null();
+        assert_eq!(*atomic.get_mut(), None);
+        *atomic.get_mut() = Some(ptr);
+        assert_eq!(atomic.into_inner(), Some(ptr));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_concurrent_tag_swaps() {
+        const TAGS: u16 = if TaggedPtr::<u64>::MAX_VARIANTS < 128 { TaggedPtr::<u64>::MAX_VARIANTS as u16 } else { 128 };
+        let mut value = 0u64;
+        let ptr = &mut value as *mut u64;
+        let atomic = AtomicTaggedPtr::new(TaggedPtr::new(ptr, 0));
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        let mut current = atomic.load(Ordering::Acquire);
+                        loop {
+                            let ptr = current.unwrap();
+                            let next = Some(TaggedPtr::new(ptr.untagged_ptr(), (ptr.tag() + 1) % TAGS));
+                            match atomic.compare_exchange_weak(current, next, Ordering::AcqRel, Ordering::Acquire) {
+                                Ok(_) => break,
+                                Err(actual) => current = actual,
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        let last = atomic.load(Ordering::Relaxed).unwrap();
+        assert_eq!((last.untagged_ptr(), last.tag()), (ptr, 4000 % TAGS));
+    }
+}
@@ -0,0 +1,200 @@
+//! Tracking which arena builder allocated each arena value, so a handle that outlived
+//! its arena panics at dispatch instead of reading whatever now lives at its address.
+//!
+//! Each builder carries an [`ArenaBrand`], an ID plus a count of its resets, and records
+//! every value it allocates in a global side table keyed by address. Dispatching an
+//! arena handle looks its address up and panics if the builder that allocated it has
+//! been reset since, or if a value of another variant, possibly from another builder,
+//! has taken its place.
+//!
+//! Zero-sized values share their addresses with other allocations and aren't tracked.
+//! The checks run with `debug_assertions` or the `checked` feature, and need `std` for
+//! the table's lock; otherwise every method here compiles to nothing. They are best
+//! effort: a stale handle whose slot was refilled with the same variant goes unnoticed.
+
+#[cfg(all(feature = "std", any(debug_assertions, feature = "checked")))]
+mod table {
+    use std::collections::BTreeMap;
+    use std::mem::size_of;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+
+    /// Who allocated the value at an address
+    struct Allocation {
+        enum_name: &'static str,
+        tag: u16,
+        brand: u64,
+        resets: u64,
+    }
+
+    struct Table {
+        allocations: BTreeMap<usize, Allocation>,
+        /// Reset count of every live builder, by brand
+        builders: BTreeMap<u64, u64>,
+    }
+
+    static TABLE: Mutex<Table> = Mutex::new(Table { allocations: BTreeMap::new(), builders: BTreeMap::new() });
+    static NEXT_BRAND: AtomicU64 = AtomicU64::new(1);
+
+    fn table() -> MutexGuard<'static, Table> {
+        // A panicking check leaves the table consistent
+        TABLE.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The identity of one arena builder, recording the values it allocates
+    #[derive(Debug)]
+    pub struct ArenaBrand {
+        id: u64,
+        resets: u64,
+    }
+
+    impl ArenaBrand {
+        /// Brand a new builder
+        pub fn new() -> Self {
+            let id = NEXT_BRAND.fetch_add(1, Ordering::Relaxed);
+            table().builders.insert(id, 0);
+            Self { id, resets: 0 }
+        }
+
+        /// Record a value allocated for a `enum_name` handle with `tag`
+        pub fn record<T>(&self, enum_name: &'static str, ptr: *const T, tag: u16) {
+            if size_of::<T>() == 0 {
+                return;
+            }
+            let allocation = Allocation { enum_name, tag, brand: self.id, resets: self.resets };
+            table().allocations.insert(ptr as usize, allocation);
+        }
+
+        /// Invalidate every value recorded so far
+        pub fn reset(&mut self) {
+            self.resets += 1;
+            table().builders.insert(self.id, self.resets);
+        }
+    }
+
+    impl Drop for ArenaBrand {
+        fn drop(&mut self) {
+            let mut table = table();
+            table.builders.remove(&self.id);
+            table.allocations.retain(|_, allocation| allocation.brand != self.id);
+        }
+    }
+
+    /// Panic if the arena value a `enum_name` handle with `tag` points at is gone
+    #[track_caller]
+    pub fn check_arena_handle<T>(enum_name: &'static str, ptr: *const T, tag: u16) {
+        if size_of::<T>() == 0 {
+            return;
+        }
+        let table = table();
+        let Some(allocation) = table.allocations.get(&(ptr as usize)) else {
+            return;
+        };
+        if allocation.enum_name != enum_name {
+            return;
+        }
+        let (brand, resets, recorded_tag) = (allocation.brand, allocation.resets, allocation.tag);
+        let current = table.builders.get(&brand).copied();
+        drop(table);
+
+        if current != Some(resets) {
+            panic!(
+                "`{}` handle used after arena builder #{} that allocated it was reset; the handle outlived its arena",
+                enum_name, brand,
+            );
+        }
+        if recorded_tag != tag {
+            panic!(
+                "`{}` handle with tag {} points at a tag {} value allocated by arena builder #{}; the handle outlived the arena it was allocated in",
+                enum_name, tag, recorded_tag, brand,
+            );
+        }
+    }
+
+    /// Forget a stale allocation at the address a view is borrowing, which is no
+    /// longer arena memory
+    pub fn release_view<T>(ptr: *const T) {
+        let mut table = table();
+        let stale = table.allocations.get(&(ptr as usize)).is_some_and(|allocation| {
+            table.builders.get(&allocation.brand) != Some(&allocation.resets)
+        });
+        if stale {
+            table.allocations.remove(&(ptr as usize));
+        }
+    }
+}
+
+#[cfg(not(all(feature = "std", any(debug_assertions, feature = "checked"))))]
+mod table {
+    /// The identity of one arena builder, without checks in this build
+    #[derive(Debug)]
+    pub struct ArenaBrand;
+
+    impl ArenaBrand {
+        #[inline(always)]
+        pub fn new() -> Self {
+            Self
+        }
+
+        #[inline(always)]
+        pub fn record<T>(&self, _enum_name: &'static str, _ptr: *const T, _tag: u16) {}
+
+        #[inline(always)]
+        pub fn reset(&mut self) {}
+    }
+
+    #[inline(always)]
+    pub fn check_arena_handle<T>(_enum_name: &'static str, _ptr: *const T, _tag: u16) {}
+
+    #[inline(always)]
+    pub fn release_view<T>(_ptr: *const T) {}
+}
+
+pub use table::{check_arena_handle, release_view, ArenaBrand};
+
+impl Default for ArenaBrand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "std", any(debug_assertions, feature = "checked")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reset_invalidates() {
+        let value = 0u64;
+        let ptr = &value as *const u64;
+        let mut brand = ArenaBrand::new();
+        brand.record("Test", ptr, 1);
+        check_arena_handle("Test", ptr, 1);
+        // Other enums' handles may point at the same address
+        check_arena_handle("Other", ptr, 2);
+
+        brand.reset();
+        let stale = std::panic::catch_unwind(|| check_arena_handle("Test", ptr, 1));
+        assert!(stale.is_err());
+
+        // A view of memory no longer in the arena clears the stale record
+        release_view(ptr);
+        check_arena_handle("Test", ptr, 1);
+    }
+
+    #[test]
+    fn test_refilled_slot() {
+        let value = 0u64;
+        let ptr = &value as *const u64;
+        let first = ArenaBrand::new();
+        let second = ArenaBrand::new();
+        first.record("Test", ptr, 1);
+        second.record("Test", ptr, 2);
+        assert!(std::panic::catch_unwind(|| check_arena_handle("Test", ptr, 1)).is_err());
+        check_arena_handle("Test", ptr, 2);
+
+        // Dropping a builder forgets its values
+        drop(second);
+        check_arena_handle("Test", ptr, 1);
+        drop(first);
+    }
+}
@@ -0,0 +1,68 @@
+//! Byte encoding of handles whose variants are plain old data.
+//!
+//! With the `bytemuck` feature, an enum declared with the `bytes` flag encodes a
+//! handle as its tag byte followed by the bytes of its value, e.g. to pass handles
+//! through shared memory without a serialization framework:
+//!
+//! ```rust,ignore
+//! #[tagged_dispatch(Sample, bytes)]
+//! enum Reading {
+//!     Temperature,
+//!     Pressure,
+//! }
+//!
+//! let bytes = reading.to_bytes();
+//! let decoded = Reading::try_from(bytes.as_slice())?;
+//! ```
+//!
+//! Arena enums decode through their builder with `builder.from_bytes(&bytes)`. Tags
+//! are variant indices, so both sides must agree on the variant order.
+
+use bytemuck::Pod;
+
+/// Why a byte slice could not be decoded into a handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The slice has no tag byte
+    Empty,
+    /// The tag byte does not name a variant
+    InvalidTag(u8),
+    /// The value bytes don't match the size of the tagged variant
+    Length {
+        /// The size of the variant
+        expected: usize,
+        /// The number of value bytes given
+        found: usize,
+    },
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "missing tag byte"),
+            DecodeError::InvalidTag(tag) => write!(f, "invalid variant tag {}", tag),
+            DecodeError::Length { expected, found } => {
+                write!(f, "expected {} value bytes, found {}", expected, found)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Split an encoded handle into its tag and value bytes.
+#[doc(hidden)]
+pub fn split_tag(bytes: &[u8]) -> Result<(u8, &[u8]), DecodeError> {
+    bytes.split_first().map(|(tag, body)| (*tag, body)).ok_or(DecodeError::Empty)
+}
+
+/// Read a `T` from exactly `size_of::<T>()` bytes, which need not be aligned.
+#[doc(hidden)]
+pub fn decode_value<T: Pod>(body: &[u8]) -> Result<T, DecodeError> {
+    let expected = core::mem::size_of::<T>();
+    if body.len() != expected {
+        return Err(DecodeError::Length { expected, found: body.len() });
+    }
+    Ok(bytemuck::pod_read_unaligned(body))
+}
@@ -0,0 +1,30 @@
+//! Drop-in replacements for other enum dispatch crates.
+//!
+//! [`enum_dispatch`] accepts the attribute syntax of the `enum_dispatch` crate, so a
+//! codebase can switch between the two, e.g. to benchmark them, by changing its import:
+//!
+//! ```rust,ignore
+//! // use enum_dispatch::enum_dispatch;
+//! use tagged_dispatch::compat::enum_dispatch;
+//!
+//! #[enum_dispatch]
+//! trait KnobControl {
+//!     fn set_position(&mut self, value: f64);
+//!     fn get_value(&self) -> f64;
+//! }
+//!
+//! #[enum_dispatch(KnobControl)]
+//! enum Knob {
+//!     LinearKnob,
+//!     LogarithmicKnob,
+//! }
+//!
+//! let mut knob = Knob::LinearKnob(LinearKnob::default());
+//! knob.set_position(0.5);
+//! let linear: LinearKnob = knob.try_into().unwrap();
+//! ```
+//!
+//! Code that matches on the enum's variants needs rewriting, since the enum becomes a
+//! handle; `match_knob!` and `tag_type()` cover most uses.
+
+pub use tagged_dispatch_macros::enum_dispatch;
@@ -0,0 +1,100 @@
+//! Access to macro expansions written by `TAGGED_DISPATCH_DUMP`.
+//!
+//! When the `TAGGED_DISPATCH_DUMP` environment variable is set while compiling, every
+//! `#[tagged_dispatch]` item writes its formatted expansion to
+//! `$TAGGED_DISPATCH_DUMP/<crate name>/<item name>.rs`. Items sharing a name within one
+//! crate are written as `<item name>-2.rs`, `<item name>-3.rs`, ... in expansion order.
+//!
+//! The helpers here read those files back, which makes the expansions easy to feed into
+//! snapshot testing tools such as `insta`:
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn shape_expansion() {
+//!     insta::assert_snapshot!(tagged_dispatch::expansion!("Shape").unwrap());
+//! }
+//! ```
+//!
+//! Cargo does not track the environment variable, so touch the source file (or
+//! `cargo clean -p <crate>`) after setting it to force the items to be re-expanded.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the environment variable that enables dumping expansions.
+pub const DUMP_ENV_VAR: &str = "TAGGED_DISPATCH_DUMP";
+
+/// The dump directory configured through `TAGGED_DISPATCH_DUMP`, if any.
+pub fn dump_dir() -> Option<PathBuf> {
+    std::env::var_os(DUMP_ENV_VAR).map(PathBuf::from)
+}
+
+/// Path of the dumped expansion for `item` in the crate named `crate_name`.
+///
+/// `crate_name` is the name cargo passes as `CARGO_CRATE_NAME` (hyphens replaced by
+/// underscores); the [`expansion!`](crate::expansion) macro fills it in automatically.
+pub fn expansion_path(crate_name: &str, item: &str) -> Option<PathBuf> {
+    dump_dir().map(|dir| expansion_path_in(&dir, crate_name, item))
+}
+
+/// Path of the dumped expansion for `item` in the crate named `crate_name`, under the
+/// dump directory `dir`.
+pub fn expansion_path_in(dir: &Path, crate_name: &str, item: &str) -> PathBuf {
+    dir.join(crate_name).join(format!("{}.rs", item))
+}
+
+/// Read the dumped expansion for `item` in the crate named `crate_name`.
+///
+/// Returns an error of kind [`io::ErrorKind::NotFound`] if `TAGGED_DISPATCH_DUMP` is unset
+/// or the item has not been dumped.
+pub fn expansion(crate_name: &str, item: &str) -> io::Result<String> {
+    let dir = dump_dir().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "TAGGED_DISPATCH_DUMP is not set",
+        )
+    })?;
+    expansion_in(&dir, crate_name, item)
+}
+
+/// Read the dumped expansion for `item` in the crate named `crate_name` from the dump
+/// directory `dir`, e.g. one a build script passed on rather than the environment.
+///
+/// Returns an error of kind [`io::ErrorKind::NotFound`] if the item has not been dumped.
+pub fn expansion_in(dir: &Path, crate_name: &str, item: &str) -> io::Result<String> {
+    std::fs::read_to_string(expansion_path_in(dir, crate_name, item))
+}
+
+/// Read the dumped expansion of an item defined in the current crate.
+///
+/// Shorthand for [`dump::expansion(env!("CARGO_CRATE_NAME"), item)`](crate::dump::expansion).
+#[macro_export]
+macro_rules! expansion {
+    ($item:expr) => {
+        $crate::dump::expansion(env!("CARGO_CRATE_NAME"), $item)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_expansion() {
+        let dir = std::env::temp_dir().join(format!("tagged_dispatch_dump_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("my_crate")).unwrap();
+        std::fs::write(dir.join("my_crate").join("Shape.rs"), "struct Shape;").unwrap();
+
+        assert_eq!(expansion_in(&dir, "my_crate", "Shape").unwrap(), "struct Shape;");
+        assert_eq!(
+            expansion_in(&dir, "my_crate", "Missing").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            expansion_in(&dir, "other_crate", "Shape").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -4,15 +4,33 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
+// Tags live in the high bits of a 64-bit address
+#[cfg(not(target_pointer_width = "64"))]
+compile_error!("tagged_dispatch requires a target with 64-bit pointers");
+
 use core::marker::PhantomData;
+use core::num::NonZeroUsize;
+use core::ptr::NonNull;
 
 #[cfg(not(feature = "std"))]
 use alloc::boxed::Box;
 #[cfg(feature = "std")]
 use std::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::alloc::alloc as alloc_raw;
+#[cfg(feature = "std")]
+use std::alloc::alloc as alloc_raw;
+#[cfg(not(feature = "std"))]
+use alloc::rc::Rc;
+#[cfg(feature = "std")]
+use std::rc::Rc;
+#[cfg(all(not(feature = "std"), target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+#[cfg(all(feature = "std", target_has_atomic = "ptr"))]
+use std::sync::Arc;
 
-// Re-export the macro
-pub use tagged_dispatch_macros::tagged_dispatch;
+// Re-export the macros
+pub use tagged_dispatch_macros::{tagged_dispatch, tagged_dispatch_external, TaggedDispatch};
 
 // Re-export allocator crates when their features are enabled
 #[cfg(feature = "allocator-bumpalo")]
@@ -21,10 +39,219 @@ pub use bumpalo;
 #[cfg(feature = "allocator-typed-arena")]
 pub use typed_arena;
 
+// Re-export tracing so generated instrumentation doesn't require a direct dependency
+#[cfg(feature = "tracing")]
+pub use tracing;
+
+#[cfg(feature = "slotmap")]
+pub use slotmap;
+
+#[cfg(feature = "slotmap")]
+pub mod slot_map;
+
+#[cfg(feature = "bytemuck")]
+pub use bytemuck;
+
+#[cfg(feature = "bytemuck")]
+pub mod bytes;
+
+#[cfg(feature = "std")]
+pub mod dump;
+
+#[cfg(feature = "std")]
+pub mod pool;
+
+pub mod compat;
+
+pub mod metered;
+pub use metered::{Meter, Metered};
+
+#[cfg(target_has_atomic = "ptr")]
+pub mod atomic;
+#[cfg(target_has_atomic = "ptr")]
+pub use atomic::AtomicTaggedPtr;
+
+#[cfg(target_has_atomic = "ptr")]
+pub mod plugin;
+
+#[cfg(feature = "test-util")]
+pub mod test_util;
+
+#[cfg(feature = "registry")]
+pub mod registry;
+#[cfg(feature = "registry")]
+pub use registry::registry;
+
+/// Whether this build is in pointer-authentication mode, from the `pac` feature or an
+/// arm64e target.
+///
+/// In this mode, tagged pointers are always stripped of their tag before use, even
+/// on Apple Silicon where the hardware would otherwise ignore it, so no tagged
+/// address ever reaches a load or an authentication instruction.
+pub const POINTER_AUTH: bool = cfg!(tagged_dispatch_pac);
+
+/// How many low address bits user-space pointers may use, 56 unless this crate is
+/// built with `TAGGED_DISPATCH_ADDRESS_BITS` set to a value from 47 to 57.
+///
+/// The high-bit pointer types keep bit `ADDRESS_BITS` as their spare bit and put
+/// the tag above it, so this decides [`DEFAULT_TAG_BITS`]. Raise it for systems
+/// that hand out addresses above bit 55, or lower it to make room for wider tags;
+/// [`verify_platform`] checks the choice against the running system.
+pub const ADDRESS_BITS: u32 = parse_address_bits(env!("TAGGED_DISPATCH_ADDRESS_BITS"));
+
+/// The tag width of [`TaggedPtr`] when none is given, and of the high part of
+/// [`WideTaggedPtr`]: every bit above [`ADDRESS_BITS`] and the spare bit, 7 by default
+pub const DEFAULT_TAG_BITS: u32 = 63 - ADDRESS_BITS;
+
+/// Parse the decimal the build script passes on, which it has already range-checked
+const fn parse_address_bits(bits: &str) -> u32 {
+    let bytes = bits.as_bytes();
+    let mut value = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    value
+}
+
+pub mod platform;
+pub use platform::verify_platform;
+
+pub mod traverse;
+
+mod brand;
+
+/// Assert that `bits`, the part of an address a pointer type stores its tag in, is 0.
+/// Debug builds always check, release builds only with the `checked` feature.
+macro_rules! check_addr {
+    ($bits:expr, $msg:literal) => {
+        if cfg!(any(debug_assertions, feature = "checked")) {
+            assert_eq!($bits, 0, $msg);
+        }
+    };
+}
+
+/// Wrap the packed bits of a tagged pointer. They are never zero for a real value,
+/// which leaves the zero bit pattern as a niche, so `Option<Handle>` is one word.
+#[inline(always)]
+const fn nonzero_bits(bits: usize) -> NonZeroUsize {
+    match NonZeroUsize::new(bits) {
+        Some(bits) => bits,
+        None => null_tagged_ptr(),
+    }
+}
+
+#[cold]
+#[track_caller]
+const fn null_tagged_ptr() -> ! {
+    panic!("a null pointer with tag 0 can't be stored in a tagged pointer")
+}
+
+/// Why a pointer and a tag can't be combined into a tagged pointer, from `try_new`,
+/// or why a `try_<variant>` constructor couldn't create a handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagError {
+    /// The tag doesn't fit in the pointer type's tag bits
+    TagOutOfRange {
+        /// The rejected tag
+        tag: u16,
+        /// The number of tags the pointer type can store
+        max_variants: usize,
+    },
+    /// The address already uses bits the tag is stored in: high bits set by the
+    /// platform (see [`verify_platform`]), or low bits of an under-aligned value
+    AddressInUse {
+        /// The rejected address
+        addr: usize,
+    },
+    /// A null pointer with tag 0, which would leave every bit zero; that pattern
+    /// is the niche `Option` uses for `None`
+    NullPointer,
+    /// The variant's allocator or arena couldn't provide memory for the value
+    AllocFailed {
+        /// The size of the value that didn't fit
+        size: usize,
+    },
+    /// The bits of a NaN other than `f64::NAN`, given as a [`NanBoxedPtr`] number;
+    /// some of them would read back as a boxed pointer
+    NonCanonicalNan {
+        /// The rejected bits
+        bits: u64,
+    },
+}
+
+impl core::fmt::Display for TagError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TagError::TagOutOfRange { tag, max_variants } => {
+                write!(f, "tag {} is out of range for {} variants", tag, max_variants)
+            }
+            TagError::AddressInUse { addr } => {
+                write!(f, "address {:#x} overlaps the tag bits", addr)
+            }
+            TagError::NullPointer => {
+                write!(f, "a null pointer with tag 0 can't be stored")
+            }
+            TagError::AllocFailed { size } => {
+                write!(f, "failed to allocate {} bytes", size)
+            }
+            TagError::NonCanonicalNan { bits } => {
+                write!(f, "NaN bits {:#018x} aren't the canonical NaN", bits)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TagError {}
+
+/// A string that doesn't name any variant, from a generated tag type's `FromStr` impl
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseVariantError {
+    enum_name: &'static str,
+}
+
+impl ParseVariantError {
+    #[doc(hidden)]
+    pub const fn new(enum_name: &'static str) -> Self {
+        Self { enum_name }
+    }
+
+    /// The enum whose variant names were expected
+    pub const fn enum_name(&self) -> &'static str {
+        self.enum_name
+    }
+}
+
+impl core::fmt::Display for ParseVariantError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "not a `{}` variant name", self.enum_name)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseVariantError {}
+
 /// The core tagged pointer type used internally.
 ///
-/// Uses the top 7 bits of a 64-bit pointer for type tagging,
-/// supporting up to 128 different types while maintaining an 8-byte size.
+/// Uses the top `TAG_BITS` bits of a 64-bit pointer for type tagging, by default
+/// [`DEFAULT_TAG_BITS`] = 7, supporting up to 128 different types while maintaining
+/// an 8-byte size. Narrower
+/// tags leave more of the address alone, for address spaces wider than 56 bits;
+/// wider ones, up to 16, need addresses below `1 << (63 - TAG_BITS)`, which holds
+/// for 4-level paging (47-bit user space) with `TAG_BITS <= 16`.
+///
+/// `TaggedPtr::new` always builds the default-width pointer; other widths are
+/// created with [`from_parts`](Self::from_parts):
+///
+/// ```rust
+/// # use tagged_dispatch::TaggedPtr;
+/// let mut value = 5u64;
+/// let ptr = TaggedPtr::<u64, 16>::from_parts(&mut value, 40_000);
+/// assert_eq!(ptr.tag(), 40_000);
+/// assert_eq!(unsafe { *ptr.as_ref() }, 5);
+/// ```
 ///
 /// # Platform Optimizations
 ///
@@ -33,48 +260,151 @@ pub use typed_arena;
 /// ignore the top byte of pointers during memory access, eliminating the need
 /// for manual masking operations. This provides a measurable performance
 /// improvement by reducing instructions on the critical path of every trait
-/// method dispatch.
+/// method dispatch. Builds in [`POINTER_AUTH`] mode mask the tag off instead, as
+/// do tags wider than 7 bits, which reach below the top byte.
 #[repr(transparent)]
-pub struct TaggedPtr<T> {
-    ptr: usize,
+pub struct TaggedPtr<T, const TAG_BITS: u32 = DEFAULT_TAG_BITS> {
+    ptr: NonZeroUsize,
     _phantom: PhantomData<T>,
 }
 
 impl<T> TaggedPtr<T> {
-    const TAG_BITS: usize = 7;
-    const TAG_SHIFT: usize = 64 - Self::TAG_BITS;
-    const TAG_MASK: usize = ((1 << Self::TAG_BITS) - 1) << Self::TAG_SHIFT;
-    #[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
-    const PTR_MASK: usize = !Self::TAG_MASK;
+    /// Create a new tagged pointer
+    ///
+    /// A pointer that already uses the tag bits is rejected in debug builds, or
+    /// always with the `checked` feature; see [`verify_platform`].
+    ///
+    /// # Panics
+    ///
+    /// If `ptr` is null and `tag` is 0, since the result can't be null; use
+    /// [`try_new`](Self::try_new) to check instead.
+    #[inline(always)]
+    pub fn new(ptr: *mut T, tag: u16) -> Self {
+        Self::from_parts(ptr, tag)
+    }
+
+    /// Create a new tagged pointer, or report why `ptr` and `tag` don't fit instead of
+    /// only checking in debug builds like [`new`](Self::new)
+    #[inline]
+    pub fn try_new(ptr: *mut T, tag: u16) -> Result<Self, TagError> {
+        Self::try_from_parts(ptr, tag)
+    }
+
+    /// Whether `ptr` leaves the tag bits and the spare bit free, so [`new`](Self::new)
+    /// can store it
+    #[inline(always)]
+    pub fn can_tag(ptr: *const T) -> bool {
+        Self::fits(ptr)
+    }
+}
+
+impl<T, const TAG_BITS: u32> TaggedPtr<T, TAG_BITS> {
+    const TAG_SHIFT: u32 = 64 - TAG_BITS;
+    const TAG_MASK: usize = ((1 << TAG_BITS) - 1) << Self::TAG_SHIFT;
+    /// The bit right below the tag, also unused by user-space addresses and free
+    /// for wrappers such as the generated `Cow` handles
+    const SPARE_BIT: usize = 1 << (Self::TAG_SHIFT - 1);
+    const PTR_MASK: usize = !(Self::TAG_MASK | Self::SPARE_BIT);
+    const VALID_WIDTH: () = assert!(TAG_BITS >= 1 && TAG_BITS <= 16, "TaggedPtr supports 1 to 16 tag bits");
     
-    /// Maximum number of variants supported (2^7 = 128)
-    pub const MAX_VARIANTS: usize = 1 << Self::TAG_BITS;
+    /// Maximum number of variants supported (2^TAG_BITS, 128 by default)
+    ///
+    /// The `#[tagged_dispatch]` macro rejects enums with more variants than this
+    /// at compile time:
+    ///
+    /// ```compile_fail
+    /// # use tagged_dispatch::tagged_dispatch;
+    /// # // Written for the default width; wider configured tags hold 129 variants
+    /// # const _: () = assert!(tagged_dispatch::DEFAULT_TAG_BITS <= 7);
+    /// # macro_rules! too_wide {
+    /// #     ($($variant:ident),*) => {
+    /// #         $( #[derive(Clone)] struct $variant; )*
+    /// #[tagged_dispatch]
+    /// enum TooWide {
+    ///     // ... 129 variants ...
+    /// #     $($variant,)*
+    /// }
+    /// #     };
+    /// # }
+    /// # too_wide!(
+    /// # V0, V1, V2, V3, V4, V5, V6, V7, V8, V9, V10, V11, V12, V13, V14, V15,
+    /// # V16, V17, V18, V19, V20, V21, V22, V23, V24, V25, V26, V27, V28, V29, V30, V31,
+    /// # V32, V33, V34, V35, V36, V37, V38, V39, V40, V41, V42, V43, V44, V45, V46, V47,
+    /// # V48, V49, V50, V51, V52, V53, V54, V55, V56, V57, V58, V59, V60, V61, V62, V63,
+    /// # V64, V65, V66, V67, V68, V69, V70, V71, V72, V73, V74, V75, V76, V77, V78, V79,
+    /// # V80, V81, V82, V83, V84, V85, V86, V87, V88, V89, V90, V91, V92, V93, V94, V95,
+    /// # V96, V97, V98, V99, V100, V101, V102, V103, V104, V105, V106, V107, V108, V109, V110, V111,
+    /// # V112, V113, V114, V115, V116, V117, V118, V119, V120, V121, V122, V123, V124, V125, V126, V127,
+    /// # V128
+    /// # );
+    /// ```
+    pub const MAX_VARIANTS: usize = 1 << TAG_BITS;
     
-    /// Create a new tagged pointer
+    /// Create a tagged pointer of this width
+    ///
+    /// Checks `ptr` the same way as [`TaggedPtr::new`].
     #[inline(always)]
-    pub fn new(ptr: *mut T, tag: u8) -> Self {
+    pub fn from_parts(ptr: *mut T, tag: u16) -> Self {
+        let () = Self::VALID_WIDTH;
         debug_assert!(
-            tag < Self::MAX_VARIANTS as u8,
-            "Tag must be less than 128 (7 bits)"
+            (tag as usize) < Self::MAX_VARIANTS,
+            "Tag must be less than {} ({} bits)",
+            Self::MAX_VARIANTS,
+            TAG_BITS
         );
         
         let addr = ptr as usize;
-        debug_assert_eq!(
-            addr & Self::TAG_MASK, 
-            0, 
+        check_addr!(
+            addr & (Self::TAG_MASK | Self::SPARE_BIT),
             "Pointer already has high bits set!"
         );
         
         Self {
-            ptr: addr | ((tag as usize) << Self::TAG_SHIFT),
+            ptr: nonzero_bits(addr | ((tag as usize) << Self::TAG_SHIFT)),
             _phantom: PhantomData,
         }
     }
     
+    /// Create a tagged pointer of this width, or report why `ptr` and `tag` don't fit
+    #[inline]
+    pub fn try_from_parts(ptr: *mut T, tag: u16) -> Result<Self, TagError> {
+        if tag as usize >= Self::MAX_VARIANTS {
+            return Err(TagError::TagOutOfRange { tag, max_variants: Self::MAX_VARIANTS });
+        }
+        if !Self::fits(ptr) {
+            return Err(TagError::AddressInUse { addr: ptr as usize });
+        }
+        if ptr.is_null() && tag == 0 {
+            return Err(TagError::NullPointer);
+        }
+        Ok(Self::from_parts(ptr, tag))
+    }
+
+    /// The address and tag packed into one word, as stored
+    #[inline(always)]
+    pub const fn to_bits(self) -> usize {
+        self.ptr.get()
+    }
+
+    /// Rebuild a tagged pointer from [`to_bits`](Self::to_bits)
+    #[inline(always)]
+    pub const fn from_bits(bits: usize) -> Self {
+        Self {
+            ptr: nonzero_bits(bits),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Whether `ptr` leaves this width's tag bits and spare bit free
+    #[inline(always)]
+    pub fn fits(ptr: *const T) -> bool {
+        ptr as usize & (Self::TAG_MASK | Self::SPARE_BIT) == 0
+    }
+
     /// Get the tag value
     #[inline(always)]
-    pub fn tag(&self) -> u8 {
-        ((self.ptr & Self::TAG_MASK) >> Self::TAG_SHIFT) as u8
+    pub fn tag(&self) -> u16 {
+        ((self.ptr.get() & Self::TAG_MASK) >> Self::TAG_SHIFT) as u16
     }
     
     /// Get the untagged pointer.
@@ -85,22 +415,27 @@ impl<T> TaggedPtr<T> {
     /// # Platform Optimization
     /// On macOS ARM64 (Apple Silicon), this method leverages the hardware's Top Byte Ignore (TBI)
     /// feature, which automatically masks the top byte during memory access. This eliminates the
-    /// need for software masking, providing a performance improvement.
-    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+    /// need for software masking, providing a performance improvement. Tags wider
+    /// than 7 bits extend below the ignored byte and are still masked.
+    #[cfg(all(target_os = "macos", target_arch = "aarch64", not(tagged_dispatch_pac)))]
     #[inline(always)]
     pub fn ptr(&self) -> *mut T {
-        self.ptr as *mut T
+        if TAG_BITS <= 7 {
+            self.ptr.get() as *mut T
+        } else {
+            (self.ptr.get() & Self::PTR_MASK) as *mut T
+        }
     }
 
     /// Get the untagged pointer (standard implementation).
     ///
     /// # Safety
     /// The returned pointer is only valid if the original pointer passed to `new` is still valid.
-    #[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
+    #[cfg(not(all(target_os = "macos", target_arch = "aarch64", not(tagged_dispatch_pac))))]
     #[inline(always)]
     pub fn ptr(&self) -> *mut T {
         // Standard implementation: manually mask off the tag bits
-        (self.ptr & Self::PTR_MASK) as *mut T
+        (self.ptr.get() & Self::PTR_MASK) as *mut T
     }
 
     /// Get the untagged pointer for deallocation.
@@ -113,8 +448,34 @@ impl<T> TaggedPtr<T> {
     #[doc(hidden)]
     #[inline(always)]
     pub fn untagged_ptr(&self) -> *mut T {
-        const PTR_MASK: usize = !(0x7F << 57);
-        (self.ptr & PTR_MASK) as *mut T
+        (self.ptr.get() & Self::PTR_MASK) as *mut T
+    }
+
+    /// Set the spare bit below the tag, which `ptr` and `untagged_ptr` mask off
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn with_spare_bit(self) -> Self {
+        Self {
+            ptr: self.ptr | Self::SPARE_BIT,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Clear the spare bit again
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn without_spare_bit(self) -> Self {
+        Self {
+            ptr: nonzero_bits(self.ptr.get() & !Self::SPARE_BIT),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Whether the spare bit is set
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn spare_bit(&self) -> bool {
+        self.ptr.get() & Self::SPARE_BIT != 0
     }
     
     /// Get a reference to the pointed value.
@@ -144,21 +505,41 @@ impl<T> TaggedPtr<T> {
     pub fn is_null(&self) -> bool {
         self.ptr() as usize == 0
     }
+
+    /// The packed bits in place, which `#[inline_value]` variants are borrowed from
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn bits_ref(&self) -> &usize {
+        // SAFETY: `NonZeroUsize` has the layout of `usize`
+        unsafe { &*(&self.ptr as *const NonZeroUsize as *const usize) }
+    }
+
+    /// The packed bits in place, which `&mut self` dispatch updates inline values through
+    ///
+    /// # Safety
+    ///
+    /// The bits must stay a valid encoding of the handle's variant.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub unsafe fn bits_mut(&mut self) -> &mut usize {
+        // SAFETY: `NonZeroUsize` has the layout of `usize`
+        &mut *(&mut self.ptr as *mut NonZeroUsize as *mut usize)
+    }
 }
 
 // Safety: TaggedPtr is Send/Sync if T is Send/Sync
-unsafe impl<T: Send> Send for TaggedPtr<T> {}
-unsafe impl<T: Sync> Sync for TaggedPtr<T> {}
+unsafe impl<T: Send, const TAG_BITS: u32> Send for TaggedPtr<T, TAG_BITS> {}
+unsafe impl<T: Sync, const TAG_BITS: u32> Sync for TaggedPtr<T, TAG_BITS> {}
 
-impl<T> Clone for TaggedPtr<T> {
+impl<T, const TAG_BITS: u32> Clone for TaggedPtr<T, TAG_BITS> {
     fn clone(&self) -> Self {
         *self
     }
 }
 
-impl<T> Copy for TaggedPtr<T> {}
+impl<T, const TAG_BITS: u32> Copy for TaggedPtr<T, TAG_BITS> {}
 
-impl<T> core::fmt::Debug for TaggedPtr<T> {
+impl<T, const TAG_BITS: u32> core::fmt::Debug for TaggedPtr<T, TAG_BITS> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("TaggedPtr")
             .field("tag", &self.tag())
@@ -167,22 +548,22 @@ impl<T> core::fmt::Debug for TaggedPtr<T> {
     }
 }
 
-impl<T> core::cmp::PartialEq for TaggedPtr<T> {
+impl<T, const TAG_BITS: u32> core::cmp::PartialEq for TaggedPtr<T, TAG_BITS> {
     fn eq(&self, other: &Self) -> bool {
         // Compare the raw pointer values (tag + address)
         self.ptr == other.ptr
     }
 }
 
-impl<T> core::cmp::Eq for TaggedPtr<T> {}
+impl<T, const TAG_BITS: u32> core::cmp::Eq for TaggedPtr<T, TAG_BITS> {}
 
-impl<T> core::cmp::PartialOrd for TaggedPtr<T> {
+impl<T, const TAG_BITS: u32> core::cmp::PartialOrd for TaggedPtr<T, TAG_BITS> {
     fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl<T> core::cmp::Ord for TaggedPtr<T> {
+impl<T, const TAG_BITS: u32> core::cmp::Ord for TaggedPtr<T, TAG_BITS> {
     fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         // Compare the raw pointer values (tag is in high bits, so this
         // naturally orders by tag first, then by address)
@@ -190,121 +571,1412 @@ impl<T> core::cmp::Ord for TaggedPtr<T> {
     }
 }
 
-/// Allocator trait for arena-allocated tagged pointers.
-///
-/// This trait should be implemented by arena allocators to enable
-/// the arena version of tagged dispatch.
-///
-/// # Example
-///
-/// ```rust
-/// use tagged_dispatch::TaggedAllocator;
-/// use bumpalo::Bump;
+/// The pointer stored by single-variant tagged dispatch types.
 ///
-/// // Bumpalo automatically implements TaggedAllocator when the feature is enabled
-/// let arena = Bump::new();
-/// let ptr = arena.alloc(42);
-/// ```
-pub trait TaggedAllocator {
-    /// Allocate space for a value and return a pointer to it.
-    ///
-    /// The allocated memory should have the same lifetime as the allocator.
-    fn alloc<T>(&self, value: T) -> *mut T;
+/// It has the same interface as [`TaggedPtr`], but its tag is always 0 and never
+/// stored, so reading the value needs no masking and dispatch compiles down to a
+/// direct call. The pointer is never null.
+#[repr(transparent)]
+pub struct UntaggedPtr<T> {
+    ptr: NonNull<T>,
 }
 
-// Implement TaggedAllocator for common arena allocators when their features are enabled
+impl<T> UntaggedPtr<T> {
+    /// Create a new pointer; the only valid tag is 0
+    ///
+    /// # Panics
+    ///
+    /// If `ptr` is null; use [`try_new`](Self::try_new) to check instead.
+    #[inline(always)]
+    pub fn new(ptr: *mut T, tag: u16) -> Self {
+        debug_assert_eq!(tag, 0, "single-variant pointers have no tag");
+        match NonNull::new(ptr) {
+            Some(ptr) => Self { ptr },
+            None => null_tagged_ptr(),
+        }
+    }
 
-#[cfg(feature = "bumpalo")]
-impl TaggedAllocator for bumpalo::Bump {
+    /// Create a new pointer, failing for any tag but 0 or a null pointer
     #[inline]
-    fn alloc<T>(&self, value: T) -> *mut T {
-        bumpalo::Bump::alloc(self, value) as *mut T
+    pub fn try_new(ptr: *mut T, tag: u16) -> Result<Self, TagError> {
+        if tag != 0 {
+            return Err(TagError::TagOutOfRange { tag, max_variants: 1 });
+        }
+        let ptr = NonNull::new(ptr).ok_or(TagError::NullPointer)?;
+        Ok(Self { ptr })
     }
-}
 
-// Note: typed_arena doesn't implement TaggedAllocator directly
-// because it can only allocate values of a single type T.
-// Instead, the arena builder pattern generates separate arenas
-// for each variant type when typed_arena is enabled.
+    /// Get the tag value, which is always 0
+    #[inline(always)]
+    pub fn tag(&self) -> u16 {
+        0
+    }
 
-/// Statistics for arena memory usage.
-#[derive(Debug, Clone, Copy, Default)]
-pub struct ArenaStats {
-    /// Total bytes currently allocated
-    pub allocated_bytes: usize,
-    /// Total capacity of all chunks
-    pub chunk_capacity: usize,
-}
+    /// Get the pointer
+    #[inline(always)]
+    pub fn ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
 
-/// Trait for arena builders generated by the macro.
-///
-/// Provides memory management capabilities for arena-allocated
-/// tagged dispatch types.
-pub trait ArenaBuilder<'a>: Sized {
-    /// Create a new builder with default settings.
-    ///
-    /// When both allocators are available, this prefers bumpalo
-    /// for its superior flexibility.
-    fn new() -> Self;
+    /// Get the pointer for deallocation, the same as [`ptr`](Self::ptr)
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn untagged_ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
 
-    /// Reset all allocations, invalidating existing references.
+    /// Get a reference to the pointed value.
     ///
     /// # Safety
+    /// Same as [`TaggedPtr::as_ref`].
+    #[inline(always)]
+    pub unsafe fn as_ref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Get a mutable reference to the pointed value.
     ///
-    /// This invalidates all references previously allocated from this builder.
-    /// Using any such references after reset is undefined behavior.
-    fn reset(&mut self);
+    /// # Safety
+    /// Same as [`TaggedPtr::as_mut`].
+    #[inline(always)]
+    pub unsafe fn as_mut(&mut self) -> &mut T {
+        unsafe { self.ptr.as_mut() }
+    }
 
-    /// Clear allocations and attempt to reclaim memory.
+    /// Check if the pointer is null, which it never is
+    #[inline(always)]
+    pub fn is_null(&self) -> bool {
+        false
+    }
+
+    /// The pointer's bits in place, which `#[inline_value]` variants are borrowed from
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn bits_ref(&self) -> &usize {
+        // SAFETY: `NonNull<T>` has the layout of `usize` for sized `T`
+        unsafe { &*(&self.ptr as *const NonNull<T> as *const usize) }
+    }
+
+    /// The packed bits in place, which `&mut self` dispatch updates inline values through
     ///
-    /// More aggressive than reset, this tries to return memory to the OS.
-    fn clear(&mut self);
+    /// # Safety
+    ///
+    /// The bits must stay a valid encoding of the handle's variant.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub unsafe fn bits_mut(&mut self) -> &mut usize {
+        // SAFETY: `NonNull<T>` has the layout of `usize` for sized `T`
+        &mut *(&mut self.ptr as *mut NonNull<T> as *mut usize)
+    }
+}
 
-    /// Get current memory usage statistics.
-    fn stats(&self) -> ArenaStats;
+// Safety: UntaggedPtr is Send/Sync if T is Send/Sync
+unsafe impl<T: Send> Send for UntaggedPtr<T> {}
+unsafe impl<T: Sync> Sync for UntaggedPtr<T> {}
+
+impl<T> Clone for UntaggedPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
 }
 
-/// A simple box allocator for owned tagged pointers.
-///
-/// This is used internally by the owned version of tagged dispatch.
-pub struct BoxAllocator;
+impl<T> Copy for UntaggedPtr<T> {}
 
-impl TaggedAllocator for BoxAllocator {
-    #[inline]
-    fn alloc<T>(&self, value: T) -> *mut T {
-        Box::into_raw(Box::new(value))
+impl<T> core::fmt::Debug for UntaggedPtr<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("UntaggedPtr")
+            .field("ptr", &format_args!("{:p}", self.ptr))
+            .finish()
     }
 }
 
-// Module with helper utilities
-#[doc(hidden)]
-pub mod __private {
-    pub use core::mem;
-    pub use core::ptr;
-    pub use core::marker::PhantomData;
+impl<T> core::cmp::PartialEq for UntaggedPtr<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+}
+
+impl<T> core::cmp::Eq for UntaggedPtr<T> {}
+
+impl<T> core::cmp::PartialOrd for UntaggedPtr<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> core::cmp::Ord for UntaggedPtr<T> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.ptr.cmp(&other.ptr)
+    }
+}
+
+/// The pointer stored by tagged dispatch types declared with `tag_bits = N` for
+/// `N > DEFAULT_TAG_BITS`, which have more variants than fit in the high bits.
+///
+/// The tag is split across the pointer: its high [`DEFAULT_TAG_BITS`] bits (7 unless
+/// [`ADDRESS_BITS`] is configured) go in the top of the address like [`TaggedPtr`],
+/// and the remaining `LOW_BITS = N - DEFAULT_TAG_BITS` bits go in the bottom, which
+/// are always zero for values aligned to at least `1 << LOW_BITS`. The macro takes
+/// the high width from the same `TAGGED_DISPATCH_ADDRESS_BITS` setting and checks
+/// that alignment for every variant type at compile time.
+///
+/// Reading the value always masks both ends, so this skips the Top Byte Ignore
+/// optimization [`TaggedPtr`] uses on Apple Silicon.
+#[repr(transparent)]
+pub struct WideTaggedPtr<T, const LOW_BITS: u32> {
+    ptr: NonZeroUsize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const LOW_BITS: u32> WideTaggedPtr<T, LOW_BITS> {
+    const HIGH_BITS: u32 = DEFAULT_TAG_BITS;
+    const HIGH_SHIFT: u32 = 64 - Self::HIGH_BITS;
+    const HIGH_MASK: usize = ((1 << Self::HIGH_BITS) - 1) << Self::HIGH_SHIFT;
+    const LOW_MASK: usize = (1 << LOW_BITS) - 1;
+    const SPARE_BIT: usize = 1 << (Self::HIGH_SHIFT - 1);
+    const PTR_MASK: usize = !(Self::HIGH_MASK | Self::SPARE_BIT | Self::LOW_MASK);
+
+    /// Maximum number of variants supported (2^(DEFAULT_TAG_BITS + LOW_BITS))
+    pub const MAX_VARIANTS: usize = 1 << (Self::HIGH_BITS + LOW_BITS);
+
+    /// The alignment every pointed-to value needs, so its low bits are free
+    ///
+    /// The `#[tagged_dispatch]` macro rejects under-aligned variant types at compile
+    /// time:
+    ///
+    /// ```compile_fail
+    /// # use tagged_dispatch::tagged_dispatch;
+    /// # // Written for the default width; wider configured tags fit 9 bits up top
+    /// # const _: () = assert!(tagged_dispatch::DEFAULT_TAG_BITS <= 7);
+    /// #[derive(Clone)]
+    /// struct Byte(u8);
+    ///
+    /// #[derive(Clone)]
+    /// #[repr(align(4))]
+    /// struct Word(u32);
+    ///
+    /// #[tagged_dispatch(tag_bits = 9)]
+    /// enum Value {
+    ///     Byte, // error: `Byte` must be aligned to at least 4 bytes
+    ///     Word,
+    /// }
+    /// ```
+    pub const MIN_ALIGN: usize = 1 << LOW_BITS;
+
+    /// Create a new tagged pointer
+    ///
+    /// # Panics
+    ///
+    /// If `ptr` is null and `tag` is 0, since the result can't be null; use
+    /// [`try_new`](Self::try_new) to check instead.
+    #[inline(always)]
+    pub fn new(ptr: *mut T, tag: u16) -> Self {
+        debug_assert!(
+            (tag as usize) < Self::MAX_VARIANTS,
+            "Tag does not fit in the configured tag bits"
+        );
+
+        let addr = ptr as usize;
+        check_addr!(
+            addr & !Self::PTR_MASK,
+            "Pointer already has high bits set or is under-aligned!"
+        );
+
+        let tag = tag as usize;
+        Self {
+            ptr: nonzero_bits(addr | ((tag >> LOW_BITS) << Self::HIGH_SHIFT) | (tag & Self::LOW_MASK)),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Create a new tagged pointer, or report why `ptr` and `tag` don't fit instead of
+    /// only checking in debug builds like [`new`](Self::new)
+    #[inline]
+    pub fn try_new(ptr: *mut T, tag: u16) -> Result<Self, TagError> {
+        if tag as usize >= Self::MAX_VARIANTS {
+            return Err(TagError::TagOutOfRange { tag, max_variants: Self::MAX_VARIANTS });
+        }
+        if ptr as usize & !Self::PTR_MASK != 0 {
+            return Err(TagError::AddressInUse { addr: ptr as usize });
+        }
+        if ptr.is_null() && tag == 0 {
+            return Err(TagError::NullPointer);
+        }
+        Ok(Self::new(ptr, tag))
+    }
+
+    /// Get the tag value
+    #[inline(always)]
+    pub fn tag(&self) -> u16 {
+        let high = (self.ptr.get() & Self::HIGH_MASK) >> Self::HIGH_SHIFT;
+        ((high << LOW_BITS) | (self.ptr.get() & Self::LOW_MASK)) as u16
+    }
+
+    /// Get the untagged pointer.
+    ///
+    /// # Safety
+    /// The returned pointer is only valid if the original pointer passed to `new` is still valid.
+    #[inline(always)]
+    pub fn ptr(&self) -> *mut T {
+        (self.ptr.get() & Self::PTR_MASK) as *mut T
+    }
+
+    /// Get the untagged pointer for deallocation, the same as [`ptr`](Self::ptr)
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn untagged_ptr(&self) -> *mut T {
+        self.ptr()
+    }
+
+    /// Set the spare bit below the high tag bits, like [`TaggedPtr`]
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn with_spare_bit(self) -> Self {
+        Self {
+            ptr: self.ptr | Self::SPARE_BIT,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Clear the spare bit again
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn without_spare_bit(self) -> Self {
+        Self {
+            ptr: nonzero_bits(self.ptr.get() & !Self::SPARE_BIT),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Whether the spare bit is set
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn spare_bit(&self) -> bool {
+        self.ptr.get() & Self::SPARE_BIT != 0
+    }
+
+    /// Get a reference to the pointed value.
+    ///
+    /// # Safety
+    /// Same as [`TaggedPtr::as_ref`].
+    #[inline(always)]
+    pub unsafe fn as_ref(&self) -> &T {
+        unsafe { &*self.ptr() }
+    }
+
+    /// Get a mutable reference to the pointed value.
+    ///
+    /// # Safety
+    /// Same as [`TaggedPtr::as_mut`].
+    #[inline(always)]
+    pub unsafe fn as_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.ptr() }
+    }
+
+    /// Check if the pointer is null (ignoring the tag)
+    #[inline(always)]
+    pub fn is_null(&self) -> bool {
+        self.ptr() as usize == 0
+    }
+
+    /// The packed bits in place, which `#[inline_value]` variants are borrowed from
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn bits_ref(&self) -> &usize {
+        // SAFETY: `NonZeroUsize` has the layout of `usize`
+        unsafe { &*(&self.ptr as *const NonZeroUsize as *const usize) }
+    }
+
+    /// The packed bits in place, which `&mut self` dispatch updates inline values through
+    ///
+    /// # Safety
+    ///
+    /// The bits must stay a valid encoding of the handle's variant.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub unsafe fn bits_mut(&mut self) -> &mut usize {
+        // SAFETY: `NonZeroUsize` has the layout of `usize`
+        &mut *(&mut self.ptr as *mut NonZeroUsize as *mut usize)
+    }
+}
+
+// Safety: WideTaggedPtr is Send/Sync if T is Send/Sync
+unsafe impl<T: Send, const LOW_BITS: u32> Send for WideTaggedPtr<T, LOW_BITS> {}
+unsafe impl<T: Sync, const LOW_BITS: u32> Sync for WideTaggedPtr<T, LOW_BITS> {}
+
+impl<T, const LOW_BITS: u32> Clone for WideTaggedPtr<T, LOW_BITS> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const LOW_BITS: u32> Copy for WideTaggedPtr<T, LOW_BITS> {}
+
+impl<T, const LOW_BITS: u32> core::fmt::Debug for WideTaggedPtr<T, LOW_BITS> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WideTaggedPtr")
+            .field("tag", &self.tag())
+            .field("ptr", &format_args!("{:p}", self.ptr()))
+            .finish()
+    }
+}
+
+impl<T, const LOW_BITS: u32> core::cmp::PartialEq for WideTaggedPtr<T, LOW_BITS> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+}
+
+impl<T, const LOW_BITS: u32> core::cmp::Eq for WideTaggedPtr<T, LOW_BITS> {}
+
+impl<T, const LOW_BITS: u32> core::cmp::PartialOrd for WideTaggedPtr<T, LOW_BITS> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, const LOW_BITS: u32> core::cmp::Ord for WideTaggedPtr<T, LOW_BITS> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        // Order by tag first, then by address, like `TaggedPtr`
+        (self.tag(), self.ptr()).cmp(&(other.tag(), other.ptr()))
+    }
+}
+
+/// The pointer stored by tagged dispatch types declared with `tag = "low"`.
+///
+/// The whole tag lives in the low `BITS` bits of the address, which are always zero
+/// for values aligned to at least `1 << BITS`, and the high bits are left untouched.
+/// This works on targets where the top of a pointer can't be used, at the cost of
+/// needing that alignment for every variant type, so enums stay small. The macro
+/// sizes `BITS` to the variant count and checks the alignment at compile time.
+#[repr(transparent)]
+pub struct LowTaggedPtr<T, const BITS: u32> {
+    ptr: NonZeroUsize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const BITS: u32> LowTaggedPtr<T, BITS> {
+    const TAG_MASK: usize = (1 << BITS) - 1;
+
+    /// Maximum number of variants supported (2^BITS)
+    pub const MAX_VARIANTS: usize = 1 << BITS;
+
+    /// The alignment every pointed-to value needs, so its low bits are free
+    pub const MIN_ALIGN: usize = 1 << BITS;
+
+    /// Create a new tagged pointer
+    ///
+    /// # Panics
+    ///
+    /// If `ptr` is null and `tag` is 0, since the result can't be null; use
+    /// [`try_new`](Self::try_new) to check instead.
+    #[inline(always)]
+    pub fn new(ptr: *mut T, tag: u16) -> Self {
+        debug_assert!(
+            (tag as usize) < Self::MAX_VARIANTS,
+            "Tag does not fit in the pointer's alignment bits"
+        );
+
+        let addr = ptr as usize;
+        check_addr!(
+            addr & Self::TAG_MASK,
+            "Pointer is under-aligned for its tag!"
+        );
+
+        Self {
+            ptr: nonzero_bits(addr | tag as usize),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Create a new tagged pointer, or report why `ptr` and `tag` don't fit instead of
+    /// only checking in debug builds like [`new`](Self::new)
+    #[inline]
+    pub fn try_new(ptr: *mut T, tag: u16) -> Result<Self, TagError> {
+        if tag as usize >= Self::MAX_VARIANTS {
+            return Err(TagError::TagOutOfRange { tag, max_variants: Self::MAX_VARIANTS });
+        }
+        if ptr as usize & Self::TAG_MASK != 0 {
+            return Err(TagError::AddressInUse { addr: ptr as usize });
+        }
+        if ptr.is_null() && tag == 0 {
+            return Err(TagError::NullPointer);
+        }
+        Ok(Self::new(ptr, tag))
+    }
+
+    /// Get the tag value
+    #[inline(always)]
+    pub fn tag(&self) -> u16 {
+        (self.ptr.get() & Self::TAG_MASK) as u16
+    }
+
+    /// Get the untagged pointer.
+    ///
+    /// # Safety
+    /// The returned pointer is only valid if the original pointer passed to `new` is still valid.
+    #[inline(always)]
+    pub fn ptr(&self) -> *mut T {
+        (self.ptr.get() & !Self::TAG_MASK) as *mut T
+    }
+
+    /// Get the untagged pointer for deallocation, the same as [`ptr`](Self::ptr)
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn untagged_ptr(&self) -> *mut T {
+        self.ptr()
+    }
+
+    /// Get a reference to the pointed value.
+    ///
+    /// # Safety
+    /// Same as [`TaggedPtr::as_ref`].
+    #[inline(always)]
+    pub unsafe fn as_ref(&self) -> &T {
+        unsafe { &*self.ptr() }
+    }
+
+    /// Get a mutable reference to the pointed value.
+    ///
+    /// # Safety
+    /// Same as [`TaggedPtr::as_mut`].
+    #[inline(always)]
+    pub unsafe fn as_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.ptr() }
+    }
+
+    /// Check if the pointer is null (ignoring the tag)
+    #[inline(always)]
+    pub fn is_null(&self) -> bool {
+        self.ptr() as usize == 0
+    }
+
+    /// The packed bits in place, which `#[inline_value]` variants are borrowed from
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn bits_ref(&self) -> &usize {
+        // SAFETY: `NonZeroUsize` has the layout of `usize`
+        unsafe { &*(&self.ptr as *const NonZeroUsize as *const usize) }
+    }
+
+    /// The packed bits in place, which `&mut self` dispatch updates inline values through
+    ///
+    /// # Safety
+    ///
+    /// The bits must stay a valid encoding of the handle's variant.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub unsafe fn bits_mut(&mut self) -> &mut usize {
+        // SAFETY: `NonZeroUsize` has the layout of `usize`
+        &mut *(&mut self.ptr as *mut NonZeroUsize as *mut usize)
+    }
+}
+
+// Safety: LowTaggedPtr is Send/Sync if T is Send/Sync
+unsafe impl<T: Send, const BITS: u32> Send for LowTaggedPtr<T, BITS> {}
+unsafe impl<T: Sync, const BITS: u32> Sync for LowTaggedPtr<T, BITS> {}
+
+impl<T, const BITS: u32> Clone for LowTaggedPtr<T, BITS> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const BITS: u32> Copy for LowTaggedPtr<T, BITS> {}
+
+impl<T, const BITS: u32> core::fmt::Debug for LowTaggedPtr<T, BITS> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LowTaggedPtr")
+            .field("tag", &self.tag())
+            .field("ptr", &format_args!("{:p}", self.ptr()))
+            .finish()
+    }
+}
+
+impl<T, const BITS: u32> core::cmp::PartialEq for LowTaggedPtr<T, BITS> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+}
+
+impl<T, const BITS: u32> core::cmp::Eq for LowTaggedPtr<T, BITS> {}
+
+impl<T, const BITS: u32> core::cmp::PartialOrd for LowTaggedPtr<T, BITS> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, const BITS: u32> core::cmp::Ord for LowTaggedPtr<T, BITS> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        // Order by tag first, then by address, like `TaggedPtr`
+        (self.tag(), self.ptr()).cmp(&(other.tag(), other.ptr()))
+    }
+}
+
+/// A NaN-boxed pointer for `nan_box` enums: either an `f64` stored as its own bits,
+/// or a tagged pointer hidden in the payload of a negative quiet NaN.
+///
+/// ```text
+/// number: any f64, with every NaN stored as 0x7FF8_0000_0000_0000
+/// boxed:  1 | 11111111111 | 1 | tag (3 bits) | address (48 bits)
+/// ```
+///
+/// Numbers report `NUMBER_TAG` as their tag, and their bits in place of an address,
+/// so the number variant can be declared anywhere in the enum. Boxed addresses must
+/// fit in 48 bits.
+#[repr(transparent)]
+pub struct NanBoxedPtr<T, const NUMBER_TAG: u16> {
+    bits: u64,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const NUMBER_TAG: u16> NanBoxedPtr<T, NUMBER_TAG> {
+    const BOXED: u64 = 0xFFF8 << 48;
+    const TAG_SHIFT: u32 = 48;
+    const PTR_MASK: u64 = (1 << Self::TAG_SHIFT) - 1;
+
+    /// Maximum number of variants supported, the number variant included
+    pub const MAX_VARIANTS: usize = 8;
+
+    /// Create a new NaN-boxed pointer. For `NUMBER_TAG`, `ptr` carries the bits of
+    /// a number from [`number_bits`](Self::number_bits) rather than an address; any
+    /// NaN is stored as `f64::NAN`, so it can't be read back as a boxed pointer.
+    #[inline(always)]
+    pub fn new(ptr: *mut T, tag: u16) -> Self {
+        if tag == NUMBER_TAG {
+            let bits = Self::number_bits(f64::from_bits(ptr as u64)) as u64;
+            return Self { bits, _phantom: PhantomData };
+        }
+        debug_assert!(
+            (tag as usize) < Self::MAX_VARIANTS,
+            "Tag must be less than {}",
+            Self::MAX_VARIANTS
+        );
+        check_addr!(
+            ptr as u64 & !Self::PTR_MASK,
+            "Pointer does not fit in the 48 bits of a NaN box!"
+        );
+
+        Self {
+            bits: Self::BOXED | (tag as u64) << Self::TAG_SHIFT | ptr as u64,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Create a new NaN-boxed pointer, or report why `ptr` and `tag` don't fit instead
+    /// of only checking in debug builds like [`new`](Self::new)
+    #[inline]
+    pub fn try_new(ptr: *mut T, tag: u16) -> Result<Self, TagError> {
+        if tag as usize >= Self::MAX_VARIANTS {
+            return Err(TagError::TagOutOfRange { tag, max_variants: Self::MAX_VARIANTS });
+        }
+        if tag == NUMBER_TAG {
+            let bits = ptr as u64;
+            if f64::from_bits(bits).is_nan() && bits != f64::NAN.to_bits() {
+                return Err(TagError::NonCanonicalNan { bits });
+            }
+        } else if ptr as u64 & !Self::PTR_MASK != 0 {
+            return Err(TagError::AddressInUse { addr: ptr as usize });
+        }
+        Ok(Self::new(ptr, tag))
+    }
+
+    /// The bits `new` stores for `value`. NaNs are canonicalized, so no number is
+    /// ever mistaken for a boxed pointer.
+    #[inline(always)]
+    pub fn number_bits(value: f64) -> *mut T {
+        let value = if value.is_nan() { f64::NAN } else { value };
+        value.to_bits() as usize as *mut T
+    }
+
+    /// Whether this holds a pointer rather than a number
+    #[inline(always)]
+    pub fn is_boxed(&self) -> bool {
+        self.bits & Self::BOXED == Self::BOXED
+    }
+
+    /// Get the number, if this holds one
+    #[inline(always)]
+    pub fn number(&self) -> Option<f64> {
+        (!self.is_boxed()).then(|| f64::from_bits(self.bits))
+    }
+
+    /// Get the tag value, `NUMBER_TAG` for numbers
+    #[inline(always)]
+    pub fn tag(&self) -> u16 {
+        if self.is_boxed() {
+            (self.bits >> Self::TAG_SHIFT) as u16 & 0b111
+        } else {
+            NUMBER_TAG
+        }
+    }
+
+    /// Get the unboxed pointer, or a number's bits.
+    ///
+    /// # Safety
+    /// The returned pointer is only valid if the original pointer passed to `new` is still valid.
+    #[inline(always)]
+    pub fn ptr(&self) -> *mut T {
+        if self.is_boxed() {
+            (self.bits & Self::PTR_MASK) as usize as *mut T
+        } else {
+            self.bits as usize as *mut T
+        }
+    }
+
+    /// Get the unboxed pointer for deallocation, the same as [`ptr`](Self::ptr)
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn untagged_ptr(&self) -> *mut T {
+        self.ptr()
+    }
+
+    /// Check if the pointer is null (ignoring the tag), as is the number `0.0`
+    #[inline(always)]
+    pub fn is_null(&self) -> bool {
+        self.ptr().is_null()
+    }
+
+    /// The packed bits in place, which the number is borrowed from
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn bits_ref(&self) -> &usize {
+        // SAFETY: `u64` and `usize` have the same layout on the 64-bit targets supported
+        unsafe { &*(&self.bits as *const u64 as *const usize) }
+    }
+
+    /// The packed bits in place, which `&mut self` dispatch updates inline values through
+    ///
+    /// # Safety
+    ///
+    /// The bits must stay a valid encoding of the handle's variant.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub unsafe fn bits_mut(&mut self) -> &mut usize {
+        // SAFETY: `u64` and `usize` have the same layout on the 64-bit targets supported
+        &mut *(&mut self.bits as *mut u64 as *mut usize)
+    }
+}
+
+// Safety: NanBoxedPtr is Send/Sync if T is Send/Sync
+unsafe impl<T: Send, const NUMBER_TAG: u16> Send for NanBoxedPtr<T, NUMBER_TAG> {}
+unsafe impl<T: Sync, const NUMBER_TAG: u16> Sync for NanBoxedPtr<T, NUMBER_TAG> {}
+
+impl<T, const NUMBER_TAG: u16> Clone for NanBoxedPtr<T, NUMBER_TAG> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const NUMBER_TAG: u16> Copy for NanBoxedPtr<T, NUMBER_TAG> {}
+
+impl<T, const NUMBER_TAG: u16> core::fmt::Debug for NanBoxedPtr<T, NUMBER_TAG> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.number() {
+            Some(number) => f.debug_tuple("NanBoxedPtr").field(&number).finish(),
+            None => f.debug_struct("NanBoxedPtr")
+                .field("tag", &self.tag())
+                .field("ptr", &format_args!("{:p}", self.ptr()))
+                .finish(),
+        }
+    }
+}
+
+impl<T, const NUMBER_TAG: u16> core::cmp::PartialEq for NanBoxedPtr<T, NUMBER_TAG> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+
+impl<T, const NUMBER_TAG: u16> core::cmp::Eq for NanBoxedPtr<T, NUMBER_TAG> {}
+
+impl<T, const NUMBER_TAG: u16> core::cmp::PartialOrd for NanBoxedPtr<T, NUMBER_TAG> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, const NUMBER_TAG: u16> core::cmp::Ord for NanBoxedPtr<T, NUMBER_TAG> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        // Order by tag first, then by address or number bits, like `TaggedPtr`
+        (self.tag(), self.ptr()).cmp(&(other.tag(), other.ptr()))
+    }
+}
+
+/// Allocator trait for arena-allocated tagged pointers.
+///
+/// This trait should be implemented by arena allocators to enable
+/// the arena version of tagged dispatch.
+///
+/// # Example
+///
+/// ```rust
+/// use tagged_dispatch::TaggedAllocator;
+/// use bumpalo::Bump;
+///
+/// // Bumpalo automatically implements TaggedAllocator when the feature is enabled
+/// let arena = Bump::new();
+/// let ptr = arena.alloc(42);
+/// ```
+pub trait TaggedAllocator {
+    /// Allocate space for a value and return a pointer to it.
+    ///
+    /// The allocated memory should have the same lifetime as the allocator.
+    fn alloc<T>(&self, value: T) -> *mut T;
+}
+
+// Implement TaggedAllocator for common arena allocators when their features are enabled
+
+#[cfg(feature = "bumpalo")]
+impl TaggedAllocator for bumpalo::Bump {
+    #[inline]
+    fn alloc<T>(&self, value: T) -> *mut T {
+        bumpalo::Bump::alloc(self, value) as *mut T
+    }
+}
+
+// Note: typed_arena doesn't implement TaggedAllocator directly
+// because it can only allocate values of a single type T.
+// Instead, the arena builder pattern generates separate arenas
+// for each variant type when typed_arena is enabled.
+
+/// Statistics for arena memory usage.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArenaStats {
+    /// Total bytes currently allocated
+    pub allocated_bytes: usize,
+    /// Total capacity of all chunks
+    pub chunk_capacity: usize,
+}
+
+/// Trait for arena builders generated by the macro.
+///
+/// Provides memory management capabilities for arena-allocated
+/// tagged dispatch types.
+pub trait ArenaBuilder<'a>: Sized {
+    /// Create a new builder with default settings.
+    ///
+    /// When both allocators are available, this prefers bumpalo
+    /// for its superior flexibility.
+    fn new() -> Self;
+
+    /// Reset all allocations, invalidating existing references.
+    ///
+    /// # Safety
+    ///
+    /// This invalidates all references previously allocated from this builder.
+    /// Using any such references after reset is undefined behavior.
+    fn reset(&mut self);
+
+    /// Clear allocations and attempt to reclaim memory.
+    ///
+    /// More aggressive than reset, this tries to return memory to the OS.
+    fn clear(&mut self);
+
+    /// Get current memory usage statistics.
+    fn stats(&self) -> ArenaStats;
+}
+
+/// A simple box allocator for owned tagged pointers.
+///
+/// This is used internally by the owned version of tagged dispatch.
+pub struct BoxAllocator;
+
+impl TaggedAllocator for BoxAllocator {
+    #[inline]
+    fn alloc<T>(&self, value: T) -> *mut T {
+        Box::into_raw(Box::new(value))
+    }
+}
+
+/// Storage for one variant of an owned tagged dispatch type.
+///
+/// Variants use [`BoxAllocator`] unless they name another allocator with
+/// `#[alloc_with(Path)]`, e.g. a dedicated pool for one large, frequently created
+/// variant. The generated type calls `alloc` when wrapping a value and `take` or
+/// `free` when giving it up.
+///
+/// # Safety
+///
+/// `alloc` must return a pointer that is valid for `T` and only uses the low
+/// [`ADDRESS_BITS`] bits,
+/// and it must stay valid until passed to `take` or `free`.
+pub unsafe trait VariantAllocator<T> {
+    /// Move `value` into new storage.
+    fn alloc(value: T) -> core::ptr::NonNull<T>;
+
+    /// Move `value` into new storage, or give it back if there is no memory for it,
+    /// for the `try_<variant>` constructors of `fallible` enums.
+    ///
+    /// Defaults to [`alloc`](Self::alloc), which can't report failure.
+    fn try_alloc(value: T) -> Result<core::ptr::NonNull<T>, T> {
+        Ok(Self::alloc(value))
+    }
+
+    /// Move the value back out and release its storage.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`alloc`](Self::alloc) and not have been released yet.
+    unsafe fn take(ptr: core::ptr::NonNull<T>) -> T;
+
+    /// Drop the value in place and release its storage.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`take`](Self::take).
+    unsafe fn free(ptr: core::ptr::NonNull<T>) {
+        drop(Self::take(ptr));
+    }
+
+    /// Borrow the value mutably, or `None` while other handles share it.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`take`](Self::take), and the value must not be borrowed elsewhere
+    /// for `'a`.
+    unsafe fn get_mut<'a>(ptr: core::ptr::NonNull<T>) -> Option<&'a mut T> {
+        Some(&mut *ptr.as_ptr())
+    }
+}
+
+unsafe impl<T> VariantAllocator<T> for BoxAllocator {
+    #[inline]
+    fn alloc(value: T) -> core::ptr::NonNull<T> {
+        // SAFETY: `Box::into_raw` never returns null
+        unsafe { core::ptr::NonNull::new_unchecked(Box::into_raw(Box::new(value))) }
+    }
+
+    /// Allocates like `Box::new`, but returns `value` if the global allocator fails
+    /// instead of aborting
+    fn try_alloc(value: T) -> Result<core::ptr::NonNull<T>, T> {
+        let layout = core::alloc::Layout::new::<T>();
+        if layout.size() == 0 {
+            return Ok(<Self as VariantAllocator<T>>::alloc(value));
+        }
+        // SAFETY: the layout has a non-zero size, and `Box` frees it with the same one
+        let Some(ptr) = core::ptr::NonNull::new(unsafe { alloc_raw(layout) } as *mut T) else {
+            return Err(value);
+        };
+        unsafe { ptr.as_ptr().write(value) };
+        Ok(ptr)
+    }
+
+    #[inline]
+    unsafe fn take(ptr: core::ptr::NonNull<T>) -> T {
+        *Box::from_raw(ptr.as_ptr())
+    }
+
+    #[inline]
+    unsafe fn free(ptr: core::ptr::NonNull<T>) {
+        drop(Box::from_raw(ptr.as_ptr()));
+    }
+}
+
+/// A [`VariantAllocator`] whose values can be shared by several handles, counting
+/// references instead of copying the value when a handle is cloned.
+///
+/// # Safety
+///
+/// After [`retain`](Self::retain), the value must stay valid until
+/// [`free`](VariantAllocator::free) has been called once more, and
+/// [`take`](VariantAllocator::take) must give back a copy while other references
+/// remain.
+pub unsafe trait SharedAllocator<T>: VariantAllocator<T> {
+    /// Add a reference to the value, released by another `free` or `take`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`alloc`](VariantAllocator::alloc) and still be referenced.
+    unsafe fn retain(ptr: core::ptr::NonNull<T>);
+}
+
+/// Shares values between handles through an [`Arc`], used by enums with the
+/// `shared` flag.
+///
+/// Cloning a handle bumps the atomic reference count, and the value is dropped with
+/// the last handle. Taking the value out, for consuming methods or `TryFrom`,
+/// clones it while other handles still hold it.
+#[cfg(target_has_atomic = "ptr")]
+pub struct ArcAllocator;
+
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl<T: Clone> VariantAllocator<T> for ArcAllocator {
+    #[inline]
+    fn alloc(value: T) -> core::ptr::NonNull<T> {
+        // SAFETY: `Arc::into_raw` never returns null
+        unsafe { core::ptr::NonNull::new_unchecked(Arc::into_raw(Arc::new(value)) as *mut T) }
+    }
+
+    #[inline]
+    unsafe fn take(ptr: core::ptr::NonNull<T>) -> T {
+        Arc::unwrap_or_clone(Arc::from_raw(ptr.as_ptr()))
+    }
+
+    #[inline]
+    unsafe fn free(ptr: core::ptr::NonNull<T>) {
+        drop(Arc::from_raw(ptr.as_ptr()));
+    }
+
+    #[inline]
+    unsafe fn get_mut<'a>(ptr: core::ptr::NonNull<T>) -> Option<&'a mut T> {
+        // Borrow the handle's reference without releasing it
+        let mut shared = core::mem::ManuallyDrop::new(Arc::from_raw(ptr.as_ptr()));
+        Arc::get_mut(&mut shared)?;
+        Some(&mut *ptr.as_ptr())
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+unsafe impl<T: Clone> SharedAllocator<T> for ArcAllocator {
+    #[inline]
+    unsafe fn retain(ptr: core::ptr::NonNull<T>) {
+        Arc::increment_strong_count(ptr.as_ptr());
+    }
+}
+
+/// Shares values between handles through an [`Rc`], used by enums with the `rc`
+/// flag.
+///
+/// Like [`ArcAllocator`], but the reference count isn't atomic, so handles stay on
+/// the thread that created them.
+pub struct RcAllocator;
+
+unsafe impl<T: Clone> VariantAllocator<T> for RcAllocator {
+    #[inline]
+    fn alloc(value: T) -> core::ptr::NonNull<T> {
+        // SAFETY: `Rc::into_raw` never returns null
+        unsafe { core::ptr::NonNull::new_unchecked(Rc::into_raw(Rc::new(value)) as *mut T) }
+    }
+
+    #[inline]
+    unsafe fn take(ptr: core::ptr::NonNull<T>) -> T {
+        Rc::unwrap_or_clone(Rc::from_raw(ptr.as_ptr()))
+    }
+
+    #[inline]
+    unsafe fn free(ptr: core::ptr::NonNull<T>) {
+        drop(Rc::from_raw(ptr.as_ptr()));
+    }
+
+    #[inline]
+    unsafe fn get_mut<'a>(ptr: core::ptr::NonNull<T>) -> Option<&'a mut T> {
+        // Borrow the handle's reference without releasing it
+        let mut shared = core::mem::ManuallyDrop::new(Rc::from_raw(ptr.as_ptr()));
+        Rc::get_mut(&mut shared)?;
+        Some(&mut *ptr.as_ptr())
+    }
+}
+
+unsafe impl<T: Clone> SharedAllocator<T> for RcAllocator {
+    #[inline]
+    unsafe fn retain(ptr: core::ptr::NonNull<T>) {
+        Rc::increment_strong_count(ptr.as_ptr());
+    }
+}
+
+/// How many bytes of a value fit below the tag, the size limit of `#[inline_value]`
+/// variants: 6 with the default [`ADDRESS_BITS`]. The top address byte is left for
+/// a marker bit, so even a zero value has a bit set.
+pub const INLINE_VALUE_BYTES: usize = ADDRESS_BITS as usize / 8 - 1;
+
+/// A value small enough to be stored in a handle's address bits instead of behind
+/// a pointer, for variants marked `#[inline_value]`.
+///
+/// Implemented for the primitive types that fit. Small `Copy` types of your own,
+/// such as unit structs or wrappers around a `bool`, can implement it too.
+///
+/// # Safety
+///
+/// The type must be at most [`INLINE_VALUE_BYTES`] bytes, and have no padding, so
+/// that every byte of a value is initialized.
+pub unsafe trait InlineValue: Copy {}
+
+macro_rules! impl_inline_value {
+    ($($ty:ty),*) => {
+        $(unsafe impl InlineValue for $ty {})*
+    };
+}
+
+impl_inline_value!((), bool, char, u8, u16, u32, i8, i16, i32, f32);
+
+// Module with helper utilities
+#[doc(hidden)]
+pub mod __private {
+    pub use tagged_dispatch_macros::TaggedDispatchCfg;
+    pub use tagged_dispatch_macros::flatten_enum;
+    pub use core::mem;
+    pub use core::ptr;
+    pub use core::marker::PhantomData;
+    #[cfg(not(feature = "std"))]
+    pub use alloc::vec::Vec;
+    #[cfg(feature = "std")]
+    pub use std::vec::Vec;
+    pub use crate::brand::{check_arena_handle, release_view, ArenaBrand};
+
+    /// Abort the process from generated code that can't safely unwind, with or without
+    /// `std`: panicking again while the first panic unwinds aborts
+    #[cold]
+    pub fn abort(message: &str) -> ! {
+        struct Abort;
+
+        impl Drop for Abort {
+            fn drop(&mut self) {
+                panic!("aborting");
+            }
+        }
+
+        let _abort = Abort;
+        panic!("{}", message);
+    }
+
+    /// Allocation of a variant value by type, implemented by generated arena builders
+    /// so dispatch methods returning `Self` can re-wrap their result.
+    #[diagnostic::on_unimplemented(
+        message = "`{Self}` can't allocate a variant by its type `{T}`",
+        note = "methods returning `Self` need each variant to hold a different type"
+    )]
+    pub trait AllocVariant<'a, T> {
+        /// The arena handle the builder produces
+        type Handle;
+
+        /// Allocate `value` in the arena as the variant holding `T`
+        fn alloc_variant(&'a self, value: T) -> Self::Handle;
+    }
+
+    /// Conversion of a variant value into an owned handle, implemented by generated
+    /// owned enums so dispatch methods returning `Self` can re-wrap their result even
+    /// when the variant has no public `From` impl.
+    #[diagnostic::on_unimplemented(
+        message = "`{Self}` can't wrap a variant by its type `{T}`",
+        note = "methods returning `Self` need each variant to hold a different type"
+    )]
+    pub trait FromVariant<T> {
+        /// Wrap `value` as the variant holding `T`
+        fn from_variant(value: T) -> Self;
+    }
+
+    /// Access to a plugin value as the trait object `D`, implemented by the generated
+    /// plugin types of `open` enums for each dispatched trait and its supertraits.
+    #[diagnostic::on_unimplemented(
+        message = "`{Self}` can't hand out its value as a `{D}`",
+        note = "plugins of an `open` enum only implement the traits the enum dispatches"
+    )]
+    pub trait PluginCast<D: ?Sized> {
+        /// Borrow the value as a `D`
+        fn cast(&self) -> &D;
+
+        /// Borrow the value mutably as a `D`
+        fn cast_mut(&mut self) -> &mut D;
+    }
+
+    /// Report a tag outside `enum_name`'s variants, the default invalid-tag policy
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    pub fn invalid_tag(enum_name: &'static str, tag: usize) -> ! {
+        panic!("invalid tag {} in a `{}` handle; the handle was corrupted or built unsoundly", tag, enum_name)
+    }
+
+    /// Write `value` pretty-printed and indented one level, for generated `dump` methods
+    pub fn dump_value(out: &mut impl core::fmt::Write, value: &impl core::fmt::Debug) -> core::fmt::Result {
+        use core::fmt::Write;
+
+        writeln!(Indented { out, line_start: true }, "{:#?}", value)
+    }
+
+    /// Writes through to `out`, indenting every line by four spaces
+    struct Indented<'a, W> {
+        out: &'a mut W,
+        line_start: bool,
+    }
+
+    impl<W: core::fmt::Write> core::fmt::Write for Indented<'_, W> {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            for line in s.split_inclusive('\n') {
+                if self.line_start {
+                    self.out.write_str("    ")?;
+                }
+                self.out.write_str(line)?;
+                self.line_start = line.ends_with('\n');
+            }
+            Ok(())
+        }
+    }
+
+    /// Drop glue for a `T` stored by `A`, referenced from the drop tables of `drop_table` enums.
+    ///
+    /// Being generic rather than generated, it is instantiated once per type and shared
+    /// by every enum in the crate that has `T` as a variant.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `A::alloc` with its tag removed, and must not be used afterwards.
+    pub unsafe fn drop_variant<T, A: super::VariantAllocator<T>>(ptr: *mut ()) {
+        A::free(core::ptr::NonNull::new_unchecked(ptr as *mut T));
+    }
+
+    /// Drop table entry for an `#[inline_value]` variant, which owns no allocation
+    pub unsafe fn drop_inline(_ptr: *mut ()) {}
+
+    /// Pack an `#[inline_value]` variant into the address bits of a handle
+    #[inline(always)]
+    pub fn pack_inline<T: super::InlineValue>(value: T) -> *mut () {
+        const { assert!(core::mem::size_of::<T>() <= super::INLINE_VALUE_BYTES) };
+        let mut bytes = [0u8; 8];
+        // SAFETY: `InlineValue` types fit and have every byte initialized
+        unsafe {
+            core::ptr::copy_nonoverlapping(&value as *const T as *const u8, bytes.as_mut_ptr(), core::mem::size_of::<T>());
+        }
+        // Keep zero values with tag 0 off the all-zero niche of `Option<Handle>`
+        (usize::from_le_bytes(bytes) | 1 << (super::ADDRESS_BITS - 1)) as *mut ()
+    }
+
+    /// Unpack an `#[inline_value]` variant from a handle's address bits
+    ///
+    /// # Safety
+    ///
+    /// `addr` must come from [`pack_inline`] for the same `T`. Bits above the value
+    /// are ignored, so the address may still carry its marker and tag.
+    #[inline(always)]
+    pub unsafe fn unpack_inline<T: super::InlineValue>(addr: usize) -> T {
+        let bytes = addr.to_le_bytes();
+        core::ptr::read_unaligned(bytes.as_ptr() as *const T)
+    }
+
+    /// Variants stored behind their handle's pointer
+    pub struct Pointed;
+
+    /// `#[inline_value]` variants, stored in their handle's address bits
+    pub struct Inline;
+
+    /// An `#[inline_value]` variant unpacked onto the stack, for dispatch to borrow
+    pub struct InlineRef<T>(T);
+
+    impl<T> core::ops::Deref for InlineRef<T> {
+        type Target = T;
+
+        #[inline(always)]
+        fn deref(&self) -> &T {
+            &self.0
+        }
+    }
+
+    /// Where a handle keeps its variant's value, so generated dispatch can read any
+    /// variant the same way.
+    ///
+    /// # Safety
+    ///
+    /// Implementations must read back exactly what the generated constructors stored.
+    pub unsafe trait VariantStorage<T> {
+        /// Whether the value lives in the address bits, with no allocation to check
+        const INLINE: bool;
+
+        /// A borrow of the value, or a copy for inline values
+        type Ref<'a>: core::ops::Deref<Target = T>
+        where
+            T: 'a;
+
+        /// Borrow the value at `addr`, the handle's `ptr()`
+        ///
+        /// # Safety
+        ///
+        /// `addr` must come from a live handle holding a `T`.
+        unsafe fn get<'a>(addr: usize) -> Self::Ref<'a>
+        where
+            T: 'a;
+
+        /// Move the value out of `addr`, the handle's `untagged_ptr()`, freeing
+        /// anything `A` allocated for it
+        ///
+        /// # Safety
+        ///
+        /// As for `get`, and the handle must not be used or dropped afterwards.
+        unsafe fn take<A: super::VariantAllocator<T>>(addr: usize) -> T;
+
+        /// Borrow the value for as long as the handle, given its `ptr()` and its
+        /// packed `bits`, which inline values are borrowed from in place
+        ///
+        /// # Safety
+        ///
+        /// As for `get`, with `bits` the handle's own `bits_ref()`.
+        unsafe fn borrow(addr: usize, bits: &usize) -> &T;
+
+        /// Run `f` on the value mutably, given the handle's `untagged_ptr()` and its
+        /// packed `bits`, or return `None` while `A` shares the value with other handles
+        ///
+        /// # Safety
+        ///
+        /// As for `take`, with `bits` the handle's own `bits_mut()`.
+        unsafe fn modify<A: super::VariantAllocator<T>, R>(addr: usize, bits: &mut usize, f: impl FnOnce(&mut T) -> R) -> Option<R>;
+    }
+
+    unsafe impl<T> VariantStorage<T> for Pointed {
+        const INLINE: bool = false;
+
+        type Ref<'a> = &'a T where T: 'a;
+
+        #[inline(always)]
+        unsafe fn get<'a>(addr: usize) -> Self::Ref<'a>
+        where
+            T: 'a,
+        {
+            &*(addr as *const T)
+        }
+
+        #[inline(always)]
+        unsafe fn take<A: super::VariantAllocator<T>>(addr: usize) -> T {
+            A::take(core::ptr::NonNull::new_unchecked(addr as *mut T))
+        }
+
+        #[inline(always)]
+        unsafe fn borrow(addr: usize, _bits: &usize) -> &T {
+            &*(addr as *const T)
+        }
+
+        #[inline(always)]
+        unsafe fn modify<A: super::VariantAllocator<T>, R>(addr: usize, _bits: &mut usize, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+            A::get_mut(core::ptr::NonNull::new_unchecked(addr as *mut T)).map(f)
+        }
+    }
+
+    /// Variants whose value is packed into the handle rather than allocated
+    pub trait PackedStorage<T>: VariantStorage<T> {
+        /// The bits a generated constructor stores for `value`
+        fn pack(value: T) -> *mut ();
+    }
+
+    impl<T: super::InlineValue> PackedStorage<T> for Inline {
+        #[inline(always)]
+        fn pack(value: T) -> *mut () {
+            pack_inline(value)
+        }
+    }
+
+    /// The `f64` variant of a `nan_box` enum, stored as the handle's bits
+    pub struct NanBoxedNumber;
+
+    unsafe impl VariantStorage<f64> for NanBoxedNumber {
+        const INLINE: bool = true;
+
+        type Ref<'a> = InlineRef<f64>;
+
+        #[inline(always)]
+        unsafe fn get<'a>(addr: usize) -> Self::Ref<'a> {
+            InlineRef(f64::from_bits(addr as u64))
+        }
+
+        #[inline(always)]
+        unsafe fn take<A: super::VariantAllocator<f64>>(addr: usize) -> f64 {
+            f64::from_bits(addr as u64)
+        }
+
+        #[inline(always)]
+        unsafe fn borrow(_addr: usize, bits: &usize) -> &f64 {
+            // A number's bits are the whole handle
+            &*(bits as *const usize as *const f64)
+        }
+
+        #[inline(always)]
+        unsafe fn modify<A: super::VariantAllocator<f64>, R>(_addr: usize, bits: &mut usize, f: impl FnOnce(&mut f64) -> R) -> Option<R> {
+            // Repack afterwards, since a NaN with a payload would read back as a pointer
+            let mut number = f64::from_bits(*bits as u64);
+            let result = f(&mut number);
+            *bits = super::NanBoxedPtr::<(), 0>::number_bits(number) as usize;
+            Some(result)
+        }
+    }
+
+    impl PackedStorage<f64> for NanBoxedNumber {
+        #[inline(always)]
+        fn pack(value: f64) -> *mut () {
+            super::NanBoxedPtr::<(), 0>::number_bits(value)
+        }
+    }
+
+    unsafe impl<T: super::InlineValue> VariantStorage<T> for Inline {
+        const INLINE: bool = true;
+
+        type Ref<'a> = InlineRef<T> where T: 'a;
+
+        #[inline(always)]
+        unsafe fn get<'a>(addr: usize) -> Self::Ref<'a>
+        where
+            T: 'a,
+        {
+            InlineRef(unpack_inline(addr))
+        }
+
+        #[inline(always)]
+        unsafe fn take<A: super::VariantAllocator<T>>(addr: usize) -> T {
+            unpack_inline(addr)
+        }
+
+        #[inline(always)]
+        unsafe fn borrow(_addr: usize, bits: &usize) -> &T {
+            // The value is the low-order bytes of the word, which come last on big-endian targets
+            let offset = if cfg!(target_endian = "big") {
+                core::mem::size_of::<usize>() - core::mem::size_of::<T>()
+            } else {
+                0
+            };
+            &*((bits as *const usize as *const u8).add(offset) as *const T)
+        }
+
+        #[inline(always)]
+        unsafe fn modify<A: super::VariantAllocator<T>, R>(addr: usize, bits: &mut usize, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+            // Only the value's own bytes change, leaving the marker and tag alone
+            let mut value = unpack_inline::<T>(addr);
+            let result = f(&mut value);
+            let offset = if cfg!(target_endian = "big") {
+                core::mem::size_of::<usize>() - core::mem::size_of::<T>()
+            } else {
+                0
+            };
+            core::ptr::write((bits as *mut usize as *mut u8).add(offset) as *mut T, value);
+            Some(result)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_pointer_auth_mode() {
+        // The feature always turns the mode on
+        const _: () = assert!(!cfg!(feature = "pac") || POINTER_AUTH);
+
+        // With pointer authentication the tag is always masked off
+        #[cfg(tagged_dispatch_pac)]
+        {
+            let mut value = 1u32;
+            let addr = &mut value as *mut u32;
+            let last = (TaggedPtr::<u32>::MAX_VARIANTS - 1) as u16;
+            assert_eq!(TaggedPtr::new(addr, last).ptr(), addr);
+        }
+    }
+
     #[test]
     fn test_tag_extraction() {
         let ptr = core::ptr::null_mut::<u32>();
-        let tagged = TaggedPtr::new(ptr, 127);
-        assert_eq!(tagged.tag(), 127);
+        let last = (TaggedPtr::<u32>::MAX_VARIANTS - 1) as u16;
+        let tagged = TaggedPtr::new(ptr, last);
+        assert_eq!(tagged.tag(), last);
 
         // On macOS ARM64 with TBI, the pointer retains the tag bits
         // because the hardware ignores them automatically
-        #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+        #[cfg(all(target_os = "macos", target_arch = "aarch64", not(tagged_dispatch_pac)))]
         {
             // The returned pointer should have the tag in the high byte
             let returned_ptr = tagged.ptr() as usize;
-            let expected = ptr as usize | (127usize << TaggedPtr::<u32>::TAG_SHIFT);
+            let expected = ptr as usize | ((last as usize) << TaggedPtr::<u32>::TAG_SHIFT);
             assert_eq!(returned_ptr, expected);
         }
 
-        #[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
+        #[cfg(not(all(target_os = "macos", target_arch = "aarch64", not(tagged_dispatch_pac))))]
         {
             assert_eq!(tagged.ptr(), ptr);
         }
@@ -315,19 +1987,19 @@ mod tests {
         let value = Box::new(42u32);
         let ptr = Box::into_raw(value);
 
-        for tag in 0..128u8 {
+        for tag in 0..TaggedPtr::<u32>::MAX_VARIANTS.min(128) as u16 {
             let tagged = TaggedPtr::new(ptr, tag);
             assert_eq!(tagged.tag(), tag);
 
             // On macOS ARM64 with TBI, the pointer retains the tag bits
-            #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+            #[cfg(all(target_os = "macos", target_arch = "aarch64", not(tagged_dispatch_pac)))]
             {
                 let returned_ptr = tagged.ptr() as usize;
                 let expected = ptr as usize | ((tag as usize) << TaggedPtr::<u32>::TAG_SHIFT);
                 assert_eq!(returned_ptr, expected);
             }
 
-            #[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
+            #[cfg(not(all(target_os = "macos", target_arch = "aarch64", not(tagged_dispatch_pac))))]
             {
                 assert_eq!(tagged.ptr(), ptr);
             }
@@ -341,12 +2013,173 @@ mod tests {
     fn test_size() {
         assert_eq!(core::mem::size_of::<TaggedPtr<()>>(), 8);
     }
-    
+
+    #[test]
+    fn test_spare_bit() {
+        let mut value = 7u32;
+        let addr = &mut value as *mut u32;
+        let last = (TaggedPtr::<u32>::MAX_VARIANTS - 1) as u16;
+        let ptr = TaggedPtr::new(addr, last);
+        let marked = ptr.with_spare_bit();
+
+        assert!(!ptr.spare_bit());
+        assert!(marked.spare_bit());
+        assert_eq!(marked.tag(), last);
+        assert_eq!(marked.untagged_ptr(), addr);
+        assert_eq!(unsafe { *marked.as_ref() }, 7);
+
+        let last = (WideTaggedPtr::<u32, 2>::MAX_VARIANTS - 1).min(u16::MAX as usize) as u16;
+        let wide = WideTaggedPtr::<u32, 2>::new(addr, last).with_spare_bit();
+        assert!(wide.spare_bit());
+        assert_eq!(wide.tag(), last);
+        assert_eq!(wide.ptr(), addr);
+    }
+
+    #[test]
+    fn test_untagged_ptr() {
+        let mut value = 7u32;
+        let ptr = UntaggedPtr::new(&mut value as *mut u32, 0);
+
+        assert_eq!(ptr.tag(), 0);
+        assert_eq!(ptr.ptr(), &mut value as *mut u32);
+        assert_eq!(core::mem::size_of::<UntaggedPtr<u32>>(), core::mem::size_of::<TaggedPtr<u32>>());
+        assert_eq!(UntaggedPtr::try_new(core::ptr::null_mut::<u32>(), 0), Err(TagError::NullPointer));
+    }
+
+    #[test]
+    fn test_option_niche() {
+        let word = core::mem::size_of::<usize>();
+        assert_eq!(core::mem::size_of::<Option<TaggedPtr<u32>>>(), word);
+        assert_eq!(core::mem::size_of::<Option<UntaggedPtr<u32>>>(), word);
+        assert_eq!(core::mem::size_of::<Option<WideTaggedPtr<u64, 3>>>(), word);
+        assert_eq!(core::mem::size_of::<Option<LowTaggedPtr<u64, 2>>>(), word);
+
+        // Only a null pointer with tag 0 would be all zero bits
+        let null = core::ptr::null_mut::<u64>();
+        assert!(TaggedPtr::new(null, 1).is_null());
+        assert_eq!(TaggedPtr::try_new(null, 0), Err(TagError::NullPointer));
+        assert_eq!(LowTaggedPtr::<u64, 2>::try_new(null, 0), Err(TagError::NullPointer));
+    }
+
+    #[test]
+    #[should_panic(expected = "null pointer with tag 0")]
+    fn test_null_untagged() {
+        let _tagged = TaggedPtr::new(core::ptr::null_mut::<u32>(), 0);
+    }
+
+    #[test]
+    fn test_wide_tagged_ptr() {
+        let mut value = 7u64;
+        let addr = &mut value as *mut u64;
+
+        let last = (WideTaggedPtr::<u64, 3>::MAX_VARIANTS - 1).min(u16::MAX as usize) as u16;
+        for tag in [0, 1, 7, 8, 300, last] {
+            let ptr = WideTaggedPtr::<u64, 3>::new(addr, tag);
+            assert_eq!(ptr.tag(), tag);
+            assert_eq!(ptr.ptr(), addr);
+            assert_eq!(unsafe { *ptr.as_ref() }, 7);
+        }
+
+        assert_eq!(WideTaggedPtr::<u64, 3>::MAX_VARIANTS, 1 << (DEFAULT_TAG_BITS + 3));
+        assert!(WideTaggedPtr::<u64, 3>::new(addr, 1) < WideTaggedPtr::<u64, 3>::new(addr, 8));
+        assert!(WideTaggedPtr::<u64, 3>::new(core::ptr::null_mut(), 5).is_null());
+    }
+
+    #[test]
+    fn test_low_tagged_ptr() {
+        let mut value = 7u64;
+        let addr = &mut value as *mut u64;
+
+        for tag in 0..8 {
+            let ptr = LowTaggedPtr::<u64, 3>::new(addr, tag);
+            assert_eq!(ptr.tag(), tag);
+            assert_eq!(ptr.ptr(), addr);
+            assert_eq!(unsafe { *ptr.as_ref() }, 7);
+        }
+
+        assert_eq!(LowTaggedPtr::<u64, 3>::MAX_VARIANTS, 8);
+        assert!(LowTaggedPtr::<u64, 2>::new(core::ptr::null_mut(), 3).is_null());
+    }
+
     #[test]
     #[should_panic(expected = "Tag must be less than 128")]
     fn test_tag_overflow() {
         let ptr = core::ptr::null_mut::<u32>();
-        let _tagged = TaggedPtr::new(ptr, 128);
+        let _tagged = TaggedPtr::<u32, 7>::from_parts(ptr, TaggedPtr::<u32, 7>::MAX_VARIANTS as u16);
+    }
+
+    #[test]
+    fn test_try_new() {
+        let mut value = 7u64;
+        let addr = &mut value as *mut u64;
+
+        assert_eq!(TaggedPtr::try_new(addr, 5).unwrap().tag(), 5);
+        // A 16-bit default tag takes every `u16`
+        if let Ok(max) = u16::try_from(TaggedPtr::<u64>::MAX_VARIANTS) {
+            assert_eq!(
+                TaggedPtr::try_new(addr, max),
+                Err(TagError::TagOutOfRange { tag: max, max_variants: max as usize })
+            );
+        }
+        let high = (addr as usize | 1 << 60) as *mut u64;
+        assert_eq!(TaggedPtr::try_new(high, 1), Err(TagError::AddressInUse { addr: high as usize }));
+
+        assert_eq!(WideTaggedPtr::<u64, 3>::try_new(addr, 500).unwrap().tag(), 500);
+        let misaligned = (addr as usize + 1) as *mut u64;
+        assert!(WideTaggedPtr::<u64, 3>::try_new(misaligned, 1).is_err());
+        assert!(LowTaggedPtr::<u64, 3>::try_new(misaligned, 1).is_err());
+        assert!(LowTaggedPtr::<u64, 3>::try_new(addr, 8).is_err());
+        assert!(UntaggedPtr::try_new(addr, 1).is_err());
+    }
+
+    #[test]
+    fn test_tag_widths() {
+        let mut value = 11u64;
+        let addr = &mut value as *mut u64;
+
+        let narrow = TaggedPtr::<u64, 3>::from_parts(addr, 7);
+        assert_eq!(TaggedPtr::<u64, 3>::MAX_VARIANTS, 8);
+        assert_eq!((narrow.tag(), narrow.untagged_ptr()), (7, addr));
+        assert_eq!(unsafe { *narrow.as_ref() }, 11);
+        // Bits 56-59 are outside a 3-bit tag
+        let high = (addr as usize | 1 << 57) as *mut u64;
+        assert!(TaggedPtr::<u64, 3>::fits(high));
+        assert!(!TaggedPtr::<u64>::fits(high));
+
+        let wide = TaggedPtr::<u64, 16>::from_parts(addr, u16::MAX).with_spare_bit();
+        assert_eq!(TaggedPtr::<u64, 16>::MAX_VARIANTS, 65536);
+        assert_eq!((wide.tag(), wide.ptr()), (u16::MAX, addr));
+        assert!(wide.spare_bit());
+        assert_eq!(
+            TaggedPtr::<u64, 3>::try_from_parts(addr, 8),
+            Err(TagError::TagOutOfRange { tag: 8, max_variants: 8 })
+        );
+    }
+
+    #[test]
+    #[cfg(any(debug_assertions, feature = "checked"))]
+    #[should_panic(expected = "Pointer already has high bits set")]
+    fn test_address_using_tag_bits() {
+        let ptr = (0xB4usize << 56 | 0x1000) as *mut u32;
+        assert!(!TaggedPtr::can_tag(ptr));
+        let _tagged = TaggedPtr::new(ptr, 1);
+    }
+
+    #[test]
+    #[cfg(any(debug_assertions, feature = "checked"))]
+    #[should_panic(expected = "Pointer already has high bits set")]
+    fn test_address_using_spare_bit() {
+        let ptr = (TaggedPtr::<u32>::SPARE_BIT | 0x1000) as *mut u32;
+        assert!(!TaggedPtr::can_tag(ptr));
+        assert!(TaggedPtr::try_new(ptr, 1).is_err());
+        let _tagged = TaggedPtr::new(ptr, 1);
+    }
+
+    #[test]
+    #[cfg(any(debug_assertions, feature = "checked"))]
+    #[should_panic(expected = "Pointer is under-aligned for its tag")]
+    fn test_under_aligned_low_tagged_ptr() {
+        let _tagged = LowTaggedPtr::<u64, 3>::new(0x1004 as *mut u64, 1);
     }
     
     #[cfg(feature = "bumpalo")]
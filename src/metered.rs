@@ -0,0 +1,286 @@
+//! Per-variant allocation accounting and budgets.
+//!
+//! [`Metered`] wraps another allocator and records, for every variant type it stores,
+//! how many values were allocated and released and how many bytes they occupy. A
+//! variant can be given a budget of live bytes; allocating past it panics, so a runaway
+//! variant is caught where it is created rather than by a global allocator.
+//!
+//! Arena builders meter allocations made through `with_metered_bumpalo`:
+//!
+//! ```rust,ignore
+//! let arena = Metered::new(Bump::new());
+//! arena.meter().set_budget::<Particle>(64 * 1024);
+//!
+//! let builder = ShapeArenaBuilder::with_metered_bumpalo(&arena);
+//! builder.circle(Circle { radius: 1.0 });
+//! assert_eq!(arena.meter().usage::<Circle>().allocations, 1);
+//! ```
+//!
+//! Owned variants opt in with `#[alloc_with(Metered<A>)]`, which stores the value
+//! through `A` and records it in the process-wide [`Meter::global`]:
+//!
+//! ```rust,ignore
+//! #[tagged_dispatch(Draw)]
+//! enum Shape {
+//!     #[alloc_with(Metered<BoxAllocator>)]
+//!     Circle,
+//!     Rectangle,
+//! }
+//!
+//! Meter::global().set_budget::<Circle>(1 << 20);
+//! ```
+//!
+//! Usage is keyed by [`core::any::type_name`], so enums sharing a variant type share
+//! its budget.
+
+use core::cell::UnsafeCell;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use crate::{TaggedAllocator, VariantAllocator};
+
+/// Allocation counts and sizes recorded for one variant type, or summed over all of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Values allocated
+    pub allocations: usize,
+    /// Values released again; arenas never release individual values
+    pub frees: usize,
+    /// Bytes allocated in total
+    pub bytes: usize,
+    /// Bytes allocated and not yet released
+    pub live_bytes: usize,
+}
+
+#[derive(Default)]
+struct Entry {
+    usage: Usage,
+    budget: Option<usize>,
+}
+
+/// Usage and budgets per variant type, shared between threads.
+pub struct Meter {
+    locked: AtomicBool,
+    entries: UnsafeCell<BTreeMap<&'static str, Entry>>,
+}
+
+// SAFETY: `entries` is only accessed while holding `locked`
+unsafe impl Sync for Meter {}
+
+static GLOBAL: Meter = Meter::new();
+
+impl Meter {
+    /// Create a meter with no usage and no budgets.
+    pub const fn new() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            entries: UnsafeCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// The meter used by `#[alloc_with(Metered<A>)]` variants of owned enums.
+    pub fn global() -> &'static Meter {
+        &GLOBAL
+    }
+
+    /// Limit the live bytes of `T` values; allocating beyond it panics.
+    pub fn set_budget<T>(&self, max_live_bytes: usize) {
+        self.with_entry::<T, _>(|entry| entry.budget = Some(max_live_bytes));
+    }
+
+    /// Remove the budget of `T`.
+    pub fn clear_budget<T>(&self) {
+        self.with_entry::<T, _>(|entry| entry.budget = None);
+    }
+
+    /// The usage recorded for `T`.
+    pub fn usage<T>(&self) -> Usage {
+        self.with_entries(|entries| {
+            entries
+                .get(core::any::type_name::<T>())
+                .map(|entry| entry.usage)
+                .unwrap_or_default()
+        })
+    }
+
+    /// The usage summed over every variant type.
+    pub fn total(&self) -> Usage {
+        self.with_entries(|entries| {
+            entries.values().fold(Usage::default(), |total, entry| Usage {
+                allocations: total.allocations + entry.usage.allocations,
+                frees: total.frees + entry.usage.frees,
+                bytes: total.bytes + entry.usage.bytes,
+                live_bytes: total.live_bytes + entry.usage.live_bytes,
+            })
+        })
+    }
+
+    /// Forget all recorded usage while keeping budgets, e.g. after resetting an arena.
+    pub fn reset(&self) {
+        self.with_entries(|entries| {
+            for entry in entries.values_mut() {
+                entry.usage = Usage::default();
+            }
+        });
+    }
+
+    /// Record the allocation of one `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocation would take `T` past its budget. Nothing is recorded then.
+    pub fn record_alloc<T>(&self) {
+        let size = core::mem::size_of::<T>();
+        let exceeded = self.with_entry::<T, _>(|entry| match entry.budget {
+            Some(budget) if entry.usage.live_bytes + size > budget => Some(budget),
+            _ => {
+                entry.usage.allocations += 1;
+                entry.usage.bytes += size;
+                entry.usage.live_bytes += size;
+                None
+            }
+        });
+
+        if let Some(budget) = exceeded {
+            panic!(
+                "allocation budget of {} bytes exceeded for `{}`",
+                budget,
+                core::any::type_name::<T>()
+            );
+        }
+    }
+
+    /// Record the release of one `T`.
+    pub fn record_free<T>(&self) {
+        let size = core::mem::size_of::<T>();
+        self.with_entry::<T, _>(|entry| {
+            entry.usage.frees += 1;
+            entry.usage.live_bytes = entry.usage.live_bytes.saturating_sub(size);
+        });
+    }
+
+    fn with_entry<T, R>(&self, f: impl FnOnce(&mut Entry) -> R) -> R {
+        self.with_entries(|entries| f(entries.entry(core::any::type_name::<T>()).or_default()))
+    }
+
+    fn with_entries<R>(&self, f: impl FnOnce(&mut BTreeMap<&'static str, Entry>) -> R) -> R {
+        struct Unlock<'a>(&'a AtomicBool);
+
+        impl Drop for Unlock<'_> {
+            fn drop(&mut self) {
+                self.0.store(false, Ordering::Release);
+            }
+        }
+
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        let _unlock = Unlock(&self.locked);
+
+        // SAFETY: holding `locked` gives exclusive access to `entries`
+        f(unsafe { &mut *self.entries.get() })
+    }
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for Meter {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.with_entries(|entries| {
+            f.debug_map()
+                .entries(entries.iter().map(|(name, entry)| (name, &entry.usage)))
+                .finish()
+        })
+    }
+}
+
+/// An allocator decorator that records every allocation in a [`Meter`].
+///
+/// As a [`TaggedAllocator`] it records into its own meter; as a [`VariantAllocator`]
+/// for `#[alloc_with(Metered<A>)]` it records into [`Meter::global`].
+#[derive(Debug, Default)]
+pub struct Metered<A> {
+    inner: A,
+    meter: Meter,
+}
+
+impl<A> Metered<A> {
+    /// Wrap `inner` with an empty meter.
+    pub const fn new(inner: A) -> Self {
+        Self { inner, meter: Meter::new() }
+    }
+
+    /// The meter recording this allocator's allocations.
+    pub fn meter(&self) -> &Meter {
+        &self.meter
+    }
+
+    /// The wrapped allocator.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// The wrapped allocator, e.g. to reset an arena. Call [`Meter::reset`] alongside.
+    pub fn inner_mut(&mut self) -> &mut A {
+        &mut self.inner
+    }
+
+    /// Unwrap the allocator, discarding the meter.
+    pub fn into_inner(self) -> A {
+        self.inner
+    }
+}
+
+impl<A: TaggedAllocator> TaggedAllocator for Metered<A> {
+    #[inline]
+    fn alloc<T>(&self, value: T) -> *mut T {
+        self.meter.record_alloc::<T>();
+        self.inner.alloc(value)
+    }
+}
+
+// SAFETY: storage comes from `A` unchanged
+unsafe impl<T, A: VariantAllocator<T>> VariantAllocator<T> for Metered<A> {
+    #[inline]
+    fn alloc(value: T) -> NonNull<T> {
+        GLOBAL.record_alloc::<T>();
+        A::alloc(value)
+    }
+
+    #[inline]
+    fn try_alloc(value: T) -> Result<NonNull<T>, T> {
+        let ptr = A::try_alloc(value)?;
+        GLOBAL.record_alloc::<T>();
+        Ok(ptr)
+    }
+
+    #[inline]
+    unsafe fn take(ptr: NonNull<T>) -> T {
+        GLOBAL.record_free::<T>();
+        A::take(ptr)
+    }
+
+    #[inline]
+    unsafe fn free(ptr: NonNull<T>) {
+        GLOBAL.record_free::<T>();
+        A::free(ptr)
+    }
+
+    #[inline]
+    unsafe fn get_mut<'a>(ptr: NonNull<T>) -> Option<&'a mut T> {
+        A::get_mut(ptr)
+    }
+}
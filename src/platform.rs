@@ -0,0 +1,189 @@
+//! Startup checks that the high address bits tags are stored in are free.
+//!
+//! `TaggedPtr` and `WideTaggedPtr` assume user-space addresses never use bits
+//! [`ADDRESS_BITS`] and up, 56 by default. That holds for 4-level paging and for
+//! Linux 5-level paging, which still gives user space at most 56 address bits, but
+//! not once the hardware or allocator starts putting metadata in the top byte:
+//!
+//! - Intel Linear Address Masking (LAM), enabled per process on Linux, lets
+//!   pointers carry metadata in bits 57-62
+//! - Arm Memory Tagging (MTE), and allocators that tag heap pointers in the top
+//!   byte like Android's, hand out addresses that already have those bits set
+//!
+//! Handles built from such pointers would lose the metadata when their tag is
+//! stored. [`verify_platform`] detects these setups so a program can refuse to start,
+//! or fall back to `tag = "low"` enums, which only use alignment bits:
+//!
+//! ```rust
+//! if let Err(err) = tagged_dispatch::verify_platform() {
+//!     panic!("high-bit tagging is unusable here: {}", err);
+//! }
+//! ```
+//!
+//! The tag position itself is fixed at compile time: building with
+//! `TAGGED_DISPATCH_ADDRESS_BITS=N` moves it above bit `N`, trading tag bits for
+//! address bits, and [`verify_platform`] then also rejects systems whose address
+//! space is wider than `N` bits. Separately, the `checked` feature makes the pointer
+//! constructors reject addresses that use the tag bits in release builds too, rather
+//! than only under `debug_assertions`.
+
+use core::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+
+use crate::{TaggedPtr, ADDRESS_BITS};
+
+/// Why high-bit tagging is unsafe on the current system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlatformError {
+    /// Linear Address Masking is enabled for this process, so pointers may carry
+    /// metadata in the tag bits
+    AddressMasking {
+        /// The process's untag mask, with the bits LAM ignores cleared
+        untag_mask: u64,
+    },
+    /// An address from the allocator or the stack already uses the tag bits, as
+    /// with memory tagging or a top-byte-tagging allocator
+    TaggedAddress {
+        /// The offending address
+        addr: usize,
+    },
+    /// The system can map user-space addresses wider than [`ADDRESS_BITS`], as with
+    /// 5-level paging when the crate was built for fewer address bits
+    AddressSpace {
+        /// How many address bits the system may hand out
+        address_bits: u32,
+    },
+}
+
+impl fmt::Display for PlatformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlatformError::AddressMasking { untag_mask } => {
+                write!(f, "linear address masking is enabled (untag mask {:#x})", untag_mask)
+            }
+            PlatformError::TaggedAddress { addr } => {
+                write!(f, "address {:#x} already uses the pointer tag bits", addr)
+            }
+            PlatformError::AddressSpace { address_bits } => write!(
+                f,
+                "the system uses {}-bit addresses, but tags are laid out for {} bits",
+                address_bits, ADDRESS_BITS
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PlatformError {}
+
+/// Check that this process can store tags in the high address bits.
+///
+/// Looks for address masking and address spaces wider than [`ADDRESS_BITS`] where
+/// the OS reports them, and probes a heap and a stack address for bits already in
+/// use. Low-bit enums (`tag = "low"`) are unaffected by any of these and don't need
+/// this check.
+pub fn verify_platform() -> Result<(), PlatformError> {
+    check_address_masking()?;
+    check_address_space()?;
+
+    let heap = Box::new(0u64);
+    check_addr(&*heap)?;
+    let stack = 0u64;
+    check_addr(&stack)
+}
+
+fn check_addr(ptr: *const u64) -> Result<(), PlatformError> {
+    if TaggedPtr::can_tag(ptr) {
+        Ok(())
+    } else {
+        Err(PlatformError::TaggedAddress { addr: ptr as usize })
+    }
+}
+
+/// Read the untag mask Linux reports for x86-64 processes with LAM support
+#[cfg(all(feature = "std", target_os = "linux", target_arch = "x86_64"))]
+fn check_address_masking() -> Result<(), PlatformError> {
+    // Kernels without LAM support don't report a mask, which means no masking
+    let Ok(status) = std::fs::read_to_string("/proc/self/status") else {
+        return Ok(());
+    };
+    let untag_mask = status
+        .lines()
+        .find_map(|line| line.strip_prefix("untag_mask:"))
+        .and_then(|mask| u64::from_str_radix(mask.trim().trim_start_matches("0x"), 16).ok())
+        .unwrap_or(u64::MAX);
+
+    if untag_mask == u64::MAX {
+        Ok(())
+    } else {
+        Err(PlatformError::AddressMasking { untag_mask })
+    }
+}
+
+#[cfg(not(all(feature = "std", target_os = "linux", target_arch = "x86_64")))]
+fn check_address_masking() -> Result<(), PlatformError> {
+    Ok(())
+}
+
+/// Linux lists `la57` among the CPU flags when it runs with 5-level paging, which
+/// lets user space map addresses up to bit 55 instead of bit 46
+#[cfg(all(feature = "std", target_os = "linux", target_arch = "x86_64"))]
+fn check_address_space() -> Result<(), PlatformError> {
+    let Ok(cpuinfo) = std::fs::read_to_string("/proc/cpuinfo") else {
+        return Ok(());
+    };
+    let la57 = cpuinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("flags"))
+        .is_some_and(|flags| flags.split_whitespace().any(|flag| flag == "la57"));
+    check_address_bits(if la57 { 56 } else { 47 })
+}
+
+#[cfg(not(all(feature = "std", target_os = "linux", target_arch = "x86_64")))]
+fn check_address_space() -> Result<(), PlatformError> {
+    Ok(())
+}
+
+#[cfg_attr(not(all(feature = "std", target_os = "linux", target_arch = "x86_64")), allow(dead_code))]
+fn check_address_bits(address_bits: u32) -> Result<(), PlatformError> {
+    if address_bits <= ADDRESS_BITS {
+        Ok(())
+    } else {
+        Err(PlatformError::AddressSpace { address_bits })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_verify_platform() {
+        // The test hosts tag pointers fine; anything else would break the whole suite
+        assert_eq!(verify_platform(), Ok(()));
+    }
+
+    #[test]
+    fn test_tagged_address_detected() {
+        let addr = 0x7f00_0000_1000usize | (0xB4 << 56);
+        assert_eq!(check_addr(addr as *const u64), Err(PlatformError::TaggedAddress { addr }));
+        assert_eq!(
+            PlatformError::TaggedAddress { addr }.to_string(),
+            "address 0xb4007f0000001000 already uses the pointer tag bits"
+        );
+    }
+
+    #[test]
+    fn test_address_space_width() {
+        assert_eq!(check_address_bits(47), Ok(()));
+        assert_eq!(check_address_bits(ADDRESS_BITS), Ok(()));
+        assert_eq!(
+            check_address_bits(ADDRESS_BITS + 1),
+            Err(PlatformError::AddressSpace { address_bits: ADDRESS_BITS + 1 })
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! Values of types registered at runtime with an `open` enum.
+//!
+//! An enum with the `open` flag gets a `Plugin` variant holding a `<Enum>Plugin`: a
+//! boxed value of any type registered with the generated `<Enum>Registry`, with a
+//! pointer to its [`PluginType`]. Calls on the variant go through the function
+//! pointers of that table, so crates loaded after the enum was written can add their
+//! own implementations without falling back to `Box<dyn Trait>` everywhere:
+//!
+//! ```rust,ignore
+//! #[tagged_dispatch(Draw, open)]
+//! enum Shape {
+//!     Circle,
+//!     Rectangle,
+//! }
+//!
+//! // At startup, in the plugin
+//! ShapeRegistry::register::<Hexagon>()?;
+//!
+//! let shape = Shape::plugin(ShapePlugin::new(Hexagon::new(2.0)).ok().unwrap());
+//! shape.draw();
+//! ```
+//!
+//! Registered types are kept in a lock-free list per enum, and registering a type again
+//! has no effect. Every plugin value shares the `Plugin` tag and is told apart by its
+//! table, keyed by `TypeId`, so plugins never claim tags of their own and can't collide.
+//!
+//! A plugin built against another version of the enum, e.g. a dynamic library compiled
+//! before a variant or trait was added, would otherwise construct handles with stale
+//! tags or register tables of the wrong shape. Each registry records the [`Abi`] it was
+//! built with, and registering compares it with the caller's, so a stale plugin fails
+//! with [`RegisterError::AbiMismatch`] when it registers instead of misdispatching
+//! later.
+
+use core::any::TypeId;
+use core::fmt;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::Box;
+
+/// Version of the [`PluginType`] table layout, changed whenever the tables change shape
+pub const ABI_VERSION: u32 = 1;
+
+/// What a plugin table was built for: the table layout and a fingerprint of the enum's
+/// variants and dispatched traits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Abi {
+    /// The [`ABI_VERSION`] of the `tagged_dispatch` build
+    pub version: u32,
+    /// A hash of the enum's name, variants, and dispatched traits, computed by the macro
+    pub fingerprint: u64,
+}
+
+impl Abi {
+    /// The ABI of an enum with this fingerprint, built with this version of the crate
+    pub const fn new(fingerprint: u64) -> Self {
+        Self { version: ABI_VERSION, fingerprint }
+    }
+}
+
+/// Why a type couldn't be registered with an `open` enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterError {
+    /// The registering code was built against a different version of the enum or of
+    /// `tagged_dispatch` than the registry
+    AbiMismatch {
+        /// The registry's ABI
+        expected: Abi,
+        /// The registering code's ABI
+        found: Abi,
+    },
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisterError::AbiMismatch { expected, found } => write!(
+                f,
+                "plugin built for ABI {}/{:#018x}, but the registry expects {}/{:#018x}",
+                found.version, found.fingerprint, expected.version, expected.fingerprint,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RegisterError {}
+
+/// The function-pointer table of a type registered with an `open` enum.
+///
+/// `C` holds one pointer per dispatched trait, turning a pointer to the value into a
+/// trait object.
+pub struct PluginType<C: 'static> {
+    type_id: fn() -> TypeId,
+    type_name: &'static str,
+    clone: unsafe fn(NonNull<()>) -> NonNull<()>,
+    drop: unsafe fn(NonNull<()>),
+    casts: C,
+    next: *const PluginType<C>,
+}
+
+// SAFETY: a published table is never modified, and only holds function pointers
+// besides `casts`
+unsafe impl<C: Sync> Sync for PluginType<C> {}
+
+impl<C: 'static> PluginType<C> {
+    fn new<T: Clone + 'static>(casts: C) -> Self {
+        unsafe fn clone_value<T: Clone>(value: NonNull<()>) -> NonNull<()> {
+            NonNull::from(Box::leak(Box::new(value.cast::<T>().as_ref().clone()))).cast()
+        }
+        unsafe fn drop_value<T>(value: NonNull<()>) {
+            drop(Box::from_raw(value.cast::<T>().as_ptr()));
+        }
+        Self {
+            type_id: TypeId::of::<T>,
+            type_name: core::any::type_name::<T>(),
+            clone: clone_value::<T>,
+            drop: drop_value::<T>,
+            casts,
+            next: ptr::null(),
+        }
+    }
+
+    /// The registered type's name, from [`core::any::type_name`]
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Whether this is the table of `T`
+    pub fn is<T: 'static>(&self) -> bool {
+        (self.type_id)() == TypeId::of::<T>()
+    }
+
+    /// The trait object casts
+    pub fn casts(&self) -> &C {
+        &self.casts
+    }
+}
+
+impl<C: 'static> fmt::Debug for PluginType<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PluginType").field("type_name", &self.type_name).finish_non_exhaustive()
+    }
+}
+
+/// A boxed value of a registered type, with the table its calls go through.
+pub struct Plugin<C: 'static> {
+    ty: &'static PluginType<C>,
+    value: NonNull<()>,
+}
+
+impl<C: 'static> Plugin<C> {
+    /// Box `value` as the type `ty` describes, or give it back if `ty` is another type's
+    pub fn new<T: 'static>(ty: &'static PluginType<C>, value: T) -> Result<Self, T> {
+        if !ty.is::<T>() {
+            return Err(value);
+        }
+        Ok(Self {
+            ty,
+            value: NonNull::from(Box::leak(Box::new(value))).cast(),
+        })
+    }
+
+    /// The table of the value's type
+    pub fn plugin_type(&self) -> &'static PluginType<C> {
+        self.ty
+    }
+
+    /// The boxed value, for the casts in [`PluginType::casts`]
+    pub fn as_ptr(&self) -> NonNull<()> {
+        self.value
+    }
+
+    /// Borrow the value if it is a `T`
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        // SAFETY: the value is a `T` when the table is `T`'s
+        self.ty.is::<T>().then(|| unsafe { self.value.cast::<T>().as_ref() })
+    }
+
+    /// Borrow the value mutably if it is a `T`
+    pub fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        // SAFETY: as in `downcast_ref`, and `self` owns the box
+        self.ty.is::<T>().then(|| unsafe { self.value.cast::<T>().as_mut() })
+    }
+}
+
+impl<C: 'static> Clone for Plugin<C> {
+    fn clone(&self) -> Self {
+        Self {
+            ty: self.ty,
+            // SAFETY: `value` is a live box of the table's type
+            value: unsafe { (self.ty.clone)(self.value) },
+        }
+    }
+}
+
+impl<C: 'static> Drop for Plugin<C> {
+    fn drop(&mut self) {
+        // SAFETY: `value` is a live box of the table's type, released only here
+        unsafe { (self.ty.drop)(self.value) }
+    }
+}
+
+impl<C: 'static> fmt::Debug for Plugin<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Plugin").field(&self.ty.type_name).finish()
+    }
+}
+
+/// The types registered with one `open` enum, as a list only ever added to.
+pub struct PluginRegistry<C: 'static> {
+    abi: Abi,
+    head: AtomicPtr<PluginType<C>>,
+}
+
+// SAFETY: entries are published through `head` and immutable afterwards
+unsafe impl<C: Sync> Sync for PluginRegistry<C> {}
+
+impl<C: 'static> PluginRegistry<C> {
+    /// Create a registry with no types, accepting registrations built for `abi`.
+    pub const fn new(abi: Abi) -> Self {
+        Self { abi, head: AtomicPtr::new(ptr::null_mut()) }
+    }
+
+    /// The ABI registrations must be built for
+    pub fn abi(&self) -> Abi {
+        self.abi
+    }
+
+    /// Register `T` from code built for `abi`, returning its table; `casts` is dropped if
+    /// `T` is already registered
+    pub fn register<T: Clone + 'static>(&self, abi: Abi, casts: C) -> Result<&'static PluginType<C>, RegisterError> {
+        if abi != self.abi {
+            return Err(RegisterError::AbiMismatch { expected: self.abi, found: abi });
+        }
+        if let Some(ty) = self.get::<T>() {
+            return Ok(ty);
+        }
+        let ty = Box::into_raw(Box::new(PluginType::new::<T>(casts)));
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            // SAFETY: `ty` isn't published until the exchange succeeds
+            unsafe { (*ty).next = head };
+            match self.head.compare_exchange_weak(head, ty, Ordering::AcqRel, Ordering::Acquire) {
+                // SAFETY: published entries are leaked and never modified
+                Ok(_) => return Ok(unsafe { &*ty }),
+                Err(current) => {
+                    head = current;
+                    // Another thread may have registered `T` in the meantime
+                    if let Some(existing) = self.get::<T>() {
+                        // SAFETY: `ty` was never published
+                        drop(unsafe { Box::from_raw(ty) });
+                        return Ok(existing);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The table of `T`, if it is registered
+    pub fn get<T: 'static>(&self) -> Option<&'static PluginType<C>> {
+        self.iter().find(|ty| ty.is::<T>())
+    }
+
+    /// Every registered type, most recently registered first
+    pub fn iter(&self) -> impl Iterator<Item = &'static PluginType<C>> {
+        // SAFETY: published entries are leaked and never modified
+        let head = unsafe { self.head.load(Ordering::Acquire).as_ref() };
+        core::iter::successors(head, |ty| unsafe { ty.next.as_ref() })
+    }
+}
+
+impl<C: 'static> fmt::Debug for PluginRegistry<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter().map(PluginType::type_name)).finish()
+    }
+}
@@ -0,0 +1,205 @@
+//! An inventory of every tagged enum linked into the program.
+//!
+//! With the `registry` feature, each `#[tagged_dispatch]` enum places an [`EnumInfo`]
+//! in a linker section, so [`registry()`] lists them all at runtime without any
+//! registration code, e.g. to audit where handles exist and how big their variants are:
+//!
+//! ```rust,ignore
+//! for info in tagged_dispatch::registry() {
+//!     println!("{}", info);
+//! }
+//! ```
+//!
+//! Collection works on ELF targets (Linux, Android, the BSDs) and Apple targets; on
+//! others [`registry()`] is empty.
+
+use core::fmt;
+
+/// How an enum's handle stores its tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagLayout {
+    /// A single variant, stored as an untagged pointer
+    Untagged,
+    /// The default 7-bit tag in the top of the address
+    High,
+    /// A `tag_bits = bits` tag split between the top and the low alignment bits
+    Wide {
+        /// The tag width
+        bits: u32,
+    },
+    /// A `tag = "low"` tag in the low alignment bits
+    Low {
+        /// The tag width
+        bits: u32,
+    },
+    /// A `nan_box` enum, with numbers stored inline and pointers in NaN payloads
+    NanBox,
+}
+
+impl fmt::Display for TagLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagLayout::Untagged => write!(f, "untagged"),
+            TagLayout::High => write!(f, "high 7-bit"),
+            TagLayout::Wide { bits } => write!(f, "wide {}-bit", bits),
+            TagLayout::Low { bits } => write!(f, "low {}-bit", bits),
+            TagLayout::NanBox => write!(f, "NaN-boxed"),
+        }
+    }
+}
+
+/// A variant of a registered enum
+#[derive(Debug)]
+pub struct VariantInfo {
+    /// The variant name
+    pub name: &'static str,
+    /// The variant type as written in the enum
+    pub type_name: &'static str,
+    /// `size_of` the variant type
+    pub size: usize,
+    /// `align_of` the variant type
+    pub align: usize,
+}
+
+/// A tagged enum generated in this program
+#[derive(Debug)]
+pub struct EnumInfo {
+    /// The enum name
+    pub name: &'static str,
+    /// The module the enum is declared in
+    pub module_path: &'static str,
+    /// Whether handles own their value rather than borrowing it from an arena
+    pub owned: bool,
+    /// How the tag is stored
+    pub layout: TagLayout,
+    /// `size_of` the handle
+    pub handle_size: usize,
+    /// Every variant, in tag order
+    pub variants: &'static [VariantInfo],
+}
+
+impl EnumInfo {
+    /// The size of the largest variant type
+    pub fn max_variant_size(&self) -> usize {
+        self.variants.iter().map(|variant| variant.size).max().unwrap_or(0)
+    }
+}
+
+impl fmt::Display for EnumInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}::{} ({}, {} tag, {} variants, largest {} bytes)",
+            self.module_path,
+            self.name,
+            if self.owned { "owned" } else { "arena" },
+            self.layout,
+            self.variants.len(),
+            self.max_variant_size(),
+        )
+    }
+}
+
+/// Every tagged enum linked into the program, in no particular order
+pub fn registry() -> &'static [&'static EnumInfo] {
+    imp::entries()
+}
+
+/// Write one line per registered enum, each followed by its variants in tag order.
+pub fn write_report(out: &mut impl fmt::Write) -> fmt::Result {
+    for info in registry() {
+        writeln!(out, "{}", info)?;
+        for variant in info.variants {
+            writeln!(
+                out,
+                "    {}({}): {} bytes, align {}",
+                variant.name, variant.type_name, variant.size, variant.align
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Place a `&'static EnumInfo` static in the registry section
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_enum {
+    ($item:item) => {
+        #[used]
+        #[cfg_attr(
+            any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"),
+            link_section = "tagged_dispatch_registry"
+        )]
+        #[cfg_attr(any(target_os = "macos", target_os = "ios"), link_section = "__DATA,__tgdregistry")]
+        $item
+    };
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"))]
+mod imp {
+    use super::EnumInfo;
+
+    // The linker defines these around the section's contents
+    extern "Rust" {
+        #[link_name = "__start_tagged_dispatch_registry"]
+        static START: [&'static EnumInfo; 0];
+        #[link_name = "__stop_tagged_dispatch_registry"]
+        static STOP: [&'static EnumInfo; 0];
+    }
+
+    // Makes sure the section exists even when no enum is registered
+    crate::__register_enum! {
+        static EMPTY: [&EnumInfo; 0] = [];
+    }
+
+    pub(super) fn entries() -> &'static [&'static EnumInfo] {
+        core::hint::black_box(&EMPTY);
+        unsafe {
+            let start = START.as_ptr();
+            let len = STOP.as_ptr().offset_from(start) as usize;
+            core::slice::from_raw_parts(start, len)
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod imp {
+    use super::EnumInfo;
+
+    extern "Rust" {
+        #[link_name = "\x01section$start$__DATA$__tgdregistry"]
+        static START: [&'static EnumInfo; 0];
+        #[link_name = "\x01section$end$__DATA$__tgdregistry"]
+        static STOP: [&'static EnumInfo; 0];
+    }
+
+    crate::__register_enum! {
+        static EMPTY: [&EnumInfo; 0] = [];
+    }
+
+    pub(super) fn entries() -> &'static [&'static EnumInfo] {
+        core::hint::black_box(&EMPTY);
+        unsafe {
+            let start = START.as_ptr();
+            let len = STOP.as_ptr().offset_from(start) as usize;
+            core::slice::from_raw_parts(start, len)
+        }
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "macos",
+    target_os = "ios"
+)))]
+mod imp {
+    use super::EnumInfo;
+
+    pub(super) fn entries() -> &'static [&'static EnumInfo] {
+        &[]
+    }
+}
@@ -0,0 +1,42 @@
+//! Storing tagged handles in [`slotmap`] containers.
+//!
+//! With the `slotmap` feature, every `#[tagged_dispatch]` enum `Shape` also gets the
+//! aliases `ShapeMap<K = DefaultKey>` for `SlotMap<K, Shape>` and
+//! `ShapeSecondaryMap<K = DefaultKey>` for `SecondaryMap<K, Shape>`. Handles are
+//! stored as they are, so dispatch works straight through indexing:
+//!
+//! ```rust,ignore
+//! use tagged_dispatch::slot_map::SlotMapExt;
+//!
+//! let mut shapes = ShapeMap::new();
+//! let key = shapes.insert_variant(Circle { radius: 1.0 });
+//! shapes[key].draw();
+//! ```
+
+use slotmap::{Key, SecondaryMap, SlotMap};
+
+/// Insert variant values into a slotmap of handles without naming the enum.
+pub trait SlotMapExt<K: Key, V> {
+    /// Wrap `value` in a handle and insert it, returning its key.
+    fn insert_variant<T: Into<V>>(&mut self, value: T) -> K;
+}
+
+impl<K: Key, V> SlotMapExt<K, V> for SlotMap<K, V> {
+    #[inline]
+    fn insert_variant<T: Into<V>>(&mut self, value: T) -> K {
+        self.insert(value.into())
+    }
+}
+
+/// Attach variant values to existing keys in a secondary map of handles.
+pub trait SecondaryMapExt<K: Key, V> {
+    /// Wrap `value` in a handle and store it under `key`, returning the handle it replaced.
+    fn insert_variant<T: Into<V>>(&mut self, key: K, value: T) -> Option<V>;
+}
+
+impl<K: Key, V> SecondaryMapExt<K, V> for SecondaryMap<K, V> {
+    #[inline]
+    fn insert_variant<T: Into<V>>(&mut self, key: K, value: T) -> Option<V> {
+        self.insert(key, value.into())
+    }
+}
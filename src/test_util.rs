@@ -0,0 +1,182 @@
+//! Conformance checks for tagged enums, for use in downstream test suites.
+//!
+//! With the `test-util` feature, every `#[tagged_dispatch]` enum implements
+//! [`TaggedEnum`], and the assertions here check the invariants any generated type
+//! must uphold. Pass one value of every variant:
+//!
+//! ```rust,ignore
+//! use tagged_dispatch::test_util;
+//!
+//! #[test]
+//! fn shape_conformance() {
+//!     test_util::assert_conformance(&[
+//!         Shape::circle(Circle { radius: 1.0 }),
+//!         Shape::rectangle(Rectangle { width: 2.0, height: 3.0 }),
+//!     ]);
+//! }
+//! ```
+//!
+//! Dropping is checked with [`assert_drops_once`], which needs variant types that
+//! carry a [`DropToken`], e.g. test-only fixtures.
+
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Introspection implemented by every generated enum when the `test-util` feature is enabled.
+pub trait TaggedEnum: Sized {
+    /// The generated `<Enum>Type`
+    type Type: Copy + Eq + Debug + 'static;
+
+    /// Every variant, in tag order
+    const VARIANTS: &'static [Self::Type];
+
+    /// Whether handles own their value rather than borrowing it from an arena
+    const OWNED: bool;
+
+    /// The variant type of this value
+    fn tag_type(&self) -> Self::Type;
+
+    /// The raw tag stored in the pointer
+    fn tag(&self) -> u16;
+
+    /// The address of the value, without its tag
+    fn value_ptr(&self) -> *const ();
+}
+
+/// Check that every value's tag matches its variant type and points at a value, and
+/// that `values` covers every variant.
+///
+/// # Panics
+///
+/// Panics describing the first violation found.
+pub fn assert_tag_round_trip<E: TaggedEnum>(values: &[E]) {
+    let mut seen = BTreeSet::new();
+
+    for value in values {
+        let tag = value.tag();
+        let variant = E::VARIANTS.get(tag as usize).unwrap_or_else(|| {
+            panic!("tag {} is out of range for {} variants", tag, E::VARIANTS.len())
+        });
+        assert_eq!(*variant, value.tag_type(), "tag {} does not round-trip to its variant", tag);
+        assert!(!value.value_ptr().is_null(), "{:?} holds a null pointer", variant);
+        seen.insert(tag);
+    }
+
+    for (tag, variant) in E::VARIANTS.iter().enumerate() {
+        assert!(seen.contains(&(tag as u16)), "no value of variant {:?} was given", variant);
+    }
+}
+
+/// Check that clones keep their variant, and that owned clones hold their own copy of
+/// the value while arena clones share it.
+///
+/// # Panics
+///
+/// Panics describing the first violation found.
+pub fn assert_clone_independent<E: TaggedEnum + Clone>(values: &[E]) {
+    for value in values {
+        let clone = value.clone();
+        assert_eq!(clone.tag_type(), value.tag_type(), "clone changed the variant");
+
+        if E::OWNED {
+            assert_ne!(
+                clone.value_ptr(),
+                value.value_ptr(),
+                "clone of {:?} shares its value",
+                value.tag_type()
+            );
+        } else {
+            assert_eq!(
+                clone.value_ptr(),
+                value.value_ptr(),
+                "arena clone of {:?} copied its value",
+                value.tag_type()
+            );
+        }
+
+        drop(clone);
+        assert_eq!(E::VARIANTS[value.tag() as usize], value.tag_type(), "dropping a clone corrupted the original");
+    }
+}
+
+/// Check that handles are exactly one pointer in size and alignment.
+///
+/// # Panics
+///
+/// Panics if either differs from `usize`.
+pub fn assert_size_invariants<E: TaggedEnum>() {
+    assert_eq!(core::mem::size_of::<E>(), core::mem::size_of::<usize>(), "handle is not pointer-sized");
+    assert_eq!(core::mem::align_of::<E>(), core::mem::align_of::<usize>(), "handle is not pointer-aligned");
+}
+
+/// Run [`assert_tag_round_trip`], [`assert_clone_independent`], and
+/// [`assert_size_invariants`] over one value of every variant.
+pub fn assert_conformance<E: TaggedEnum + Clone>(values: &[E]) {
+    assert_size_invariants::<E>();
+    assert_tag_round_trip(values);
+    assert_clone_independent(values);
+}
+
+/// Counts how many of its [`DropToken`]s have been dropped.
+#[derive(Debug, Default)]
+pub struct DropCounter {
+    drops: Arc<AtomicUsize>,
+}
+
+impl DropCounter {
+    /// Create a counter with no drops.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A new token to embed in a value.
+    pub fn token(&self) -> DropToken {
+        DropToken(self.drops.clone())
+    }
+
+    /// How many tokens have been dropped so far.
+    pub fn drops(&self) -> usize {
+        self.drops.load(Ordering::SeqCst)
+    }
+}
+
+/// A field that reports its drop to a [`DropCounter`]. Cloning it creates a separate token.
+#[derive(Debug, Clone)]
+pub struct DropToken(Arc<AtomicUsize>);
+
+impl Drop for DropToken {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Check that owned handles drop their value exactly once, and that clones drop theirs
+/// separately. `make` builds the handles from values holding tokens of the given counter,
+/// one token per value.
+///
+/// # Panics
+///
+/// Panics if a value is dropped too early, twice, or never, or if `E` is an arena enum.
+pub fn assert_drops_once<E, F>(make: F)
+where
+    E: TaggedEnum + Clone,
+    F: FnOnce(&DropCounter) -> Vec<E>,
+{
+    assert!(E::OWNED, "arena handles never drop their values");
+
+    let counter = DropCounter::new();
+    let values = make(&counter);
+    let count = values.len();
+    assert_eq!(counter.drops(), 0, "values were dropped while constructing the handles");
+
+    let clones = values.clone();
+    assert_eq!(counter.drops(), 0, "cloning dropped a value");
+
+    drop(values);
+    assert_eq!(counter.drops(), count, "dropping the handles did not drop each value once");
+
+    drop(clones);
+    assert_eq!(counter.drops(), 2 * count, "dropping the clones did not drop each value once");
+}
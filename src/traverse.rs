@@ -0,0 +1,214 @@
+//! Walking structures linked through handles, such as ASTs and scene graphs.
+//!
+//! Every generated enum implements [`Node`], identifying a value by its address, so
+//! the traversals here only need a function listing a node's children:
+//!
+//! ```rust,ignore
+//! fn children<'a>(expr: &Expr<'a>) -> Vec<Expr<'a>> {
+//!     match <&Binary>::try_from(*expr) {
+//!         Ok(binary) => vec![binary.lhs, binary.rhs],
+//!         Err(_) => Vec::new(),
+//!     }
+//! }
+//!
+//! for expr in traverse::dfs(root, children) {
+//!     println!("{}", expr.describe());
+//! }
+//! assert!(traverse::find_cycle(root, children).is_none());
+//! ```
+//!
+//! Each node is yielded once, even when it is reachable along several paths or
+//! through a cycle. Handles with a spare pointer bit (high-bit tags, except with the
+//! `cow` flag) also implement [`Mark`], for marking nodes in place during a walk
+//! without a side table.
+
+#[cfg(not(feature = "std"))]
+use alloc::{collections::{BTreeSet, VecDeque}, vec::Vec};
+#[cfg(feature = "std")]
+use std::collections::{BTreeSet, VecDeque};
+
+/// A handle whose identity is the value it points to.
+pub trait Node {
+    /// The address of the value, the same for every handle to it
+    fn node_id(&self) -> usize;
+}
+
+/// A handle that can carry a mark in its pointer's spare bit.
+///
+/// The mark doesn't change what the handle points to or how it dispatches, but
+/// marked and unmarked handles to the same value compare unequal. Copies that share
+/// the value (arena and `shared` handles) keep the mark, while clones that copy the
+/// value start unmarked.
+pub trait Mark: Node {
+    /// Whether the handle is marked
+    fn is_marked(&self) -> bool;
+
+    /// Set or clear the mark
+    fn set_marked(&mut self, marked: bool);
+}
+
+/// Depth-first, pre-order iterator over the nodes reachable from a root, from [`dfs`]
+pub struct Dfs<H, F> {
+    stack: Vec<H>,
+    visited: BTreeSet<usize>,
+    children: F,
+}
+
+/// Visit `root` and everything reachable from it depth first, each node before its
+/// children and children in the order `children` lists them
+pub fn dfs<H, F, I>(root: H, children: F) -> Dfs<H, F>
+where
+    H: Node,
+    F: FnMut(&H) -> I,
+    I: IntoIterator<Item = H>,
+{
+    Dfs { stack: Vec::from([root]), visited: BTreeSet::new(), children }
+}
+
+impl<H, F, I> Iterator for Dfs<H, F>
+where
+    H: Node,
+    F: FnMut(&H) -> I,
+    I: IntoIterator<Item = H>,
+{
+    type Item = H;
+
+    fn next(&mut self) -> Option<H> {
+        while let Some(node) = self.stack.pop() {
+            if !self.visited.insert(node.node_id()) {
+                continue;
+            }
+            // Pushed in reverse so the first child is visited first
+            let start = self.stack.len();
+            self.stack.extend((self.children)(&node));
+            self.stack[start..].reverse();
+            return Some(node);
+        }
+        None
+    }
+}
+
+/// Breadth-first iterator over the nodes reachable from a root, from [`bfs`]
+pub struct Bfs<H, F> {
+    queue: VecDeque<H>,
+    visited: BTreeSet<usize>,
+    children: F,
+}
+
+/// Visit `root` and everything reachable from it breadth first, in order of distance
+/// from the root
+pub fn bfs<H, F, I>(root: H, children: F) -> Bfs<H, F>
+where
+    H: Node,
+    F: FnMut(&H) -> I,
+    I: IntoIterator<Item = H>,
+{
+    let mut visited = BTreeSet::new();
+    visited.insert(root.node_id());
+    Bfs { queue: VecDeque::from([root]), visited, children }
+}
+
+impl<H, F, I> Iterator for Bfs<H, F>
+where
+    H: Node,
+    F: FnMut(&H) -> I,
+    I: IntoIterator<Item = H>,
+{
+    type Item = H;
+
+    fn next(&mut self) -> Option<H> {
+        let node = self.queue.pop_front()?;
+        for child in (self.children)(&node) {
+            if self.visited.insert(child.node_id()) {
+                self.queue.push_back(child);
+            }
+        }
+        Some(node)
+    }
+}
+
+/// Find a cycle reachable from `root`, returned as the path of nodes from the first
+/// node on the cycle back to the one linking to it
+pub fn find_cycle<H, F, I>(root: H, mut children: F) -> Option<Vec<H>>
+where
+    H: Node,
+    F: FnMut(&H) -> I,
+    I: IntoIterator<Item = H>,
+{
+    // The current path with each node's unvisited children, and every finished node
+    let mut path: Vec<(H, I::IntoIter)> = Vec::new();
+    let mut on_path = BTreeSet::new();
+    let mut finished = BTreeSet::new();
+
+    on_path.insert(root.node_id());
+    let root_children = children(&root).into_iter();
+    path.push((root, root_children));
+
+    while let Some((_, pending)) = path.last_mut() {
+        match pending.next() {
+            Some(child) => {
+                let id = child.node_id();
+                if on_path.contains(&id) {
+                    let start = path.iter().position(|(node, _)| node.node_id() == id)?;
+                    return Some(path.drain(start..).map(|(node, _)| node).collect());
+                }
+                if !finished.contains(&id) {
+                    on_path.insert(id);
+                    let grandchildren = children(&child).into_iter();
+                    path.push((child, grandchildren));
+                }
+            }
+            None => {
+                let (node, _) = path.pop()?;
+                on_path.remove(&node.node_id());
+                finished.insert(node.node_id());
+            }
+        }
+    }
+    None
+}
+
+/// Whether a cycle is reachable from `root`
+pub fn has_cycle<H, F, I>(root: H, children: F) -> bool
+where
+    H: Node,
+    F: FnMut(&H) -> I,
+    I: IntoIterator<Item = H>,
+{
+    find_cycle(root, children).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    // Nodes are indices into an adjacency list, identified by themselves
+    impl Node for usize {
+        fn node_id(&self) -> usize {
+            *self
+        }
+    }
+
+    fn graph<'a>(edges: &'a [&'a [usize]]) -> impl FnMut(&usize) -> Vec<usize> + 'a {
+        move |node| edges[*node].to_vec()
+    }
+
+    const DIAMOND: &[&[usize]] = &[&[1, 2], &[3], &[3], &[]];
+
+    #[test]
+    fn test_orders() {
+        assert_eq!(dfs(0, graph(DIAMOND)).collect::<Vec<_>>(), [0, 1, 3, 2]);
+        assert_eq!(bfs(0, graph(DIAMOND)).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        assert!(!has_cycle(0, graph(DIAMOND)));
+    }
+
+    #[test]
+    fn test_cycles() {
+        let cyclic: &[&[usize]] = &[&[1], &[2, 3], &[], &[1]];
+        assert_eq!(dfs(0, graph(cyclic)).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        assert_eq!(find_cycle(0, graph(cyclic)), Some(vec![1, 3]));
+        assert_eq!(find_cycle(0, graph(&[&[0]])), Some(vec![0]));
+    }
+}
@@ -0,0 +1,20 @@
+//! Reads the address width the high-bit tags are laid out around, which must match
+//! the runtime crate's so the generated checks agree with its pointer types
+
+/// Address bits free of tags unless `TAGGED_DISPATCH_ADDRESS_BITS` says otherwise
+const DEFAULT_ADDRESS_BITS: u32 = 56;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=TAGGED_DISPATCH_ADDRESS_BITS");
+
+    // Range-checked by the runtime crate's build script as well
+    let address_bits = match std::env::var("TAGGED_DISPATCH_ADDRESS_BITS") {
+        Ok(bits) => match bits.trim().parse::<u32>() {
+            Ok(bits) if (47..=57).contains(&bits) => bits,
+            _ => panic!("TAGGED_DISPATCH_ADDRESS_BITS must be a number between 47 and 57, got `{}`", bits),
+        },
+        Err(_) => DEFAULT_ADDRESS_BITS,
+    };
+    println!("cargo:rustc-env=TAGGED_DISPATCH_ADDRESS_BITS={}", address_bits);
+}
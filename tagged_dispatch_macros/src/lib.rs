@@ -9,12 +9,41 @@ use syn::{
     parse::{Parse, ParseStream},
     parse_macro_input,
     punctuated::Punctuated,
-    Data, DataEnum, DeriveInput, Fields,
+    Attribute, Data, DataEnum, DeriveInput, Fields,
     Ident, ItemTrait, Path, Result, Token, TraitItem, TraitItemFn,
     Type,
 };
 use heck::ToSnakeCase;
-use proc_macro2::TokenStream as TokenStream2;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+
+/// Address bits free of tags, from `TAGGED_DISPATCH_ADDRESS_BITS` (mirrors `ADDRESS_BITS`)
+const ADDRESS_BITS: u32 = parse_address_bits(env!("TAGGED_DISPATCH_ADDRESS_BITS"));
+
+/// Tag bits stored in the top of the address (mirrors `DEFAULT_TAG_BITS`), 7 by default
+const HIGH_TAG_BITS: u32 = 63 - ADDRESS_BITS;
+
+/// Narrowest tag accepted by `tag_bits = N`: the high bits of the default layout, so
+/// every address width accepts the same values
+const MIN_TAG_BITS: u32 = 7;
+
+/// Widest tag accepted by `tag_bits = N`
+const MAX_TAG_BITS: u32 = 16;
+
+/// Parse the decimal the build script passes on, which it has already range-checked
+const fn parse_address_bits(bits: &str) -> u32 {
+    let bytes = bits.as_bytes();
+    let mut value = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    value
+}
+
+/// Variants a `nan_box` enum can hold (mirrors `NanBoxedPtr::MAX_VARIANTS`)
+const NAN_BOX_VARIANTS: usize = 8;
 
 // Helper functions for conditional code generation based on features
 
@@ -35,8 +64,11 @@ fn generate_allocator_arms(field_name: &Ident, ty: &Type, arena_type_name: &Iden
 
     #[cfg(feature = "allocator-bumpalo")]
     arms.push(quote! {
-        #arena_type_name::Bumpalo { arena, .. } => {
+        #arena_type_name::Bumpalo { arena, meter, .. } => {
             unsafe {
+                if let Some(meter) = meter.as_ref() {
+                    meter.record_alloc::<#ty>();
+                }
                 let arena_ref = &**arena;
                 arena_ref.alloc(value) as *mut #ty as *mut ()
             }
@@ -54,6 +86,144 @@ fn generate_allocator_arms(field_name: &Ident, ty: &Type, arena_type_name: &Iden
     }
 }
 
+/// Generate allocator match arms like `generate_allocator_arms`, but returning
+/// `TagError::AllocFailed` when a bump arena can't allocate (typed arenas abort)
+fn generate_try_allocator_arms(field_name: &Ident, ty: &Type, arena_type_name: &Ident) -> TokenStream2 {
+    #[cfg(any(feature = "allocator-typed-arena", feature = "allocator-bumpalo"))]
+    let mut arms = vec![];
+
+    #[cfg(not(any(feature = "allocator-typed-arena", feature = "allocator-bumpalo")))]
+    let arms: Vec<TokenStream2> = vec![];
+
+    #[cfg(feature = "allocator-typed-arena")]
+    arms.push(quote! {
+        #arena_type_name::Typed { #field_name, .. } => {
+            #field_name.alloc(value) as *mut #ty as *mut ()
+        }
+    });
+
+    #[cfg(feature = "allocator-bumpalo")]
+    arms.push(quote! {
+        #arena_type_name::Bumpalo { arena, meter, .. } => {
+            unsafe {
+                let arena_ref = &**arena;
+                let Ok(slot) = arena_ref.try_alloc(value) else {
+                    return Err(::tagged_dispatch::TagError::AllocFailed { size: ::core::mem::size_of::<#ty>() });
+                };
+                if let Some(meter) = meter.as_ref() {
+                    meter.record_alloc::<#ty>();
+                }
+                slot as *mut #ty as *mut ()
+            }
+        }
+    });
+
+    if arms.is_empty() {
+        let _ = (field_name, ty, arena_type_name); // Suppress unused warnings
+        quote! {
+            _ => compile_error!("At least one allocator feature must be enabled (allocator-typed-arena or allocator-bumpalo)")
+        }
+    } else {
+        quote! { #(#arms)* }
+    }
+}
+
+/// Generate allocator match arms that initialize a value in place through `init`
+fn generate_allocator_emplace_arms(field_name: &Ident, ty: &Type, arena_type_name: &Ident) -> TokenStream2 {
+    #[cfg(any(feature = "allocator-typed-arena", feature = "allocator-bumpalo"))]
+    let mut arms = vec![];
+
+    #[cfg(not(any(feature = "allocator-typed-arena", feature = "allocator-bumpalo")))]
+    let arms: Vec<TokenStream2> = vec![];
+
+    // Only confirm the typed arena slot once `init` has returned, so a panicking
+    // initializer never leaves an uninitialized value for the arena to drop. The slot
+    // isn't reserved meanwhile, so if `init` allocated into the same arena, its value
+    // now sits in the slot and was overwritten, and the arena would hand out an
+    // unwritten one; neither can be undone.
+    #[cfg(feature = "allocator-typed-arena")]
+    arms.push(quote! {
+        #arena_type_name::Typed { #field_name, .. } => {
+            #field_name.reserve_extend(1);
+            let slot = #field_name.uninitialized_array() as *mut ::core::mem::MaybeUninit<#ty>;
+            init(&mut *slot);
+            let confirmed = #field_name.alloc_uninitialized(1).as_mut_ptr();
+            if confirmed != slot {
+                ::tagged_dispatch::__private::abort(concat!(
+                    "`init` allocated a `", stringify!(#ty), "` in the typed arena it was initializing a slot of"
+                ));
+            }
+            confirmed as *mut ()
+        }
+    });
+
+    #[cfg(feature = "allocator-bumpalo")]
+    arms.push(quote! {
+        #arena_type_name::Bumpalo { arena, meter, .. } => {
+            if let Some(meter) = meter.as_ref() {
+                meter.record_alloc::<#ty>();
+            }
+            let arena_ref = &**arena;
+            let slot = arena_ref
+                .alloc_layout(::core::alloc::Layout::new::<#ty>())
+                .cast::<::core::mem::MaybeUninit<#ty>>()
+                .as_ptr();
+            init(&mut *slot);
+            slot as *mut ()
+        }
+    });
+
+    // If no allocators are enabled, generate a compile error
+    if arms.is_empty() {
+        let _ = (field_name, ty, arena_type_name); // Suppress unused warnings
+        quote! {
+            _ => compile_error!("At least one allocator feature must be enabled (allocator-typed-arena or allocator-bumpalo)")
+        }
+    } else {
+        quote! { #(#arms)* }
+    }
+}
+
+/// Generate allocator match arms that reserve an uninitialized slot
+fn generate_allocator_uninit_arms(field_name: &Ident, ty: &Type, arena_type_name: &Ident) -> TokenStream2 {
+    #[cfg(any(feature = "allocator-typed-arena", feature = "allocator-bumpalo"))]
+    let mut arms = vec![];
+
+    #[cfg(not(any(feature = "allocator-typed-arena", feature = "allocator-bumpalo")))]
+    let arms: Vec<TokenStream2> = vec![];
+
+    #[cfg(feature = "allocator-typed-arena")]
+    arms.push(quote! {
+        #arena_type_name::Typed { #field_name, .. } => {
+            #field_name.alloc_uninitialized(1).as_mut_ptr()
+        }
+    });
+
+    #[cfg(feature = "allocator-bumpalo")]
+    arms.push(quote! {
+        #arena_type_name::Bumpalo { arena, meter, .. } => {
+            if let Some(meter) = meter.as_ref() {
+                meter.record_alloc::<#ty>();
+            }
+            let arena_ref = &**arena;
+            arena_ref
+                .alloc_layout(::core::alloc::Layout::new::<#ty>())
+                .cast::<::core::mem::MaybeUninit<#ty>>()
+                .as_ptr()
+        }
+    });
+
+    // If no allocators are enabled, generate a compile error
+    if arms.is_empty() {
+        let _ = (field_name, ty, arena_type_name); // Suppress unused warnings
+        quote! {
+            _ => compile_error!("At least one allocator feature must be enabled (allocator-typed-arena or allocator-bumpalo)")
+        }
+    } else {
+        quote! { #(#arms)* }
+    }
+}
+
 /// Generate arena enum definition based on enabled features
 fn generate_arena_enum(arena_type_name: &Ident, lifetime: &TokenStream2, typed_arena_fields: &[TokenStream2]) -> TokenStream2 {
     #[cfg(any(feature = "allocator-typed-arena", feature = "allocator-bumpalo"))]
@@ -66,6 +236,7 @@ fn generate_arena_enum(arena_type_name: &Ident, lifetime: &TokenStream2, typed_a
     variants.push(quote! {
         Typed {
             #(#typed_arena_fields,)*
+            _phantom: ::core::marker::PhantomData<&#lifetime ()>,
         }
     });
 
@@ -74,6 +245,7 @@ fn generate_arena_enum(arena_type_name: &Ident, lifetime: &TokenStream2, typed_a
         Bumpalo {
             arena: *mut ::tagged_dispatch::bumpalo::Bump,
             owned: bool,
+            meter: *const ::tagged_dispatch::Meter,
             _phantom: ::core::marker::PhantomData<&#lifetime ()>,
         }
     });
@@ -119,34 +291,54 @@ fn generate_builder_methods(
     builder_name: &Ident,
     arena_type_name: &Ident,
     typed_arena_inits: &[TokenStream2],
-    lifetime: &TokenStream2
+    typed_arena_sized_inits: &[TokenStream2],
+    lifetime: &TokenStream2,
+    extra_inits: &TokenStream2,
 ) -> TokenStream2 {
     #[cfg(not(feature = "allocator-bumpalo"))]
     let _ = (builder_name, lifetime);
     #[cfg(not(feature = "allocator-typed-arena"))]
-    let _ = typed_arena_inits;
+    let _ = (typed_arena_inits, typed_arena_sized_inits);
     #[cfg(any(feature = "allocator-typed-arena", feature = "allocator-bumpalo"))]
     let mut methods = vec![];
 
     #[cfg(not(any(feature = "allocator-typed-arena", feature = "allocator-bumpalo")))]
     let methods: Vec<TokenStream2> = {
-        let _ = (builder_name, arena_type_name, typed_arena_inits, lifetime);
+        let _ = (builder_name, arena_type_name, typed_arena_inits, typed_arena_sized_inits, lifetime, extra_inits);
         vec![]
     };
 
     #[cfg(feature = "allocator-bumpalo")]
     methods.push(quote! {
-        /// Create a builder with owned bumpalo arena
+        /// Create a builder with owned bumpalo arena, freed when the builder is dropped
         pub fn with_bumpalo() -> #builder_name<'static> {
-            // Use a leaked Box to get 'static lifetime for owned arena - is there a better way to
-            // do this? Maybe a OnceCell?
-            let arena = Box::leak(Box::new(::tagged_dispatch::bumpalo::Bump::new()));
+            // Handles borrow the builder, so none outlive the arena it frees in `Drop`
+            let arena = Box::into_raw(Box::new(::tagged_dispatch::bumpalo::Bump::new()));
+            #builder_name {
+                allocator: #arena_type_name::Bumpalo {
+                    arena,
+                    owned: true,
+                    meter: ::core::ptr::null(),
+                    _phantom: ::core::marker::PhantomData,
+                },
+                #extra_inits
+                _phantom: ::core::marker::PhantomData,
+            }
+        }
+
+        /// Create a builder with owned bumpalo arena whose first chunk holds at least
+        /// `bytes`, so a predictable working set is allocated up front rather than chunk
+        /// by chunk
+        pub fn with_capacity(bytes: usize) -> #builder_name<'static> {
+            let arena = Box::into_raw(Box::new(::tagged_dispatch::bumpalo::Bump::with_capacity(bytes)));
             #builder_name {
                 allocator: #arena_type_name::Bumpalo {
-                    arena: arena as *mut _,
+                    arena,
                     owned: true,
+                    meter: ::core::ptr::null(),
                     _phantom: ::core::marker::PhantomData,
                 },
+                #extra_inits
                 _phantom: ::core::marker::PhantomData,
             }
         }
@@ -157,13 +349,34 @@ fn generate_builder_methods(
                 allocator: #arena_type_name::Bumpalo {
                     arena: arena as *const _ as *mut _,
                     owned: false,
+                    meter: ::core::ptr::null(),
+                    _phantom: ::core::marker::PhantomData,
+                },
+                #extra_inits
+                _phantom: ::core::marker::PhantomData,
+            }
+        }
+
+        /// Create a builder with external bumpalo arena that records each allocation
+        /// in the arena's meter
+        pub fn with_metered_bumpalo(
+            arena: &#lifetime ::tagged_dispatch::Metered<::tagged_dispatch::bumpalo::Bump>,
+        ) -> Self {
+            Self {
+                allocator: #arena_type_name::Bumpalo {
+                    arena: arena.inner() as *const _ as *mut _,
+                    owned: false,
+                    meter: arena.meter(),
                     _phantom: ::core::marker::PhantomData,
                 },
+                #extra_inits
                 _phantom: ::core::marker::PhantomData,
             }
         }
     });
 
+    #[cfg(feature = "allocator-typed-arena")]
+    let variant_count = typed_arena_sized_inits.len();
     #[cfg(feature = "allocator-typed-arena")]
     methods.push(quote! {
         /// Create a builder with typed arenas
@@ -171,7 +384,22 @@ fn generate_builder_methods(
             Self {
                 allocator: #arena_type_name::Typed {
                     #(#typed_arena_inits,)*
+                    _phantom: ::core::marker::PhantomData,
+                },
+                #extra_inits
+                _phantom: ::core::marker::PhantomData,
+            }
+        }
+
+        /// Create a builder with typed arenas whose first chunks hold `counts[i]` values
+        /// of the `i`th variant, in declaration order
+        pub fn with_typed_arena_capacity(counts: [usize; #variant_count]) -> Self {
+            Self {
+                allocator: #arena_type_name::Typed {
+                    #(#typed_arena_sized_inits,)*
+                    _phantom: ::core::marker::PhantomData,
                 },
+                #extra_inits
                 _phantom: ::core::marker::PhantomData,
             }
         }
@@ -202,6 +430,7 @@ fn generate_reset_impl(
             // typed_arena doesn't support reset, must create new arenas
             self.allocator = #arena_type_name::Typed {
                 #(#typed_arena_inits2,)*
+                _phantom: ::core::marker::PhantomData,
             };
         }
     });
@@ -226,6 +455,50 @@ fn generate_reset_impl(
     }
 }
 
+/// Generate the part of `clear` that releases an owned bumpalo arena's chunks, which
+/// `reset` keeps for reuse
+fn generate_clear_impl(arena_type_name: &Ident) -> TokenStream2 {
+    // Resetting typed arenas already replaces them
+    #[cfg(not(feature = "allocator-bumpalo"))]
+    {
+        let _ = arena_type_name;
+        quote! {}
+    }
+
+    #[cfg(feature = "allocator-bumpalo")]
+    quote! {
+        #[allow(irrefutable_let_patterns)]
+        if let #arena_type_name::Bumpalo { arena, owned: true, .. } = &mut self.allocator {
+            // SAFETY: we own the arena, and `&mut self` rules out handles into it
+            unsafe { **arena = ::tagged_dispatch::bumpalo::Bump::new() };
+        }
+    }
+}
+
+/// Generate the builder's `Drop` implementation, freeing an owned bumpalo arena
+fn generate_builder_drop_impl(builder_name: &Ident, arena_type_name: &Ident, lifetime: &TokenStream2) -> TokenStream2 {
+    // Typed arenas are fields of the builder and drop with it
+    #[cfg(not(feature = "allocator-bumpalo"))]
+    {
+        let _ = (builder_name, arena_type_name, lifetime);
+        quote! {}
+    }
+
+    #[cfg(feature = "allocator-bumpalo")]
+    quote! {
+        impl<#lifetime> Drop for #builder_name<#lifetime> {
+            fn drop(&mut self) {
+                #[allow(irrefutable_let_patterns)]
+                if let #arena_type_name::Bumpalo { arena, owned: true, .. } = &self.allocator {
+                    // SAFETY: owned arenas come from `Box::into_raw` in `with_bumpalo`, and
+                    // every handle into it borrows the builder
+                    unsafe { drop(Box::from_raw(*arena)) };
+                }
+            }
+        }
+    }
+}
+
 /// Generate stats implementation based on enabled features
 fn generate_stats_impl(arena_type_name: &Ident) -> TokenStream2 {
     #[cfg(any(feature = "allocator-typed-arena", feature = "allocator-bumpalo"))]
@@ -275,9 +548,41 @@ fn generate_stats_impl(arena_type_name: &Ident) -> TokenStream2 {
 ///
 ///     #[no_dispatch]
 ///     fn debug_name(&self) -> &str { "drawable" }
+///
+///     // Evaluated once on the enum instead of per variant (variant overrides are bypassed)
+///     #[enum_default]
+///     fn describe(&self) -> String { format!("{} drawable", self.debug_name()) }
+///
+///     // Returned as `Box<dyn Iterator<Item = Point> + '_>` on the enum
+///     #[dispatch_boxed]
+///     fn points(&self) -> impl Iterator<Item = Point>;
 /// }
 /// ```
 ///
+/// `#[tagged_dispatch(enum_defaults)]` on a trait treats every default method as `#[enum_default]`.
+///
+/// An empty trait whose supertraits are `#[tagged_dispatch]` traits, such as
+/// `trait GameObject: Draw + Update {}`, dispatches all of their methods. Traits
+/// with methods of their own do the same with `#[tagged_dispatch(supertraits)]`,
+/// or `#[tagged_dispatch(supertraits(Draw))]` to inherit only the named ones.
+///
+/// `#[tagged_dispatch(ref_impls)]` also implements the trait for `&Enum` and
+/// `&mut Enum` by forwarding to the dispatch methods, so handles can be passed
+/// to `impl Trait` parameters without giving them up. This requires every
+/// method to take `&self` (or be `#[no_dispatch]` with a default body).
+/// Std supertraits like `Clone` or `Debug` are skipped.
+///
+/// `#[tagged_dispatch(map_errors)]` on a trait with a `type Error` generates
+/// `<Enum>Error`, holding each variant's error in a variant of the same name, and
+/// makes methods returning `Result<T, Self::Error>` return `Result<T, <Enum>Error>`.
+///
+/// Enums also get `as_<trait>(&self) -> &dyn Trait`, e.g. `as_draw()`, for APIs
+/// taking trait objects, when the trait is dyn-compatible: no associated consts or
+/// types, no `Sized` std supertraits, and no generic, receiver-less, `async`, or
+/// `Self`-mentioning methods without `where Self: Sized`. `#[tagged_dispatch(no_dyn)]`
+/// skips it for traits the check can't see are incompatible, such as ones with an
+/// incompatible supertrait of your own.
+///
 /// # For Enums
 ///
 /// By default, generates `Debug`, `PartialEq`, `Eq`, `PartialOrd`, and `Ord` implementations.
@@ -311,13 +616,99 @@ fn generate_stats_impl(arena_type_name: &Ident) -> TokenStream2 {
 /// - `no_ord` - Skip PartialOrd/Ord implementations
 /// - `no_cmp` - Skip all comparison traits (equivalent to `no_eq, no_ord`)
 /// - `no_traits` - Skip all automatic trait implementations
+/// - `no_clone` - Skip the owned handle's `Clone` implementation, so variant types
+///   don't need to be `Clone`. Owned enums only
+/// - `drop_table` - Drop owned values through a static table of drop functions
+///   shared with other enums, instead of an inlined `match`
+/// - `no_size_assert` - Skip the compile-time check that the enum is exactly one
+///   tagged pointer in size
+/// - `non_exhaustive` - Mark the generated `*Type` enum `#[non_exhaustive]`, so
+///   adding variants to a public enum is not a breaking change for downstream matches
+/// - `stable_hash` - Generate `stable_hash(&mut hasher)`, hashing the tag and the
+///   variant's value (which must implement `Hash`) instead of its address
+/// - `display` - Implement `Display` by forwarding to the variant's own `Display`
+///   (same as `#[delegate(Display)]`)
+/// - `hash` - Implement `Hash` over the tag and the variant's value (which must
+///   implement `Hash`), rather than the pointer
+/// - `bytes` - Encode handles as a tag byte followed by the value's bytes with
+///   `to_bytes()`, and decode them with `TryFrom<&[u8]>` (owned) or
+///   `builder.from_bytes(..)` (arena); every variant must be `bytemuck::Pod`, and
+///   the `bytemuck` feature must be enabled
+/// - `send` / `sync` - Implement `Send` / `Sync` for the handle, after checking at
+///   compile time that every variant allows it. Handles are neither by default
+/// - `fallible` - Also generate `try_<variant>(value)` constructors (and builder
+///   methods) returning a `tagged_dispatch::TagError` when the value's address
+///   overlaps the tag, instead of only checking in debug builds, or when its
+///   allocator (`VariantAllocator::try_alloc`) or bump arena is out of memory
+/// - `shared` - Store owned values in an `Arc`, so cloning a handle bumps a
+///   reference count instead of copying the value. With `send` / `sync`, every
+///   variant must be both `Send` and `Sync`
+/// - `rc` - Like `shared`, but with an `Rc` for single-threaded code, so the count
+///   isn't atomic and the handle can't be `send` / `sync`
+/// - `nan_box` - Store the enum's one `f64` variant as the handle's own bits, and
+///   the other variants' pointers in the payload of a quiet NaN, so numbers never
+///   allocate. Holds at most 8 variants, and pointers must fit in 48 bits
+/// - `open` - Add a `Plugin` variant holding values of types registered at runtime
+///   with the generated `<Enum>Registry`, dispatched through per-type function
+///   pointer tables. Owned enums only
+/// - `inline_small` - Store variants holding a primitive that fits in the address
+///   bits (`bool`, `char`, `()`, `f32`, and integers up to 32 bits) inline, as if
+///   marked `#[inline_value]`, and box the rest
+/// - `pooled` - Keep the blocks of dropped values in a per-thread pool for each
+///   variant type and reuse them in constructors, instead of freeing them. Owned
+///   enums without type parameters only, and needs the `std` feature
+/// - `columns` - Generate `<Enum>Columns`, storing values in one `Vec` per variant
+///   type, with `run(&mut pass)` handing each variant's values to a
+///   `<Enum>ColumnPass` as a typed slice. Owned enums without type parameters only
+/// - `owned = Enum` - On an arena enum, generate `Enum::clone_into_arena(&builder)`
+///   and `to_owned()` to convert between it and an owned enum over the same
+///   variants, paired by name
+///
+/// Traits whose method names clash with another listed trait can be written as
+/// `qualified(Trait)`; their methods are then generated as `<method>_as_<trait>`.
+///
+/// A `#[base(field: Type, ...)]` attribute below `#[tagged_dispatch(...)]` generates
+/// `field()` (and, for owned enums, `field_mut()`) accessors for fields every
+/// variant type has.
+///
+/// A `#[delegate(AsRef<str>, Display, ...)]` attribute implements the listed std
+/// traits for the enum by dispatching to the variant types. The supported traits
+/// are `AsRef<T>`, `Borrow<T>`, `Display`, and `Debug` (which replaces the
+/// generated `Debug` impl).
+///
+/// Std operator traits written as `ops::Trait` (`Neg`, `Not`, `Add<Rhs>` and the
+/// other binary operators, `Index<Idx>`) are implemented for the enum directly,
+/// with the first variant's `Output`.
+///
+/// On owned enums, `#[alloc_with(Pool)]` on a variant stores that variant through
+/// `Pool`'s `tagged_dispatch::VariantAllocator` implementation instead of a `Box`;
+/// `#[alloc_with(Metered<A>)]` also records it in `tagged_dispatch::Meter::global()`.
+/// `#[constructor_vis(pub(crate))]` on a variant restricts the visibility of its
+/// constructors (a bare `#[constructor_vis]` makes them private) and omits its
+/// `From` impl, so it can only be created by the defining crate or module.
+/// An `#[on_drop(hook)]` attribute on an owned enum calls `hook(tag, ptr)` with the
+/// `<Enum>Type` and a pointer to the value right before a handle drops its variant.
+/// `#[inline_value]` on a variant whose type implements `tagged_dispatch::InlineValue`
+/// packs the value into the handle's address bits instead of allocating it, and
+/// dispatch reads a copy of it from the stack.
+/// `#[default]` on a variant whose type implements `Default` implements `Default` for
+/// an owned enum and generates `take()`, and generates `builder.default_<variant>()`
+/// for an arena enum; `replace()` is always generated on owned enums. Derived enums
+/// use `#[default_variant]`, since `#[default]` belongs to `#[derive(Default)]` there.
+/// `#[flatten]` on a `Variant(Inner)` variant of an owned enum splices the variants of
+/// the owned enum `Inner` in its place, with re-tagging `From<Inner>` and
+/// `TryFrom<Enum>` impls and an `is_<variant>()` check.
 #[proc_macro_attribute]
 pub fn tagged_dispatch(args: TokenStream, input: TokenStream) -> TokenStream {
     // Check if this is being applied to a trait or an enum
     if let Ok(trait_def) = syn::parse::<ItemTrait>(input.clone()) {
-        process_trait(trait_def)
+        let item_name = trait_def.ident.clone();
+        dump_expansion(&item_name, process_trait(args, trait_def))
     } else if let Ok(enum_def) = syn::parse::<DeriveInput>(input) {
-        process_enum(args, enum_def)
+        if has_cfg_variants(&enum_def) {
+            return defer_cfg_variants(args.into(), &enum_def).into();
+        }
+        expand_enum(args, enum_def)
     } else {
         syn::Error::new(
             proc_macro2::Span::call_site(),
@@ -328,688 +719,6346 @@ pub fn tagged_dispatch(args: TokenStream, input: TokenStream) -> TokenStream {
     }
 }
 
-/// Process a trait definition with #[tagged_dispatch]
-fn process_trait(mut trait_def: ItemTrait) -> TokenStream {
-    let trait_name = &trait_def.ident;
-    
-    // Extract methods that should be dispatched (those without #[no_dispatch])
-    let dispatch_methods: Vec<_> = trait_def.items.iter().filter_map(|item| {
-        if let TraitItem::Fn(method) = item {
-            let has_no_dispatch = method.attrs.iter().any(|attr| 
-                attr.path().is_ident("no_dispatch")
-            );
-            if !has_no_dispatch {
-                Some(method.clone())
-            } else {
-                None
+/// Derive-macro alternative to `#[tagged_dispatch(...)]` for the enum side.
+///
+/// A derive cannot replace the enum it is attached to, so the tagged handle is
+/// generated as a separate type, named `Tagged<Enum>` unless overridden with
+/// `name = ...`. The traits and flags go in one or more `#[dispatch(...)]`
+/// helper attributes and accept everything the attribute form does, plus:
+///
+/// - `name = Shape` - Name of the generated handle type
+/// - `arena` - Generate an arena handle (`Shape<'a>`) instead of an owned one
+///
+/// ```ignore
+/// #[derive(Debug, TaggedDispatch)]
+/// #[dispatch(Draw, name = Shape)]
+/// enum ShapeValue {
+///     Circle(Circle),
+///     Rectangle(Rectangle),
+/// }
+///
+/// let shape: Shape = ShapeValue::Circle(Circle { radius: 1.0 }).into();
+/// shape.draw();
+/// ```
+///
+/// When every variant carries a value and the handle is owned, a
+/// `From<Enum>` implementation for the handle is generated as well. The
+/// `#[base(...)]`, `#[delegate(...)]`, `#[alloc_with(...)]`, `#[on_drop(...)]`,
+/// `#[constructor_vis(...)]` and `#[inline_value]` attributes work the same as on a
+/// `#[tagged_dispatch]` enum.
+#[proc_macro_derive(TaggedDispatch, attributes(dispatch, base, delegate, alloc_with, on_drop, constructor, constructor_vis, inline_value, variant_type, default_variant))]
+pub fn derive_tagged_dispatch(input: TokenStream) -> TokenStream {
+    let enum_def = parse_macro_input!(input as DeriveInput);
+    let item_name = enum_def.ident.clone();
+    dump_expansion(&item_name, process_derive(enum_def))
+}
+
+/// Second stage of `#[tagged_dispatch]` on enums with `#[cfg]` variants.
+///
+/// Attribute macros see an enum's variants before `cfg` is evaluated, derives see them
+/// after. The attribute re-emits the variant names on a private enum deriving this,
+/// with the full enum in its `#[tagged_dispatch_cfg]` helper, and only the variants
+/// that survive are generated, so the enabled variants get contiguous tags.
+#[doc(hidden)]
+#[proc_macro_derive(TaggedDispatchCfg, attributes(tagged_dispatch_cfg))]
+pub fn derive_tagged_dispatch_cfg(input: TokenStream) -> TokenStream {
+    let enabled = parse_macro_input!(input as DeriveInput);
+    let Some(attr) = enabled.attrs.iter().find(|attr| attr.path().is_ident("tagged_dispatch_cfg")) else {
+        return syn::Error::new_spanned(&enabled.ident, "missing `#[tagged_dispatch_cfg]`").to_compile_error().into();
+    };
+    let parsed = attr.parse_args_with(|input: ParseStream| {
+        let args;
+        syn::bracketed!(args in input);
+        let args: TokenStream2 = args.parse()?;
+        Ok((args, input.parse::<DeriveInput>()?))
+    });
+    let (args, mut enum_def) = match parsed {
+        Ok(parsed) => parsed,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    if let (Data::Enum(enabled), Data::Enum(data_enum)) = (&enabled.data, &mut enum_def.data) {
+        data_enum.variants = std::mem::take(&mut data_enum.variants)
+            .into_iter()
+            .filter(|variant| enabled.variants.iter().any(|enabled| enabled.ident == variant.ident))
+            .map(|mut variant| {
+                variant.attrs.retain(|attr| !attr.path().is_ident("cfg"));
+                variant
+            })
+            .collect();
+    }
+    expand_enum(args.into(), enum_def)
+}
+
+/// Splice the variants of an inner enum into an enum with `#[flatten]` variants.
+///
+/// Invoked by the inner enum's `__<enum>_flatten!` macro as
+/// `flatten_enum! { [args] Variant enum ... [(Variant, Type, Allocator, inline), ...] }`,
+/// replacing `Variant` with the listed variants, which keep the inner enum's storage
+/// so handles convert by re-tagging.
+#[doc(hidden)]
+#[proc_macro]
+pub fn flatten_enum(input: TokenStream) -> TokenStream {
+    let parsed = syn::parse::Parser::parse(|input: ParseStream| {
+        let args;
+        syn::bracketed!(args in input);
+        let args: TokenStream2 = args.parse()?;
+        let flattened: Ident = input.parse()?;
+        let enum_def: DeriveInput = input.parse()?;
+        let list;
+        syn::bracketed!(list in input);
+        let inner_variants = list.parse_terminated(FlattenedVariant::parse, Token![,])?;
+        Ok((args, flattened, enum_def, inner_variants))
+    }, input);
+    let (args, flattened, mut enum_def, inner_variants) = match parsed {
+        Ok(parsed) => parsed,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    if let Data::Enum(data_enum) = &mut enum_def.data {
+        let mut variants = Punctuated::new();
+        for variant in std::mem::take(&mut data_enum.variants) {
+            if variant.ident != flattened {
+                variants.push(variant);
+                continue;
+            }
+            let inner_type = variant_field_type(&variant);
+            for inner in &inner_variants {
+                let FlattenedVariant { variant: inner_variant, ty, allocator, inline } = inner;
+                let storage = if inline.value {
+                    quote! { #[inline_value] }
+                } else {
+                    quote! { #[alloc_with(#allocator)] }
+                };
+                variants.push(syn::parse_quote! {
+                    #[tagged_dispatch_flattened(#flattened, #inner_type)]
+                    #storage
+                    #inner_variant(#ty)
+                });
             }
-        } else {
-            None
         }
-    }).collect();
-    
-    // Remove #[no_dispatch] trait members
-    for item in &mut trait_def.items {
-        if let TraitItem::Fn(method) = item {
-            method.attrs.retain(|attr| !attr.path().is_ident("no_dispatch"));
+        data_enum.variants = variants;
+    }
+    expand_enum(args.into(), enum_def)
+}
+
+/// A variant listed by an inner enum's `__<enum>_flatten!` macro
+struct FlattenedVariant {
+    variant: Ident,
+    ty: Type,
+    allocator: Path,
+    inline: syn::LitBool,
+}
+
+impl Parse for FlattenedVariant {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        syn::parenthesized!(content in input);
+        let variant = content.parse()?;
+        content.parse::<Token![,]>()?;
+        let ty = content.parse()?;
+        content.parse::<Token![,]>()?;
+        let allocator = content.parse()?;
+        content.parse::<Token![,]>()?;
+        let inline = content.parse()?;
+        Ok(FlattenedVariant { variant, ty, allocator, inline })
+    }
+}
+
+/// Generate an enum, first splicing in the variants of any `#[flatten]` variants
+fn expand_enum(args: TokenStream, enum_def: DeriveInput) -> TokenStream {
+    match flatten_variant(&enum_def) {
+        Some(variant) => defer_flatten(args.into(), &enum_def, variant).into(),
+        None => {
+            let item_name = enum_def.ident.clone();
+            dump_expansion(&item_name, process_enum(args, enum_def))
+        }
+    }
+}
+
+/// The first variant of an enum marked `#[flatten]`
+fn flatten_variant(enum_def: &DeriveInput) -> Option<&syn::Variant> {
+    match &enum_def.data {
+        Data::Enum(data_enum) => data_enum.variants.iter()
+            .find(|variant| variant.attrs.iter().any(|attr| attr.path().is_ident("flatten"))),
+        _ => None,
+    }
+}
+
+/// The type a `Variant(Type)` variant holds, or the one a shorthand variant names
+fn variant_field_type(variant: &syn::Variant) -> Type {
+    match &variant.fields {
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => fields.unnamed[0].ty.clone(),
+        _ => {
+            let ident = &variant.ident;
+            syn::parse_quote!(#ident)
+        }
+    }
+}
+
+/// Hand an enum over to the `__<enum>_flatten!` macro of the enum its `#[flatten]`
+/// `variant` holds, which passes its variants on to `flatten_enum!`
+fn defer_flatten(args: TokenStream2, enum_def: &DeriveInput, variant: &syn::Variant) -> TokenStream2 {
+    let crate_path = syn::parse2::<TraitListWithFlags>(args.clone()).ok().and_then(|parsed| parsed.flags.crate_path);
+    let inner_type = variant_field_type(variant);
+    let macro_path = match &inner_type {
+        Type::Path(path) if path.qself.is_none() && variant.fields.len() <= 1 => {
+            let mut macro_path = path.path.clone();
+            let last = macro_path.segments.last_mut().unwrap();
+            last.ident = format_ident!("__{}_flatten", last.ident.to_string().to_snake_case(), span = last.ident.span());
+            last.arguments = syn::PathArguments::None;
+            macro_path
+        }
+        _ => {
+            return syn::Error::new_spanned(variant, "`#[flatten]` variants hold a `#[tagged_dispatch]` enum, as `Variant(Enum)`")
+                .to_compile_error();
+        }
+    };
+    let variant_ident = &variant.ident;
+    let mut enum_def = enum_def.clone();
+    if let Data::Enum(data_enum) = &mut enum_def.data {
+        for variant in data_enum.variants.iter_mut().filter(|other| other.ident == *variant_ident) {
+            variant.attrs.retain(|attr| !attr.path().is_ident("flatten"));
         }
     }
-    
-    // Generate the dispatch implementation macro name
-    let macro_name = format_ident!("__impl_{}_dispatch", trait_name.to_string().to_snake_case());
-    
-    // Generate dispatch method implementations
-    let dispatch_impls: Vec<_> = dispatch_methods.iter().map(|method| {
-        generate_dispatch_method(method)
-    }).collect();
-    
     let output = quote! {
-        // The original trait
-        #trait_def
-        
-        // Hidden macro that implements dispatch for this trait
+        #macro_path! { [#args] #variant_ident #enum_def }
+    };
+    with_crate_path(output, crate_path.as_ref())
+}
+
+/// Generate `__<enum>_flatten!`, which lists the enum's variants for enums that
+/// `#[flatten]` it, re-exported next to the enum like the trait dispatch macros
+fn generate_flatten_macro(enum_name: &Ident, ptr_type: &TokenStream2, variants: &[(Ident, Type)], enum_attrs: &EnumAttrs) -> TokenStream2 {
+    let macro_name = format_ident!("__{}_flatten", enum_name.to_string().to_snake_case());
+    let listed = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let allocator = enum_attrs.allocator(i);
+        let inline = enum_attrs.is_inline(i);
+        quote! { (#variant, #ty, #allocator, #inline) }
+    });
+    quote! {
         #[doc(hidden)]
         macro_rules! #macro_name {
-            (
-                $enum_name:ident,
-                $enum_type_name:ident,
-                owned,
-                [$(($variant:ident, $type:ty)),* $(,)?]
-            ) => {
-                impl $enum_name {
-                    #(#dispatch_impls)*
-                }
-            };
-            
-            // Arena version with lifetime
-            (
-                $enum_name:ident,
-                $enum_type_name:ident,
-                $lifetime:lifetime,
-                [$(($variant:ident, $type:ty)),* $(,)?]
-            ) => {
-                impl<$lifetime> $enum_name<$lifetime> {
-                    #(#dispatch_impls)*
-                }
+            ($($enum_def:tt)*) => {
+                ::tagged_dispatch::__private::flatten_enum! { $($enum_def)* [#(#listed),*] }
             };
         }
-    };
-    
-    TokenStream::from(output)
-}
-
-/// Process an enum definition with #[tagged_dispatch(Trait1, Trait2, ...)]
-fn process_enum(args: TokenStream, mut enum_def: DeriveInput) -> TokenStream {
-    // Parse the trait list and flags
-    let parsed = parse_macro_input!(args as TraitListWithFlags);
-
-    let enum_name = &enum_def.ident;
-    let vis = &enum_def.vis;
-    let generics = &enum_def.generics;
-
-    // Check if this is an arena version (has lifetime parameter)
-    let has_lifetime = !generics.lifetimes().collect::<Vec<_>>().is_empty();
-    let lifetime = generics.lifetimes().next().map(|lt| &lt.lifetime);
-
-    // Transform enum variants to ensure they all have types
-    let variants = if let Data::Enum(ref mut data_enum) = enum_def.data {
-        process_enum_variants(data_enum)
-    } else {
-        return syn::Error::new_spanned(
-            enum_def,
-            "tagged_dispatch can only be applied to enums"
-        )
-        .to_compile_error()
-        .into();
-    };
 
-    // Generate the implementation based on whether it's arena or owned
-    if has_lifetime {
-        generate_arena_impl(enum_name, vis, lifetime.unwrap(), &variants, &parsed.traits, &parsed.flags)
-    } else {
-        generate_owned_impl(enum_name, vis, &variants, &parsed.traits, &parsed.flags)
-    }
-}
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #macro_name;
 
-/// Process enum variants, converting shorthand syntax to full syntax
-fn process_enum_variants(data_enum: &mut DataEnum) -> Vec<(Ident, Type)> {
-    data_enum.variants.iter_mut().map(|variant| {
-        match &mut variant.fields {
-            Fields::Unit => {
-                // Shorthand: convert `Circle` to `Circle(Circle)`
-                let type_name = &variant.ident;
-                let type_path: Type = syn::parse_quote!(#type_name);
-                
-                // Update the variant to have the type
-                variant.fields = Fields::Unnamed(syn::parse_quote!((#type_path)));
-                
-                (variant.ident.clone(), type_path)
-            }
-            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
-                // Already has a type: `Circle(SomeType)`
-                let inner_type = fields.unnamed.first().unwrap().ty.clone();
-                (variant.ident.clone(), inner_type)
-            }
-            _ => {
-                panic!("Each variant must either be a unit variant or have exactly one unnamed field");
+        impl #enum_name {
+            /// Rebuild a handle taken apart by an enum this one is flattened into
+            ///
+            /// # Safety
+            ///
+            /// `ptr` and `tag` must come from a handle of a flattened variant, re-tagged
+            /// to this enum's tags, and are owned by the new handle.
+            #[doc(hidden)]
+            #[inline]
+            pub unsafe fn __from_flattened(ptr: *mut (), tag: u16) -> Self {
+                Self(<#ptr_type>::new(ptr, tag), ::core::marker::PhantomData)
             }
         }
-    }).collect()
+    }
 }
 
-/// Generate implementation for owned version (no lifetime)
-fn generate_owned_impl(
-    enum_name: &Ident,
-    vis: &syn::Visibility,
-    variants: &[(Ident, Type)],
-    traits: &[Path],
-    flags: &TraitGenerationFlags,
-) -> TokenStream {
-    let enum_type_name = format_ident!("{}Type", enum_name);
-    
-    // Generate variant constructors
-    let constructors = variants.iter().enumerate().map(|(i, (variant, ty))| {
-        let tag = i as u8;
-        let method_name = format_ident!("{}", variant.to_string().to_snake_case());
-        quote! {
-            #[doc = concat!("Create a `", stringify!(#variant), "` variant")]
-            #[inline]
-            pub fn #method_name(value: #ty) -> Self {
-                let boxed = Box::new(value);
-                let ptr = Box::into_raw(boxed) as *mut ();
-                Self(::tagged_dispatch::TaggedPtr::new(ptr, #tag))
-            }
+/// Generate the conversions between an enum and the enums flattened into it, which
+/// only re-tag the handle, and `is_<variant>()` for each flattened variant
+fn generate_flatten_conversions(enum_name: &Ident, ptr_type: &TokenStream2, enum_attrs: &EnumAttrs) -> TokenStream2 {
+    // Flattened variants are spliced in one run per inner enum
+    let mut groups: Vec<(&Ident, &Type, usize, usize)> = Vec::new();
+    for (i, flattened) in enum_attrs.flattened.iter().enumerate() {
+        let Some((outer, inner)) = flattened else { continue };
+        match groups.last_mut() {
+            Some((last, _, start, len)) if *last == outer && *start + *len == i => *len += 1,
+            _ => groups.push((outer, inner, i, 1)),
         }
-    });
-    
-    // Generate From implementations
-    let from_impls = variants.iter().enumerate().map(|(i, (_variant, ty))| {
-        let tag = i as u8;
+    }
+    let conversions = groups.iter().map(|(outer, inner, start, len)| {
+        let is_name = format_ident!("is_{}", constructor_ident(outer));
+        let is_doc = format!("Whether the value is one of the `{}` variants", outer);
+        let start = *start as u16;
+        let end = start + *len as u16;
         quote! {
-            impl From<#ty> for #enum_name {
-                fn from(value: #ty) -> Self {
-                    let boxed = Box::new(value);
-                    let ptr = Box::into_raw(boxed) as *mut ();
-                    Self(::tagged_dispatch::TaggedPtr::new(ptr, #tag))
+            impl #enum_name {
+                #[doc = #is_doc]
+                #[inline]
+                pub fn #is_name(&self) -> bool {
+                    (#start..#end).contains(&self.0.tag())
                 }
             }
-        }
-    });
-    
-    // Generate Drop implementation
-    let drop_arms = variants.iter().enumerate().map(|(i, (_variant, ty))| {
-        let tag = i as u8;
-        quote! {
-            #tag => {
-                // Use untagged_ptr() for deallocation to ensure we pass
-                // the original pointer to Box::from_raw
-                let ptr = self.0.untagged_ptr() as *mut #ty;
-                drop(Box::from_raw(ptr));
+
+            impl From<#inner> for #enum_name {
+                #[inline]
+                fn from(value: #inner) -> Self {
+                    // Flattened variants keep the inner enum's storage, so only the tag changes
+                    let raw = value.into_raw();
+                    Self(<#ptr_type>::new(raw.untagged_ptr(), raw.tag() + #start), ::core::marker::PhantomData)
+                }
             }
-        }
-    });
-    
-    // Generate Clone implementation
-    let clone_arms = variants.iter().enumerate().map(|(i, (variant, ty))| {
-        let method_name = format_ident!("{}", variant.to_string().to_snake_case());
-        let tag = i as u8;
-        quote! {
-            #tag => {
-                // Use ptr() which benefits from TBI on supported platforms
-                let ptr = self.0.ptr() as *const #ty;
-                let cloned = (*ptr).clone();
-                Self::#method_name(cloned)
+
+            impl TryFrom<#enum_name> for #inner {
+                type Error = #enum_name;
+
+                #[inline]
+                fn try_from(value: #enum_name) -> Result<Self, Self::Error> {
+                    if !value.#is_name() {
+                        return Err(value);
+                    }
+                    let value = ::core::mem::ManuallyDrop::new(value);
+                    let ptr = value.0.untagged_ptr();
+                    let tag = value.0.tag() - #start;
+                    Ok(unsafe { <#inner>::__from_flattened(ptr, tag) })
+                }
             }
         }
     });
-    
-    // Generate enum variants
-    let enum_variants = variants.iter().map(|(variant, _)| {
-        quote! { #variant }
+    quote! { #(#conversions)* }
+}
+
+/// Whether any of an enum's variants is behind a `#[cfg(...)]`
+fn has_cfg_variants(enum_def: &DeriveInput) -> bool {
+    matches!(&enum_def.data, Data::Enum(data_enum)
+        if data_enum.variants.iter().any(|variant| cfg_attrs(&variant.attrs).next().is_some()))
+}
+
+/// Hand an enum with `#[cfg]` variants over to `TaggedDispatchCfg`, which generates it
+/// from the variants the compiler keeps
+fn defer_cfg_variants(args: TokenStream2, enum_def: &DeriveInput) -> TokenStream2 {
+    let crate_path = syn::parse2::<TraitListWithFlags>(args.clone()).ok().and_then(|parsed| parsed.flags.crate_path);
+    let Data::Enum(data_enum) = &enum_def.data else {
+        unreachable!("only enums have variants");
+    };
+    let enabled_name = format_ident!("__{}CfgVariants", enum_def.ident);
+    let variants = data_enum.variants.iter().map(|variant| {
+        let cfg_attrs = cfg_attrs(&variant.attrs);
+        let ident = &variant.ident;
+        quote! { #(#cfg_attrs)* #ident }
     });
-    
-    // Generate variant list for dispatch macros
-    let variant_list: Vec<_> = variants.iter().map(|(variant, ty)| {
-        quote! { (#variant, #ty) }
-    }).collect();
+    let output = quote! {
+        #[derive(::tagged_dispatch::__private::TaggedDispatchCfg)]
+        #[tagged_dispatch_cfg([#args] #enum_def)]
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        enum #enabled_name {
+            #(#variants,)*
+        }
+    };
+    with_crate_path(output, crate_path.as_ref())
+}
 
-    // Generate dispatch macro invocations for each trait
-    let dispatch_invocations = traits.iter().map(|trait_path| {
-        let trait_name = &trait_path.segments.last().unwrap().ident;
-        let macro_name = format_ident!("__impl_{}_dispatch", trait_name.to_string().to_snake_case());
-        let variant_list = variant_list.clone();
+/// Generate the dispatch glue for a trait defined elsewhere, such as in `std` or a
+/// third-party crate, from a redeclaration of the methods to dispatch.
+///
+/// Trait options go in an optional `#[tagged_dispatch(...)]` attribute. The trait
+/// itself is not redefined; enums list it by its bare name, with the trait in scope:
+///
+/// ```ignore
+/// use std::fmt::Write;
+///
+/// tagged_dispatch_external! {
+///     trait std::fmt::Write {
+///         fn write_str(&mut self, s: &str) -> std::fmt::Result;
+///     }
+/// }
+///
+/// #[tagged_dispatch(Write)]
+/// enum Sink {
+///     Buffer(String),
+///     Log,
+/// }
+/// ```
+///
+/// No `as_<trait>()` accessor is generated, since the redeclaration may leave out
+/// items that decide whether the trait is dyn-compatible.
+#[proc_macro]
+pub fn tagged_dispatch_external(input: TokenStream) -> TokenStream {
+    let external = parse_macro_input!(input as ExternalTrait);
+    let item_name = external.trait_def.ident.clone();
+    dump_expansion(&item_name, generate_trait_dispatch(external.options, external.trait_def, false))
+}
 
-        quote! {
-            #macro_name!(#enum_name, #enum_type_name, owned, [#(#variant_list),*]);
+/// Accept `enum_dispatch`'s attribute syntax, for switching a codebase between the
+/// two crates by changing an import.
+///
+/// `#[enum_dispatch]` on a trait and `#[enum_dispatch(Trait, ...)]` on an enum expand
+/// like `#[tagged_dispatch]`. The enum also gets a constructor named after each
+/// variant, so `Knob::LinearKnob(knob)` builds a handle as it built the enum value;
+/// `From` and `TryInto` between the enum and its variant types work unchanged:
+///
+/// ```ignore
+/// use tagged_dispatch::compat::enum_dispatch;
+///
+/// #[enum_dispatch]
+/// trait KnobControl {
+///     fn set_position(&mut self, value: f64);
+/// }
+///
+/// #[enum_dispatch(KnobControl)]
+/// enum Knob {
+///     LinearKnob,
+///     LogarithmicKnob,
+/// }
+///
+/// let mut knob: Knob = LinearKnob::default().into();
+/// knob.set_position(0.5);
+/// let linear: LinearKnob = knob.try_into().unwrap();
+/// ```
+///
+/// Traits are linked from the enum's attribute only; `#[enum_dispatch(Enum)]` on a
+/// trait is rejected. Handles can't be matched on like the enum, and enums with
+/// lifetimes are rejected rather than turned into arena enums.
+#[proc_macro_attribute]
+pub fn enum_dispatch(args: TokenStream, input: TokenStream) -> TokenStream {
+    if let Ok(trait_def) = syn::parse::<ItemTrait>(input.clone()) {
+        if !args.is_empty() {
+            let args = TokenStream2::from(args);
+            let message = format!(
+                "the `enum_dispatch` compatibility mode links traits from the enum side; \
+                 list `{}` in the enum's `#[enum_dispatch(...)]` instead",
+                trait_def.ident,
+            );
+            return syn::Error::new_spanned(args, message).to_compile_error().into();
         }
-    });
+        return tagged_dispatch(args, input);
+    }
+    let Ok(enum_def) = syn::parse::<DeriveInput>(input.clone()) else {
+        return tagged_dispatch(args, input);
+    };
+    if let Some(lifetime) = enum_def.generics.lifetimes().next() {
+        return syn::Error::new_spanned(lifetime, "`enum_dispatch` enums can't have lifetimes; use `#[tagged_dispatch]` for an arena enum")
+            .to_compile_error()
+            .into();
+    }
+    let constructors = match enum_dispatch_constructors(&enum_def) {
+        Ok(constructors) => constructors,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let crate_path = syn::parse::<TraitListWithFlags>(args.clone()).ok().and_then(|parsed| parsed.flags.crate_path);
+    let expanded = TokenStream2::from(tagged_dispatch(args, input));
+    let output = quote! {
+        #expanded
+        #constructors
+    };
+    with_crate_path(output, crate_path.as_ref()).into()
+}
 
-    // Generate compile-time trait checks
-    let trait_checks = traits.iter().flat_map(|trait_path| {
-        variants.iter().map(move |(_, ty)| {
+/// Generate the constructors named after each variant for `#[enum_dispatch]` enums,
+/// which forward to the generated ones through `FromVariant`
+fn enum_dispatch_constructors(enum_def: &DeriveInput) -> Result<TokenStream2> {
+    let Data::Enum(data_enum) = &enum_def.data else {
+        return Ok(TokenStream2::new());
+    };
+    let variants = process_enum_variants(&mut data_enum.clone())?;
+    let enum_name = &enum_def.ident;
+    let (impl_generics, ty_generics, where_clause) = enum_def.generics.split_for_impl();
+    // Variants with restricted constructors keep them restricted, and flattened
+    // variants are replaced by the inner enum's
+    let constructors = data_enum.variants.iter().zip(&variants)
+        .filter(|(variant, _)| {
+            !variant.attrs.iter().any(|attr| attr.path().is_ident("constructor_vis") || attr.path().is_ident("flatten"))
+        })
+        .map(|(variant, (ident, ty))| {
+            let cfg_attrs = cfg_attrs(&variant.attrs);
             quote! {
-                const _: fn() = || {
-                    fn assert_impl<T: #trait_path>() {}
-                    assert_impl::<#ty>();
-                };
+                #(#cfg_attrs)*
+                #[doc = concat!("Create a `", stringify!(#ident), "` variant, as `enum_dispatch` enums do")]
+                #[allow(non_snake_case)]
+                #[inline]
+                pub fn #ident(value: #ty) -> Self {
+                    <Self as ::tagged_dispatch::__private::FromVariant<#ty>>::from_variant(value)
+                }
             }
-        })
-    });
+        });
+    Ok(quote! {
+        impl #impl_generics #enum_name #ty_generics #where_clause {
+            #(#constructors)*
+        }
+    })
+}
 
-    // Conditionally generate trait implementations
-    let debug_impl = if flags.should_generate_debug() {
-        quote! {
-            impl ::core::fmt::Debug for #enum_name {
-                fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
-                    write!(f, "{}::{:?}", stringify!(#enum_name), self.tag_type())
-                }
+/// Input of `tagged_dispatch_external!`: `trait path::to::Trait { ... }`, optionally
+/// preceded by `#[tagged_dispatch(options)]`
+struct ExternalTrait {
+    options: TraitOptions,
+    trait_def: ItemTrait,
+}
+
+impl Parse for ExternalTrait {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut options = TraitOptions::default();
+        for attr in input.call(Attribute::parse_outer)? {
+            if !attr.path().is_ident("tagged_dispatch") {
+                return Err(syn::Error::new_spanned(attr, "expected `#[tagged_dispatch(...)]` trait options"));
             }
+            options = attr.parse_args()?;
         }
-    } else {
-        quote! {}
+        input.parse::<Token![trait]>()?;
+        let path: Path = input.parse()?;
+        let rest: TokenStream2 = input.parse()?;
+        // The last segment names the dispatch macro; the rest of the path only
+        // documents where the trait lives
+        let name = &path.segments.last().unwrap().ident;
+        let trait_def: ItemTrait = syn::parse2(quote! { trait #name #rest })?;
+        options.no_dyn = true;
+        Ok(ExternalTrait { options, trait_def })
+    }
+}
+
+/// Process an enum with #[derive(TaggedDispatch)]
+fn process_derive(enum_def: DeriveInput) -> TokenStream {
+    let mut args = DeriveArgs {
+        list: TraitListWithFlags {
+            traits: Vec::new(),
+            flags: TraitGenerationFlags::default(),
+        },
+        name: None,
+        arena: false,
     };
+    for attr in enum_def.attrs.iter().filter(|attr| attr.path().is_ident("dispatch")) {
+        if let Err(err) = attr.parse_args_with(|input: ParseStream| args.parse_into(input)) {
+            return err.to_compile_error().into();
+        }
+    }
 
-    let eq_impl = if flags.should_generate_eq() {
+    let enum_name = &enum_def.ident;
+    let vis = &enum_def.vis;
+
+    if args.list.flags.open {
+        return syn::Error::new_spanned(
+            enum_name,
+            "the `open` flag is only supported on `#[tagged_dispatch]` enums"
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let Data::Enum(data_enum) = &enum_def.data else {
+        return syn::Error::new_spanned(
+            &enum_def.ident,
+            "TaggedDispatch can only be derived for enums"
+        )
+        .to_compile_error()
+        .into();
+    };
+
+    if !enum_def.generics.params.is_empty() {
+        return syn::Error::new_spanned(
+            &enum_def.generics,
+            "#[derive(TaggedDispatch)] enums cannot have generic parameters; \
+             use `#[dispatch(arena)]` for an arena-allocated handle"
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if let Err(err) = check_variant_limit(enum_name, &enum_def.data, data_enum.variants.len(), &args.list.flags) {
+        return err.to_compile_error().into();
+    }
+
+    // Only value-carrying enums can be converted into the handle
+    let carries_values = data_enum.variants.iter().all(|variant| matches!(variant.fields, Fields::Unnamed(_)));
+    let variants = match process_enum_variants(&mut data_enum.clone()) {
+        Ok(variants) => variants,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let mut enum_attrs = match EnumAttrs::from_attrs(&enum_def.attrs) {
+        Ok(enum_attrs) => enum_attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    if let Err(err) = enum_attrs.read_variant_attrs(data_enum) {
+        return err.to_compile_error().into();
+    }
+    enum_attrs.apply_flags(&args.list.flags);
+    if let Err(err) = enum_attrs.read_nan_box(enum_name, &variants, &args.list.flags) {
+        return err.to_compile_error().into();
+    }
+    enum_attrs.infer_inline_values(&variants, &args.list.flags);
+
+    let handle_name = args.name.unwrap_or_else(|| format_ident!("Tagged{}", enum_name));
+    enum_attrs.apply_pool(&handle_name, &args.list.flags);
+    let traits = &args.list.traits;
+    let flags = &args.list.flags;
+
+    if args.arena {
+        let lifetime: syn::Lifetime = syn::parse_quote!('a);
+        let output = generate_arena_impl(&handle_name, vis, &lifetime, &variants, traits, flags, &enum_attrs);
+        return with_crate_path(output.into(), flags.crate_path.as_ref()).into();
+    }
+
+    let handle_impl = TokenStream2::from(generate_owned_impl(&handle_name, vis, &syn::Generics::default(), &variants, traits, flags, &enum_attrs));
+    let from_enum_impl = if carries_values {
+        let arms = variants.iter().enumerate().map(|(i, (variant, _))| {
+            let method_name = enum_attrs.constructor(i, variant);
+            quote! { #enum_name::#variant(value) => Self::#method_name(value) }
+        });
         quote! {
-            impl ::core::cmp::PartialEq for #enum_name {
-                fn eq(&self, other: &Self) -> bool {
-                    self.0.eq(&other.0)
+            impl From<#enum_name> for #handle_name {
+                fn from(value: #enum_name) -> Self {
+                    match value {
+                        #(#arms,)*
+                    }
                 }
             }
-
-            impl ::core::cmp::Eq for #enum_name {}
         }
     } else {
         quote! {}
     };
 
-    let ord_impl = if flags.should_generate_ord() {
-        quote! {
-            impl ::core::cmp::PartialOrd for #enum_name {
-                fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {
-                    self.0.partial_cmp(&other.0)
+    let output = quote! {
+        #handle_impl
+        #from_enum_impl
+    };
+    with_crate_path(output, flags.crate_path.as_ref()).into()
+}
+
+/// Rewrite the `::tagged_dispatch::` paths in generated code to start at `crate_path`
+/// instead, for crates that re-export `tagged_dispatch` (`crate = "..."`)
+fn with_crate_path(output: TokenStream2, crate_path: Option<&Path>) -> TokenStream2 {
+    fn rewrite(tokens: TokenStream2, crate_path: &Path) -> TokenStream2 {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        let mut rewritten = TokenStream2::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            if let [TokenTree::Punct(first), TokenTree::Punct(second), TokenTree::Ident(name), TokenTree::Punct(next), ..] = &tokens[i..] {
+                if first.as_char() == ':' && second.as_char() == ':' && name == "tagged_dispatch" && next.as_char() == ':' {
+                    rewritten.extend(quote! { #crate_path });
+                    i += 3;
+                    continue;
                 }
             }
-
-            impl ::core::cmp::Ord for #enum_name {
-                fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
-                    self.0.cmp(&other.0)
+            match &tokens[i] {
+                TokenTree::Group(group) => {
+                    let mut inner = proc_macro2::Group::new(group.delimiter(), rewrite(group.stream(), crate_path));
+                    inner.set_span(group.span());
+                    rewritten.extend([TokenTree::Group(inner)]);
                 }
+                token => rewritten.extend([token.clone()]),
             }
+            i += 1;
         }
-    } else {
-        quote! {}
-    };
+        rewritten
+    }
 
-    let output = quote! {
-        /// Tagged pointer dispatch type
-        #[repr(transparent)]
-        #vis struct #enum_name(::tagged_dispatch::TaggedPtr<()>);
+    match crate_path {
+        Some(crate_path) => rewrite(output, crate_path),
+        None => output,
+    }
+}
 
-        /// Type variants for compile-time checking
-        #[repr(u8)]
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-        #vis enum #enum_type_name {
-            #(#enum_variants,)*
-        }
+/// Environment variable naming a directory to write expanded code into
+const DUMP_ENV_VAR: &str = "TAGGED_DISPATCH_DUMP";
 
-        impl #enum_name {
-            #(#constructors)*
+/// Write the expansion of `item` to `$TAGGED_DISPATCH_DUMP/<crate>/<item>.rs` when the
+/// environment variable is set, returning the expansion unchanged.
+///
+/// Items with the same name in one crate are written as `<item>-2.rs`, `<item>-3.rs`, ...
+/// in expansion order. Dumping is best effort: I/O errors never fail the build.
+fn dump_expansion(item: &Ident, output: TokenStream) -> TokenStream {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
 
-            #[inline(always)]
-            pub fn tag_type(&self) -> #enum_type_name {
-                unsafe { ::core::mem::transmute(self.0.tag()) }
+    static SEEN: Mutex<Option<HashMap<String, usize>>> = Mutex::new(None);
+
+    let Some(dir) = std::env::var_os(DUMP_ENV_VAR) else {
+        return output;
+    };
+    let crate_name = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "unknown".into());
+    let dir = std::path::PathBuf::from(dir).join(&crate_name);
+
+    let file_stem = {
+        let mut seen = SEEN.lock().unwrap_or_else(|e| e.into_inner());
+        let count = seen
+            .get_or_insert_with(HashMap::new)
+            .entry(format!("{}::{}", crate_name, item))
+            .or_insert(0);
+        *count += 1;
+        if *count == 1 { item.to_string() } else { format!("{}-{}", item, count) }
+    };
+
+    let tokens = TokenStream2::from(output.clone());
+    let formatted = match syn::parse2::<syn::File>(tokens.clone()) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => tokens.to_string(),
+    };
+
+    let _ = std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(dir.join(format!("{}.rs", file_stem)), formatted));
+
+    output
+}
+
+/// Process a trait definition with #[tagged_dispatch]
+fn process_trait(args: TokenStream, trait_def: ItemTrait) -> TokenStream {
+    let options = parse_macro_input!(args as TraitOptions);
+    generate_trait_dispatch(options, trait_def, true)
+}
+
+/// Generate the hidden dispatch macro for a trait, along with the trait itself when
+/// `define_trait` is set (`tagged_dispatch_external!` only redeclares a foreign trait)
+fn generate_trait_dispatch(options: TraitOptions, mut trait_def: ItemTrait, define_trait: bool) -> TokenStream {
+    let trait_name = &trait_def.ident;
+    
+    // Extract methods that should be dispatched (those without #[no_dispatch])
+    let dispatch_methods: Vec<_> = trait_def.items.iter().filter_map(|item| {
+        if let TraitItem::Fn(method) = item {
+            let has_no_dispatch = method.attrs.iter().any(|attr| 
+                attr.path().is_ident("no_dispatch")
+            );
+            if !has_no_dispatch && method.sig.receiver().is_some() {
+                Some(method.clone())
+            } else {
+                None
             }
+        } else {
+            None
         }
+    }).collect();
 
-        #debug_impl
-        #eq_impl
-        #ord_impl
+    // Associated functions without a receiver get a per-variant lookup instead
+    // (`Trait::f()` becomes `value.f_for_variant()`), unless their signature
+    // mentions `Self` and so cannot be expressed on the enum. Associated consts
+    // likewise become a per-tag table plus an accessor (`Trait::PRIORITY`
+    // becomes `value.priority()`).
+    let variant_lookup_methods: Vec<_> = trait_def.items.iter().filter_map(|item| {
+        match item {
+            TraitItem::Fn(method) if method.sig.receiver().is_none() && !mentions_self(&method.sig) => {
+                Some(generate_variant_lookup_method(method))
+            }
+            TraitItem::Const(constant) if constant.generics.params.is_empty() && !mentions_self(&constant.ty) => {
+                Some(generate_const_lookup(constant, None))
+            }
+            _ => None,
+        }
+    }).collect();
+    
+    // Enums listing the trait as `qualified(Trait)` get every method suffixed with
+    // `_as_<trait>` instead, so traits sharing a method name can be dispatched together
+    let qualifier = format!("as_{}", trait_name.to_string().to_snake_case());
+    let qualified_lookup_methods: Vec<_> = trait_def.items.iter().filter_map(|item| {
+        match item {
+            TraitItem::Fn(method) if method.sig.receiver().is_none() && !mentions_self(&method.sig) => {
+                Some(generate_variant_lookup_method_named(method, Some(&qualifier)))
+            }
+            TraitItem::Const(constant) if constant.generics.params.is_empty() && !mentions_self(&constant.ty) => {
+                Some(generate_const_lookup(constant, Some(&qualifier)))
+            }
+            _ => None,
+        }
+    }).collect();
 
-        #(#from_impls)*
-        
-        impl Drop for #enum_name {
-            fn drop(&mut self) {
-                if self.0.is_null() {
-                    return;
-                }
-                
+    // Generate dispatch method implementations, evaluating opted-in default
+    // methods once at the enum level instead of inside every variant
+    let mut dispatch_impls = Vec::new();
+    let mut arena_dispatch_impls = Vec::new();
+    let mut qualified_dispatch_impls = Vec::new();
+    let mut qualified_arena_dispatch_impls = Vec::new();
+    for method in &dispatch_methods {
+        // Default bodies call sibling methods by their unqualified names, so
+        // qualified methods always dispatch per variant
+        qualified_dispatch_impls.push(generate_dispatch_method_named(method, Some(&qualifier), true, options.map_errors));
+        qualified_arena_dispatch_impls.push(generate_dispatch_method_named(method, Some(&qualifier), false, options.map_errors));
+
+        let dispatch_boxed = method.attrs.iter().any(|attr| attr.path().is_ident("dispatch_boxed"));
+        if dispatch_boxed && boxed_return_type(method).is_none() {
+            return syn::Error::new_spanned(
+                &method.sig.output,
+                "#[dispatch_boxed] requires an `impl Trait` return type"
+            )
+            .to_compile_error()
+            .into();
+        }
+
+        let has_enum_default = method.attrs.iter().any(|attr| attr.path().is_ident("enum_default"));
+        if has_enum_default && method.default.is_none() {
+            return syn::Error::new_spanned(
+                &method.sig,
+                "#[enum_default] requires a default method body"
+            )
+            .to_compile_error()
+            .into();
+        }
+
+        if (has_enum_default || options.enum_defaults) && method.default.is_some() {
+            dispatch_impls.push(generate_enum_default_method(method));
+            if !method.sig.receiver().is_some_and(|receiver| receiver.reference.is_some() && receiver.mutability.is_some()) {
+                arena_dispatch_impls.push(generate_enum_default_method(method));
+            }
+        } else {
+            dispatch_impls.push(generate_dispatch_method(method, true, options.map_errors));
+            arena_dispatch_impls.push(generate_dispatch_method(method, false, options.map_errors));
+        }
+    }
+
+    // `impl Trait for &Enum` forwards every dispatched method to the inherent one
+    let (ref_methods, qualified_ref_methods) = if options.ref_impls {
+        if let Err(err) = check_ref_impls(&trait_def) {
+            return err.to_compile_error().into();
+        }
+        (
+            dispatch_methods.iter().map(|method| generate_ref_forward(method, None)).collect(),
+            dispatch_methods.iter().map(|method| generate_ref_forward(method, Some(&qualifier))).collect(),
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    let ref_impls = |methods: &[TokenStream2], enum_ty: TokenStream2, generics: TokenStream2| {
+        if !options.ref_impls {
+            return quote! {};
+        }
+        quote! {
+            impl<'__r, #generics> $trait_path for &'__r #enum_ty {
+                #(#methods)*
+            }
+
+            impl<'__r, #generics> $trait_path for &'__r mut #enum_ty {
+                #(#methods)*
+            }
+        }
+    };
+    let owned_ref_impls = ref_impls(&ref_methods, quote! { $enum_name<$($args)*> }, quote! { $($params)* });
+    let arena_ref_impls = ref_impls(&ref_methods, quote! { $enum_name<$lifetime> }, quote! { $lifetime });
+    let qualified_owned_ref_impls = ref_impls(&qualified_ref_methods, quote! { $enum_name<$($args)*> }, quote! { $($params)* });
+    let qualified_arena_ref_impls = ref_impls(&qualified_ref_methods, quote! { $enum_name<$lifetime> }, quote! { $lifetime });
+
+    // Remove #[no_dispatch], #[enum_default] and #[dispatch_boxed] markers from trait members
+    for item in &mut trait_def.items {
+        if let TraitItem::Fn(method) = item {
+            method.attrs.retain(|attr| {
+                !attr.path().is_ident("no_dispatch")
+                    && !attr.path().is_ident("enum_default")
+                    && !attr.path().is_ident("dispatch_boxed")
+            });
+        }
+    }
+    
+    // Traits used like aliases (`trait GameObject: Draw + Update {}`) dispatch
+    // the methods of their supertraits, so one handle covers the whole set
+    let supertraits: Vec<_> = if options.supertraits || trait_def.items.is_empty() {
+        dispatched_supertraits(&trait_def)
+    } else {
+        Vec::new()
+    };
+    let supertraits: Vec<_> = if options.selected_supertraits.is_empty() {
+        supertraits
+    } else {
+        let supertrait_name = |path: &Path| path.segments.last().map(|segment| segment.ident.clone());
+        if let Some(unknown) = options.selected_supertraits.iter().find(|name| {
+            !supertraits.iter().any(|path| supertrait_name(path).as_ref() == Some(*name))
+        }) {
+            return syn::Error::new_spanned(
+                unknown,
+                format!("`{}` is not a dispatchable supertrait of `{}`", unknown, trait_name)
+            )
+            .to_compile_error()
+            .into();
+        }
+        supertraits.into_iter().filter(|path| {
+            supertrait_name(path).is_some_and(|name| options.selected_supertraits.contains(&name))
+        }).collect()
+    };
+    let supertrait_invocations = |qualified: TokenStream2, mode: TokenStream2, variant: TokenStream2| {
+        let invocations = supertraits.iter().map(|supertrait| {
+            let supertrait_macro = dispatch_macro_path(supertrait);
+            quote! {
+                #supertrait_macro!(#qualified #supertrait, $enum_name, $enum_type_name, #mode, [$(#variant),*], $vis $error_name);
+            }
+        });
+        quote! { #(#invocations)* }
+    };
+    let owned_variant = quote! { ($variant, $type, $alloc, $storage) };
+    let arena_variant = quote! { ($variant, $type, $storage) };
+    let owned_mode = quote! { owned [$($params)*] [$($args)*] };
+    let owned_supertraits = supertrait_invocations(quote! {}, owned_mode.clone(), owned_variant.clone());
+    let arena_supertraits = supertrait_invocations(quote! {}, quote! { $lifetime }, arena_variant.clone());
+    let qualified_owned_supertraits = supertrait_invocations(quote! { @qualified }, owned_mode, owned_variant);
+    let qualified_arena_supertraits = supertrait_invocations(quote! { @qualified }, quote! { $lifetime }, arena_variant);
+
+    // `as_<trait>()` hands the value out as a trait object, for dyn-compatible traits
+    let as_dyn = |owned: bool| {
+        if options.no_dyn || !is_dyn_compatible(&trait_def) {
+            return quote! {};
+        }
+        let method_name = format_ident!("{}", qualifier);
+        let with_name = format_ident!("with_{}", trait_name.to_string().to_snake_case());
+        let doc = format!("Borrow the value as a `&dyn {}`, for APIs taking trait objects", trait_name);
+        let with_doc = format!("Run `f` on the value as a `&dyn {}` and return its result", trait_name);
+        let check_handle = (!owned).then(|| quote! {
+            if !<$storage as ::tagged_dispatch::__private::VariantStorage<$type>>::INLINE {
+                ::tagged_dispatch::__private::check_arena_handle(stringify!($enum_name), self.0.untagged_ptr() as *const $type, self.0.tag());
+            }
+        });
+        quote! {
+            #[doc = #doc]
+            #[inline]
+            // A private trait leaves the method usable only where the trait is, and it's
+            // generated for every dyn-compatible trait whether or not it's called
+            #[allow(private_interfaces, dead_code)]
+            pub fn #method_name(&self) -> &dyn $trait_path {
                 unsafe {
-                    match self.0.tag() {
-                        #(#drop_arms)*
-                        _ => unreachable!("Invalid tag"),
+                    match self.tag_type() {
+                        $(
+                            $enum_type_name::$variant => {
+                                #check_handle
+                                <$storage as ::tagged_dispatch::__private::VariantStorage<$type>>::borrow(self.0.ptr() as usize, self.0.bits_ref())
+                            }
+                        )*
                     }
                 }
             }
+
+            #[doc = #with_doc]
+            #[inline]
+            #[allow(private_bounds, dead_code)]
+            pub fn #with_name<R>(&self, f: impl FnOnce(&dyn $trait_path) -> R) -> R {
+                f(self.#method_name())
+            }
         }
-        
-        impl Clone for #enum_name {
-            fn clone(&self) -> Self {
-                unsafe {
-                    match self.0.tag() {
-                        #(#clone_arms)*
-                        _ => unreachable!("Invalid tag"),
+    };
+    let owned_as_dyn = as_dyn(true);
+    let arena_as_dyn = as_dyn(false);
+
+    // `open` enums implement the trait for their plugin type, calling through the
+    // registered type's trait object; supertraits are reached by upcasting
+    let plugin_impl = match check_plugin_trait(&trait_def, &dispatch_methods, &options) {
+        Err(reason) => {
+            let message = format!("`{}` can't be dispatched to the plugins of an `open` enum: {}", trait_name, reason);
+            quote! { compile_error!(#message); }
+        }
+        Ok(()) => {
+            let forwards = dispatch_methods.iter().map(generate_plugin_forward);
+            let supertrait_impls = supertraits.iter().map(|supertrait| {
+                let supertrait_macro = dispatch_macro_path(supertrait);
+                quote! {
+                    impl ::tagged_dispatch::__private::PluginCast<dyn #supertrait> for $plugin {
+                        #[inline]
+                        fn cast(&self) -> &(dyn #supertrait + 'static) {
+                            ::tagged_dispatch::__private::PluginCast::<dyn $trait_path>::cast(self)
+                        }
+
+                        #[inline]
+                        fn cast_mut(&mut self) -> &mut (dyn #supertrait + 'static) {
+                            ::tagged_dispatch::__private::PluginCast::<dyn $trait_path>::cast_mut(self)
+                        }
                     }
+                    #supertrait_macro!(@registered #supertrait, $plugin);
+                }
+            });
+            quote! {
+                impl $trait_path for $plugin {
+                    #(#forwards)*
                 }
+                #(#supertrait_impls)*
             }
         }
+    };
+
+    // `map_errors` traits define the enum's unified error type, one variant per enum variant
+    let has_error_type = trait_def.items.iter().any(|item| matches!(item, TraitItem::Type(ty) if ty.ident == "Error"));
+    if options.map_errors && !has_error_type {
+        return syn::Error::new_spanned(trait_name, "`map_errors` requires an associated `type Error`")
+            .to_compile_error()
+            .into();
+    }
+    // Generic owned enums pass their type parameters along, which the error type shares
+    let error_enum = |params: TokenStream2, args: TokenStream2| options.map_errors.then(|| quote! {
+        #[doc = concat!("Error returned by `", stringify!($enum_name), "`'s `", stringify!(#trait_name), "` methods, holding the variant's own error")]
+        #[derive(Debug)]
+        #[allow(dead_code)] // Callers may only ever look at the tag
+        $vis enum $error_name<#params> {
+            $(
+                #[allow(missing_docs)]
+                $variant(<$type as $trait_path>::Error),
+            )*
+        }
+
+        #[allow(dead_code)]
+        impl<#params> $error_name<#args> {
+            /// Get the variant the error came from
+            pub fn tag_type(&self) -> $enum_type_name {
+                match self {
+                    $($error_name::$variant(_) => $enum_type_name::$variant,)*
+                }
+            }
+        }
+    });
+    let owned_error_enum = error_enum(quote! { $($params)* }, quote! { $($args)* });
+    let arena_error_enum = error_enum(quote! {}, quote! {});
+
+    // Generate the dispatch implementation macro name. It is re-exported from the trait's
+    // module so enums elsewhere can reach it through the trait's path; `export` puts it at
+    // the crate root under a unique name (macro names there are shared by every module).
+    let macro_name = format_ident!("__impl_{}_dispatch", trait_name.to_string().to_snake_case());
+    let (defined_name, macro_export, reexport) = if options.export {
+        static EXPORTED: AtomicUsize = AtomicUsize::new(0);
+        let id = EXPORTED.fetch_add(1, AtomicOrdering::Relaxed);
+        let exported_name = format_ident!("__tagged_dispatch_{}_{}", trait_name.to_string().to_snake_case(), id);
+        (exported_name.clone(), quote! { #[macro_export] }, quote! { pub use #exported_name as #macro_name; })
+    } else {
+        (macro_name.clone(), quote! {}, quote! { pub(crate) use #macro_name; })
+    };
+
+    let trait_item = define_trait.then(|| quote! { #trait_def });
+
+    let output = quote! {
+        // The original trait
+        #trait_item
         
-        // Apply dispatch implementations for each trait
-        #(#dispatch_invocations)*
-        
-        // Compile-time trait implementation checks
-        #(#trait_checks)*
-        
-        // Size assertion
-        const _: () = assert!(::core::mem::size_of::<#enum_name>() == 8);
+        // Hidden macro that implements dispatch for this trait
+        #[doc(hidden)]
+        #macro_export
+        macro_rules! #defined_name {
+            (@registered $trait_path:path, $plugin:ty) => {
+                #plugin_impl
+            };
+
+            (
+                @qualified
+                $trait_path:path,
+                $enum_name:ident,
+                $enum_type_name:ident,
+                owned [$($params:tt)*] [$($args:tt)*],
+                [$(($variant:ident, $type:ty, $alloc:ty, $storage:ty)),* $(,)?],
+                $vis:vis $error_name:ident
+            ) => {
+                #owned_error_enum
+                impl<$($params)*> $enum_name<$($args)*> {
+                    #(#qualified_dispatch_impls)*
+                    #(#qualified_lookup_methods)*
+                    #owned_as_dyn
+                }
+                #qualified_owned_ref_impls
+                #qualified_owned_supertraits
+            };
+
+            (
+                @qualified
+                $trait_path:path,
+                $enum_name:ident,
+                $enum_type_name:ident,
+                $lifetime:lifetime,
+                [$(($variant:ident, $type:ty, $storage:ty)),* $(,)?],
+                $vis:vis $error_name:ident
+            ) => {
+                #arena_error_enum
+                impl<$lifetime> $enum_name<$lifetime> {
+                    #(#qualified_arena_dispatch_impls)*
+                    #(#qualified_lookup_methods)*
+                    #arena_as_dyn
+                }
+                #qualified_arena_ref_impls
+                #qualified_arena_supertraits
+            };
+
+            (
+                $trait_path:path,
+                $enum_name:ident,
+                $enum_type_name:ident,
+                owned [$($params:tt)*] [$($args:tt)*],
+                [$(($variant:ident, $type:ty, $alloc:ty, $storage:ty)),* $(,)?],
+                $vis:vis $error_name:ident
+            ) => {
+                #owned_error_enum
+                impl<$($params)*> $enum_name<$($args)*> {
+                    #(#dispatch_impls)*
+                    #(#variant_lookup_methods)*
+                    #owned_as_dyn
+                }
+                #owned_ref_impls
+                #owned_supertraits
+            };
+            
+            // Arena version with lifetime
+            (
+                $trait_path:path,
+                $enum_name:ident,
+                $enum_type_name:ident,
+                $lifetime:lifetime,
+                [$(($variant:ident, $type:ty, $storage:ty)),* $(,)?],
+                $vis:vis $error_name:ident
+            ) => {
+                #arena_error_enum
+                impl<$lifetime> $enum_name<$lifetime> {
+                    #(#arena_dispatch_impls)*
+                    #(#variant_lookup_methods)*
+                    #arena_as_dyn
+                }
+                #arena_ref_impls
+                #arena_supertraits
+            };
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        #reexport
     };
     
-    TokenStream::from(output)
+    with_crate_path(output, options.crate_path.as_ref()).into()
 }
 
-/// Generate implementation for arena version (has lifetime)
-fn generate_arena_impl(
-    enum_name: &Ident,
-    vis: &syn::Visibility,
-    lifetime: &syn::Lifetime,
-    variants: &[(Ident, Type)],
-    traits: &[Path],
-    flags: &TraitGenerationFlags,
-) -> TokenStream {
-    let enum_type_name = format_ident!("{}Type", enum_name);
-    let builder_name = format_ident!("{}ArenaBuilder", enum_name);
-    let arena_type_name = format_ident!("{}ArenaType", enum_name);
+/// Path of the dispatch macro generated for the trait at `trait_path`, which the trait
+/// macro re-exports next to the trait (`shapes::Draw` -> `shapes::__impl_draw_dispatch`)
+fn dispatch_macro_path(trait_path: &Path) -> Path {
+    let mut macro_path = trait_path.clone();
+    let last = macro_path.segments.last_mut().unwrap();
+    last.ident = format_ident!("__impl_{}_dispatch", last.ident.to_string().to_snake_case(), span = last.ident.span());
+    last.arguments = syn::PathArguments::None;
+    macro_path
+}
 
-    // Generate typed arena field declarations for each variant
-    let typed_arena_fields: Vec<_> = variants.iter().map(|(variant, ty)| {
-        let field_name = format_ident!("{}_arena", variant.to_string().to_snake_case());
-        quote! { #field_name: ::typed_arena::Arena<#ty> }
-    }).collect();
+/// Process an enum definition with #[tagged_dispatch(Trait1, Trait2, ...)]
+fn process_enum(args: TokenStream, mut enum_def: DeriveInput) -> TokenStream {
+    // Parse the trait list and flags
+    let parsed = parse_macro_input!(args as TraitListWithFlags);
 
-    // Generate typed arena field initializations
-    let typed_arena_inits: Vec<_> = variants.iter().map(|(variant, _ty)| {
-        let field_name = format_ident!("{}_arena", variant.to_string().to_snake_case());
-        quote! { #field_name: ::typed_arena::Arena::new() }
-    }).collect();
+    // `open` enums get one more variant for the types registered at runtime
+    if parsed.flags.open {
+        if let Err(err) = add_plugin_variant(&mut enum_def, &parsed.flags) {
+            return err.to_compile_error().into();
+        }
+    }
 
-    // Clone for second usage in reset
-    let typed_arena_inits2 = typed_arena_inits.clone();
+    let enum_name = &enum_def.ident;
+    let vis = &enum_def.vis;
+    let generics = &enum_def.generics;
 
-    // Generate builder methods for each variant
-    let builder_methods = variants.iter().enumerate().map(|(i, (variant, ty))| {
-        let tag = i as u8;
-        let method_name = format_ident!("{}", variant.to_string().to_snake_case());
-        let field_name = format_ident!("{}_arena", variant.to_string().to_snake_case());
+    // Check if this is an arena version (has lifetime parameter)
+    let has_lifetime = !generics.lifetimes().collect::<Vec<_>>().is_empty();
+    let lifetime = generics.lifetimes().next().map(|lt| &lt.lifetime);
 
-        // Generate allocator match arms based on enabled features at macro build time
-        let allocator_arms = generate_allocator_arms(&field_name, ty, &arena_type_name);
+    // Transform enum variants to ensure they all have types
+    let variants = if let Data::Enum(ref mut data_enum) = enum_def.data {
+        match process_enum_variants(data_enum) {
+            Ok(variants) => variants,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        return syn::Error::new_spanned(
+            enum_def,
+            "tagged_dispatch can only be applied to enums"
+        )
+        .to_compile_error()
+        .into();
+    };
 
-        quote! {
-            #[doc = concat!("Create a `", stringify!(#variant), "` variant in the arena")]
-            #[inline]
-            pub fn #method_name(&#lifetime self, value: #ty) -> #enum_name<#lifetime> {
-                let ptr = match &self.allocator {
-                    #allocator_arms
-                };
+    if let Err(err) = check_variant_limit(enum_name, &enum_def.data, variants.len(), &parsed.flags) {
+        return err.to_compile_error().into();
+    }
+
+    let mut enum_attrs = match EnumAttrs::from_attrs(&enum_def.attrs) {
+        Ok(enum_attrs) => enum_attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    if let Data::Enum(data_enum) = &enum_def.data {
+        if let Err(err) = enum_attrs.read_variant_attrs(data_enum) {
+            return err.to_compile_error().into();
+        }
+    }
+    enum_attrs.apply_flags(&parsed.flags);
+    if let Err(err) = enum_attrs.read_nan_box(enum_name, &variants, &parsed.flags) {
+        return err.to_compile_error().into();
+    }
+    // Type parameters rule out inline values, so generic enums box every variant
+    if generics.type_params().next().is_none() {
+        enum_attrs.infer_inline_values(&variants, &parsed.flags);
+    }
+    enum_attrs.apply_pool(enum_name, &parsed.flags);
+
+    if let Err(err) = check_generics(generics, &parsed.flags, &enum_attrs) {
+        return err.to_compile_error().into();
+    }
+
+    // Generate the implementation based on whether it's arena or owned
+    let output = if has_lifetime {
+        generate_arena_impl(enum_name, vis, lifetime.unwrap(), &variants, &parsed.traits, &parsed.flags, &enum_attrs)
+    } else {
+        generate_owned_impl(enum_name, vis, generics, &variants, &parsed.traits, &parsed.flags, &enum_attrs)
+    };
+    with_crate_path(output.into(), parsed.flags.crate_path.as_ref()).into()
+}
+
+/// Add the `Plugin(<Enum>Plugin)` variant of an `open` enum
+fn add_plugin_variant(enum_def: &mut DeriveInput, flags: &TraitGenerationFlags) -> Result<()> {
+    let enum_name = &enum_def.ident;
+    let conflict = if !enum_def.generics.params.is_empty() {
+        Some("the `open` flag is only supported on owned enums without type parameters")
+    } else if flags.send || flags.sync {
+        Some("registered types aren't checked for `Send` or `Sync`, so `open` enums can't be `send` or `sync`")
+    } else {
+        None
+    };
+    if let Some(conflict) = conflict {
+        return Err(syn::Error::new_spanned(enum_name, conflict));
+    }
+    let Data::Enum(data_enum) = &mut enum_def.data else {
+        return Ok(());
+    };
+    if let Some(variant) = data_enum.variants.iter().find(|variant| variant.ident == "Plugin") {
+        return Err(syn::Error::new_spanned(variant, "`open` enums name their own `Plugin` variant"));
+    }
+    let plugin_name = format_ident!("{}Plugin", enum_name);
+    let doc = format!("A value of a type registered with `{}Registry`", enum_name);
+    data_enum.variants.push(syn::parse_quote! {
+        #[doc = #doc]
+        Plugin(#plugin_name)
+    });
+    Ok(())
+}
+
+/// Generate the plugin type and `<Enum>Registry` of an `open` enum.
+///
+/// Every registered type gets a table of casts to each dispatched trait's object,
+/// which the trait's dispatch macro implements the trait for the plugin type with.
+fn generate_plugin_registry(vis: &syn::Visibility, enum_name: &Ident, variants: &[(Ident, Type)], traits: &[DispatchTrait]) -> TokenStream2 {
+    let plugin_name = format_ident!("{}Plugin", enum_name);
+    let registry_name = format_ident!("{}Registry", enum_name);
+    let casts_name = format_ident!("{}PluginCasts", enum_name);
+    let trait_paths: Vec<_> = traits.iter()
+        .map(|dispatch_trait| &dispatch_trait.path)
+        .filter(|path| operator_trait(path).is_none())
+        .collect();
+    let cast_fns: Vec<_> = (0..trait_paths.len()).map(|i| format_ident!("cast_{}", i)).collect();
+    let indices = (0..trait_paths.len()).map(syn::Index::from);
+    let plugin_invocations = trait_paths.iter().map(|path| {
+        let macro_name = dispatch_macro_path(path);
+        quote! { #macro_name!(@registered #path, #plugin_name); }
+    });
+    let plugin_doc = format!(
+        "A value of a type registered with [`{}`], for the `Plugin` variant of [`{}`]",
+        registry_name, enum_name,
+    );
+    let registry_doc = format!("The types registered as plugins of [`{}`]", enum_name);
+    let fingerprint = plugin_abi_fingerprint(enum_name, variants, &trait_paths);
+
+    quote! {
+        /// Casts of a registered value to each dispatched trait's object
+        #[derive(Clone, Copy)]
+        struct #casts_name(#(fn(::core::ptr::NonNull<()>) -> ::core::ptr::NonNull<dyn #trait_paths>),*);
+
+        #[doc = #plugin_doc]
+        #[derive(Clone, Debug)]
+        #vis struct #plugin_name(::tagged_dispatch::plugin::Plugin<#casts_name>);
+
+        impl #plugin_name {
+            /// Box `value` as a plugin, or give it back if its type isn't registered
+            pub fn new<T: 'static>(value: T) -> ::core::result::Result<Self, T> {
+                match #registry_name::types().get::<T>() {
+                    Some(ty) => ::tagged_dispatch::plugin::Plugin::new(ty, value).map(Self),
+                    None => Err(value),
+                }
+            }
+
+            /// The registered type's name
+            pub fn type_name(&self) -> &'static str {
+                self.0.plugin_type().type_name()
+            }
+
+            /// Borrow the value if it is a `T`
+            pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+                self.0.downcast_ref()
+            }
+
+            /// Borrow the value mutably if it is a `T`
+            pub fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+                self.0.downcast_mut()
+            }
+
+            /// Borrow the value as one of the dispatched traits' objects, e.g.
+            /// `as_dyn::<dyn Draw>()`
+            ///
+            /// The reference points at the registered type's own vtable, so a run of calls
+            /// through it looks up the plugin table once instead of on every call.
+            #[inline]
+            #[allow(private_bounds)]
+            pub fn as_dyn<D: ?Sized>(&self) -> &D
+            where
+                Self: ::tagged_dispatch::__private::PluginCast<D>,
+            {
+                ::tagged_dispatch::__private::PluginCast::<D>::cast(self)
+            }
+
+            /// Borrow the value mutably as one of the dispatched traits' objects, resolved
+            /// once like [`as_dyn`](Self::as_dyn)
+            #[inline]
+            #[allow(private_bounds)]
+            pub fn as_dyn_mut<D: ?Sized>(&mut self) -> &mut D
+            where
+                Self: ::tagged_dispatch::__private::PluginCast<D>,
+            {
+                ::tagged_dispatch::__private::PluginCast::<D>::cast_mut(self)
+            }
+        }
+
+        #(
+            impl ::tagged_dispatch::__private::PluginCast<dyn #trait_paths> for #plugin_name {
+                #[inline]
+                fn cast(&self) -> &(dyn #trait_paths + 'static) {
+                    // SAFETY: the cast belongs to the value's type, and `self` owns the value
+                    unsafe { (self.0.plugin_type().casts().#indices)(self.0.as_ptr()).as_ref() }
+                }
+
+                #[inline]
+                fn cast_mut(&mut self) -> &mut (dyn #trait_paths + 'static) {
+                    unsafe { (self.0.plugin_type().casts().#indices)(self.0.as_ptr()).as_mut() }
+                }
+            }
+        )*
+
+        #(#plugin_invocations)*
+
+        #[doc = #registry_doc]
+        #vis struct #registry_name;
+
+        impl #registry_name {
+            /// The ABI this build of the enum registers plugins with
+            pub const ABI: ::tagged_dispatch::plugin::Abi = ::tagged_dispatch::plugin::Abi::new(#fingerprint);
+
+            fn types() -> &'static ::tagged_dispatch::plugin::PluginRegistry<#casts_name> {
+                static TYPES: ::tagged_dispatch::plugin::PluginRegistry<#casts_name> = ::tagged_dispatch::plugin::PluginRegistry::new(#registry_name::ABI);
+                &TYPES
+            }
+
+            /// Register `T`, so `new` accepts its values; registering it again has no effect
+            ///
+            /// Fails if this code was built against another version of the enum than the
+            /// registry, e.g. in a plugin library that wasn't rebuilt.
+            #[allow(private_bounds)]
+            pub fn register<T: #(#trait_paths +)* Clone + 'static>() -> ::core::result::Result<(), ::tagged_dispatch::plugin::RegisterError> {
+                #(
+                    fn #cast_fns<T: #trait_paths + 'static>(value: ::core::ptr::NonNull<()>) -> ::core::ptr::NonNull<dyn #trait_paths> {
+                        value.cast::<T>()
+                    }
+                )*
+                Self::types().register::<T>(Self::ABI, #casts_name(#(#cast_fns::<T>),*)).map(|_| ())
+            }
+
+            /// Whether `T` is registered
+            pub fn is_registered<T: 'static>() -> bool {
+                Self::types().get::<T>().is_some()
+            }
+
+            /// The names of the registered types, most recently registered first
+            pub fn type_names() -> impl Iterator<Item = &'static str> {
+                Self::types().iter().map(|ty| ty.type_name())
+            }
+        }
+    }
+}
+
+/// Hash an `open` enum's name, variants, and plugin traits (FNV-1a), so plugins built
+/// against a different definition are caught when they register
+fn plugin_abi_fingerprint(enum_name: &Ident, variants: &[(Ident, Type)], trait_paths: &[&Path]) -> u64 {
+    let mut text = enum_name.to_string();
+    for (variant, ty) in variants {
+        text.push_str(&format!(";{}({})", variant, quote! { #ty }));
+    }
+    for path in trait_paths {
+        text.push_str(&format!(";{}", quote! { #path }));
+    }
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Reject type parameters where they aren't supported: on arena enums, in `where`
+/// clauses, and with options whose compile-time checks need concrete variant types
+fn check_generics(generics: &syn::Generics, flags: &TraitGenerationFlags, enum_attrs: &EnumAttrs) -> Result<()> {
+    let Some(param) = generics.params.iter().find(|param| !matches!(param, syn::GenericParam::Lifetime(_))) else {
+        return Ok(());
+    };
+    if generics.lifetimes().next().is_some() {
+        return Err(syn::Error::new_spanned(param, "type parameters are only supported on owned enums"));
+    }
+    if let Some(where_clause) = &generics.where_clause {
+        return Err(syn::Error::new_spanned(where_clause, "bound the enum's type parameters in place instead of in a `where` clause"));
+    }
+    let unsupported = if flags.low_tag || flags.low_tag_bits() > 0 {
+        Some("`tag = \"low\"` and `tag_bits`")
+    } else if flags.nan_box {
+        Some("`nan_box`")
+    } else if flags.cow {
+        Some("the `cow` flag")
+    } else if flags.bytes {
+        Some("the `bytes` flag")
+    } else if flags.drop_table {
+        // The table is a static, which can't name the enum's type parameters
+        Some("the `drop_table` flag")
+    } else if enum_attrs.inline_values.iter().any(|inline| *inline) {
+        Some("`#[inline_value]` variants")
+    } else {
+        None
+    };
+    match unsupported {
+        Some(option) => Err(syn::Error::new_spanned(param, format!("{} can't be used on enums with type parameters", option))),
+        None => Ok(()),
+    }
+}
+
+/// Reject enums with more variants than the tag can represent.
+///
+/// The tag only has room for `flags.max_variants()` distinct values, so this is checked
+/// at expansion time rather than letting the tag silently wrap at runtime.
+fn check_variant_limit(enum_name: &Ident, data: &Data, count: usize, flags: &TraitGenerationFlags) -> Result<()> {
+    let max_variants = flags.max_variants();
+    if count <= max_variants {
+        return Ok(());
+    }
+    let first_excess = match data {
+        Data::Enum(data_enum) => &data_enum.variants[max_variants],
+        _ => unreachable!(),
+    };
+    if flags.nan_box {
+        return Err(syn::Error::new_spanned(
+            first_excess,
+            format!(
+                "`nan_box` enums hold at most {} variants (3-bit tag in the NaN payload), but `{}` declares {}",
+                max_variants, enum_name, count,
+            ),
+        ));
+    }
+    if flags.bytes && max_variants < 1 << (HIGH_TAG_BITS + flags.low_tag_bits()) {
+        return Err(syn::Error::new_spanned(
+            first_excess,
+            format!(
+                "the `bytes` flag encodes a one-byte tag, so `{}` holds at most {} variants but declares {}; split it into several smaller tagged enums",
+                enum_name, max_variants, count,
+            ),
+        ));
+    }
+    let tag_bits = HIGH_TAG_BITS + flags.low_tag_bits();
+    let needed_bits = usize::BITS - (count - 1).leading_zeros();
+    // Only layouts that spill the tag into the low bits can take `tag_bits`
+    let low_bits = needed_bits.checked_sub(HIGH_TAG_BITS).filter(|bits| *bits > 0);
+    let hint = match low_bits {
+        Some(low_bits) if needed_bits <= MAX_TAG_BITS && !flags.bytes && !flags.low_tag => format!(
+            "use `tag_bits = {}` (variant types must then be aligned to {} bytes) or split it into several smaller tagged enums",
+            needed_bits,
+            1usize << low_bits,
+        ),
+        _ => "split it into several smaller tagged enums".to_string(),
+    };
+    Err(syn::Error::new_spanned(
+        first_excess,
+        format!(
+            "tagged_dispatch supports at most {} variants ({}-bit tag), but `{}` declares {}; {}",
+            max_variants,
+            tag_bits,
+            enum_name,
+            count,
+            hint,
+        )
+    ))
+}
+
+/// Process enum variants, converting shorthand syntax to full syntax.
+///
+/// A shorthand variant holds the type of the same name, or the one given by
+/// `#[variant_type = "path::to::Type"]`.
+fn process_enum_variants(data_enum: &mut DataEnum) -> Result<Vec<(Ident, Type)>> {
+    data_enum.variants.iter_mut().map(|variant| {
+        let variant_type = variant.attrs.iter()
+            .find(|attr| attr.path().is_ident("variant_type"))
+            .map(|attr| match &attr.meta {
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }),
+                    ..
+                }) => lit.parse::<Type>(),
+                _ => Err(syn::Error::new_spanned(attr, "expected `#[variant_type = \"path::to::Type\"]`")),
+            })
+            .transpose()?;
+        match &mut variant.fields {
+            Fields::Unit => {
+                // Shorthand: convert `Circle` to `Circle(Circle)`
+                let type_name = &variant.ident;
+                let type_path: Type = variant_type.unwrap_or_else(|| syn::parse_quote!(#type_name));
+                
+                // Update the variant to have the type
+                variant.fields = Fields::Unnamed(syn::parse_quote!((#type_path)));
+                
+                Ok((variant.ident.clone(), type_path))
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                if variant_type.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        &variant.ident,
+                        "`#[variant_type]` is only for shorthand variants; this variant already names its type",
+                    ));
+                }
+                // Already has a type: `Circle(SomeType)`
+                let inner_type = fields.unnamed.first().unwrap().ty.clone();
+                Ok((variant.ident.clone(), inner_type))
+            }
+            _ => {
+                panic!("Each variant must either be a unit variant or have exactly one unnamed field");
+            }
+        }
+    }).collect()
+}
+
+/// Whether another variant holds the same type as variant `i`, so conversions keyed
+/// by the type can't be generated for either
+fn shares_type(variants: &[(Ident, Type)], i: usize) -> bool {
+    let ty = &variants[i].1;
+    variants.iter().enumerate().any(|(j, (_, other))| j != i && other == ty)
+}
+
+/// Name of the generated constructor for a variant (`Circle` -> `circle`).
+///
+/// The identifier carries the variant's span so IDE navigation from a
+/// constructor call lands on the variant declaration.
+fn constructor_ident(variant: &Ident) -> Ident {
+    Ident::new(&variant.to_string().to_snake_case(), variant.span())
+}
+
+/// Generate `is_<variant>()`, `as_<variant>()` and `try_into_<variant>()` for looking
+/// at the concrete variant, like matching on a plain enum.
+///
+/// `try_into_<variant>()` backs the `TryFrom` impls; arena handles (with `lifetime`)
+/// give up a reference into the arena rather than the value.
+/// With `mutable`, boxed variants also get `as_<variant>_mut()`, which mutates in
+/// place and so is only as visible as the variant's constructors.
+fn generate_variant_accessors(
+    enum_type_name: &Ident,
+    variants: &[(Ident, Type)],
+    enum_attrs: &EnumAttrs,
+    lifetime: Option<&syn::Lifetime>,
+    mutable: bool,
+) -> TokenStream2 {
+    let accessors = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let name = constructor_ident(variant);
+        let is_name = format_ident!("is_{}", name, span = variant.span());
+        let as_name = format_ident!("as_{}", name, span = variant.span());
+        let try_into_name = format_ident!("try_into_{}", name, span = variant.span());
+        let storage = enum_attrs.storage(i);
+        let target = match lifetime {
+            Some(lifetime) if !enum_attrs.is_inline(i) => quote! { &#lifetime #ty },
+            _ => quote! { #ty },
+        };
+        let take_value = match lifetime {
+            None => {
+                let allocator = enum_attrs.allocator(i);
+                quote! {
+                    let value = ::core::mem::ManuallyDrop::new(self);
+                    Ok(unsafe {
+                        <#storage as ::tagged_dispatch::__private::VariantStorage<#ty>>::take::<#allocator>(value.0.untagged_ptr() as usize)
+                    })
+                }
+            }
+            // Inline values aren't in the arena, so they are copied out instead
+            Some(_) if enum_attrs.is_inline(i) => quote! {
+                Ok(unsafe { *<#storage as ::tagged_dispatch::__private::VariantStorage<#ty>>::get(self.0.ptr() as usize) })
+            },
+            Some(_) => quote! {
+                Ok(unsafe { &*(self.0.ptr() as *const #ty) })
+            },
+        };
+        // Inline values live in the handle's bits, which a `&mut` could corrupt
+        let as_mut = (mutable && !enum_attrs.is_inline(i)).then(|| {
+            let as_mut_name = format_ident!("as_{}_mut", name, span = variant.span());
+            let ctor_vis = enum_attrs.constructor_vis(i);
+            quote! {
+                #[doc = concat!("Mutably borrow the `", stringify!(#variant), "` value, if the handle holds one")]
+                #[inline]
+                #ctor_vis fn #as_mut_name(&mut self) -> Option<&mut #ty> {
+                    if !self.#is_name() {
+                        return None;
+                    }
+                    Some(unsafe { &mut *(self.0.ptr() as *mut #ty) })
+                }
+            }
+        });
+        quote! {
+            #[doc = concat!("Whether the handle holds a `", stringify!(#variant), "`")]
+            #[inline]
+            pub fn #is_name(&self) -> bool {
+                self.tag_type() == #enum_type_name::#variant
+            }
+
+            #[doc = concat!("Borrow the `", stringify!(#variant), "` value, if the handle holds one")]
+            #[inline]
+            pub fn #as_name(&self) -> Option<&#ty> {
+                if !self.#is_name() {
+                    return None;
+                }
+                Some(unsafe {
+                    <#storage as ::tagged_dispatch::__private::VariantStorage<#ty>>::borrow(self.0.ptr() as usize, self.0.bits_ref())
+                })
+            }
+
+            #as_mut
+
+            #[doc = concat!("Take the `", stringify!(#variant), "` value out, or get the handle back if it holds another variant")]
+            #[inline]
+            pub fn #try_into_name(self) -> Result<#target, Self> {
+                if !self.#is_name() {
+                    return Err(self);
+                }
+                #take_value
+            }
+        }
+    });
+    quote! { #(#accessors)* }
+}
+
+/// Generate reflection metadata: `VARIANT_COUNT`, `variant_name()` and `type_name()` on
+/// the handle, and an impl on the tag type listing every variant with its names, which
+/// `Display` and `FromStr` use
+fn generate_reflection(enum_name: &Ident, enum_type_name: &Ident, variants: &[(Ident, Type)], generic: bool) -> (TokenStream2, TokenStream2) {
+    let count = variants.len();
+    let variant_names: Vec<_> = variants.iter().map(|(variant, _)| variant).collect();
+    let type_name_arms: Vec<_> = variants.iter().map(|(variant, ty)| {
+        quote! { #enum_type_name::#variant => ::core::any::type_name::<#ty>() }
+    }).collect();
+
+    // The tag type isn't generic, so only the handle knows a generic enum's variant types
+    let (handle_type_name, tag_type_name) = if generic {
+        (quote! { match self.tag_type() { #(#type_name_arms,)* } }, quote! {})
+    } else {
+        (quote! { self.tag_type().type_name() }, quote! {
+            /// Full name of the variant's type, as given by `core::any::type_name`
+            pub fn type_name(self) -> &'static str {
+                match self {
+                    #(#type_name_arms,)*
+                }
+            }
+        })
+    };
+
+    let methods = quote! {
+        /// Number of variants
+        pub const VARIANT_COUNT: usize = #count;
+
+        /// Name of the handle's variant
+        #[inline]
+        pub fn variant_name(&self) -> &'static str {
+            self.tag_type().name()
+        }
+
+        /// Full type name of the handle's value, as given by `core::any::type_name`
+        #[inline]
+        pub fn type_name(&self) -> &'static str {
+            #handle_type_name
+        }
+    };
+    let type_impl = quote! {
+        #[allow(dead_code)]
+        impl #enum_type_name {
+            /// Every variant, in tag order
+            pub const ALL: [Self; #count] = [#(Self::#variant_names),*];
+
+            /// Name of the variant
+            pub const fn name(self) -> &'static str {
+                match self {
+                    #(Self::#variant_names => stringify!(#variant_names),)*
+                }
+            }
+
+            #tag_type_name
+        }
+
+        impl ::core::fmt::Display for #enum_type_name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.pad(self.name())
+            }
+        }
+
+        impl ::core::str::FromStr for #enum_type_name {
+            type Err = ::tagged_dispatch::ParseVariantError;
+
+            fn from_str(name: &str) -> Result<Self, Self::Err> {
+                match name {
+                    #(stringify!(#variant_names) => Ok(Self::#variant_names),)*
+                    _ => Err(::tagged_dispatch::ParseVariantError::new(stringify!(#enum_name))),
+                }
+            }
+        }
+    };
+    (methods, type_impl)
+}
+
+/// Generate `From` and `TryFrom` conversions between the tag type and its `repr`
+/// integer, for storing tags without transmuting
+fn generate_tag_conversions(enum_type_name: &Ident, variants: &[(Ident, Type)], flags: &TraitGenerationFlags) -> TokenStream2 {
+    let tag_repr = flags.tag_repr(variants.len());
+    let count = variants.len();
+    let arms = variants.iter().enumerate().map(|(i, (variant, _))| {
+        let tag = flags.repr_tag_literal(i, variants.len());
+        quote! { #tag => Ok(#enum_type_name::#variant) }
+    });
+    quote! {
+        impl ::core::convert::From<#enum_type_name> for #tag_repr {
+            #[inline]
+            fn from(ty: #enum_type_name) -> Self {
+                ty as #tag_repr
+            }
+        }
+
+        impl ::core::convert::TryFrom<#tag_repr> for #enum_type_name {
+            type Error = ::tagged_dispatch::TagError;
+
+            #[inline]
+            fn try_from(tag: #tag_repr) -> Result<Self, Self::Error> {
+                match tag {
+                    #(#arms,)*
+                    _ => Err(::tagged_dispatch::TagError::TagOutOfRange { tag: tag as u16, max_variants: #count }),
+                }
+            }
+        }
+    }
+}
+
+/// Generate `map_concrete()` and the `<Enum>Mapper` builder it returns, which takes one
+/// closure per variant and runs the one matching the handle.
+///
+/// Owned handles are borrowed by the mapper; arena handles are `Copy`, so the mapper
+/// keeps its own copy under the enum's lifetime.
+fn generate_concrete_mapper(
+    vis: &syn::Visibility,
+    enum_name: &Ident,
+    variants: &[(Ident, Type)],
+    lifetime: Option<&syn::Lifetime>,
+    generics: &syn::Generics,
+) -> (TokenStream2, TokenStream2) {
+    let mapper_name = format_ident!("{}Mapper", enum_name);
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let (mapper_lifetime, handle_ty, returned_lifetime, handle) = match lifetime {
+        Some(lifetime) => (quote! { #lifetime }, quote! { #enum_name<#lifetime> }, quote! { #lifetime }, quote! { *self }),
+        None => (quote! { 'h }, quote! { &'h #enum_name #ty_generics }, quote! { '_ }, quote! { self }),
+    };
+    // The enum's type parameters follow the mapper's own
+    let params = &generics.params;
+    let args = generic_args(generics);
+    let args = quote! { #(, #args)* };
+    let variant_methods = variants.iter().map(|(variant, ty)| {
+        let name = constructor_ident(variant);
+        let as_name = format_ident!("as_{}", name, span = variant.span());
+        quote! {
+            #[doc = concat!("Map the value with `f` if the handle holds a `", stringify!(#variant), "`")]
+            #[inline]
+            pub fn #name(mut self, f: impl FnOnce(&#ty) -> R) -> Self {
+                if self.result.is_none() {
+                    if let Some(value) = self.handle.#as_name() {
+                        self.result = Some(f(value));
+                    }
+                }
+                self
+            }
+        }
+    });
+
+    let method = quote! {
+        /// Map the concrete value with one closure per variant, e.g.
+        /// `handle.map_concrete().circle(|c| c.radius).finish()`
+        #[inline]
+        pub fn map_concrete<R>(&self) -> #mapper_name<#returned_lifetime, R #args> {
+            #mapper_name { handle: #handle, result: None }
+        }
+    };
+    let mapper = quote! {
+        #[doc = concat!("Builder returned by [`", stringify!(#enum_name), "::map_concrete`]")]
+        #[must_use = "the mapped value is only available from `finish`"]
+        #vis struct #mapper_name<#mapper_lifetime, R, #params> {
+            handle: #handle_ty,
+            result: Option<R>,
+        }
+
+        #[allow(dead_code)]
+        impl<#mapper_lifetime, R, #params> #mapper_name<#mapper_lifetime, R #args> {
+            #(#variant_methods)*
+
+            /// Get the mapped value, or `None` if no closure was given for the handle's variant
+            #[inline]
+            pub fn finish(self) -> Option<R> {
+                self.result
+            }
+        }
+    };
+    (method, mapper)
+}
+
+/// Generate accessors for fields shared by every variant type, declared with
+/// `#[base(field: Type, ...)]` on the enum.
+///
+/// Each accessor matches on the tag and borrows the field directly from the
+/// concrete value. Owned handles also get a `<field>_mut` accessor; arena handles
+/// are `Copy` and may borrow shared data, so they only hand out `&` references.
+fn generate_base_accessors(
+    enum_type_name: &Ident,
+    variants: &[(Ident, Type)],
+    base_fields: &[BaseField],
+    mutable: bool,
+) -> TokenStream2 {
+    let accessors = base_fields.iter().map(|BaseField { name, ty }| {
+        let arms = variants.iter().map(|(variant, variant_ty)| {
+            quote! {
+                #enum_type_name::#variant => &(*(self.0.ptr() as *const #variant_ty)).#name
+            }
+        });
+        let accessor = quote! {
+            #[doc = concat!("Borrow the `", stringify!(#name), "` field shared by every variant")]
+            #[inline]
+            pub fn #name(&self) -> &#ty {
+                unsafe {
+                    match self.tag_type() {
+                        #(#arms,)*
+                    }
+                }
+            }
+        };
+
+        if !mutable {
+            return accessor;
+        }
+
+        let mut_name = format_ident!("{}_mut", name, span = name.span());
+        let mut_arms = variants.iter().map(|(variant, variant_ty)| {
+            quote! {
+                #enum_type_name::#variant => &mut (*(self.0.ptr() as *mut #variant_ty)).#name
+            }
+        });
+        quote! {
+            #accessor
+
+            #[doc = concat!("Mutably borrow the `", stringify!(#name), "` field shared by every variant")]
+            #[inline]
+            pub fn #mut_name(&mut self) -> &mut #ty {
+                unsafe {
+                    match self.tag_type() {
+                        #(#mut_arms,)*
+                    }
+                }
+            }
+        }
+    });
+
+    quote! { #(#accessors)* }
+}
+
+/// Traits `#[delegate(...)]` knows the methods of
+const DELEGATE_TRAITS: &[&str] = &["AsRef<T>", "Borrow<T>", "Display", "Debug"];
+
+/// The single method of a delegable trait: its name, extra parameters, their
+/// names, and the return type.
+///
+/// The macro cannot see the definition of a foreign trait, so only the traits
+/// in DELEGATE_TRAITS are supported.
+fn delegate_signature(path: &Path) -> Result<(Ident, TokenStream2, TokenStream2, TokenStream2)> {
+    let segment = path.segments.last().expect("paths have at least one segment");
+    let type_arg = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(syn::GenericArgument::Type(ty)) if args.args.len() == 1 => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    match (segment.ident.to_string().as_str(), type_arg) {
+        ("AsRef", Some(ty)) => Ok((format_ident!("as_ref"), quote! {}, quote! {}, quote! { &#ty })),
+        ("Borrow", Some(ty)) => Ok((format_ident!("borrow"), quote! {}, quote! {}, quote! { &#ty })),
+        ("Display" | "Debug", None) => Ok((
+            format_ident!("fmt"),
+            quote! { , f: &mut ::core::fmt::Formatter<'_> },
+            quote! { , f },
+            quote! { ::core::fmt::Result },
+        )),
+        _ => Err(syn::Error::new_spanned(
+            path,
+            format!("#[delegate] supports: {}", DELEGATE_TRAITS.join(", "))
+        )),
+    }
+}
+
+/// Generate impls of `#[delegate(...)]` traits that forward to the variant types
+fn generate_delegate_impls(
+    impl_generics: &TokenStream2,
+    self_ty: &TokenStream2,
+    enum_type_name: &Ident,
+    variants: &[(Ident, Type)],
+    enum_attrs: &EnumAttrs,
+) -> TokenStream2 {
+    let impls = enum_attrs.delegates.iter().map(|trait_path| {
+        let (method, params, args, output) = delegate_signature(trait_path).expect("validated while parsing");
+        let arms = variants.iter().enumerate().map(|(i, (variant, ty))| {
+            let value = enum_attrs.variant_ref(i, ty, quote! { self });
+            quote! {
+                #enum_type_name::#variant => {
+                    <#ty as #trait_path>::#method(#value #args)
+                }
+            }
+        });
+
+        quote! {
+            impl #impl_generics #trait_path for #self_ty {
+                #[inline]
+                fn #method(&self #params) -> #output {
+                    unsafe {
+                        match self.tag_type() {
+                            #(#arms)*
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    quote! { #(#impls)* }
+}
+
+/// How a std operator trait's method takes its operands
+#[derive(Clone, Copy)]
+enum OperatorKind {
+    /// `fn neg(self) -> Output`
+    Unary,
+    /// `fn add(self, rhs: Rhs) -> Output`
+    Binary,
+    /// `fn index(&self, index: Idx) -> &Output`
+    Index,
+}
+
+/// Std operator traits that can appear in the trait list as `ops::<Trait>`
+const OPERATOR_TRAITS: &[(&str, &str, OperatorKind)] = &[
+    ("Neg", "neg", OperatorKind::Unary),
+    ("Not", "not", OperatorKind::Unary),
+    ("Add", "add", OperatorKind::Binary),
+    ("Sub", "sub", OperatorKind::Binary),
+    ("Mul", "mul", OperatorKind::Binary),
+    ("Div", "div", OperatorKind::Binary),
+    ("Rem", "rem", OperatorKind::Binary),
+    ("BitAnd", "bitand", OperatorKind::Binary),
+    ("BitOr", "bitor", OperatorKind::Binary),
+    ("BitXor", "bitxor", OperatorKind::Binary),
+    ("Shl", "shl", OperatorKind::Binary),
+    ("Shr", "shr", OperatorKind::Binary),
+    ("Index", "index", OperatorKind::Index),
+];
+
+/// Look up a trait path like `ops::Neg` or `std::ops::Add<f64>` in [`OPERATOR_TRAITS`]
+fn operator_trait(path: &Path) -> Option<(Ident, Ident, OperatorKind)> {
+    let mut segments = path.segments.iter().rev();
+    let last = segments.next()?;
+    if segments.next()?.ident != "ops" {
+        return None;
+    }
+    OPERATOR_TRAITS.iter().find(|(name, _, _)| last.ident == name).map(|(_, method, kind)| {
+        (last.ident.clone(), Ident::new(method, last.ident.span()), *kind)
+    })
+}
+
+/// Implement a std operator trait for the enum by matching on the tag, if `dispatch_trait`
+/// is one. The enum's `Output` is the first variant's, which every variant has to share.
+///
+/// Operators taking `self` get the value the same way consuming dispatch methods do:
+/// owned handles move it out of its allocation, arena handles clone it.
+fn generate_operator_impl(
+    dispatch_trait: &DispatchTrait,
+    impl_generics: &TokenStream2,
+    self_ty: &TokenStream2,
+    enum_type_name: &Ident,
+    variants: &[(Ident, Type)],
+    enum_attrs: &EnumAttrs,
+    owned: bool,
+) -> Option<TokenStream2> {
+    let (trait_name, method_name, kind) = operator_trait(&dispatch_trait.path)?;
+    let (_, first_ty) = variants.first()?;
+
+    let args = match &dispatch_trait.path.segments.last()?.arguments {
+        syn::PathArguments::AngleBracketed(args) => Some(args.args.clone()),
+        _ => None,
+    };
+    if dispatch_trait.qualified {
+        return Some(syn::Error::new_spanned(&dispatch_trait.path, "operator traits cannot be `qualified`").to_compile_error());
+    }
+    if args.is_none() && !matches!(kind, OperatorKind::Unary) {
+        return Some(syn::Error::new_spanned(
+            &dispatch_trait.path,
+            format!("`{}` needs an explicit operand type, e.g. `ops::{}<f64>`", trait_name, trait_name)
+        ).to_compile_error());
+    }
+    let op_trait = quote! { ::core::ops::#trait_name<#args> };
+
+    let arms = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let storage = enum_attrs.storage(i);
+        let value = match kind {
+            OperatorKind::Index => enum_attrs.variant_ref(i, ty, quote! { this }),
+            _ if owned => {
+                let allocator = enum_attrs.allocator(i);
+                quote! {
+                    <#storage as ::tagged_dispatch::__private::VariantStorage<#ty>>::take::<#allocator>(this.0.untagged_ptr() as usize)
+                }
+            }
+            _ => {
+                let value = enum_attrs.variant_ref(i, ty, quote! { this });
+                quote! { <#ty as ::core::clone::Clone>::clone(#value) }
+            }
+        };
+        let operand = match kind {
+            OperatorKind::Unary => quote! {},
+            OperatorKind::Binary => quote! { , rhs },
+            OperatorKind::Index => quote! { , index },
+        };
+        quote! {
+            #enum_type_name::#variant => <#ty as #op_trait>::#method_name(#value #operand),
+        }
+    });
+
+    let signature = match kind {
+        OperatorKind::Unary => quote! { fn #method_name(self) -> Self::Output },
+        OperatorKind::Binary => quote! { fn #method_name(self, rhs: #args) -> Self::Output },
+        OperatorKind::Index => quote! { fn #method_name(&self, index: #args) -> &Self::Output },
+    };
+    // By-value owned operators take over the allocation, so the handle must not drop it
+    let take_handle = if owned && !matches!(kind, OperatorKind::Index) {
+        quote! { let this = ::core::mem::ManuallyDrop::new(self); }
+    } else {
+        quote! { let this = self; }
+    };
+
+    Some(quote! {
+        impl #impl_generics #op_trait for #self_ty {
+            type Output = <#first_ty as #op_trait>::Output;
+
+            #[inline]
+            #signature {
+                #take_handle
+                unsafe {
+                    match this.tag_type() {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Generate `stable_hash` for the `stable_hash` flag. Unlike the pointer-based
+/// comparisons, it only depends on the tag and the value, so it is the same across
+/// processes as long as the variant order and the variants' `Hash` impls are.
+fn generate_stable_hash(
+    enum_type_name: &Ident,
+    variants: &[(Ident, Type)],
+    flags: &TraitGenerationFlags,
+    enum_attrs: &EnumAttrs,
+) -> TokenStream2 {
+    if !flags.stable_hash {
+        return quote! {};
+    }
+    let body = generate_value_hash_body(enum_type_name, variants, flags, enum_attrs);
+    quote! {
+        /// Hash the variant tag and the value it holds, independent of where it is allocated
+        pub fn stable_hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+            #body
+        }
+    }
+}
+
+/// Generate `to_bytes` and `encoded_len` for the `bytes` flag. `bytes_of` requires
+/// the variants to be `Pod`, so other variants are rejected at compile time.
+fn generate_bytes_methods(enum_type_name: &Ident, variants: &[(Ident, Type)], flags: &TraitGenerationFlags) -> TokenStream2 {
+    if !flags.bytes {
+        return quote! {};
+    }
+
+    #[cfg(feature = "bytemuck")]
+    {
+        let byte_arms = variants.iter().map(|(variant, ty)| {
+            quote! {
+                #enum_type_name::#variant => ::tagged_dispatch::bytemuck::bytes_of(&*(self.0.ptr() as *const #ty)),
+            }
+        });
+        let len_arms = variants.iter().map(|(variant, ty)| {
+            quote! {
+                #enum_type_name::#variant => ::core::mem::size_of::<#ty>(),
+            }
+        });
+        quote! {
+            /// The length of `to_bytes()`: one tag byte plus the size of the value
+            pub fn encoded_len(&self) -> usize {
+                1 + match self.tag_type() {
+                    #(#len_arms)*
+                }
+            }
+
+            /// Encode the handle as its tag byte followed by the bytes of its value
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let value = unsafe {
+                    match self.tag_type() {
+                        #(#byte_arms)*
+                    }
+                };
+                let mut bytes = Vec::with_capacity(1 + value.len());
+                bytes.push(self.0.tag() as u8);
+                bytes.extend_from_slice(value);
+                bytes
+            }
+        }
+    }
+
+    #[cfg(not(feature = "bytemuck"))]
+    {
+        let _ = (enum_type_name, variants);
+        quote! {
+            compile_error!("the `bytes` flag requires the `bytemuck` feature of tagged_dispatch");
+        }
+    }
+}
+
+/// Generate the decoding arms for the `bytes` flag, constructing each variant through
+/// `construct(ctor_ident)`
+fn generate_bytes_decode(
+    variants: &[(Ident, Type)],
+    flags: &TraitGenerationFlags,
+    enum_attrs: &EnumAttrs,
+    construct: impl Fn(&Ident) -> TokenStream2,
+) -> Option<TokenStream2> {
+    if !cfg!(feature = "bytemuck") || !flags.bytes {
+        return None;
+    }
+
+    let arms = variants.iter().enumerate().map(|(i, (variant, _))| {
+        let tag = i as u8;
+        let construct = construct(&enum_attrs.constructor(i, variant));
+        quote! {
+            #tag => Ok(#construct(::tagged_dispatch::bytes::decode_value(body)?)),
+        }
+    });
+    Some(quote! {
+        let (tag, body) = ::tagged_dispatch::bytes::split_tag(bytes)?;
+        match tag {
+            #(#arms)*
+            _ => Err(::tagged_dispatch::bytes::DecodeError::InvalidTag(tag)),
+        }
+    })
+}
+
+/// Generate a `Hash` impl over the tag and the value for the `hash` flag
+fn generate_hash_impl(
+    impl_generics: &TokenStream2,
+    self_ty: &TokenStream2,
+    enum_type_name: &Ident,
+    variants: &[(Ident, Type)],
+    flags: &TraitGenerationFlags,
+    enum_attrs: &EnumAttrs,
+) -> TokenStream2 {
+    if !flags.hash {
+        return quote! {};
+    }
+    let body = generate_value_hash_body(enum_type_name, variants, flags, enum_attrs);
+    quote! {
+        impl #impl_generics ::core::hash::Hash for #self_ty {
+            fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+                #body
+            }
+        }
+    }
+}
+
+/// Hash `self`'s tag, then its value through the variant type's `Hash` into `state`
+fn generate_value_hash_body(
+    enum_type_name: &Ident,
+    variants: &[(Ident, Type)],
+    flags: &TraitGenerationFlags,
+    enum_attrs: &EnumAttrs,
+) -> TokenStream2 {
+    let tag_repr = flags.tag_repr(variants.len());
+    let arms = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let value = enum_attrs.variant_ref(i, ty, quote! { self });
+        quote! {
+            #enum_type_name::#variant => unsafe { ::core::hash::Hash::hash(#value, state) },
+        }
+    });
+    quote! {
+        ::core::hash::Hash::hash(&(self.0.tag() as #tag_repr), state);
+        match self.tag_type() {
+            #(#arms)*
+        }
+    }
+}
+
+/// The arguments naming each of `generics`' parameters, as in `Enum<T, N>`
+fn generic_args(generics: &syn::Generics) -> Vec<TokenStream2> {
+    generics.params.iter().map(|param| match param {
+        syn::GenericParam::Type(param) => {
+            let ident = &param.ident;
+            quote! { #ident }
+        }
+        syn::GenericParam::Const(param) => {
+            let ident = &param.ident;
+            quote! { #ident }
+        }
+        syn::GenericParam::Lifetime(param) => {
+            let lifetime = &param.lifetime;
+            quote! { #lifetime }
+        }
+    }).collect()
+}
+
+/// Wrap statements checking variant types at compile time in an item, inside a
+/// generic function when the enum has type parameters for the types to mention
+fn type_check(impl_generics: &TokenStream2, body: TokenStream2) -> TokenStream2 {
+    if impl_generics.is_empty() {
+        return quote! {
+            const _: fn() = || {
+                #body
+            };
+        };
+    }
+    quote! {
+        const _: () = {
+            #[allow(dead_code)]
+            fn check #impl_generics () {
+                #body
+            }
+        };
+    }
+}
+
+/// Generate the compile-time check that a handle is exactly one tagged pointer,
+/// unless disabled with `no_size_assert`
+fn generate_size_assert(handle_ty: &TokenStream2, flags: &TraitGenerationFlags) -> TokenStream2 {
+    if flags.no_size_assert {
+        return quote! {};
+    }
+
+    quote! {
+        // Size assertion
+        const _: () = assert!(
+            ::core::mem::size_of::<#handle_ty>() == ::core::mem::size_of::<::tagged_dispatch::TaggedPtr<()>>()
+        );
+    }
+}
+
+/// Generate the `Send` and `Sync` impls requested by the `send` and `sync` flags, with
+/// compile-time checks on every variant type. Owned handles own their value, so they
+/// are `Send` when it is; arena handles share it like `&T`, so both require `T: Sync`.
+fn generate_thread_safety_impls(
+    impl_generics: &TokenStream2,
+    self_ty: &TokenStream2,
+    variants: &[(Ident, Type)],
+    flags: &TraitGenerationFlags,
+    owned: bool,
+) -> TokenStream2 {
+    let mut impls = vec![];
+
+    // `shared` handles are like `Arc<T>`, which needs both for either
+    let (send_required, sync_required) = if flags.ref_counted() {
+        (quote! { Send + Sync }, quote! { Send + Sync })
+    } else if owned {
+        (quote! { Send }, quote! { Sync })
+    } else {
+        (quote! { Sync }, quote! { Sync })
+    };
+    for (enabled, marker, required) in [
+        (flags.send, quote! { Send }, send_required),
+        (flags.sync, quote! { Sync }, sync_required),
+    ] {
+        if !enabled {
+            continue;
+        }
+        let checks = variants.iter().map(|(_, ty)| {
+            type_check(impl_generics, quote! {
+                fn assert_thread_safe<T: ?Sized + #required>() {}
+                assert_thread_safe::<#ty>();
+            })
+        });
+        impls.push(quote! {
+            #(#checks)*
+
+            // SAFETY: every variant type was checked above
+            unsafe impl #impl_generics #marker for #self_ty {}
+        });
+    }
+
+    quote! { #(#impls)* }
+}
+
+/// The pointer type stored in a handle. A single variant needs no tag, so its handle
+/// stores a plain pointer and dispatch skips both the tag match and the masking.
+/// Tags wider than 7 bits spill into the low alignment bits, and `tag = "low"` keeps
+/// the whole tag there. `cow` handles keep a tagged pointer for its spare bit.
+/// `nan_box` handles store their number variant as a float and the rest in NaN payloads.
+fn handle_ptr_type(variants: &[(Ident, Type)], flags: &TraitGenerationFlags) -> TokenStream2 {
+    if flags.nan_box {
+        let number_tag = variants.iter().position(|(_, ty)| is_f64(ty)).unwrap_or(0) as u16;
+        quote! { ::tagged_dispatch::NanBoxedPtr<(), #number_tag> }
+    } else if variants.len() == 1 && !flags.cow {
+        quote! { ::tagged_dispatch::UntaggedPtr<()> }
+    } else if flags.low_tag {
+        let bits = flags.align_tag_bits(variants.len());
+        quote! { ::tagged_dispatch::LowTaggedPtr<(), #bits> }
+    } else if flags.low_tag_bits() > 0 {
+        let low_bits = flags.low_tag_bits();
+        quote! { ::tagged_dispatch::WideTaggedPtr<(), #low_bits> }
+    } else {
+        quote! { ::tagged_dispatch::TaggedPtr<()> }
+    }
+}
+
+/// Whether `ty` is the `f64` a `nan_box` enum stores inline
+fn is_f64(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("f64"))
+}
+
+/// Primitives the runtime crate implements `InlineValue` for (mirrors `impl_inline_value!`)
+const INLINE_PRIMITIVES: &[&str] = &["bool", "char", "u8", "u16", "u32", "i8", "i16", "i32", "f32"];
+
+/// Whether `ty` is a primitive that `inline_small` stores inline
+fn is_inline_primitive(ty: &Type) -> bool {
+    match ty {
+        Type::Tuple(tuple) => tuple.elems.is_empty(),
+        Type::Path(path) if path.qself.is_none() => {
+            path.path.get_ident().is_some_and(|ident| INLINE_PRIMITIVES.iter().any(|name| ident == name))
+        }
+        _ => false,
+    }
+}
+
+/// Implement `traverse::Node`, identifying a handle by its value's address, and
+/// `traverse::Mark` when the pointer has a spare bit the enum doesn't use itself
+fn generate_node_impls(
+    impl_generics: &TokenStream2,
+    self_ty: &TokenStream2,
+    variants: &[(Ident, Type)],
+    flags: &TraitGenerationFlags,
+) -> TokenStream2 {
+    let has_spare_bit = !flags.low_tag && !flags.cow && !flags.nan_box && variants.len() > 1;
+    let mark_impl = has_spare_bit.then(|| quote! {
+        impl #impl_generics ::tagged_dispatch::traverse::Mark for #self_ty {
+            #[inline]
+            fn is_marked(&self) -> bool {
+                self.0.spare_bit()
+            }
+
+            #[inline]
+            fn set_marked(&mut self, marked: bool) {
+                self.0 = if marked { self.0.with_spare_bit() } else { self.0.without_spare_bit() };
+            }
+        }
+    });
+    quote! {
+        impl #impl_generics ::tagged_dispatch::traverse::Node for #self_ty {
+            #[inline]
+            fn node_id(&self) -> usize {
+                self.0.untagged_ptr() as usize
+            }
+        }
+
+        #mark_impl
+    }
+}
+
+/// Generate `<Enum>Cow<'a>` for the `cow` flag: a handle that either owns its value or
+/// borrows one, e.g. a static node, told apart by the pointer's spare bit. It derefs to
+/// the owned handle type, so dispatch is the same for both.
+fn generate_cow_type(
+    vis: &syn::Visibility,
+    enum_name: &Ident,
+    variants: &[(Ident, Type)],
+    flags: &TraitGenerationFlags,
+    enum_attrs: &EnumAttrs,
+) -> TokenStream2 {
+    if !flags.cow {
+        return quote! {};
+    }
+
+    let cow_name = format_ident!("{}Cow", enum_name);
+    let ptr_type = handle_ptr_type(variants, flags);
+
+    let borrowed_constructors = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let tag = flags.tag_literal(i);
+        let method_name = format_ident!("borrowed_{}", constructor_ident(variant), span = variant.span());
+        let ctor_vis = enum_attrs.constructor_vis(i);
+        quote! {
+            #[doc = concat!("Borrow a `", stringify!(#variant), "` value without copying it")]
+            #[inline]
+            #ctor_vis fn #method_name(value: &'a #ty) -> Self {
+                let ptr = value as *const #ty as *mut ();
+                let handle = #enum_name(<#ptr_type>::new(ptr, #tag).with_spare_bit(), ::core::marker::PhantomData);
+                Self(::core::mem::ManuallyDrop::new(handle), ::core::marker::PhantomData)
+            }
+        }
+    });
+
+    let from_borrowed_impls = variants.iter().enumerate().filter(|(i, _)| !enum_attrs.is_restricted(*i) && !shares_type(variants, *i)).map(|(_, (variant, ty))| {
+        let method_name = format_ident!("borrowed_{}", constructor_ident(variant));
+        quote! {
+            impl<'a> From<&'a #ty> for #cow_name<'a> {
+                #[inline]
+                fn from(value: &'a #ty) -> Self {
+                    Self::#method_name(value)
+                }
+            }
+        }
+    });
+
+    let debug_impl = (flags.should_generate_debug() || enum_attrs.delegates_debug()).then(|| quote! {
+        impl ::core::fmt::Debug for #cow_name<'_> {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                let state = if self.is_borrowed() { "Borrowed" } else { "Owned" };
+                f.debug_tuple(state).field(&**self).finish()
+            }
+        }
+    });
+
+    quote! {
+        #[doc = concat!("Either an owned `", stringify!(#enum_name), "` or a borrowed variant value, dispatching the same way through `Deref`")]
+        #vis struct #cow_name<'a>(
+            ::core::mem::ManuallyDrop<#enum_name>,
+            // Borrowed values are shared, so crossing threads needs the handle to be `Sync` too
+            ::core::marker::PhantomData<&'a #enum_name>
+        );
+
+        impl<'a> #cow_name<'a> {
+            #(#borrowed_constructors)*
+
+            /// Whether this borrows its value rather than owning it
+            #[inline(always)]
+            pub fn is_borrowed(&self) -> bool {
+                self.0.0.spare_bit()
+            }
+
+            /// Whether this owns its value
+            #[inline(always)]
+            pub fn is_owned(&self) -> bool {
+                !self.is_borrowed()
+            }
+
+            /// Get the owned handle, cloning the value if it is borrowed
+            pub fn into_owned(self) -> #enum_name {
+                if self.is_borrowed() {
+                    return #enum_name::clone(&self);
+                }
+                let this = ::core::mem::ManuallyDrop::new(self);
+                unsafe { ::core::ptr::read(&*this.0) }
+            }
+        }
+
+        impl ::core::ops::Deref for #cow_name<'_> {
+            type Target = #enum_name;
+
+            #[inline(always)]
+            fn deref(&self) -> &#enum_name {
+                &self.0
+            }
+        }
+
+        impl From<#enum_name> for #cow_name<'_> {
+            #[inline]
+            fn from(value: #enum_name) -> Self {
+                Self(::core::mem::ManuallyDrop::new(value), ::core::marker::PhantomData)
+            }
+        }
+
+        #(#from_borrowed_impls)*
+
+        impl Clone for #cow_name<'_> {
+            fn clone(&self) -> Self {
+                if self.is_borrowed() {
+                    // Copies the borrow; the pointer is never freed
+                    let handle = #enum_name(self.0.0, ::core::marker::PhantomData);
+                    return Self(::core::mem::ManuallyDrop::new(handle), ::core::marker::PhantomData);
+                }
+                Self::from((**self).clone())
+            }
+        }
+
+        impl Drop for #cow_name<'_> {
+            fn drop(&mut self) {
+                if self.is_owned() {
+                    unsafe { ::core::mem::ManuallyDrop::drop(&mut self.0) }
+                }
+            }
+        }
+
+        #debug_impl
+    }
+}
+
+/// Check at compile time that the high tag bits, whose width depends on the address
+/// bits `tagged_dispatch` was built for, can hold every variant's tag
+fn generate_tag_width_assert(enum_name: &Ident, variants: &[(Ident, Type)], flags: &TraitGenerationFlags) -> TokenStream2 {
+    if flags.low_tag || variants.len() == 1 {
+        return quote! {};
+    }
+    let ptr_type = handle_ptr_type(variants, flags);
+    let count = variants.len();
+    let message = format!(
+        "`{}` has {} variants, more than the tag holds with the configured `TAGGED_DISPATCH_ADDRESS_BITS`",
+        enum_name, count,
+    );
+    quote! {
+        const _: () = assert!(#count <= <#ptr_type>::MAX_VARIANTS, #message);
+    }
+}
+
+/// Check at compile time that every variant type is aligned enough to leave the low
+/// tag bits of its address free
+fn generate_tag_align_asserts(variants: &[(Ident, Type)], flags: &TraitGenerationFlags) -> TokenStream2 {
+    let low_bits = flags.align_tag_bits(variants.len());
+    if low_bits == 0 || variants.len() == 1 {
+        return quote! {};
+    }
+
+    let option = if flags.low_tag {
+        format!("`tag = \"low\"` with {} variants", variants.len())
+    } else {
+        format!("`tag_bits = {}`", HIGH_TAG_BITS + low_bits)
+    };
+    let asserts = variants.iter().map(|(variant, ty)| {
+        let message = format!(
+            "`{}` must be aligned to at least {} bytes to use {}; add `#[repr(align({}))]`",
+            variant,
+            1usize << low_bits,
+            option,
+            1usize << low_bits,
+        );
+        quote! {
+            const _: () = assert!(::core::mem::align_of::<#ty>() >= 1 << #low_bits, #message);
+        }
+    });
+    quote! { #(#asserts)* }
+}
+
+/// Generate `each_<enum>_variant!`, which invokes a caller-supplied macro once per
+/// variant as `callback!(Variant, Type, tag)`
+fn generate_each_variant_macro(enum_name: &Ident, variants: &[(Ident, Type)], flags: &TraitGenerationFlags) -> TokenStream2 {
+    let macro_name = format_ident!("each_{}_variant", enum_name.to_string().to_snake_case());
+    let invocations = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let tag = flags.repr_tag_literal(i, variants.len());
+        quote! { $callback!(#variant, #ty, #tag); }
+    });
+
+    quote! {
+        #[doc = concat!("Invoke `callback!(Variant, Type, tag)` once for every `", stringify!(#enum_name), "` variant")]
+        #[allow(unused_macros)]
+        macro_rules! #macro_name {
+            ($callback:ident) => {
+                #(#invocations)*
+            };
+        }
+    }
+}
+
+/// Generate `<Enum>Variants`, a tuple of every variant type in tag order, so generic
+/// code can name the variant set
+fn generate_variant_list(vis: &syn::Visibility, enum_name: &Ident, params: &[TokenStream2], variants: &[(Ident, Type)]) -> TokenStream2 {
+    let list_name = format_ident!("{}Variants", enum_name);
+    let types = variants.iter().map(|(_, ty)| ty);
+    quote! {
+        #[doc = concat!("Every `", stringify!(#enum_name), "` variant type, in tag order")]
+        #[allow(dead_code)]
+        #vis type #list_name<#(#params),*> = (#(#types,)*);
+    }
+}
+
+/// Generate `match_<enum>!`, which matches a handle against per-variant arms
+/// written as `Variant(binding) => expr`, binding a reference to the concrete value.
+///
+/// The arms become a `match` on the tag type, so a missing variant is a compile
+/// error unless a trailing `_ => expr` arm is given.
+fn generate_match_macro(enum_name: &Ident, enum_type_name: &Ident, variants: &[(Ident, Type)]) -> TokenStream2 {
+    let macro_name = format_ident!("match_{}", enum_name.to_string().to_snake_case());
+    let borrows = variants.iter().map(|(variant, _)| {
+        let as_name = format_ident!("as_{}", constructor_ident(variant));
+        quote! {
+            (@borrow $handle:ident, #variant) => {
+                match $handle.#as_name() {
+                    Some(value) => value,
+                    None => unreachable!(),
+                }
+            };
+        }
+    });
+
+    quote! {
+        #[doc = concat!("Match a `", stringify!(#enum_name), "` handle as `", stringify!(#macro_name), "!(handle, { Variant(value) => expr, ... })`")]
+        #[allow(unused_macros)]
+        macro_rules! #macro_name {
+            ($value:expr, { $($variant:ident ($binding:pat) => $body:expr),+ $(, _ => $default:expr)? $(,)? }) => {
+                match &$value {
+                    handle => match handle.tag_type() {
+                        $(#enum_type_name::$variant => {
+                            let $binding = #macro_name!(@borrow handle, $variant);
+                            $body
+                        })+
+                        $(_ => $default,)?
+                    },
+                }
+            };
+            #(#borrows)*
+        }
+    }
+}
+
+/// Reject options that change an enum's layout on an enum with `#[flatten]` variants,
+/// which share the storage of the inner enum's handles
+fn check_flattened(enum_name: &Ident, generics: &syn::Generics, flags: &TraitGenerationFlags, enum_attrs: &EnumAttrs) -> Result<()> {
+    if enum_attrs.flattened.iter().all(Option::is_none) {
+        return Ok(());
+    }
+    let conflict = if !generics.params.is_empty() {
+        "enums with `#[flatten]` variants can't have generic parameters"
+    } else if flags.nan_box {
+        "`#[flatten]` can't be combined with `nan_box`"
+    } else if flags.ref_counted() {
+        "`#[flatten]` variants own their value and can't be combined with `shared` or `rc`"
+    } else if flags.cow {
+        "the `cow` flag can't be combined with `#[flatten]`"
+    } else {
+        return Ok(());
+    };
+    Err(syn::Error::new_spanned(enum_name, conflict))
+}
+
+/// Reject options that need a value owned by a single handle on a `shared` or `rc` enum
+fn check_shared(enum_name: &Ident, flags: &TraitGenerationFlags, enum_attrs: &EnumAttrs) -> Result<()> {
+    let mode = match (flags.shared, flags.rc) {
+        (false, false) => return Ok(()),
+        (true, true) => {
+            return Err(syn::Error::new_spanned(enum_name, "the `shared` and `rc` flags can't be combined"));
+        }
+        (true, false) => "shared",
+        (false, true) => "rc",
+    };
+    let conflict = if flags.cow {
+        format!("the `cow` flag can't be combined with `{mode}`")
+    } else if enum_attrs.on_drop.is_some() {
+        format!("`#[on_drop]` runs for every dropped handle and can't be combined with `{mode}`")
+    } else if enum_attrs.allocators.iter().any(Option::is_some) {
+        format!("`{mode}` enums reference count every variant and can't use `#[alloc_with]`")
+    } else if flags.rc && (flags.send || flags.sync) {
+        "`rc` handles share a non-atomic count and can't be `send` or `sync`; use `shared` instead".to_string()
+    } else {
+        return Ok(());
+    };
+    Err(syn::Error::new_spanned(enum_name, conflict))
+}
+
+/// Reject options that can't share one thread-local pool per variant type on a
+/// `pooled` enum
+fn check_pooled(enum_name: &Ident, generics: &syn::Generics, flags: &TraitGenerationFlags) -> Result<()> {
+    if !flags.pooled {
+        return Ok(());
+    }
+    let conflict = if flags.ref_counted() {
+        let mode = if flags.shared { "shared" } else { "rc" };
+        format!("`{mode}` enums reference count every variant and can't be `pooled`")
+    } else if generics.type_params().next().is_some() {
+        "`pooled` enums keep a static pool per variant type and can't have type parameters".to_string()
+    } else {
+        return Ok(());
+    };
+    Err(syn::Error::new_spanned(enum_name, conflict))
+}
+
+/// Name of the type keying a `pooled` enum's pools
+fn pool_key_ident(enum_name: &Ident) -> Ident {
+    format_ident!("__{}Pool", enum_name)
+}
+
+/// Generate the key type of a `pooled` enum's pools, with a thread-local pool for
+/// each variant type it allocates
+fn generate_pool_key(vis: &syn::Visibility, enum_name: &Ident, variants: &[(Ident, Type)], enum_attrs: &EnumAttrs) -> TokenStream2 {
+    let key = pool_key_ident(enum_name);
+    let mut seen = Vec::new();
+    let impls = variants.iter().enumerate().filter_map(|(i, (_, ty))| {
+        // Inline variants and those with their own allocator never reach the pool, and
+        // variants of the same type share one
+        let pooled = !enum_attrs.is_inline(i) && !matches!(enum_attrs.allocators.get(i), Some(Some(_)));
+        let name = quote! { #ty }.to_string();
+        if !pooled || seen.contains(&name) {
+            return None;
+        }
+        seen.push(name);
+        Some(quote! {
+            impl ::tagged_dispatch::pool::PoolKey<#ty> for #key {
+                #[inline]
+                fn with<R>(f: impl FnOnce(&::tagged_dispatch::pool::Pool<#ty>) -> R) -> Option<R> {
+                    ::std::thread_local! {
+                        static POOL: ::tagged_dispatch::pool::Pool<#ty> = const { ::tagged_dispatch::pool::Pool::new() };
+                    }
+                    POOL.try_with(f).ok()
+                }
+            }
+        })
+    }).collect::<Vec<_>>();
+    quote! {
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #vis struct #key;
+
+        #(#impls)*
+    }
+}
+
+/// Generate `<Enum>Columns`, keeping the values of each variant type in their own
+/// `Vec`, and `<Enum>ColumnPass`, which `run` calls once per variant with its slice.
+///
+/// The pass is generic over the variant type, bounded by the dispatched traits, so
+/// its loop is monomorphized for each column instead of dispatching per value.
+fn generate_columns(vis: &syn::Visibility, enum_name: &Ident, enum_type_name: &Ident, variants: &[(Ident, Type)], traits: &[DispatchTrait]) -> TokenStream2 {
+    let columns_name = format_ident!("{}Columns", enum_name);
+    let pass_name = format_ident!("{}ColumnPass", enum_name);
+    let trait_paths: Vec<_> = traits.iter()
+        .map(|dispatch_trait| &dispatch_trait.path)
+        .filter(|path| operator_trait(path).is_none())
+        .collect();
+    let fields: Vec<_> = variants.iter().map(|(variant, _)| constructor_ident(variant)).collect();
+    let types: Vec<_> = variants.iter().map(|(_, ty)| ty).collect();
+    let methods = variants.iter().zip(&fields).map(|((variant, ty), field)| {
+        let push = format_ident!("push_{}", field);
+        let column = format_ident!("{}_column", field);
+        let column_mut = format_ident!("{}_column_mut", field);
+        quote! {
+            #[doc = concat!("Add a `", stringify!(#variant), "` value, returning its index in the column")]
+            pub fn #push(&mut self, value: #ty) -> usize {
+                self.#field.push(value);
+                self.#field.len() - 1
+            }
+
+            #[doc = concat!("The `", stringify!(#variant), "` values, in the order they were added")]
+            pub fn #column(&self) -> &[#ty] {
+                &self.#field
+            }
+
+            #[doc = concat!("The `", stringify!(#variant), "` values, mutably")]
+            pub fn #column_mut(&mut self) -> &mut [#ty] {
+                &mut self.#field
+            }
+        }
+    });
+    let runs = variants.iter().zip(&fields).map(|((variant, _), field)| quote! {
+        pass.column(#enum_type_name::#variant, &self.#field);
+    });
+    let columns_doc = format!("The values of every [`{}`] variant, stored by type with one `Vec` per variant", enum_name);
+    let pass_doc = format!("A pass over [`{}`], run once per variant with that variant's values", columns_name);
+
+    quote! {
+        #[doc = #columns_doc]
+        #vis struct #columns_name {
+            #(#fields: ::tagged_dispatch::__private::Vec<#types>,)*
+        }
+
+        #[doc = #pass_doc]
+        #[allow(private_bounds)]
+        #vis trait #pass_name {
+            /// Visit the values of the `tag` variant
+            fn column<T: #(#trait_paths +)* 'static>(&mut self, tag: #enum_type_name, values: &[T]);
+        }
+
+        impl #columns_name {
+            /// Create an empty store
+            pub fn new() -> Self {
+                Self { #(#fields: ::tagged_dispatch::__private::Vec::new(),)* }
+            }
+
+            #(#methods)*
+
+            /// Hand each variant's values to `pass` in turn, in tag order, empty columns
+            /// included
+            pub fn run<P: #pass_name>(&self, pass: &mut P) {
+                #(#runs)*
+            }
+
+            /// The number of values across all columns
+            pub fn len(&self) -> usize {
+                0 #(+ self.#fields.len())*
+            }
+
+            /// Whether every column is empty
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            /// Remove every value, keeping the columns' capacity
+            pub fn clear(&mut self) {
+                #(self.#fields.clear();)*
+            }
+        }
+
+        impl ::core::default::Default for #columns_name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    }
+}
+
+/// Reject options that need every variant behind a pointer on an enum with
+/// `#[inline_value]` variants
+fn check_inline_values(variants: &[(Ident, Type)], flags: &TraitGenerationFlags, enum_attrs: &EnumAttrs) -> Result<()> {
+    let Some((i, (variant, _))) = variants.iter().enumerate().find(|(i, _)| enum_attrs.is_inline(*i)) else {
+        return Ok(());
+    };
+    let conflict = if flags.low_tag || flags.low_tag_bits() > 0 {
+        "`#[inline_value]` variants need the whole tag above the address, so they can't be combined with `tag = \"low\"` or `tag_bits` wider than the high tag bits"
+    } else if flags.cow {
+        "the `cow` flag can't be combined with `#[inline_value]` variants"
+    } else if flags.bytes {
+        "the `bytes` flag can't be combined with `#[inline_value]` variants"
+    } else if enum_attrs.on_drop.is_some() {
+        "`#[on_drop]` sees each value through its address and can't be combined with `#[inline_value]` variants"
+    } else if !enum_attrs.base_fields.is_empty() {
+        "`#[base(...)]` accessors borrow from the value's address and can't be combined with `#[inline_value]` variants"
+    } else if matches!(enum_attrs.allocators.get(i), Some(Some(_))) {
+        "`#[inline_value]` variants aren't allocated and can't use `#[alloc_with]`"
+    } else {
+        return Ok(());
+    };
+    Err(syn::Error::new_spanned(variant, conflict))
+}
+
+/// Check at compile time that each `#[inline_value]` variant fits in the address bits
+fn generate_inline_value_asserts(variants: &[(Ident, Type)], enum_attrs: &EnumAttrs) -> TokenStream2 {
+    let asserts = variants.iter().zip(&enum_attrs.inline_values).filter(|(_, inline)| **inline).map(|((variant, ty), _)| {
+        quote! {
+            const _: fn() = || {
+                fn assert_inline_value<T: ::tagged_dispatch::InlineValue>() {}
+                assert_inline_value::<#ty>();
+            };
+            const _: () = assert!(
+                ::core::mem::size_of::<#ty>() <= ::tagged_dispatch::INLINE_VALUE_BYTES,
+                concat!("`", stringify!(#variant), "` is too large for #[inline_value]; values must fit below the tag")
+            );
+        }
+    });
+    quote! { #(#asserts)* }
+}
+
+/// Generate implementation for owned version (no lifetime)
+fn generate_owned_impl(
+    enum_name: &Ident,
+    vis: &syn::Visibility,
+    generics: &syn::Generics,
+    variants: &[(Ident, Type)],
+    traits: &[DispatchTrait],
+    flags: &TraitGenerationFlags,
+    enum_attrs: &EnumAttrs,
+) -> TokenStream {
+    if let Some(owned) = &flags.owned {
+        return syn::Error::new_spanned(owned, "`owned = ...` links an arena enum to its owned counterpart and is only supported on arena enums")
+            .to_compile_error()
+            .into();
+    }
+    // Owned `cow` values clone the handle
+    if flags.no_clone && flags.cow {
+        return syn::Error::new_spanned(enum_name, "the `cow` flag clones owned handles and can't be combined with `no_clone`")
+            .to_compile_error()
+            .into();
+    }
+    // Owned handles have no builder to record them
+    if flags.dump {
+        return syn::Error::new_spanned(enum_name, "the `dump` flag is only supported on arena enums")
+            .to_compile_error()
+            .into();
+    }
+    if let Err(err) = check_flattened(enum_name, generics, flags, enum_attrs) {
+        return err.to_compile_error().into();
+    }
+    if let Err(err) = check_shared(enum_name, flags, enum_attrs) {
+        return err.to_compile_error().into();
+    }
+    if let Err(err) = check_pooled(enum_name, generics, flags) {
+        return err.to_compile_error().into();
+    }
+    if flags.columns && generics.type_params().next().is_some() {
+        return syn::Error::new_spanned(enum_name, "`columns` enums name every variant type in their column store and can't have type parameters")
+            .to_compile_error()
+            .into();
+    }
+    if let Err(err) = check_inline_values(variants, flags, enum_attrs) {
+        return err.to_compile_error().into();
+    }
+
+    // Type parameters are threaded through every impl; the dispatch macros take them
+    // as bare parameter and argument lists
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    let impl_generics = quote! { #impl_generics };
+    let self_ty = quote! { #enum_name #ty_generics };
+    let generic = !generics.params.is_empty();
+    let params = &generics.params;
+    let args = generic_args(generics);
+
+    let enum_type_name = format_ident!("{}Type", enum_name);
+    let each_variant_macro = generate_each_variant_macro(enum_name, variants, flags);
+    let match_macro = generate_match_macro(enum_name, &enum_type_name, variants);
+    let variant_types = generate_variant_list(vis, enum_name, &args, variants);
+    let plugin_registry = flags.open.then(|| generate_plugin_registry(vis, enum_name, variants, traits));
+    let pool_key = flags.pooled.then(|| generate_pool_key(vis, enum_name, variants, enum_attrs));
+    let columns = flags.columns.then(|| generate_columns(vis, enum_name, &enum_type_name, variants, traits));
+    let take_replace = generate_take_replace(&impl_generics, &self_ty, variants, enum_attrs);
+    let ptr_type = handle_ptr_type(variants, flags);
+    // Only enums with a single layout and owned values can be spliced into another, and
+    // pooled variants would name a pool key that may not be in scope there
+    let flatten_macro = (!generic && !flags.nan_box && !flags.ref_counted() && !flags.pooled)
+        .then(|| generate_flatten_macro(enum_name, &ptr_type, variants, enum_attrs));
+    let flatten_conversions = generate_flatten_conversions(enum_name, &ptr_type, enum_attrs);
+    let base_accessors = generate_base_accessors(&enum_type_name, variants, &enum_attrs.base_fields, !flags.ref_counted());
+    let variant_accessors = generate_variant_accessors(&enum_type_name, variants, enum_attrs, None, !flags.ref_counted());
+    let (map_concrete, mapper) = generate_concrete_mapper(vis, enum_name, variants, None, generics);
+    let (reflection_methods, type_reflection) = generate_reflection(enum_name, &enum_type_name, variants, generic);
+    let tag_conversions = generate_tag_conversions(&enum_type_name, variants, flags);
+    let stable_hash = generate_stable_hash(&enum_type_name, variants, flags, enum_attrs);
+    let slotmap_aliases = generate_slotmap_aliases(vis, enum_name, None, &args);
+    let hash_impl = generate_hash_impl(&impl_generics, &self_ty, &enum_type_name, variants, flags, enum_attrs);
+    let bytes_methods = generate_bytes_methods(&enum_type_name, variants, flags);
+    let bytes_decode = generate_bytes_decode(variants, flags, enum_attrs, |ctor| quote! { Self::#ctor }).map(|body| quote! {
+        impl<'b> ::core::convert::TryFrom<&'b [u8]> for #enum_name {
+            type Error = ::tagged_dispatch::bytes::DecodeError;
+
+            /// Decode a handle written by `to_bytes`
+            fn try_from(bytes: &'b [u8]) -> Result<Self, Self::Error> {
+                #body
+            }
+        }
+    });
+    let test_util_impl = generate_test_util_impl(&impl_generics, &self_ty, &enum_type_name, variants, true);
+    let thread_safety_impls = generate_thread_safety_impls(&impl_generics, &self_ty, variants, flags, true);
+    let node_impls = generate_node_impls(&impl_generics, &self_ty, variants, flags);
+    let cow_type = generate_cow_type(vis, enum_name, variants, flags, enum_attrs);
+    // Generic enums have no single layout to register
+    let registry_entry = (!generic).then(|| generate_registry_entry(enum_name, &self_ty, variants, flags, true));
+    let tag_repr = flags.tag_repr(variants.len());
+    let tag_type_check = flags.tag_type_check(enum_name, variants.len());
+    let tag_align_asserts = generate_tag_align_asserts(variants, flags);
+    let tag_width_assert = generate_tag_width_assert(enum_name, variants, flags);
+    let inline_value_asserts = generate_inline_value_asserts(variants, enum_attrs);
+    let delegate_impls = generate_delegate_impls(
+        &impl_generics,
+        &self_ty,
+        &enum_type_name,
+        variants,
+        enum_attrs,
+    );
+    
+    // Generate variant constructors
+    let constructors = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let tag = flags.tag_literal(i);
+        let method_name = enum_attrs.constructor(i, variant);
+        let allocator = enum_attrs.allocator(i);
+        let ctor_vis = enum_attrs.constructor_vis(i);
+        let ptr = if enum_attrs.is_inline(i) {
+            enum_attrs.pack(i, ty, quote! { value })
+        } else {
+            quote! { <#allocator as ::tagged_dispatch::VariantAllocator<#ty>>::alloc(value).as_ptr() as *mut () }
+        };
+        let variant_docs = enum_attrs.variant_docs(i);
+        quote! {
+            #[doc = concat!("Create a `", stringify!(#variant), "` variant")]
+            #variant_docs
+            #[inline]
+            #ctor_vis fn #method_name(value: #ty) -> Self {
+                let ptr = #ptr;
+                Self(<#ptr_type>::new(ptr, #tag), ::core::marker::PhantomData)
+            }
+        }
+    });
+
+    // With `fallible`, also generate constructors that check the address in release builds
+    let try_constructors = variants.iter().enumerate().filter(|_| flags.fallible).map(|(i, (variant, ty))| {
+        let tag = flags.tag_literal(i);
+        let method_name = format_ident!("try_{}", constructor_ident(variant), span = variant.span());
+        let allocator = enum_attrs.allocator(i);
+        let ctor_vis = enum_attrs.constructor_vis(i);
+        // Inline values have no address to overlap the tag
+        if enum_attrs.is_inline(i) {
+            let ctor = enum_attrs.constructor(i, variant);
+            return quote! {
+                #[doc = concat!("Create a `", stringify!(#variant), "` variant, which is stored inline and never fails")]
+                #[inline]
+                #ctor_vis fn #method_name(value: #ty) -> Result<Self, ::tagged_dispatch::TagError> {
+                    Ok(Self::#ctor(value))
+                }
+            };
+        }
+        quote! {
+            #[doc = concat!("Create a `", stringify!(#variant), "` variant, or return an error if it can't be allocated or its address overlaps the tag")]
+            ///
+            /// The value is dropped on error.
+            #[inline]
+            #ctor_vis fn #method_name(value: #ty) -> Result<Self, ::tagged_dispatch::TagError> {
+                let ptr = match <#allocator as ::tagged_dispatch::VariantAllocator<#ty>>::try_alloc(value) {
+                    Ok(ptr) => ptr,
+                    Err(_) => return Err(::tagged_dispatch::TagError::AllocFailed { size: ::core::mem::size_of::<#ty>() }),
+                };
+                match <#ptr_type>::try_new(ptr.as_ptr() as *mut (), #tag) {
+                    Ok(tagged) => Ok(Self(tagged, ::core::marker::PhantomData)),
+                    Err(err) => {
+                        unsafe { <#allocator as ::tagged_dispatch::VariantAllocator<#ty>>::free(ptr) };
+                        Err(err)
+                    }
+                }
+            }
+        }
+    });
+
+    // Generate in-place constructors for variants too large to build on the stack.
+    // These write into a box, so variants with their own allocator or stored inline
+    // don't get one, and neither do `shared` or `rc` enums.
+    let emplace_constructors = variants.iter().enumerate().filter(|(i, _)| {
+        !flags.ref_counted() && !enum_attrs.is_inline(*i) && !matches!(enum_attrs.allocators.get(*i), Some(Some(_)))
+    }).map(|(i, (variant, ty))| {
+        let tag = flags.tag_literal(i);
+        let method_name = format_ident!("{}_with", constructor_ident(variant), span = variant.span());
+        let ctor_vis = enum_attrs.constructor_vis(i);
+        quote! {
+            #[doc = concat!("Create a `", stringify!(#variant), "` variant by initializing it directly in its heap allocation")]
+            ///
+            /// # Safety
+            ///
+            /// `init` must fully initialize the slot before returning.
+            #[inline]
+            #ctor_vis unsafe fn #method_name(init: impl FnOnce(&mut ::core::mem::MaybeUninit<#ty>)) -> Self {
+                let mut boxed = Box::<#ty>::new_uninit();
+                init(&mut *boxed);
+                let ptr = Box::into_raw(boxed.assume_init()) as *mut ();
+                Self(<#ptr_type>::new(ptr, #tag), ::core::marker::PhantomData)
+            }
+        }
+    });
+    
+    // Generate From implementations. Trait impls are always public, so variants with
+    // restricted constructors only get the hidden conversion used by `Self`-returning methods.
+    // Conversions keyed by type can't tell apart variants sharing one, which only
+    // get their constructors and accessors.
+    let from_impls = variants.iter().enumerate().filter(|(i, _)| !shares_type(variants, *i)).map(|(i, (variant, ty))| {
+        let method_name = enum_attrs.constructor(i, variant);
+        let from_impl = (!enum_attrs.is_restricted(i)).then(|| quote! {
+            impl #impl_generics From<#ty> for #self_ty {
+                #[inline]
+                fn from(value: #ty) -> Self {
+                    Self::#method_name(value)
+                }
+            }
+        });
+        quote! {
+            #from_impl
+
+            impl #impl_generics ::tagged_dispatch::__private::FromVariant<#ty> for #self_ty {
+                #[inline]
+                fn from_variant(value: #ty) -> Self {
+                    Self::#method_name(value)
+                }
+            }
+        }
+    });
+    
+    // Generate fallible extraction back into the variant types, returning the
+    // handle unchanged when it holds a different variant. Inline values can only be copied out.
+    let try_from_impls = variants.iter().enumerate().filter(|(i, _)| !shares_type(variants, *i)).map(|(i, (variant, ty))| {
+        let try_into_name = format_ident!("try_into_{}", constructor_ident(variant));
+        let try_from_ref = (!enum_attrs.is_inline(i)).then(|| quote! {
+            impl<'h, #params> ::core::convert::TryFrom<&'h #self_ty> for &'h #ty {
+                type Error = &'h #self_ty;
+
+                fn try_from(value: &'h #self_ty) -> Result<Self, Self::Error> {
+                    if value.tag_type() != #enum_type_name::#variant {
+                        return Err(value);
+                    }
+                    Ok(unsafe { &*(value.0.ptr() as *const #ty) })
+                }
+            }
+        });
+        quote! {
+            impl #impl_generics ::core::convert::TryFrom<#self_ty> for #ty {
+                type Error = #self_ty;
+
+                fn try_from(value: #self_ty) -> Result<Self, Self::Error> {
+                    value.#try_into_name()
+                }
+            }
+
+            #try_from_ref
+        }
+    });
+
+    let invalid_tag = flags.invalid_tag_handler(enum_name, quote! { tag });
+
+    // Generate Drop implementation
+    let drop_arms = variants.iter().enumerate().map(|(i, (_variant, ty))| {
+        let tag = flags.tag_literal(i);
+        let allocator = enum_attrs.allocator(i);
+        if enum_attrs.is_inline(i) {
+            return quote! { #tag => {} };
+        }
+        quote! {
+            #tag => {
+                // Use untagged_ptr() for deallocation to ensure we pass
+                // the original pointer back to the allocator
+                let ptr = self.0.untagged_ptr() as *mut #ty;
+                <#allocator as ::tagged_dispatch::VariantAllocator<#ty>>::free(::core::ptr::NonNull::new_unchecked(ptr));
+            }
+        }
+    });
+    
+    let non_exhaustive = flags.non_exhaustive.then(|| quote! { #[non_exhaustive] });
+    // A generic handle's size can't be checked without its type arguments, but it is
+    // the same struct whatever they are
+    let size_assert = (!generic).then(|| generate_size_assert(&self_ty, flags));
+
+    // The hook sees the value while it is still alive, right before it is dropped
+    let on_drop = enum_attrs.on_drop.as_ref().map(|hook| quote! {
+        #hook(self.tag_type(), self.0.ptr() as *const ());
+    });
+
+    let drop_impl = if flags.drop_table {
+        // One shared `drop_variant::<T, A>` per variant type instead of inlined drop glue
+        let drop_fns = variants.iter().enumerate().map(|(i, (_variant, ty))| {
+            let allocator = enum_attrs.allocator(i);
+            if enum_attrs.is_inline(i) {
+                return quote! { ::tagged_dispatch::__private::drop_inline };
+            }
+            quote! { ::tagged_dispatch::__private::drop_variant::<#ty, #allocator> }
+        });
+        let variant_count = variants.len();
+        quote! {
+            impl #impl_generics Drop for #self_ty {
+                fn drop(&mut self) {
+                    static DROP_TABLE: [unsafe fn(*mut ()); #variant_count] = [#(#drop_fns),*];
+
+                    #on_drop
+
+                    unsafe {
+                        // Out-of-range tags go through the invalid-tag policy like `match` drops
+                        let drop_fn = match DROP_TABLE.get(self.0.tag() as usize) {
+                            Some(drop_fn) => *drop_fn,
+                            None => {
+                                let tag = self.0.tag();
+                                #invalid_tag
+                            }
+                        };
+                        drop_fn(self.0.untagged_ptr() as *mut ());
+                    }
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics Drop for #self_ty {
+                fn drop(&mut self) {
+                    #on_drop
+
+                    unsafe {
+                        match self.0.tag() {
+                            #(#drop_arms)*
+                            tag => #invalid_tag,
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    // Generate Clone implementation; `shared` and `rc` handles add a reference instead of copying.
+    // Cloning boxed values is what requires `Clone` on every variant, so `no_clone` skips it
+    let clone_arms = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let method_name = enum_attrs.constructor(i, variant);
+        let tag = flags.tag_literal(i);
+        // Inline values are `Copy`, so the bits are the whole value
+        if enum_attrs.is_inline(i) {
+            return quote! { #tag => { Self(self.0, ::core::marker::PhantomData) } };
+        }
+        if flags.ref_counted() {
+            let allocator = enum_attrs.allocator(i);
+            return quote! {
+                #tag => {
+                    let ptr = self.0.untagged_ptr() as *mut #ty;
+                    <#allocator as ::tagged_dispatch::SharedAllocator<#ty>>::retain(::core::ptr::NonNull::new_unchecked(ptr));
+                    Self(self.0, ::core::marker::PhantomData)
+                }
+            };
+        }
+        quote! {
+            #tag => {
+                // Use ptr() which benefits from TBI on supported platforms
+                let ptr = self.0.ptr() as *const #ty;
+                let cloned = (*ptr).clone();
+                Self::#method_name(cloned)
+            }
+        }
+    });
+    let clone_impl = (!flags.no_clone).then(|| quote! {
+        impl #impl_generics Clone for #self_ty {
+            fn clone(&self) -> Self {
+                unsafe {
+                    match self.0.tag() {
+                        #(#clone_arms)*
+                        tag => #invalid_tag,
+                    }
+                }
+            }
+        }
+    });
+    
+    // Generate enum variants
+    let enum_variants = variants.iter().map(|(variant, _)| {
+        quote! { #variant }
+    });
+    
+    // Generate variant list for dispatch macros; owned variants also carry their
+    // allocator so consuming methods can give the value back
+    let variant_list: Vec<_> = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let allocator = enum_attrs.allocator(i);
+        let storage = enum_attrs.storage(i);
+        quote! { (#variant, #ty, #allocator, #storage) }
+    }).collect();
+
+    // `map_errors` traits generate the enum's error type under this name
+    let error_name = format_ident!("{}Error", enum_name);
+
+    // Generate dispatch macro invocations for each trait; std operators are implemented directly
+    let dispatch_invocations = traits.iter().map(|dispatch_trait| {
+        if let Some(operator_impl) = generate_operator_impl(
+            dispatch_trait, &impl_generics, &self_ty, &enum_type_name, variants, enum_attrs, true,
+        ) {
+            return operator_impl;
+        }
+        let trait_path = &dispatch_trait.path;
+        let macro_name = dispatch_macro_path(trait_path);
+        let qualified = dispatch_trait.qualified.then(|| quote! { @qualified });
+        let variant_list = variant_list.clone();
+
+        quote! {
+            #macro_name!(#qualified #trait_path, #enum_name, #enum_type_name, owned [#params] [#(#args),*], [#(#variant_list),*], #vis #error_name);
+        }
+    });
+
+    // Generate compile-time trait checks
+    let check_generics = &impl_generics;
+    let trait_checks = traits.iter().flat_map(|dispatch_trait| {
+        let trait_path = &dispatch_trait.path;
+        variants.iter().map(move |(_, ty)| {
+            type_check(check_generics, quote! {
+                fn assert_impl<T: #trait_path>() {}
+                assert_impl::<#ty>();
+            })
+        })
+    });
+
+    // Conditionally generate trait implementations
+    let debug_impl = if flags.should_generate_debug() && !enum_attrs.delegates_debug() {
+        quote! {
+            impl #impl_generics ::core::fmt::Debug for #self_ty {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                    write!(f, "{}::{:?}", stringify!(#enum_name), self.tag_type())
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let eq_impl = if flags.should_generate_eq() {
+        quote! {
+            impl #impl_generics ::core::cmp::PartialEq for #self_ty {
+                fn eq(&self, other: &Self) -> bool {
+                    self.0.eq(&other.0)
+                }
+            }
+
+            impl #impl_generics ::core::cmp::Eq for #self_ty {}
+        }
+    } else {
+        quote! {}
+    };
+
+    let ord_impl = if flags.should_generate_ord() {
+        quote! {
+            impl #impl_generics ::core::cmp::PartialOrd for #self_ty {
+                fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {
+                    self.0.partial_cmp(&other.0)
+                }
+            }
+
+            impl #impl_generics ::core::cmp::Ord for #self_ty {
+                fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                    self.0.cmp(&other.0)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // The handle owns values of its type parameters, as far as drop checking goes
+    let marker = if generic {
+        quote! { (*const (), #(#args,)*) }
+    } else {
+        quote! { *const () }
+    };
+
+    let output = quote! {
+        /// Tagged pointer dispatch type
+        #[repr(transparent)]
+        #vis struct #enum_name #impl_generics (
+            #ptr_type,
+            // Not `Send` or `Sync` unless the `send` / `sync` flags vouch for the variants
+            ::core::marker::PhantomData<#marker>
+        );
+
+        /// Type variants for compile-time checking
+        #[repr(#tag_repr)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        #non_exhaustive
+        #vis enum #enum_type_name {
+            #(#enum_variants,)*
+        }
+
+        #type_reflection
+        #tag_conversions
+
+        impl #impl_generics #self_ty {
+            #(#constructors)*
+
+            #(#try_constructors)*
+
+            #(#emplace_constructors)*
+
+            #variant_accessors
+            #map_concrete
+            #reflection_methods
+            #base_accessors
+            #stable_hash
+            #bytes_methods
+
+            /// Get the variant type of this value
+            #[inline(always)]
+            pub fn tag_type(&self) -> #enum_type_name {
+                let tag = self.0.tag() as #tag_repr;
+                #tag_type_check
+                unsafe { ::core::mem::transmute(tag) }
+            }
+
+            /// Give up the handle without dropping its value, returning its tagged pointer,
+            /// e.g. to store it in an `AtomicTaggedPtr`
+            #[inline(always)]
+            pub fn into_raw(self) -> #ptr_type {
+                ::core::mem::ManuallyDrop::new(self).0
+            }
+
+            /// Rebuild a handle from [`into_raw`](Self::into_raw)
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must come from `into_raw` on this enum, and is owned by the new handle.
+            #[inline(always)]
+            pub unsafe fn from_raw(ptr: #ptr_type) -> Self {
+                Self(ptr, ::core::marker::PhantomData)
+            }
+        }
+
+        #bytes_decode
+
+        #debug_impl
+        #eq_impl
+        #ord_impl
+        #delegate_impls
+        #hash_impl
+
+        #(#from_impls)*
+        #(#try_from_impls)*
+        
+        #drop_impl
+        #clone_impl
+        
+        // Apply dispatch implementations for each trait
+        #(#dispatch_invocations)*
+        #plugin_registry
+        #pool_key
+        #columns
+        #flatten_macro
+        #flatten_conversions
+        #take_replace
+        
+        // Compile-time trait implementation checks
+        #(#trait_checks)*
+
+        // Per-variant metaprogramming helpers
+        #each_variant_macro
+        #match_macro
+        #variant_types
+        #mapper
+
+        #slotmap_aliases
+        #test_util_impl
+        #thread_safety_impls
+        #node_impls
+        #cow_type
+        #registry_entry
+        
+        #size_assert
+        #tag_align_asserts
+        #tag_width_assert
+        #inline_value_asserts
+    };
+    
+    TokenStream::from(output)
+}
+
+/// Generate `clone_into_arena` on the owned enum an arena enum names with
+/// `owned = Enum` and `to_owned` on the arena enum, pairing variants by name
+fn generate_owned_conversions(
+    enum_name: &Ident,
+    enum_type_name: &Ident,
+    builder_name: &Ident,
+    lifetime: &syn::Lifetime,
+    owned: &Path,
+    variants: &[(Ident, Type)],
+    enum_attrs: &EnumAttrs,
+) -> TokenStream2 {
+    let into_arena = variants.iter().enumerate().map(|(i, (variant, _))| {
+        let as_name = format_ident!("as_{}", constructor_ident(variant));
+        let method_name = enum_attrs.constructor(i, variant);
+        quote! {
+            if let Some(value) = self.#as_name() {
+                return builder.#method_name(::core::clone::Clone::clone(value));
+            }
+        }
+    });
+    let to_owned_arms = variants.iter().map(|(variant, ty)| {
+        let as_name = format_ident!("as_{}", constructor_ident(variant));
+        quote! {
+            #enum_type_name::#variant => {
+                let value = ::core::clone::Clone::clone(self.#as_name().unwrap());
+                <#owned as ::tagged_dispatch::__private::FromVariant<#ty>>::from_variant(value)
+            }
+        }
+    });
+    let mismatch = format!("`{}` and `{}` must have the same variants", quote!(#owned).to_string().replace(' ', ""), enum_name);
+    quote! {
+        // Variants pair up by name, so with equal counts neither enum has one the other lacks
+        const _: () = assert!(<#owned>::VARIANT_COUNT == #enum_name::VARIANT_COUNT, #mismatch);
+
+        impl #owned {
+            /// Clone the value into `builder`'s arena, as the arena enum's handle
+            pub fn clone_into_arena<#lifetime>(&self, builder: &#lifetime #builder_name<#lifetime>) -> #enum_name<#lifetime> {
+                #(#into_arena)*
+                unreachable!("both enums have the same variants")
+            }
+        }
+
+        impl<#lifetime> #enum_name<#lifetime> {
+            /// Clone the value out of the arena, as the owned enum's handle
+            pub fn to_owned(&self) -> #owned {
+                match self.tag_type() {
+                    #(#to_owned_arms)*
+                }
+            }
+        }
+    }
+}
+
+/// Generate `replace` for owned enums, and `take` and `Default` when a variant is
+/// marked `#[default]`
+fn generate_take_replace(
+    impl_generics: &TokenStream2,
+    self_ty: &TokenStream2,
+    variants: &[(Ident, Type)],
+    enum_attrs: &EnumAttrs,
+) -> TokenStream2 {
+    let default = enum_attrs.default_variant.map(|i| {
+        let (variant, ty) = &variants[i];
+        let ctor = enum_attrs.constructor(i, variant);
+        let take_doc = format!("Take the value out, leaving a default `{}` behind", variant);
+        quote! {
+            impl #impl_generics #self_ty {
+                #[doc = #take_doc]
+                #[inline]
+                pub fn take(&mut self) -> Self {
+                    self.replace(::core::default::Default::default())
+                }
+            }
+
+            impl #impl_generics ::core::default::Default for #self_ty {
+                #[inline]
+                fn default() -> Self {
+                    Self::#ctor(<#ty as ::core::default::Default>::default())
+                }
+            }
+        }
+    });
+    quote! {
+        impl #impl_generics #self_ty {
+            /// Replace the value with `new`, returning the old one
+            #[inline]
+            pub fn replace(&mut self, new: Self) -> Self {
+                ::core::mem::replace(self, new)
+            }
+        }
+
+        #default
+    }
+}
+
+/// Generate `clone_in`, deep-cloning an arena handle's value into another builder.
+/// The `Clone` bounds are higher-ranked so enums with non-`Clone` variants still
+/// compile, just without a callable `clone_in`
+fn generate_clone_in(
+    enum_name: &Ident,
+    enum_type_name: &Ident,
+    builder_name: &Ident,
+    lifetime: &syn::Lifetime,
+    variants: &[(Ident, Type)],
+    enum_attrs: &EnumAttrs,
+) -> TokenStream2 {
+    let arms = variants.iter().enumerate().map(|(i, (variant, _))| {
+        let as_name = format_ident!("as_{}", constructor_ident(variant));
+        let method_name = enum_attrs.constructor(i, variant);
+        quote! {
+            #enum_type_name::#variant => builder.#method_name(::core::clone::Clone::clone(self.#as_name().unwrap())),
+        }
+    });
+    let bounds = variants.iter().map(|(_, ty)| quote! { for<'__clone> #ty: ::core::clone::Clone });
+    quote! {
+        impl<#lifetime> #enum_name<#lifetime> {
+            /// Deep-clone the value into `builder`'s arena, e.g. to keep it past the
+            /// arena it is in now
+            pub fn clone_in<'__to>(&self, builder: &'__to #builder_name<'__to>) -> #enum_name<'__to>
+            where
+                #(#bounds,)*
+            {
+                match self.tag_type() {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+}
+
+/// Generate implementation for arena version (has lifetime)
+fn generate_arena_impl(
+    enum_name: &Ident,
+    vis: &syn::Visibility,
+    lifetime: &syn::Lifetime,
+    variants: &[(Ident, Type)],
+    traits: &[DispatchTrait],
+    flags: &TraitGenerationFlags,
+    enum_attrs: &EnumAttrs,
+) -> TokenStream {
+    // Flattened variants are converted by re-tagging an owned handle
+    if enum_attrs.flattened.iter().any(Option::is_some) {
+        return syn::Error::new_spanned(enum_name, "`#[flatten]` is only supported on owned enums")
+            .to_compile_error()
+            .into();
+    }
+    // Arena variants live as long as their arena, so there is nothing to hook
+    if let Some(path) = enum_attrs.allocators.iter().flatten().next() {
+        return syn::Error::new_spanned(path, "#[alloc_with] is only supported on owned enums")
+            .to_compile_error()
+            .into();
+    }
+    // Arena handles are `Copy` whatever their variants are
+    if flags.no_clone {
+        return syn::Error::new_spanned(enum_name, "the `no_clone` flag is only supported on owned enums")
+            .to_compile_error()
+            .into();
+    }
+    // Arena values are freed with their arena, never one at a time
+    if flags.pooled {
+        return syn::Error::new_spanned(enum_name, "the `pooled` flag is only supported on owned enums")
+            .to_compile_error()
+            .into();
+    }
+    // Arena enums already borrow typed slices with `view_all`
+    if flags.columns {
+        return syn::Error::new_spanned(enum_name, "the `columns` flag is only supported on owned enums")
+            .to_compile_error()
+            .into();
+    }
+    // Arena handles already borrow their value
+    if flags.cow {
+        return syn::Error::new_spanned(enum_name, "the `cow` flag is only supported on owned enums")
+            .to_compile_error()
+            .into();
+    }
+    // The arena already keeps each value alive for every handle
+    if flags.shared || flags.rc {
+        let mode = if flags.shared { "shared" } else { "rc" };
+        return syn::Error::new_spanned(enum_name, format!("the `{mode}` flag is only supported on owned enums"))
+            .to_compile_error()
+            .into();
+    }
+    if let Some(path) = &enum_attrs.on_drop {
+        return syn::Error::new_spanned(path, "#[on_drop] is only supported on owned enums")
+            .to_compile_error()
+            .into();
+    }
+    if let Err(err) = check_inline_values(variants, flags, enum_attrs) {
+        return err.to_compile_error().into();
+    }
+
+    let enum_type_name = format_ident!("{}Type", enum_name);
+    let each_variant_macro = generate_each_variant_macro(enum_name, variants, flags);
+    let match_macro = generate_match_macro(enum_name, &enum_type_name, variants);
+    let variant_types = generate_variant_list(vis, enum_name, &[], variants);
+    let base_accessors = generate_base_accessors(&enum_type_name, variants, &enum_attrs.base_fields, false);
+    let variant_accessors = generate_variant_accessors(&enum_type_name, variants, enum_attrs, Some(lifetime), false);
+    let (map_concrete, mapper) = generate_concrete_mapper(vis, enum_name, variants, Some(lifetime), &syn::Generics::default());
+    let (reflection_methods, type_reflection) = generate_reflection(enum_name, &enum_type_name, variants, false);
+    let tag_conversions = generate_tag_conversions(&enum_type_name, variants, flags);
+    let stable_hash = generate_stable_hash(&enum_type_name, variants, flags, enum_attrs);
+    let slotmap_aliases = generate_slotmap_aliases(vis, enum_name, Some(lifetime), &[]);
+    let hash_impl = generate_hash_impl(&quote! { <#lifetime> }, &quote! { #enum_name<#lifetime> }, &enum_type_name, variants, flags, enum_attrs);
+    let bytes_methods = generate_bytes_methods(&enum_type_name, variants, flags);
+    let bytes_decode = generate_bytes_decode(variants, flags, enum_attrs, |ctor| quote! { self.#ctor }).map(|body| quote! {
+        /// Decode a handle written by `to_bytes`, allocating its value in the arena
+        pub fn from_bytes(&#lifetime self, bytes: &[u8]) -> Result<#enum_name<#lifetime>, ::tagged_dispatch::bytes::DecodeError> {
+            #body
+        }
+    });
+    let test_util_impl = generate_test_util_impl(&quote! { <#lifetime> }, &quote! { #enum_name<#lifetime> }, &enum_type_name, variants, false);
+    let thread_safety_impls = generate_thread_safety_impls(&quote! { <#lifetime> }, &quote! { #enum_name<#lifetime> }, variants, flags, false);
+    let node_impls = generate_node_impls(&quote! { <#lifetime> }, &quote! { #enum_name<#lifetime> }, variants, flags);
+    let registry_entry = generate_registry_entry(enum_name, &quote! { #enum_name<'static> }, variants, flags, false);
+    let ptr_type = handle_ptr_type(variants, flags);
+    let tag_repr = flags.tag_repr(variants.len());
+    let tag_type_check = flags.tag_type_check(enum_name, variants.len());
+    let tag_align_asserts = generate_tag_align_asserts(variants, flags);
+    let tag_width_assert = generate_tag_width_assert(enum_name, variants, flags);
+    let inline_value_asserts = generate_inline_value_asserts(variants, enum_attrs);
+    let delegate_impls = generate_delegate_impls(
+        &quote! { <#lifetime> },
+        &quote! { #enum_name<#lifetime> },
+        &enum_type_name,
+        variants,
+        enum_attrs,
+    );
+    let builder_name = format_ident!("{}ArenaBuilder", enum_name);
+    let arena_type_name = format_ident!("{}ArenaType", enum_name);
+    let owned_conversions = flags.owned.as_ref().map(|owned| {
+        generate_owned_conversions(enum_name, &enum_type_name, &builder_name, lifetime, owned, variants, enum_attrs)
+    });
+    // Values borrowing from the arena can't be moved to one with another lifetime
+    let clone_in = (!variants.iter().any(|(_, ty)| mentions_lifetime(ty, lifetime)))
+        .then(|| generate_clone_in(enum_name, &enum_type_name, &builder_name, lifetime, variants, enum_attrs));
+
+    // With `dump`, the builder records every value it allocates until it is reset.
+    // Raw pointers rather than handles keep the builder covariant in its lifetime.
+    let (live_field, live_init, record_live, clear_live) = if flags.dump {
+        (
+            quote! { live: ::core::cell::RefCell<Vec<(#enum_type_name, *const ())>>, },
+            quote! { live: ::core::cell::RefCell::new(Vec::new()), },
+            quote! { self.live.borrow_mut().push((handle.tag_type(), handle.0.ptr() as *const ())); },
+            quote! { self.live.get_mut().clear(); },
+        )
+    } else {
+        Default::default()
+    };
+    // Every builder brands what it allocates, so dispatch can catch handles that outlived it
+    let builder_inits = quote! { brand: ::tagged_dispatch::__private::ArenaBrand::new(), #live_init };
+    let brand_record = |ty: &Type| quote! {
+        self.brand.record(stringify!(#enum_name), handle.0.untagged_ptr() as *const #ty, handle.0.tag());
+    };
+
+    // Generate typed arena field declarations for each variant
+    let typed_arena_fields: Vec<_> = variants.iter().map(|(variant, ty)| {
+        let field_name = format_ident!("{}_arena", variant.to_string().to_snake_case());
+        quote! { #field_name: ::typed_arena::Arena<#ty> }
+    }).collect();
+
+    // Generate typed arena field initializations
+    let typed_arena_inits: Vec<_> = variants.iter().map(|(variant, _ty)| {
+        let field_name = format_ident!("{}_arena", variant.to_string().to_snake_case());
+        quote! { #field_name: ::typed_arena::Arena::new() }
+    }).collect();
+
+    // Clone for second usage in reset
+    let typed_arena_inits2 = typed_arena_inits.clone();
+    let typed_arena_sized_inits: Vec<_> = variants.iter().enumerate().map(|(i, (variant, _ty))| {
+        let field_name = format_ident!("{}_arena", variant.to_string().to_snake_case());
+        quote! { #field_name: ::typed_arena::Arena::with_capacity(counts[#i]) }
+    }).collect();
+
+    // Generate builder methods for each variant
+    // Arena values need a builder to be created in, so the default variant gets a
+    // builder method rather than a `Default` impl
+    let default_builder_method = enum_attrs.default_variant.map(|i| {
+        let (variant, ty) = &variants[i];
+        let ctor = enum_attrs.constructor(i, variant);
+        let method_name = format_ident!("default_{}", constructor_ident(variant), span = variant.span());
+        let ctor_vis = enum_attrs.constructor_vis(i);
+        quote! {
+            #[doc = concat!("Create a `", stringify!(#variant), "` variant holding its type's default value")]
+            #[inline]
+            #ctor_vis fn #method_name(&#lifetime self) -> #enum_name<#lifetime> {
+                self.#ctor(<#ty as ::core::default::Default>::default())
+            }
+        }
+    });
+
+    let builder_methods = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let tag = flags.tag_literal(i);
+        let method_name = enum_attrs.constructor(i, variant);
+        let field_name = format_ident!("{}_arena", variant.to_string().to_snake_case());
+
+        // Generate allocator match arms based on enabled features at macro build time
+        let allocator_arms = generate_allocator_arms(&field_name, ty, &arena_type_name);
+        let record_brand = brand_record(ty);
+        let ctor_vis = enum_attrs.constructor_vis(i);
+
+        // Inline values are packed into the handle and never touch the arena
+        let variant_docs = enum_attrs.variant_docs(i);
+        if enum_attrs.is_inline(i) {
+            let packed = enum_attrs.pack(i, ty, quote! { value });
+            return quote! {
+                #[doc = concat!("Create a `", stringify!(#variant), "` variant, stored inline in the handle")]
+                #variant_docs
+                #[inline]
+                #ctor_vis fn #method_name(&#lifetime self, value: #ty) -> #enum_name<#lifetime> {
+                    #enum_name(<#ptr_type>::new(#packed, #tag), ::core::marker::PhantomData)
+                }
+            };
+        }
+
+        quote! {
+            #[doc = concat!("Create a `", stringify!(#variant), "` variant in the arena")]
+            #variant_docs
+            #[inline]
+            #ctor_vis fn #method_name(&#lifetime self, value: #ty) -> #enum_name<#lifetime> {
+                let ptr = match &self.allocator {
+                    #allocator_arms
+                };
+                let handle = #enum_name(<#ptr_type>::new(ptr, #tag), ::core::marker::PhantomData);
+                #record_brand
+                #record_live
+
+                handle
+            }
+        }
+    });
+
+    // With `fallible`, also generate builder methods that check the address in release builds
+    let try_builder_methods = variants.iter().enumerate().filter(|(i, _)| flags.fallible && !enum_attrs.is_inline(*i)).map(|(i, (variant, ty))| {
+        let tag = flags.tag_literal(i);
+        let method_name = format_ident!("try_{}", constructor_ident(variant), span = variant.span());
+        let field_name = format_ident!("{}_arena", variant.to_string().to_snake_case());
+        let allocator_arms = generate_try_allocator_arms(&field_name, ty, &arena_type_name);
+        let record_brand = brand_record(ty);
+        let ctor_vis = enum_attrs.constructor_vis(i);
+
+        quote! {
+            #[doc = concat!("Create a `", stringify!(#variant), "` variant in the arena, or return an error if the arena is out of memory or the value's address overlaps the tag")]
+            ///
+            /// If the address overlaps the tag, the value has still been moved into the
+            /// arena, where it stays unused. Typed arenas can't report running out of memory.
+            #[inline]
+            #ctor_vis fn #method_name(&#lifetime self, value: #ty) -> Result<#enum_name<#lifetime>, ::tagged_dispatch::TagError> {
+                let ptr = match &self.allocator {
+                    #allocator_arms
+                };
+                let handle = #enum_name(<#ptr_type>::try_new(ptr, #tag)?, ::core::marker::PhantomData);
+                #record_brand
+                #record_live
+
+                Ok(handle)
+            }
+        }
+    });
+
+    // Generate in-place builder methods for variants too large to build on the stack
+    let emplace_builder_methods = variants.iter().enumerate().filter(|(i, _)| !enum_attrs.is_inline(*i)).map(|(i, (variant, ty))| {
+        let tag = flags.tag_literal(i);
+        let method_name = format_ident!("{}_with", constructor_ident(variant), span = variant.span());
+        let field_name = format_ident!("{}_arena", variant.to_string().to_snake_case());
+        let allocator_arms = generate_allocator_emplace_arms(&field_name, ty, &arena_type_name);
+        let record_brand = brand_record(ty);
+        let ctor_vis = enum_attrs.constructor_vis(i);
+
+        quote! {
+            #[doc = concat!("Create a `", stringify!(#variant), "` variant by initializing it directly in the arena")]
+            ///
+            /// # Safety
+            ///
+            /// `init` must fully initialize the slot before returning. It must not allocate
+            #[doc = concat!("another `", stringify!(#variant), "` through this builder: with typed arenas that")]
+            /// would take the slot being initialized, so the process aborts.
+            #[inline]
+            #ctor_vis unsafe fn #method_name(
+                &#lifetime self,
+                init: impl FnOnce(&mut ::core::mem::MaybeUninit<#ty>),
+            ) -> #enum_name<#lifetime> {
+                let ptr = match &self.allocator {
+                    #allocator_arms
+                };
+                let handle = #enum_name(<#ptr_type>::new(ptr, #tag), ::core::marker::PhantomData);
+                #record_brand
+                #record_live
+
+                handle
+            }
+        }
+    });
+
+    // Generate two-phase builder methods for values that are initialized incrementally
+    let pending_name = format_ident!("{}Pending", enum_name);
+    let double_buffered_name = format_ident!("{}DoubleBufferedBuilder", enum_name);
+    let bundle_name = format_ident!("{}ArenaBundle", enum_name);
+    // The bundle can be sent when the values in its arena can. The unused `for<'__send>`
+    // keeps bounds that don't hold from being errors.
+    let bundle_send_bounds = variants.iter().map(|(_, ty)| quote! { for<'__send> #ty: Send });
+    let uninit_builder_methods = variants.iter().enumerate().filter(|(i, _)| !enum_attrs.is_inline(*i)).map(|(i, (variant, ty))| {
+        let tag = flags.tag_literal(i);
+        let method_name = format_ident!("{}_uninit", constructor_ident(variant), span = variant.span());
+        let field_name = format_ident!("{}_arena", variant.to_string().to_snake_case());
+        let allocator_arms = generate_allocator_uninit_arms(&field_name, ty, &arena_type_name);
+        let record_brand = brand_record(ty);
+        let ctor_vis = enum_attrs.constructor_vis(i);
+
+        quote! {
+            #[doc = concat!("Reserve an uninitialized `", stringify!(#variant), "` variant in the arena")]
+            ///
+            /// Write the value through the returned slot, then call `assume_init` on the
+            /// pending handle to get the finished handle.
+            ///
+            /// # Safety
+            ///
+            /// The slot must be fully initialized before the builder is reset or dropped,
+            /// even if the pending handle is never used: a typed arena drops every value
+            /// it has handed out.
+            #[inline]
+            #[allow(clippy::mut_from_ref)] // Every call hands out a fresh slot
+            #ctor_vis unsafe fn #method_name(
+                &#lifetime self,
+            ) -> (&#lifetime mut ::core::mem::MaybeUninit<#ty>, #pending_name<#lifetime>) {
+                let slot = match &self.allocator {
+                    #allocator_arms
+                };
+                let handle = #enum_name(<#ptr_type>::new(slot as *mut (), #tag), ::core::marker::PhantomData);
+                #record_brand
+
+                (&mut *slot, #pending_name(handle))
+            }
+        }
+    });
+
+    // Let generated dispatch methods returning `Self` allocate a variant by its type
+    let alloc_variant_impls = variants.iter().enumerate().filter(|(i, _)| !shares_type(variants, *i)).map(|(i, (variant, ty))| {
+        let method_name = enum_attrs.constructor(i, variant);
+        quote! {
+            impl<#lifetime> ::tagged_dispatch::__private::AllocVariant<#lifetime, #ty> for #builder_name<#lifetime> {
+                type Handle = #enum_name<#lifetime>;
+
+                #[inline]
+                fn alloc_variant(&#lifetime self, value: #ty) -> #enum_name<#lifetime> {
+                    self.#method_name(value)
+                }
+            }
+        }
+    });
+
+    // Generate fallible extraction of arena references, which live as long as the arena.
+    // Inline values aren't in the arena, so they are copied out instead.
+    let try_from_impls = variants.iter().enumerate().filter(|(i, _)| !shares_type(variants, *i)).map(|(i, (variant, ty))| {
+        let try_into_name = format_ident!("try_into_{}", constructor_ident(variant));
+        let target = if enum_attrs.is_inline(i) {
+            quote! { #ty }
+        } else {
+            quote! { &#lifetime #ty }
+        };
+        quote! {
+            impl<#lifetime> ::core::convert::TryFrom<#enum_name<#lifetime>> for #target {
+                type Error = #enum_name<#lifetime>;
+
+                fn try_from(value: #enum_name<#lifetime>) -> Result<Self, Self::Error> {
+                    value.#try_into_name()
+                }
+            }
+        }
+    });
+
+    // Generate borrowed views over values stored outside any arena. Handles only
+    // ever hand out shared references, so pointing them at borrowed data is sound.
+    let view_methods = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let tag = flags.tag_literal(i);
+        let method_name = format_ident!("view_{}", constructor_ident(variant), span = variant.span());
+        let ctor_vis = enum_attrs.constructor_vis(i);
+        if enum_attrs.is_inline(i) {
+            let packed = enum_attrs.pack(i, ty, quote! { *value });
+            return quote! {
+                #[doc = concat!("Copy an existing `", stringify!(#ty), "` into an inline `", stringify!(#variant), "` variant")]
+                #[inline]
+                #ctor_vis fn #method_name(value: &#lifetime #ty) -> Self {
+                    #enum_name(<#ptr_type>::new(#packed, #tag), ::core::marker::PhantomData)
+                }
+            };
+        }
+        quote! {
+            #[doc = concat!("Borrow an existing `", stringify!(#ty), "` as a `", stringify!(#variant), "` variant without copying it")]
+            #[inline]
+            #ctor_vis fn #method_name(value: &#lifetime #ty) -> Self {
+                let ptr = value as *const #ty as *mut ();
+                ::tagged_dispatch::__private::release_view(value as *const #ty);
+                #enum_name(<#ptr_type>::new(ptr, #tag), ::core::marker::PhantomData)
+            }
+        }
+    });
+
+    // `view_all` takes one slice per variant, in declaration order
+    let view_all_params: Vec<_> = variants.iter().map(|(variant, ty)| {
+        let param = constructor_ident(variant);
+        (quote! { #param: &#lifetime [#ty] }, param)
+    }).collect();
+    let view_all_inputs = view_all_params.iter().map(|(input, _)| input);
+    let view_all_lens = view_all_params.iter().map(|(_, param)| quote! { #param.len() });
+    let view_all_extends = variants.iter().zip(&view_all_params).map(|((variant, _), (_, param))| {
+        let method_name = format_ident!("view_{}", constructor_ident(variant));
+        quote! { all_views.extend(#param.iter().map(Self::#method_name)); }
+    });
+    let view_all_vis = enum_attrs.all_variants_vis();
+
+    // Generate enum variants
+    let enum_variants = variants.iter().map(|(variant, _)| {
+        quote! { #variant }
+    });
+
+    // Generate variant list for dispatch macros
+    let variant_list: Vec<_> = variants.iter().enumerate().map(|(i, (variant, ty))| {
+        let storage = enum_attrs.storage(i);
+        quote! { (#variant, #ty, #storage) }
+    }).collect();
+
+    // `map_errors` traits generate the enum's error type under this name
+    let error_name = format_ident!("{}Error", enum_name);
+
+    // Generate dispatch macro invocations for each trait; std operators are implemented directly
+    let dispatch_invocations = traits.iter().map(|dispatch_trait| {
+        if let Some(operator_impl) = generate_operator_impl(
+            dispatch_trait, &quote! { <#lifetime> }, &quote! { #enum_name<#lifetime> }, &enum_type_name, variants, enum_attrs, false,
+        ) {
+            return operator_impl;
+        }
+        let trait_path = &dispatch_trait.path;
+        let macro_name = dispatch_macro_path(trait_path);
+        let qualified = dispatch_trait.qualified.then(|| quote! { @qualified });
+        let variant_list = variant_list.clone();
+
+        quote! {
+            #macro_name!(#qualified #trait_path, #enum_name, #enum_type_name, #lifetime, [#(#variant_list),*], #vis #error_name);
+        }
+    });
+
+    // Generate compile-time trait checks
+    let trait_checks = traits.iter().flat_map(|dispatch_trait| {
+        let trait_path = &dispatch_trait.path;
+        variants.iter().map(move |(_, ty)| {
+            quote! {
+                const _: fn() = || {
+                    fn assert_impl<T: #trait_path>() {}
+                    assert_impl::<#ty>();
+                };
+            }
+        })
+    });
+
+    let non_exhaustive = flags.non_exhaustive.then(|| quote! { #[non_exhaustive] });
+    let size_assert = generate_size_assert(&quote! { #enum_name<'static> }, flags);
+
+    // Generate the arena enum definition based on enabled features
+    // Convert lifetime to TokenStream2
+    let lifetime_tokens = quote! { #lifetime };
+    let arena_enum_definition = generate_arena_enum(&arena_type_name, &lifetime_tokens, &typed_arena_fields);
+
+    // Generate builder new implementation
+    let builder_new_impl = generate_builder_new();
+
+    // Generate builder methods
+    let builder_specific_methods = generate_builder_methods(&builder_name, &arena_type_name, &typed_arena_inits, &typed_arena_sized_inits, &lifetime_tokens, &builder_inits);
+
+    let clear_impl = generate_clear_impl(&arena_type_name);
+    let builder_drop_impl = generate_builder_drop_impl(&builder_name, &arena_type_name, &lifetime_tokens);
+
+    // Generate reset implementation
+    let reset_impl = generate_reset_impl(&arena_type_name, &typed_arena_inits2);
+
+    // Generate stats implementation
+    let stats_impl = generate_stats_impl(&arena_type_name);
+
+    let dump_method = flags.dump.then(|| {
+        let dump_variants = variants.iter().map(|(variant, ty)| quote! {
+            let values = live.iter().filter(|(tag_type, _)| *tag_type == #enum_type_name::#variant);
+            writeln!(out, "{} ({}):", stringify!(#variant), values.clone().count())?;
+            for (_, ptr) in values {
+                ::tagged_dispatch::__private::dump_value(out, unsafe { &*(*ptr as *const #ty) })?;
+            }
+        });
+        quote! {
+            /// Write every value allocated through this builder since it was created or
+            /// reset, grouped by variant, using the variant types' `Debug` impls
+            ///
+            /// Slots reserved with the `_uninit` methods are not included.
+            pub fn dump(&self, out: &mut impl ::core::fmt::Write) -> ::core::fmt::Result {
+                let live = self.live.borrow();
+                writeln!(out, "{}: {} live values", stringify!(#builder_name), live.len())?;
+                #(#dump_variants)*
+                Ok(())
+            }
+        }
+    });
+
+    // Conditionally generate trait implementations
+    let debug_impl = if flags.should_generate_debug() && !enum_attrs.delegates_debug() {
+        quote! {
+            impl<#lifetime> ::core::fmt::Debug for #enum_name<#lifetime> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                    write!(f, "{}::{:?}", stringify!(#enum_name), self.tag_type())
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let eq_impl = if flags.should_generate_eq() {
+        quote! {
+            impl<#lifetime> ::core::cmp::PartialEq for #enum_name<#lifetime> {
+                fn eq(&self, other: &Self) -> bool {
+                    self.0.eq(&other.0)
+                }
+            }
+
+            impl<#lifetime> ::core::cmp::Eq for #enum_name<#lifetime> {}
+        }
+    } else {
+        quote! {}
+    };
+
+    let ord_impl = if flags.should_generate_ord() {
+        quote! {
+            impl<#lifetime> ::core::cmp::PartialOrd for #enum_name<#lifetime> {
+                fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {
+                    self.0.partial_cmp(&other.0)
+                }
+            }
+
+            impl<#lifetime> ::core::cmp::Ord for #enum_name<#lifetime> {
+                fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                    self.0.cmp(&other.0)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let output = quote! {
+        /// Arena-allocated tagged pointer dispatch type
+        #[repr(transparent)]
+        #vis struct #enum_name<#lifetime>(
+            #ptr_type,
+            // Not `Send` or `Sync` unless the `send` / `sync` flags vouch for the variants
+            ::core::marker::PhantomData<&#lifetime *const ()>
+        );
+
+        /// Type variants for compile-time checking
+        #[repr(#tag_repr)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        #non_exhaustive
+        #vis enum #enum_type_name {
+            #(#enum_variants,)*
+        }
+
+        #type_reflection
+        #tag_conversions
+
+        // Generate arena type enum based on enabled features at macro build time
+        #arena_enum_definition
+
+        /// Arena builder for creating arena-allocated variants
+        #vis struct #builder_name<#lifetime> {
+            allocator: #arena_type_name<#lifetime>,
+            brand: ::tagged_dispatch::__private::ArenaBrand,
+            #live_field
+            _phantom: ::core::marker::PhantomData<&#lifetime ()>,
+        }
+
+        impl<#lifetime> #builder_name<#lifetime> {
+            /// Create a new builder with the default allocator
+            /// (prefers bumpalo if available)
+            pub fn new() -> Self {
+                #builder_new_impl
+            }
+
+            /// Run `build` with a builder over a fresh default arena, freed when `build`
+            /// returns
+            ///
+            /// The builder's lifetime is local to `build`, so no handle can escape it.
+            pub fn scope<R>(build: impl for<'s> FnOnce(&'s #builder_name<'s>) -> R) -> R {
+                let builder = #builder_name::new();
+                build(&builder)
+            }
+
+            #builder_specific_methods
+
+            /// Reset all allocations
+            pub fn reset(&mut self) {
+                #reset_impl
+                self.brand.reset();
+                #clear_live
+            }
+
+            /// Reset all allocations and return the arena's memory to the allocator,
+            /// rather than keeping it for reuse like `reset`
+            pub fn clear(&mut self) {
+                #clear_impl
+                self.reset();
+            }
+
+            /// Get memory usage statistics
+            pub fn stats(&self) -> ::tagged_dispatch::ArenaStats {
+                #stats_impl
+            }
+
+            #dump_method
+
+            #(#builder_methods)*
+            #default_builder_method
+
+            #(#try_builder_methods)*
+
+            #(#emplace_builder_methods)*
+
+            #(#uninit_builder_methods)*
+
+            #bytes_decode
+        }
+
+        #builder_drop_impl
+
+        /// Handle to an arena slot that has been reserved but not yet initialized
+        #[must_use = "a pending handle does nothing until `assume_init` is called"]
+        #vis struct #pending_name<#lifetime>(#enum_name<#lifetime>);
+
+        impl<#lifetime> #pending_name<#lifetime> {
+            /// Get the handle to the now initialized value
+            ///
+            /// # Safety
+            ///
+            /// The slot returned alongside this pending handle must be fully initialized.
+            #[inline]
+            pub unsafe fn assume_init(self) -> #enum_name<#lifetime> {
+                self.0
+            }
+
+            /// Get the variant type the slot was reserved for
+            #[inline]
+            pub fn tag_type(&self) -> #enum_type_name {
+                self.0.tag_type()
+            }
+        }
+
+        /// Pair of arena builders for data that has to outlive the frame that built it by one frame
+        ///
+        /// Each `flip` resets the older arena and builds the next frame in it, while the
+        /// handles kept from the last frame stay readable.
+        #vis struct #double_buffered_name {
+            frames: [#builder_name<'static>; 2],
+            current: usize,
+            // Only ever points into `frames[current]`
+            live: Vec<#enum_name<'static>>,
+        }
+
+        impl #double_buffered_name {
+            /// Create a double buffer with two default allocators and no live handles
+            pub fn new() -> Self {
+                Self {
+                    frames: [#builder_name::new(), #builder_name::new()],
+                    current: 0,
+                    live: Vec::new(),
+                }
+            }
+
+            /// Get the handles kept from the most recent frame
+            pub fn current(&self) -> &[#enum_name<'_>] {
+                &self.live
+            }
+
+            /// Build the next frame
+            ///
+            /// The older arena is reset first, then `build` gets the last frame's handles and a
+            /// builder for the new frame. The handles it returns become the new live set. The two
+            /// lifetimes are unrelated, so last frame's handles cannot be kept past the next flip.
+            pub fn flip<F>(&mut self, build: F)
+            where
+                F: for<'p, 'c> FnOnce(&[#enum_name<'p>], &'c #builder_name<'c>) -> Vec<#enum_name<'c>>,
+            {
+                let next = 1 - self.current;
+                self.frames[next].reset();
+                let kept = build(&self.live, &self.frames[next]);
+                // SAFETY: `kept` only borrows `frames[next]`, which is not reset again until the
+                // live set has moved on to the other arena
+                self.live = unsafe {
+                    ::core::mem::transmute::<Vec<#enum_name<'_>>, Vec<#enum_name<'static>>>(kept)
+                };
+                self.current = next;
+            }
+        }
+
+        /// An arena together with handles into it, which can be moved to another thread or
+        /// task as one unit when the variant types are `Send`
+        ///
+        /// Handles borrow the bundle, so they can only be used where the bundle is.
+        #vis struct #bundle_name {
+            // Only ever points into `builder`
+            handles: Vec<#enum_name<'static>>,
+            builder: #builder_name<'static>,
+        }
+
+        impl #bundle_name {
+            /// Create a bundle over a new default arena, keeping the handles `build` returns
+            pub fn new<F>(build: F) -> Self
+            where
+                F: for<'b> FnOnce(&'b #builder_name<'b>) -> Vec<#enum_name<'b>>,
+            {
+                let mut bundle = Self { handles: Vec::new(), builder: #builder_name::new() };
+                bundle.extend(|_, builder| build(builder));
+                bundle
+            }
+
+            /// Get the bundled handles
+            pub fn handles(&self) -> &[#enum_name<'_>] {
+                &self.handles
+            }
+
+            /// Allocate more values in the bundle's arena
+            ///
+            /// `build` gets the handles bundled so far and the builder, and the handles it
+            /// returns are added to the bundle.
+            pub fn extend<F>(&mut self, build: F)
+            where
+                F: for<'b> FnOnce(&[#enum_name<'b>], &'b #builder_name<'b>) -> Vec<#enum_name<'b>>,
+            {
+                let added = build(&self.handles, &self.builder);
+                // SAFETY: `added` only borrows `builder`, which moves with the handles and is
+                // never reset while the bundle exists
+                let added = unsafe {
+                    ::core::mem::transmute::<Vec<#enum_name<'_>>, Vec<#enum_name<'static>>>(added)
+                };
+                self.handles.extend(added);
+            }
+        }
+
+        // SAFETY: the handles only point into the builder's arena, which moves with them, and
+        // the values in it are `Send`
+        unsafe impl Send for #bundle_name where #(#bundle_send_bounds,)* {}
+
+        impl<#lifetime> #enum_name<#lifetime> {
+            /// Create a new arena builder for this type
+            pub fn arena_builder() -> #builder_name<#lifetime> {
+                #builder_name::new()
+            }
+
+            #(#view_methods)*
+
+            #variant_accessors
+            #map_concrete
+            #reflection_methods
+            #base_accessors
+            #stable_hash
+            #bytes_methods
+
+            /// Borrow every element of the given slices as handles without copying them,
+            /// e.g. to run a polymorphic pass over data already stored by type
+            #view_all_vis fn view_all(#(#view_all_inputs),*) -> Vec<Self> {
+                let mut all_views = Vec::with_capacity(0 #(+ #view_all_lens)*);
+                #(#view_all_extends)*
+                all_views
+            }
+
+            /// Get the variant type of this value
+            #[inline(always)]
+            pub fn tag_type(&self) -> #enum_type_name {
+                let tag = self.0.tag() as #tag_repr;
+                #tag_type_check
+                unsafe { ::core::mem::transmute(tag) }
+            }
+
+            /// Get the handle's tagged pointer, e.g. to store it in an `AtomicTaggedPtr`
+            #[inline(always)]
+            pub fn into_raw(self) -> #ptr_type {
+                self.0
+            }
+
+            /// Rebuild a handle from [`into_raw`](Self::into_raw)
+            ///
+            /// # Safety
+            ///
+            /// `ptr` must come from `into_raw` on this enum, and its value must outlive the handle's lifetime.
+            #[inline(always)]
+            pub unsafe fn from_raw(ptr: #ptr_type) -> Self {
+                Self(ptr, ::core::marker::PhantomData)
+            }
+        }
+
+        // Arena version is Copy
+        impl<#lifetime> Copy for #enum_name<#lifetime> {}
+
+        impl<#lifetime> Clone for #enum_name<#lifetime> {
+            #[inline(always)]
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        #debug_impl
+        #eq_impl
+        #ord_impl
+        #delegate_impls
+        #hash_impl
+
+        #(#try_from_impls)*
+        #(#alloc_variant_impls)*
+        #owned_conversions
+        #clone_in
+
+        // No Drop impl needed - arena handles deallocation
+
+        // Apply dispatch implementations for each trait
+        #(#dispatch_invocations)*
+
+        // Compile-time trait implementation checks
+        #(#trait_checks)*
+
+        // Per-variant metaprogramming helpers
+        #each_variant_macro
+        #match_macro
+        #variant_types
+        #mapper
+
+        #slotmap_aliases
+        #test_util_impl
+        #thread_safety_impls
+        #node_impls
+        #registry_entry
+
+        #size_assert
+        #tag_align_asserts
+        #tag_width_assert
+        #inline_value_asserts
+    };
+
+    TokenStream::from(output)
+}
+
+/// Generate `<Enum>Map` and `<Enum>SecondaryMap` slotmap aliases when the `slotmap`
+/// feature is enabled. `lifetime` is the arena lifetime, if any.
+fn generate_slotmap_aliases(
+    vis: &syn::Visibility,
+    enum_name: &Ident,
+    lifetime: Option<&syn::Lifetime>,
+    type_params: &[TokenStream2],
+) -> TokenStream2 {
+    #[cfg(feature = "slotmap")]
+    {
+        let map_name = format_ident!("{}Map", enum_name);
+        let secondary_map_name = format_ident!("{}SecondaryMap", enum_name);
+        let (params, handle) = match lifetime {
+            Some(lifetime) => (quote! { #lifetime, }, quote! { #enum_name<#lifetime> }),
+            None => (quote! { #(#type_params,)* }, quote! { #enum_name<#(#type_params),*> }),
+        };
+        quote! {
+            #[doc = concat!("`SlotMap` of `", stringify!(#enum_name), "` handles")]
+            #vis type #map_name<#params K = ::tagged_dispatch::slotmap::DefaultKey> =
+                ::tagged_dispatch::slotmap::SlotMap<K, #handle>;
+
+            #[doc = concat!("`SecondaryMap` of `", stringify!(#enum_name), "` handles")]
+            #vis type #secondary_map_name<#params K = ::tagged_dispatch::slotmap::DefaultKey> =
+                ::tagged_dispatch::slotmap::SecondaryMap<K, #handle>;
+        }
+    }
+
+    #[cfg(not(feature = "slotmap"))]
+    {
+        let _ = (vis, enum_name, lifetime, type_params);
+        quote! {}
+    }
+}
+
+/// Generate the `test_util::TaggedEnum` impl when the `test-util` feature is enabled
+fn generate_test_util_impl(
+    impl_generics: &TokenStream2,
+    self_ty: &TokenStream2,
+    enum_type_name: &Ident,
+    variants: &[(Ident, Type)],
+    owned: bool,
+) -> TokenStream2 {
+    #[cfg(feature = "test-util")]
+    {
+        let variant_names = variants.iter().map(|(variant, _)| variant);
+        quote! {
+            impl #impl_generics ::tagged_dispatch::test_util::TaggedEnum for #self_ty {
+                type Type = #enum_type_name;
+
+                const VARIANTS: &'static [#enum_type_name] = &[#(#enum_type_name::#variant_names),*];
+                const OWNED: bool = #owned;
+
+                fn tag_type(&self) -> #enum_type_name {
+                    self.tag_type()
+                }
+
+                fn tag(&self) -> u16 {
+                    self.0.tag()
+                }
+
+                fn value_ptr(&self) -> *const () {
+                    self.0.untagged_ptr() as *const ()
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "test-util"))]
+    {
+        let _ = (impl_generics, self_ty, enum_type_name, variants, owned);
+        quote! {}
+    }
+}
+
+/// Generate the `registry::EnumInfo` entry for `tagged_dispatch::registry()` when the
+/// `registry` feature is enabled
+fn generate_registry_entry(
+    enum_name: &Ident,
+    self_ty: &TokenStream2,
+    variants: &[(Ident, Type)],
+    flags: &TraitGenerationFlags,
+    owned: bool,
+) -> TokenStream2 {
+    #[cfg(feature = "registry")]
+    {
+        let layout = if flags.nan_box {
+            quote! { NanBox }
+        } else if variants.len() == 1 && !flags.cow {
+            quote! { Untagged }
+        } else if flags.low_tag {
+            let bits = flags.align_tag_bits(variants.len());
+            quote! { Low { bits: #bits } }
+        } else if let Some(bits) = flags.tag_bits.filter(|bits| *bits > HIGH_TAG_BITS) {
+            quote! { Wide { bits: #bits } }
+        } else {
+            quote! { High }
+        };
+        let variant_infos = variants.iter().map(|(variant, ty)| quote! {
+            ::tagged_dispatch::registry::VariantInfo {
+                name: stringify!(#variant),
+                type_name: stringify!(#ty),
+                size: ::core::mem::size_of::<#ty>(),
+                align: ::core::mem::align_of::<#ty>(),
+            }
+        });
+        quote! {
+            const _: () = {
+                ::tagged_dispatch::__register_enum! {
+                    static ENTRY: &::tagged_dispatch::registry::EnumInfo = &::tagged_dispatch::registry::EnumInfo {
+                        name: stringify!(#enum_name),
+                        module_path: module_path!(),
+                        owned: #owned,
+                        layout: ::tagged_dispatch::registry::TagLayout::#layout,
+                        handle_size: ::core::mem::size_of::<#self_ty>(),
+                        variants: &[#(#variant_infos),*],
+                    };
+                }
+            };
+        }
+    }
+
+    #[cfg(not(feature = "registry"))]
+    {
+        let _ = (enum_name, self_ty, variants, flags, owned);
+        quote! {}
+    }
+}
+
+/// Generate the span entered by each dispatch arm when the `tracing` feature is enabled
+fn generate_trace_span(method_name: &Ident) -> TokenStream2 {
+    #[cfg(feature = "tracing")]
+    return quote! {
+        let _span = ::tagged_dispatch::tracing::trace_span!(
+            "dispatch",
+            enum_name = stringify!($enum_name),
+            variant = stringify!($variant),
+            method = stringify!(#method_name),
+        )
+        .entered();
+    };
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = method_name;
+        quote! {}
+    }
+}
+
+/// Generate an enum-level copy of a default trait method.
+///
+/// The default body is evaluated with `self` being the enum, so the methods it
+/// calls resolve to the enum's own dispatch methods and the body is emitted once
+/// rather than once per variant. Variant overrides of the method are bypassed.
+fn generate_enum_default_method(method: &TraitItemFn) -> TokenStream2 {
+    let sig = &method.sig;
+    let body = method.default.as_ref().expect("enum default methods have a body");
+    let forwarded_attrs = method.attrs.iter().filter(|attr| {
+        FORWARDED_METHOD_ATTRS.iter().any(|name| attr.path().is_ident(name))
+    });
+
+    quote! {
+        #(#forwarded_attrs)*
+        #[inline]
+        pub #sig #body
+    }
+}
+
+/// Generate a `<name>_for_variant` method calling a receiver-less trait function
+/// on the concrete type of this value's variant
+fn generate_variant_lookup_method(method: &TraitItemFn) -> TokenStream2 {
+    generate_variant_lookup_method_named(method, None)
+}
+
+/// Generate a variant lookup method, optionally named `<name>_<qualifier>_for_variant`
+fn generate_variant_lookup_method_named(method: &TraitItemFn, qualifier: Option<&str>) -> TokenStream2 {
+    let fn_name = &method.sig.ident;
+    let lookup_name = format_ident!("{}_for_variant", qualified_ident(fn_name, qualifier));
+    let generics = &method.sig.generics;
+    let where_clause = &method.sig.generics.where_clause;
+    let inputs: Vec<_> = method.sig.inputs.iter().collect();
+    let output = &method.sig.output;
+
+    let arg_names: Vec<_> = inputs.iter().filter_map(|arg| {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
+                return Some(&pat_ident.ident);
+            }
+        }
+        None
+    }).collect();
+
+    let cfg_attrs = cfg_attrs(&method.attrs);
+
+    quote! {
+        #[doc = concat!("Call `", stringify!(#fn_name), "` on the concrete type of this value's variant")]
+        #(#cfg_attrs)*
+        #[inline]
+        // Generated for every receiver-less function, whether or not it's looked up
+        #[allow(dead_code)]
+        pub fn #lookup_name #generics (&self #(, #inputs)*) #output #where_clause {
+            match self.tag_type() {
+                $(
+                    $enum_type_name::$variant => {
+                        #[allow(deprecated)]
+                        <$type as $trait_path>::#fn_name(#(#arg_names),*)
+                    }
+                )*
+            }
+        }
+    }
+}
+
+/// Std traits that are never `#[tagged_dispatch]` traits and so are skipped when
+/// dispatching supertraits
+const NON_DISPATCH_SUPERTRAITS: &[&str] = &[
+    "Any", "Clone", "Copy", "Debug", "Default", "Display", "Eq", "Hash", "Ord",
+    "PartialEq", "PartialOrd", "Send", "Sized", "Sync", "Unpin",
+];
+
+/// Supertraits of `trait_def` whose dispatch macros should be invoked along with
+/// its own
+fn dispatched_supertraits(trait_def: &ItemTrait) -> Vec<Path> {
+    trait_def.supertraits.iter().filter_map(|bound| match bound {
+        syn::TypeParamBound::Trait(bound) if matches!(bound.modifier, syn::TraitBoundModifier::None) => {
+            let name = bound.path.segments.last()?.ident.to_string();
+            (!NON_DISPATCH_SUPERTRAITS.contains(&name.as_str())).then(|| bound.path.clone())
+        }
+        _ => None,
+    }).collect()
+}
+
+/// Generate a `<NAME>_TABLE` slice of an associated const indexed by tag, and a
+/// snake_case accessor returning the current variant's value
+fn generate_const_lookup(constant: &syn::TraitItemConst, qualifier: Option<&str>) -> TokenStream2 {
+    let const_name = &constant.ident;
+    let ty = &constant.ty;
+    let accessor = qualified_ident(&Ident::new(&const_name.to_string().to_snake_case(), const_name.span()), qualifier);
+    let table = format_ident!("{}_TABLE", accessor.to_string().to_uppercase(), span = const_name.span());
+    let forwarded_attrs = constant.attrs.iter().filter(|attr| attr.path().is_ident("doc") || attr.path().is_ident("cfg"));
+    let cfg_attrs = cfg_attrs(&constant.attrs);
+
+    quote! {
+        #[doc = concat!("`", stringify!(#const_name), "` of every variant type, indexed by tag")]
+        #(#cfg_attrs)*
+        // Generated for every associated const, whether or not it's read
+        #[allow(dead_code)]
+        pub const #table: &'static [#ty] = &[$(<$type as $trait_path>::#const_name),*];
+
+        #(#forwarded_attrs)*
+        #[inline]
+        #[allow(dead_code)]
+        pub fn #accessor(&self) -> #ty {
+            Self::#table[self.0.tag() as usize]
+        }
+    }
+}
+
+/// Check whether a signature or type mentions `Self` anywhere
+fn mentions_self(tokens: &impl quote::ToTokens) -> bool {
+    fn walk(tokens: TokenStream2) -> bool {
+        tokens.into_iter().any(|tree| match tree {
+            proc_macro2::TokenTree::Ident(ident) => ident == "Self",
+            proc_macro2::TokenTree::Group(group) => walk(group.stream()),
+            _ => false,
+        })
+    }
+    walk(tokens.to_token_stream())
+}
+
+/// Check whether a type mentions `lifetime` anywhere
+fn mentions_lifetime(tokens: &impl quote::ToTokens, lifetime: &syn::Lifetime) -> bool {
+    fn walk(tokens: TokenStream2, lifetime: &syn::Lifetime) -> bool {
+        let trees: Vec<_> = tokens.into_iter().collect();
+        trees.iter().enumerate().any(|(i, tree)| match tree {
+            proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                matches!(trees.get(i + 1), Some(proc_macro2::TokenTree::Ident(ident)) if *ident == lifetime.ident)
+            }
+            proc_macro2::TokenTree::Group(group) => walk(group.stream(), lifetime),
+            _ => false,
+        })
+    }
+    walk(tokens.to_token_stream(), lifetime)
+}
+
+/// Std supertraits that need `Sized` or mention `Self`, ruling out `dyn Trait`
+const SIZED_SUPERTRAITS: &[&str] = &["Sized", "Clone", "Copy", "Default", "PartialEq", "Eq", "PartialOrd", "Ord", "Hash"];
+
+/// Whether `dyn Trait` can be named for the trait, as far as its definition shows.
+/// Supertraits other than the std ones in `SIZED_SUPERTRAITS` are assumed compatible.
+fn is_dyn_compatible(trait_def: &ItemTrait) -> bool {
+    let sized_supertrait = trait_def.supertraits.iter().any(|bound| match bound {
+        syn::TypeParamBound::Trait(bound) => bound.path.segments.last().is_some_and(|segment| {
+            SIZED_SUPERTRAITS.iter().any(|name| segment.ident == name)
+        }),
+        _ => false,
+    });
+    if sized_supertrait {
+        return false;
+    }
+    trait_def.items.iter().all(|item| match item {
+        TraitItem::Fn(method) => requires_sized(method) || {
+            let sig = &method.sig;
+            let generic = sig.generics.params.iter().any(|param| !matches!(param, syn::GenericParam::Lifetime(_)));
+            let impl_return = matches!(&sig.output, syn::ReturnType::Type(_, ty) if matches!(&**ty, Type::ImplTrait(_)));
+            let mentions_self = sig.inputs.iter().skip(1).any(mentions_self) || mentions_self(&sig.output);
+            sig.receiver().is_some() && !generic && sig.asyncness.is_none() && !impl_return && !mentions_self
+        },
+        // Every variant would need the same associated types for `dyn Trait<Type = ..>`
+        TraitItem::Const(_) | TraitItem::Type(_) => false,
+        _ => true,
+    })
+}
+
+/// Whether a trait method opts out of trait objects with `where Self: Sized`
+fn requires_sized(method: &TraitItemFn) -> bool {
+    let Some(where_clause) = &method.sig.generics.where_clause else {
+        return false;
+    };
+    where_clause.predicates.iter().any(|predicate| match predicate {
+        syn::WherePredicate::Type(predicate) => {
+            matches!(&predicate.bounded_ty, Type::Path(path) if path.path.is_ident("Self"))
+                && predicate.bounds.iter().any(|bound| {
+                    matches!(bound, syn::TypeParamBound::Trait(bound) if bound.path.is_ident("Sized"))
+                })
+        }
+        _ => false,
+    })
+}
+
+/// Trait method attributes copied onto the generated dispatch methods
+const FORWARDED_METHOD_ATTRS: &[&str] = &["doc", "must_use", "deprecated", "track_caller", "cfg"];
+
+/// The `#[cfg(...)]` attributes of a trait item, which every method generated from it repeats
+fn cfg_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("cfg"))
+}
+
+/// Append `_<qualifier>` to a generated method name, keeping the trait method's span
+fn qualified_ident(name: &Ident, qualifier: Option<&str>) -> Ident {
+    match qualifier {
+        Some(qualifier) => format_ident!("{}_{}", name, qualifier, span = name.span()),
+        None => name.clone(),
+    }
+}
+
+/// Generate a single dispatch method implementation
+fn generate_dispatch_method(method: &TraitItemFn, owned: bool, map_errors: bool) -> proc_macro2::TokenStream {
+    generate_dispatch_method_named(method, None, owned, map_errors)
+}
+
+/// Generate a dispatch method, optionally named `<name>_<qualifier>`.
+///
+/// `owned` selects how methods taking `self` by value get at the variant: owned
+/// handles give up their box, while arena handles are `Copy` and so pass a clone.
+/// With `map_errors`, a `Result<T, Self::Error>` return type becomes
+/// `Result<T, $error_name>`, wrapping each variant's error in its own variant.
+fn generate_dispatch_method_named(
+    method: &TraitItemFn,
+    qualifier: Option<&str>,
+    owned: bool,
+    map_errors: bool,
+) -> proc_macro2::TokenStream {
+    let method_name = &method.sig.ident;
+    let generated_name = qualified_ident(method_name, qualifier);
+    let trace_span = generate_trace_span(method_name);
+
+    // Carry the trait method's docs and lint-relevant attributes over, so hover and
+    // warnings apply at the enum call sites as well
+    let forwarded_attrs = method.attrs.iter().filter(|attr| {
+        FORWARDED_METHOD_ATTRS.iter().any(|name| attr.path().is_ident(name))
+    });
+    let inputs = &method.sig.inputs;
+    let output = &method.sig.output;
+    
+    // Extract arguments (skip &self)
+    let args: Vec<_> = inputs.iter().skip(1).collect();
+    let arg_names: Vec<_> = args.iter().filter_map(|arg| {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
+                Some(&pat_ident.ident)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }).collect();
+    
+    // Locals get mixed-site hygiene so they never clash with the method's parameter names
+    let value = Ident::new("value", proc_macro2::Span::mixed_site());
+    let stored = Ident::new("stored", proc_macro2::Span::mixed_site());
+    let result = Ident::new("result", proc_macro2::Span::mixed_site());
+    let this = Ident::new("this", proc_macro2::Span::mixed_site());
+
+    let by_value = method.sig.receiver().is_some_and(|receiver| receiver.reference.is_none());
+    let by_mut = method.sig.receiver().is_some_and(|receiver| receiver.reference.is_some() && receiver.mutability.is_some());
+    // `#[inline_value]` variants are unpacked into `stored` on the stack; others borrow it in place
+    let storage = quote! { <$storage as ::tagged_dispatch::__private::VariantStorage<$type>> };
+    // Copies of an arena handle share the value, so only owned handles lend it out mutably
+    if by_mut && !owned {
+        return quote! {};
+    }
+    // Keep a named receiver lifetime, so late-bound lifetimes in the signature still resolve
+    let receiver_lifetime = method.sig.receiver().and_then(|receiver| receiver.reference.as_ref()).and_then(|(_, lifetime)| lifetime.as_ref());
+    let (receiver, take_value) = if !by_value {
+        (quote! { &#receiver_lifetime self }, quote! {
+            let #stored = #storage::get(self.0.ptr() as usize);
+            let #value = &*#stored;
+        })
+    } else if owned {
+        // Take the value back from the variant's allocator and skip our own Drop
+        (quote! { self }, quote! {
+            let #this = ::core::mem::ManuallyDrop::new(self);
+            let #value = #storage::take::<$alloc>(#this.0.untagged_ptr() as usize);
+        })
+    } else {
+        // Other copies of the handle may still point at the value
+        (quote! { self }, quote! {
+            let #stored = #storage::get(self.0.ptr() as usize);
+            let #value = <$type as ::core::clone::Clone>::clone(&*#stored);
+        })
+    };
+
+    // Arena handles can outlive their arena through unsafe code, caught here in debug builds
+    let check_handle = (!owned).then(|| quote! {
+        if !#storage::INLINE {
+            ::tagged_dispatch::__private::check_arena_handle(stringify!($enum_name), self.0.untagged_ptr() as *const $type, self.0.tag());
+        }
+    });
+
+    // A `Self` result is the variant type, so it is wrapped back into a handle with
+    // the same tag: owned handles box it, arena handles need a builder to allocate in
+    let returns_self = matches!(output, syn::ReturnType::Type(_, ty) if matches!(&**ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("Self")));
+    let boxed_output = boxed_return_type(method);
+    let mapped_ok = map_errors.then(|| mapped_error_ok_type(output)).flatten();
+    let output = match (&boxed_output, mapped_ok) {
+        (Some(boxed), _) => quote! { -> #boxed },
+        (None, Some(ok)) if owned => quote! { -> ::core::result::Result<#ok, $error_name<$($args)*>> },
+        (None, Some(ok)) => quote! { -> ::core::result::Result<#ok, $error_name> },
+        (None, None) => quote! { #output },
+    };
+    let (builder_generic, builder_param, builder_bounds, wrap) = if boxed_output.is_some() {
+        (None, quote! {}, None, quote! { Box::new(#result) })
+    } else if mapped_ok.is_some() {
+        (None, quote! {}, None, quote! { #result.map_err($error_name::$variant) })
+    } else if !returns_self {
+        (None, quote! {}, None, quote! { #result })
+    } else if owned {
+        (None, quote! {}, None, quote! {
+            <Self as ::tagged_dispatch::__private::FromVariant<$type>>::from_variant(#result)
+        })
+    } else {
+        (
+            Some(quote! { B }),
+            quote! { , builder: &$lifetime B },
+            Some(quote! { $(B: ::tagged_dispatch::__private::AllocVariant<$lifetime, $type, Handle = Self>),* }),
+            quote! { builder.alloc_variant(#result) },
+        )
+    };
+
+    // The method's own generic parameters and where clause carry over to the enum method,
+    // except bounds on `Self`, which constrain the variant types rather than the enum
+    let method_params = method.sig.generics.params.iter().map(|param| quote! { #param });
+    let generic_params: Vec<_> = method_params.chain(builder_generic).collect();
+    let generics = (!generic_params.is_empty()).then(|| quote! { <#(#generic_params),*> });
+    let method_predicates = method.sig.generics.where_clause.iter()
+        .flat_map(|clause| &clause.predicates)
+        .filter(|predicate| !matches!(predicate, syn::WherePredicate::Type(predicate)
+            if matches!(&predicate.bounded_ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("Self"))))
+        .map(|predicate| quote! { #predicate });
+    let predicates: Vec<_> = method_predicates.chain(builder_bounds).collect();
+    let where_clause = (!predicates.is_empty()).then(|| quote! { where #(#predicates),* });
+
+    if by_mut {
+        // Shared handles only lend out a value no other handle holds
+        let addr = Ident::new("addr", proc_macro2::Span::mixed_site());
+        let shared_message = "the value is shared with another handle and cannot be borrowed mutably";
+        return quote! {
+            #(#forwarded_attrs)*
+            #[inline]
+            pub fn #generated_name #generics (&#receiver_lifetime mut self #(, #args)*) #output #where_clause {
+                unsafe {
+                    match self.tag_type() {
+                        $(
+                            $enum_type_name::$variant => {
+                                #trace_span
+                                let #addr = self.0.untagged_ptr() as usize;
+                                #[allow(deprecated)]
+                                let #result = #storage::modify::<$alloc, _>(#addr, self.0.bits_mut(), |#value| {
+                                    <$type as $trait_path>::#method_name(#value #(, #arg_names)*)
+                                })
+                                .expect(#shared_message);
+                                #wrap
+                            }
+                        )*
+                    }
+                }
+            }
+        };
+    }
+
+    quote! {
+        #(#forwarded_attrs)*
+        #[inline]
+        pub fn #generated_name #generics (#receiver #builder_param #(, #args)*) #output #where_clause {
+            unsafe {
+                match self.tag_type() {
+                    $(
+                        $enum_type_name::$variant => {
+                            #trace_span
+                            #check_handle
+                            #take_value
+                            #[allow(deprecated, clippy::let_unit_value)]
+                            let #result = <$type as $trait_path>::#method_name(#value #(, #arg_names)*);
+                            #wrap
+                        }
+                    )*
+                }
+            }
+        }
+    }
+}
+
+/// Get the `T` of a `Result<T, Self::Error>` return type
+fn mapped_error_ok_type(output: &syn::ReturnType) -> Option<&Type> {
+    let syn::ReturnType::Type(_, ty) = output else {
+        return None;
+    };
+    let Type::Path(path) = &**ty else {
+        return None;
+    };
+    let segment = path.path.segments.last().filter(|segment| segment.ident == "Result")?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match (args.args.first(), args.args.get(1), args.args.len()) {
+        (Some(syn::GenericArgument::Type(ok)), Some(syn::GenericArgument::Type(Type::Path(err))), 2)
+            if err.qself.is_none() && err.path == syn::parse_quote!(Self::Error) => Some(ok),
+        _ => None,
+    }
+}
+
+/// Get the `Box<dyn ...>` a `#[dispatch_boxed]` method returns in place of its `impl Trait`.
+///
+/// Each variant returns its own opaque type, so they can only share a return type
+/// once boxed. The box borrows from `self` unless the bounds name a lifetime.
+fn boxed_return_type(method: &TraitItemFn) -> Option<TokenStream2> {
+    if !method.attrs.iter().any(|attr| attr.path().is_ident("dispatch_boxed")) {
+        return None;
+    }
+    let syn::ReturnType::Type(_, ty) = &method.sig.output else {
+        return None;
+    };
+    let Type::ImplTrait(impl_trait) = &**ty else {
+        return None;
+    };
+
+    let bounds = &impl_trait.bounds;
+    let names_lifetime = impl_trait.bounds.iter().any(|bound| matches!(bound, syn::TypeParamBound::Lifetime(_)));
+    let borrows_self = method.sig.receiver().is_some_and(|receiver| receiver.reference.is_some());
+    let self_lifetime = if borrows_self && !names_lifetime {
+        quote! { + '_ }
+    } else {
+        quote! {}
+    };
+    Some(quote! { Box<dyn #bounds #self_lifetime> })
+}
+
+/// Reject traits that `&Enum` cannot implement by forwarding to the dispatch methods
+fn check_ref_impls(trait_def: &ItemTrait) -> Result<()> {
+    for item in &trait_def.items {
+        let unsupported = match item {
+            TraitItem::Fn(method) => {
+                let no_dispatch = method.attrs.iter().any(|attr| attr.path().is_ident("no_dispatch"));
+                match method.sig.receiver() {
+                    _ if no_dispatch => method.default.is_none().then_some("#[no_dispatch] methods need a default body"),
+                    None => Some("methods without a receiver have no variant to dispatch on"),
+                    Some(receiver) if receiver.reference.is_none() || receiver.mutability.is_some() => {
+                        Some("only `&self` methods can be forwarded from a reference")
+                    }
+                    Some(_) if mentions_self(&method.sig.output) => Some("methods returning `Self` cannot be forwarded"),
+                    Some(_) => None,
+                }
+            }
+            TraitItem::Const(constant) => constant.default.is_none().then_some("associated consts need a default"),
+            TraitItem::Type(_) => Some("associated types are not supported"),
+            _ => None,
+        };
+        if let Some(reason) = unsupported {
+            return Err(syn::Error::new_spanned(item, format!("`ref_impls` cannot implement this item: {}", reason)));
+        }
+    }
+    Ok(())
+}
+
+/// Generate a trait method for `&Enum` that calls the enum's dispatch method
+fn generate_ref_forward(method: &TraitItemFn, qualifier: Option<&str>) -> TokenStream2 {
+    let sig = &method.sig;
+    let generated_name = qualified_ident(&sig.ident, qualifier);
+    let arg_names = sig.inputs.iter().skip(1).filter_map(|arg| match arg {
+        syn::FnArg::Typed(pat_type) => match &*pat_type.pat {
+            syn::Pat::Ident(pat_ident) => Some(&pat_ident.ident),
+            _ => None,
+        },
+        _ => None,
+    });
+    let cfg_attrs = cfg_attrs(&method.attrs);
+    quote! {
+        #(#cfg_attrs)*
+        #[inline]
+        #[allow(deprecated)]
+        #sig {
+            (**self).#generated_name(#(#arg_names),*)
+        }
+    }
+}
+
+/// Reject traits a plugin can't implement by calling through its trait object
+fn check_plugin_trait(trait_def: &ItemTrait, dispatch_methods: &[TraitItemFn], options: &TraitOptions) -> std::result::Result<(), &'static str> {
+    if options.no_dyn || !is_dyn_compatible(trait_def) {
+        return Err("only dyn-compatible traits can be called through a plugin's function-pointer table");
+    }
+    let no_dispatch_required = trait_def.items.iter().any(|item| matches!(item, TraitItem::Fn(method)
+        if method.default.is_none() && method.attrs.iter().any(|attr| attr.path().is_ident("no_dispatch"))));
+    if no_dispatch_required {
+        return Err("#[no_dispatch] methods need a default body");
+    }
+    for method in dispatch_methods {
+        if requires_sized(method) {
+            return Err("methods bounded `where Self: Sized` can't be called on a trait object");
+        }
+        if method.sig.receiver().is_some_and(|receiver| receiver.reference.is_none()) {
+            return Err("methods taking `self` by value can't be called on a trait object");
+        }
+    }
+    Ok(())
+}
+
+/// Generate a trait method for an `open` enum's plugin type that calls the method on
+/// the registered value's trait object
+fn generate_plugin_forward(method: &TraitItemFn) -> TokenStream2 {
+    let sig = &method.sig;
+    let method_name = &sig.ident;
+    let arg_names = sig.inputs.iter().skip(1).filter_map(|arg| match arg {
+        syn::FnArg::Typed(pat_type) => match &*pat_type.pat {
+            syn::Pat::Ident(pat_ident) => Some(&pat_ident.ident),
+            _ => None,
+        },
+        _ => None,
+    });
+    let by_mut = sig.receiver().is_some_and(|receiver| receiver.mutability.is_some());
+    let cast = if by_mut { quote! { cast_mut } } else { quote! { cast } };
+    let cfg_attrs = cfg_attrs(&method.attrs);
+    quote! {
+        #(#cfg_attrs)*
+        #[inline]
+        #[allow(deprecated)]
+        #sig {
+            <dyn $trait_path as $trait_path>::#method_name(
+                ::tagged_dispatch::__private::PluginCast::<dyn $trait_path>::#cast(self)
+                #(, #arg_names)*
+            )
+        }
+    }
+}
+
+/// Options accepted by `#[tagged_dispatch(...)]` on a trait
+#[derive(Debug, Clone, Default)]
+struct TraitOptions {
+    /// Evaluate every default method at the enum level (as if marked `#[enum_default]`)
+    enum_defaults: bool,
+    /// Also dispatch the methods of the trait's supertraits (implied for empty traits)
+    supertraits: bool,
+    /// Supertraits named in `supertraits(...)`; empty means every dispatchable one
+    selected_supertraits: Vec<Ident>,
+    /// Implement the trait for `&Enum` and `&mut Enum` by forwarding to the dispatch methods
+    ref_impls: bool,
+    /// Return `Result<T, <Enum>Error>` from methods returning `Result<T, Self::Error>`
+    map_errors: bool,
+    /// Skip the `as_<trait>()` accessor returning `&dyn Trait`
+    no_dyn: bool,
+    /// Path generated code reaches this crate through, from `crate = "..."`
+    crate_path: Option<Path>,
+    /// `#[macro_export]` the dispatch macro, so enums in other crates can use the trait
+    export: bool,
+}
+
+impl Parse for TraitOptions {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut options = TraitOptions::default();
+        let items = Punctuated::<syn::Meta, Token![,]>::parse_terminated(input)?;
+
+        for item in items {
+            if item.path().is_ident("enum_defaults") && matches!(item, syn::Meta::Path(_)) {
+                options.enum_defaults = true;
+            } else if item.path().is_ident("supertraits") && matches!(item, syn::Meta::Path(_)) {
+                options.supertraits = true;
+            } else if item.path().is_ident("ref_impls") && matches!(item, syn::Meta::Path(_)) {
+                options.ref_impls = true;
+            } else if item.path().is_ident("map_errors") && matches!(item, syn::Meta::Path(_)) {
+                options.map_errors = true;
+            } else if item.path().is_ident("no_dyn") && matches!(item, syn::Meta::Path(_)) {
+                options.no_dyn = true;
+            } else if item.path().is_ident("export") && matches!(item, syn::Meta::Path(_)) {
+                options.export = true;
+            } else if let syn::Meta::NameValue(name_value) = &item {
+                if !name_value.path.is_ident("crate") {
+                    return Err(syn::Error::new_spanned(
+                        item,
+                        "unknown trait option; expected `enum_defaults`, `supertraits`, `ref_impls`, `map_errors`, `no_dyn`, `export`, or `crate = \"...\"`"
+                    ));
+                }
+                options.crate_path = Some(parse_crate_path(&name_value.value)?);
+            } else if let syn::Meta::List(list) = &item {
+                if !list.path.is_ident("supertraits") {
+                    return Err(syn::Error::new_spanned(
+                        item,
+                        "unknown trait option; expected `enum_defaults`, `supertraits`, `ref_impls`, `map_errors`, `no_dyn`, `export`, or `crate = \"...\"`"
+                    ));
+                }
+                // `supertraits(Draw, Update)` skips supertraits that aren't `#[tagged_dispatch]`
+                let names = list.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+                options.supertraits = true;
+                options.selected_supertraits.extend(names);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    item,
+                    "unknown trait option; expected `enum_defaults`, `supertraits`, `ref_impls`, `map_errors`, `no_dyn`, `export`, or `crate = \"...\"`"
+                ));
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Configuration flags for controlling trait generation
+#[derive(Debug, Clone, Default)]
+struct TraitGenerationFlags {
+    no_debug: bool,
+    no_eq: bool,
+    no_ord: bool,
+    no_traits: bool,
+    /// Skip the owned handle's `Clone` impl, so variants needn't be `Clone`
+    no_clone: bool,
+    /// Drop owned values through a static table of shared drop functions
+    drop_table: bool,
+    /// Skip the compile-time check that the handle is a single tagged pointer
+    no_size_assert: bool,
+    /// Mark the generated `*Type` enum `#[non_exhaustive]`
+    non_exhaustive: bool,
+    /// Generate `stable_hash`, hashing the tag and the variant's value
+    stable_hash: bool,
+    /// Implement `Display` by forwarding to the variant (shorthand for `#[delegate(Display)]`)
+    display: bool,
+    /// Implement `Hash` over the tag and the variant's value
+    hash: bool,
+    /// Generate `to_bytes` and byte decoding for `bytemuck::Pod` variants
+    bytes: bool,
+    /// Implement `Send`, checking that the variants allow it
+    send: bool,
+    /// Implement `Sync`, checking that the variants allow it
+    sync: bool,
+    /// Tag width from `tag_bits = N`, split between the high and low bits when wider
+    /// than `HIGH_TAG_BITS`
+    tag_bits: Option<u32>,
+    /// Generate `<Enum>Cow<'a>`, holding either an owned handle or a borrowed value
+    cow: bool,
+    /// Store the whole tag in the low alignment bits, from `tag = "low"`
+    low_tag: bool,
+    /// What to do with a tag outside the enum, from `invalid_tag = ...`
+    invalid_tag: InvalidTagPolicy,
+    /// Record arena allocations so the builder can `dump` them
+    dump: bool,
+    /// Generate `try_<variant>` constructors that return a `TagError` instead of
+    /// storing a pointer the tag doesn't fit on
+    fallible: bool,
+    /// Reference count owned values through `ArcAllocator` instead of boxing them
+    shared: bool,
+    /// Reference count owned values through the non-atomic `RcAllocator`
+    rc: bool,
+    /// Store the `f64` variant as the handle's bits and the rest in NaN payloads
+    nan_box: bool,
+    /// Add a `Plugin` variant for types registered at runtime
+    open: bool,
+    /// Store variants of the small primitive types inline without `#[inline_value]`
+    inline_small: bool,
+    /// Recycle owned allocations through per-thread pools
+    pooled: bool,
+    /// Generate `<Enum>Columns`, a store with one `Vec` per variant type
+    columns: bool,
+    /// Prepended to every constructor name, from `constructor_prefix = "..."`
+    constructor_prefix: Option<String>,
+    /// Owned enum over the same variants an arena enum converts to and from, from
+    /// `owned = Enum`
+    owned: Option<Path>,
+    /// Path generated code reaches this crate through, from `crate = "..."`
+    crate_path: Option<Path>,
+}
+
+/// What generated code does if it ever sees a tag outside the enum, which can only
+/// happen through memory corruption or unsound code
+#[derive(Debug, Clone, Default)]
+enum InvalidTagPolicy {
+    /// Panic naming the enum and the tag, checking `tag_type()` in debug builds
+    #[default]
+    Panic,
+    /// Assume it never happens, with `unreachable_unchecked`
+    Unchecked,
+    /// Call a user `fn(&'static str, usize) -> !` with the enum name and tag, always
+    /// checking `tag_type()`
+    Hook(Path),
+}
+
+impl TraitGenerationFlags {
+    fn should_generate_debug(&self) -> bool {
+        !self.no_traits && !self.no_debug
+    }
+
+    fn should_generate_eq(&self) -> bool {
+        !self.no_traits && !self.no_eq
+    }
+
+    fn should_generate_ord(&self) -> bool {
+        !self.no_traits && !self.no_ord && !self.no_eq // Ord requires Eq
+    }
+
+    /// Whether handles share their value, through either `shared` or `rc`
+    fn ref_counted(&self) -> bool {
+        self.shared || self.rc
+    }
+
+    /// Reject combinations of options that need conflicting pointer layouts, reporting
+    /// the error at `tokens`, the option just read
+    fn check_tag_layout(&self, tokens: impl quote::ToTokens) -> Result<()> {
+        let conflict = if self.bytes && self.low_tag_bits() > 0 {
+            "the `bytes` flag encodes a one-byte tag and can't be combined with `tag_bits` wider than the high tag bits"
+        } else if self.low_tag && self.tag_bits.is_some() {
+            "`tag = \"low\"` sizes the tag to the variant count and can't be combined with `tag_bits`"
+        } else if self.low_tag && self.cow {
+            "the `cow` flag needs the spare high bit and can't be combined with `tag = \"low\"`"
+        } else {
+            return Ok(());
+        };
+        Err(syn::Error::new_spanned(tokens, conflict))
+    }
+
+    /// An expression of type `!` handling the invalid tag `tag` of `enum_name`, for use
+    /// in an unsafe context
+    fn invalid_tag_handler(&self, enum_name: &Ident, tag: TokenStream2) -> TokenStream2 {
+        match &self.invalid_tag {
+            InvalidTagPolicy::Panic => quote! {
+                ::tagged_dispatch::__private::invalid_tag(stringify!(#enum_name), #tag as usize)
+            },
+            InvalidTagPolicy::Unchecked => quote! { ::core::hint::unreachable_unchecked() },
+            InvalidTagPolicy::Hook(hook) => quote! { #hook(stringify!(#enum_name), #tag as usize) },
+        }
+    }
+
+    /// The range check `tag_type()` runs before converting a tag, per the invalid-tag policy
+    fn tag_type_check(&self, enum_name: &Ident, variant_count: usize) -> TokenStream2 {
+        let handler = self.invalid_tag_handler(enum_name, quote! { tag });
+        let check = quote! {
+            if tag as usize >= #variant_count {
+                unsafe { #handler }
+            }
+        };
+        match self.invalid_tag {
+            InvalidTagPolicy::Panic => quote! {
+                #[cfg(debug_assertions)]
+                #check
+            },
+            // An unchecked branch tells the optimizer the tag is in range
+            InvalidTagPolicy::Unchecked | InvalidTagPolicy::Hook(_) => check,
+        }
+    }
+
+    /// Bits of the tag stored in the low alignment bits of each variant's address
+    fn align_tag_bits(&self, variant_count: usize) -> u32 {
+        if self.low_tag {
+            usize::BITS - variant_count.saturating_sub(1).leading_zeros()
+        } else {
+            self.low_tag_bits()
+        }
+    }
+
+    /// Tag bits stored in the low alignment bits of the address, none if `tag_bits`
+    /// fits in the high bits
+    fn low_tag_bits(&self) -> u32 {
+        self.tag_bits.map_or(0, |bits| bits.saturating_sub(HIGH_TAG_BITS))
+    }
+
+    fn max_variants(&self) -> usize {
+        if self.nan_box {
+            return NAN_BOX_VARIANTS;
+        }
+        let max = 1 << (HIGH_TAG_BITS + self.low_tag_bits());
+        // `to_bytes` writes the tag as one byte
+        if self.bytes { max.min(256) } else { max }
+    }
+
+    /// Whether the tags of an enum with `variant_count` variants can exceed a byte,
+    /// which a wide configured high tag allows without `tag_bits`
+    fn wide_repr(&self, variant_count: usize) -> bool {
+        self.low_tag_bits() > 0 || variant_count > 256
+    }
+
+    /// The integer type tags are stored as
+    fn tag_repr(&self, variant_count: usize) -> TokenStream2 {
+        if self.wide_repr(variant_count) {
+            quote! { u16 }
+        } else {
+            quote! { u8 }
+        }
+    }
+
+    /// The tag of the `index`th variant as a literal of the pointers' `u16` tags
+    fn tag_literal(&self, index: usize) -> TokenStream2 {
+        let tag = index as u16;
+        quote! { #tag }
+    }
+
+    /// The tag of the `index`th of `variant_count` variants as a literal of the tag type
+    fn repr_tag_literal(&self, index: usize, variant_count: usize) -> TokenStream2 {
+        if self.wide_repr(variant_count) {
+            let tag = index as u16;
+            quote! { #tag }
+        } else {
+            let tag = index as u8;
+            quote! { #tag }
+        }
+    }
+}
+
+/// Flags accepted in the enum attribute's argument list
+const KNOWN_FLAGS: &[&str] = &["no_debug", "no_eq", "no_ord", "no_cmp", "no_traits", "no_clone", "drop_table", "no_size_assert", "non_exhaustive", "stable_hash", "display", "hash", "bytes", "send", "sync", "cow", "dump", "fallible", "shared", "rc", "nan_box", "open", "inline_small", "pooled", "columns"];
+
+/// Return the identifier if `path` looks like a flag (a bare lowercase identifier)
+/// rather than a trait name
+fn flag_like_ident(path: &Path) -> Option<&Ident> {
+    let ident = path.get_ident()?;
+    ident.to_string().starts_with(|c: char| c.is_ascii_lowercase()).then_some(ident)
+}
+
+/// Build the error for an unrecognized flag, suggesting the closest known flag
+fn unknown_flag_error(ident: &Ident) -> syn::Error {
+    let name = ident.to_string();
+    let suggestion = KNOWN_FLAGS
+        .iter()
+        .map(|flag| (edit_distance(&name, flag), flag))
+        .filter(|(distance, flag)| *distance <= flag.len() / 3)
+        .min_by_key(|(distance, _)| *distance);
+
+    let message = match suggestion {
+        Some((_, flag)) => format!("unknown flag `{}`; did you mean `{}`?", name, flag),
+        None => format!(
+            "unknown flag `{}`; expected a trait name or one of: {}",
+            name,
+            KNOWN_FLAGS.join(", ")
+        ),
+    };
+    syn::Error::new_spanned(ident, message)
+}
+
+/// Levenshtein distance between two strings, used for flag suggestions
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+
+    prev[b.len()]
+}
+
+/// Parse the string of a `crate = "..."` option as a path
+fn parse_crate_path(value: &syn::Expr) -> Result<Path> {
+    match value {
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) => lit.parse::<Path>(),
+        other => Err(syn::Error::new_spanned(other, "expected a path in a string, e.g. `crate = \"my_framework::tagged_dispatch\"`")),
+    }
+}
+
+/// A trait listed on a tagged enum
+struct DispatchTrait {
+    path: Path,
+    /// Listed as `qualified(Trait)`: methods are generated as `<method>_as_<trait>`
+    qualified: bool,
+}
+
+/// Parser for comma-separated trait list and optional flags
+struct TraitListWithFlags {
+    traits: Vec<DispatchTrait>,
+    flags: TraitGenerationFlags,
+}
+
+impl TraitListWithFlags {
+    /// Record a single comma-separated item as either a flag or a trait path
+    fn push_item(&mut self, item: syn::Expr) -> Result<()> {
+        let flags = &mut self.flags;
+
+        // Try to parse as a path (trait name)
+        if let syn::Expr::Path(expr_path) = item {
+            // Check if it's a known flag
+            if expr_path.path.is_ident("no_debug") {
+                flags.no_debug = true;
+            } else if expr_path.path.is_ident("no_eq") {
+                flags.no_eq = true;
+            } else if expr_path.path.is_ident("no_ord") {
+                flags.no_ord = true;
+            } else if expr_path.path.is_ident("no_cmp") {
+                flags.no_eq = true;
+                flags.no_ord = true;
+            } else if expr_path.path.is_ident("no_traits") {
+                flags.no_traits = true;
+            } else if expr_path.path.is_ident("no_clone") {
+                flags.no_clone = true;
+            } else if expr_path.path.is_ident("drop_table") {
+                flags.drop_table = true;
+            } else if expr_path.path.is_ident("no_size_assert") {
+                flags.no_size_assert = true;
+            } else if expr_path.path.is_ident("non_exhaustive") {
+                flags.non_exhaustive = true;
+            } else if expr_path.path.is_ident("stable_hash") {
+                flags.stable_hash = true;
+            } else if expr_path.path.is_ident("display") {
+                flags.display = true;
+            } else if expr_path.path.is_ident("hash") {
+                flags.hash = true;
+            } else if expr_path.path.is_ident("bytes") {
+                flags.bytes = true;
+                flags.check_tag_layout(&expr_path)?;
+            } else if expr_path.path.is_ident("send") {
+                flags.send = true;
+            } else if expr_path.path.is_ident("sync") {
+                flags.sync = true;
+            } else if expr_path.path.is_ident("cow") {
+                flags.cow = true;
+                flags.check_tag_layout(&expr_path)?;
+            } else if expr_path.path.is_ident("dump") {
+                flags.dump = true;
+            } else if expr_path.path.is_ident("fallible") {
+                flags.fallible = true;
+            } else if expr_path.path.is_ident("shared") {
+                flags.shared = true;
+            } else if expr_path.path.is_ident("rc") {
+                flags.rc = true;
+            } else if expr_path.path.is_ident("nan_box") {
+                flags.nan_box = true;
+            } else if expr_path.path.is_ident("open") {
+                flags.open = true;
+            } else if expr_path.path.is_ident("inline_small") {
+                flags.inline_small = true;
+            } else if expr_path.path.is_ident("pooled") {
+                flags.pooled = true;
+            } else if expr_path.path.is_ident("columns") {
+                flags.columns = true;
+            } else if let Some(ident) = flag_like_ident(&expr_path.path) {
+                // Lowercase bare identifiers are flags, never traits
+                return Err(unknown_flag_error(ident));
+            } else {
+                // It's a trait path
+                self.traits.push(DispatchTrait { path: expr_path.path, qualified: false });
+            }
+            Ok(())
+        } else if let syn::Expr::Assign(assign) = &item {
+            if is_ident_expr(&assign.left, "tag") {
+                flags.low_tag = match &*assign.right {
+                    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) if lit.value() == "low" => true,
+                    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) if lit.value() == "high" => false,
+                    other => return Err(syn::Error::new_spanned(other, "expected `tag = \"high\"` or `tag = \"low\"`")),
+                };
+                flags.check_tag_layout(assign)?;
+                return Ok(());
+            }
+            if is_ident_expr(&assign.left, "invalid_tag") {
+                flags.invalid_tag = match &*assign.right {
+                    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) if lit.value() == "panic" => InvalidTagPolicy::Panic,
+                    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) if lit.value() == "unchecked" => InvalidTagPolicy::Unchecked,
+                    syn::Expr::Path(expr_path) => InvalidTagPolicy::Hook(expr_path.path.clone()),
+                    other => return Err(syn::Error::new_spanned(
+                        other,
+                        "expected `invalid_tag = \"panic\"`, `invalid_tag = \"unchecked\"`, or a hook function path"
+                    )),
+                };
+                return Ok(());
+            }
+            if is_ident_expr(&assign.left, "crate") {
+                flags.crate_path = Some(parse_crate_path(&assign.right)?);
+                return Ok(());
+            }
+            if is_ident_expr(&assign.left, "constructor_prefix") {
+                flags.constructor_prefix = match &*assign.right {
+                    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) => Some(lit.value()),
+                    other => return Err(syn::Error::new_spanned(other, "expected a string, e.g. `constructor_prefix = \"make_\"`")),
+                };
+                return Ok(());
+            }
+            if is_ident_expr(&assign.left, "owned") {
+                flags.owned = match &*assign.right {
+                    syn::Expr::Path(expr_path) => Some(expr_path.path.clone()),
+                    other => return Err(syn::Error::new_spanned(other, "expected an enum, e.g. `owned = Shape`")),
+                };
+                return Ok(());
+            }
+            if !is_ident_expr(&assign.left, "tag_bits") {
+                return Err(syn::Error::new_spanned(
+                    &assign.left,
+                    "expected `tag_bits = N`, `tag = \"low\"`, `invalid_tag = ...`, `constructor_prefix = \"...\"`, `owned = Enum`, or `crate = \"...\"`"
+                ));
+            }
+            let bits = match &*assign.right {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit), .. }) => lit.base10_parse::<u32>()?,
+                other => return Err(syn::Error::new_spanned(other, "expected an integer, e.g. `tag_bits = 9`")),
+            };
+            if !(MIN_TAG_BITS..=MAX_TAG_BITS).contains(&bits) {
+                return Err(syn::Error::new_spanned(
+                    &assign.right,
+                    format!("`tag_bits` must be between {} and {}", MIN_TAG_BITS, MAX_TAG_BITS)
+                ));
+            }
+            flags.tag_bits = Some(bits);
+            flags.check_tag_layout(assign)
+        } else if let syn::Expr::Call(call) = &item {
+            // `qualified(Trait)` disambiguates method names shared with another trait
+            match (is_ident_expr(&call.func, "qualified"), call.args.first()) {
+                (true, Some(syn::Expr::Path(expr_path))) if call.args.len() == 1 => {
+                    self.traits.push(DispatchTrait { path: expr_path.path.clone(), qualified: true });
+                    Ok(())
+                }
+                (true, _) => Err(syn::Error::new_spanned(
+                    &call.args,
+                    "expected a single trait, e.g. `qualified(Render)`"
+                )),
+                (false, _) => Err(syn::Error::new_spanned(
+                    item,
+                    format!("Expected trait name or flag ({})", KNOWN_FLAGS.join(", "))
+                )),
+            }
+        } else {
+            Err(syn::Error::new_spanned(
+                item,
+                format!("Expected trait name or flag ({})", KNOWN_FLAGS.join(", "))
+            ))
+        }
+    }
+}
+
+impl Parse for TraitListWithFlags {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut list = TraitListWithFlags {
+            traits: Vec::new(),
+            flags: TraitGenerationFlags::default(),
+        };
+
+        // Parse comma-separated items
+        for item in parse_list_items(input)? {
+            list.push_item(item)?;
+        }
+
+        Ok(list)
+    }
+}
+
+/// Configuration read from attributes on the enum itself
+#[derive(Default)]
+struct EnumAttrs {
+    /// Fields every variant type has, from `#[base(field: Type, ...)]`
+    base_fields: Vec<BaseField>,
+    /// Foreign traits implemented by dispatch, from `#[delegate(...)]`
+    delegates: Vec<Path>,
+    /// Per-variant `VariantAllocator` types, from `#[alloc_with(...)]` on variants
+    allocators: Vec<Option<Path>>,
+    /// Per-variant constructor visibility, from `#[constructor_vis(...)]` on variants
+    constructor_vis: Vec<Option<syn::Visibility>>,
+    /// Per-variant constructor names, from `#[constructor = "..."]` on variants
+    constructor_names: Vec<Option<Ident>>,
+    /// Prepended to constructor names not set with `#[constructor]`
+    constructor_prefix: String,
+    /// Variants stored in the handle's address bits, marked `#[inline_value]`
+    inline_values: Vec<bool>,
+    /// Per-variant doc comments, repeated on the variant's constructors
+    variant_docs: Vec<Vec<Attribute>>,
+    /// The variant marked `#[default]` (or `#[default_variant]`), which `Default` and
+    /// `take` create
+    default_variant: Option<usize>,
+    /// The `f64` variant of a `nan_box` enum, stored as the handle's bits
+    number_variant: Option<usize>,
+    /// Function called before an owned variant is dropped, from `#[on_drop(...)]`
+    on_drop: Option<Path>,
+    /// Allocator for variants without `#[alloc_with]`, from flags like `shared`
+    default_allocator: Option<TokenStream2>,
+    /// Per-variant `#[flatten]` variant and inner enum the variant was spliced in from
+    flattened: Vec<Option<(Ident, Type)>>,
+}
+
+/// A `field: Type` entry of `#[base(...)]`
+struct BaseField {
+    name: Ident,
+    ty: Type,
+}
+
+impl Parse for BaseField {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        Ok(BaseField { name, ty })
+    }
+}
+
+impl EnumAttrs {
+    /// Whether `Debug` is implemented through `#[delegate(Debug)]`
+    fn delegates_debug(&self) -> bool {
+        self.delegates.iter().any(|path| path.segments.last().is_some_and(|segment| segment.ident == "Debug"))
+    }
+
+    fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        let mut enum_attrs = EnumAttrs::default();
+
+        for attr in attrs {
+            if attr.path().is_ident("base") {
+                let fields = attr.parse_args_with(Punctuated::<BaseField, Token![,]>::parse_terminated)?;
+                enum_attrs.base_fields.extend(fields);
+            } else if attr.path().is_ident("on_drop") {
+                enum_attrs.on_drop = Some(attr.parse_args::<Path>()?);
+            } else if attr.path().is_ident("delegate") {
+                let paths = attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)?;
+                for path in paths {
+                    delegate_signature(&path)?;
+                    enum_attrs.delegates.push(path);
+                }
+            }
+        }
+
+        Ok(enum_attrs)
+    }
+
+    /// Add the delegations requested through flags, unless already listed
+    fn apply_flags(&mut self, flags: &TraitGenerationFlags) {
+        let delegates_display = self.delegates.iter().any(|path| {
+            path.segments.last().is_some_and(|segment| segment.ident == "Display")
+        });
+        if flags.display && !delegates_display {
+            self.delegates.push(syn::parse_quote!(::core::fmt::Display));
+        }
+        self.constructor_prefix = flags.constructor_prefix.clone().unwrap_or_default();
+        if flags.shared {
+            self.default_allocator = Some(quote! { ::tagged_dispatch::ArcAllocator });
+        } else if flags.rc {
+            self.default_allocator = Some(quote! { ::tagged_dispatch::RcAllocator });
+        }
+    }
+
+    /// Collect `#[alloc_with(Path)]`, `#[constructor = "..."]` and `#[constructor_vis(...)]`
+    /// from the enum's variants
+    fn read_variant_attrs(&mut self, data_enum: &DataEnum) -> Result<()> {
+        self.allocators = data_enum.variants.iter().map(|variant| {
+            variant.attrs.iter()
+                .find(|attr| attr.path().is_ident("alloc_with"))
+                .map(|attr| attr.parse_args::<Path>())
+                .transpose()
+        }).collect::<Result<_>>()?;
+        // A bare `#[constructor_vis]` makes the constructors private
+        self.constructor_vis = data_enum.variants.iter().map(|variant| {
+            variant.attrs.iter()
+                .find(|attr| attr.path().is_ident("constructor_vis"))
+                .map(|attr| match &attr.meta {
+                    syn::Meta::Path(_) => Ok(syn::Visibility::Inherited),
+                    _ => attr.parse_args::<syn::Visibility>(),
+                })
+                .transpose()
+        }).collect::<Result<_>>()?;
+        self.constructor_names = data_enum.variants.iter().map(|variant| {
+            variant.attrs.iter()
+                .find(|attr| attr.path().is_ident("constructor"))
+                .map(|attr| match &attr.meta {
+                    syn::Meta::NameValue(syn::MetaNameValue {
+                        value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }),
+                        ..
+                    }) => lit.parse::<Ident>(),
+                    _ => Err(syn::Error::new_spanned(attr, "expected `#[constructor = \"name\"]`")),
+                })
+                .transpose()
+        }).collect::<Result<_>>()?;
+        self.inline_values = data_enum.variants.iter().map(|variant| {
+            variant.attrs.iter().any(|attr| attr.path().is_ident("inline_value"))
+        }).collect();
+        self.variant_docs = data_enum.variants.iter().map(|variant| {
+            variant.attrs.iter().filter(|attr| attr.path().is_ident("doc")).cloned().collect()
+        }).collect();
+        let mut defaults = data_enum.variants.iter().enumerate()
+            .filter(|(_, variant)| {
+                variant.attrs.iter().any(|attr| attr.path().is_ident("default") || attr.path().is_ident("default_variant"))
+            });
+        self.default_variant = defaults.next().map(|(i, _)| i);
+        if let Some((_, variant)) = defaults.next() {
+            return Err(syn::Error::new_spanned(&variant.ident, "only one variant can be `#[default]`"));
+        }
+        self.flattened = data_enum.variants.iter().map(|variant| {
+            variant.attrs.iter()
+                .find(|attr| attr.path().is_ident("tagged_dispatch_flattened"))
+                .map(|attr| attr.parse_args_with(|input: ParseStream| {
+                    let outer: Ident = input.parse()?;
+                    input.parse::<Token![,]>()?;
+                    Ok((outer, input.parse::<Type>()?))
+                }))
+                .transpose()
+        }).collect::<Result<_>>()?;
+        Ok(())
+    }
+
+    /// Find the number variant of a `nan_box` enum, rejecting options that need a
+    /// different tag layout
+    fn read_nan_box(&mut self, enum_name: &Ident, variants: &[(Ident, Type)], flags: &TraitGenerationFlags) -> Result<()> {
+        if !flags.nan_box {
+            return Ok(());
+        }
+        let conflict = if flags.low_tag || flags.tag_bits.is_some() {
+            Some("`nan_box` keeps the tag in the NaN payload and can't be combined with `tag = \"low\"` or `tag_bits`")
+        } else if flags.cow {
+            Some("the `cow` flag can't be combined with `nan_box`")
+        } else if flags.bytes {
+            Some("the `bytes` flag can't be combined with `nan_box`")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(syn::Error::new_spanned(enum_name, conflict));
+        }
+        if let Some(i) = self.inline_values.iter().position(|inline| *inline) {
+            return Err(syn::Error::new_spanned(
+                &variants[i].0,
+                "`nan_box` enums only store their `f64` variant inline and can't have `#[inline_value]` variants",
+            ));
+        }
+
+        let mut numbers = variants.iter().enumerate().filter(|(_, (_, ty))| is_f64(ty));
+        let Some((i, _)) = numbers.next() else {
+            return Err(syn::Error::new_spanned(enum_name, "`nan_box` enums need a variant holding an `f64`"));
+        };
+        if let Some((_, (variant, _))) = numbers.next() {
+            return Err(syn::Error::new_spanned(variant, "`nan_box` enums can only have one `f64` variant"));
+        }
+        self.number_variant = Some(i);
+        Ok(())
+    }
+
+    /// With `inline_small`, store the variants holding a primitive `InlineValue` type
+    /// inline, unless they name their own allocator
+    fn infer_inline_values(&mut self, variants: &[(Ident, Type)], flags: &TraitGenerationFlags) {
+        if !flags.inline_small || flags.nan_box {
+            return;
+        }
+        self.inline_values.resize(variants.len(), false);
+        for (i, (_, ty)) in variants.iter().enumerate() {
+            let has_allocator = matches!(self.allocators.get(i), Some(Some(_)));
+            if is_inline_primitive(ty) && !has_allocator {
+                self.inline_values[i] = true;
+            }
+        }
+    }
+
+    /// With `pooled`, allocate through the pools of `__<Enum>Pool`, the key generated
+    /// next to the handle `enum_name`
+    fn apply_pool(&mut self, enum_name: &Ident, flags: &TraitGenerationFlags) {
+        if flags.pooled && !flags.ref_counted() {
+            let key = pool_key_ident(enum_name);
+            self.default_allocator = Some(quote! { ::tagged_dispatch::pool::Pooled<#key> });
+        }
+    }
+
+    /// The variant's doc comments as a paragraph following a constructor's own docs
+    fn variant_docs(&self, index: usize) -> TokenStream2 {
+        match self.variant_docs.get(index) {
+            Some(docs) if !docs.is_empty() => quote! {
+                #[doc = ""]
+                #(#docs)*
+            },
+            _ => quote! {},
+        }
+    }
+
+    /// Whether the variant at `index` is stored in the address bits
+    fn is_inline(&self, index: usize) -> bool {
+        self.inline_values.get(index).copied().unwrap_or(false) || self.number_variant == Some(index)
+    }
+
+    /// The `VariantStorage` the variant at `index` is read through
+    fn storage(&self, index: usize) -> TokenStream2 {
+        if self.number_variant == Some(index) {
+            quote! { ::tagged_dispatch::__private::NanBoxedNumber }
+        } else if self.is_inline(index) {
+            quote! { ::tagged_dispatch::__private::Inline }
+        } else {
+            quote! { ::tagged_dispatch::__private::Pointed }
+        }
+    }
+
+    /// The bits an inline `value` of the variant at `index` is stored as
+    fn pack(&self, index: usize, ty: &Type, value: TokenStream2) -> TokenStream2 {
+        let storage = self.storage(index);
+        quote! { <#storage as ::tagged_dispatch::__private::PackedStorage<#ty>>::pack(#value) }
+    }
+
+    /// Borrow the value of the variant at `index` from `handle`. Inline values are
+    /// unpacked into a temporary, so the borrow only lasts for the enclosing statement.
+    fn variant_ref(&self, index: usize, ty: &Type, handle: TokenStream2) -> TokenStream2 {
+        let storage = self.storage(index);
+        quote! { &*<#storage as ::tagged_dispatch::__private::VariantStorage<#ty>>::get(#handle.0.ptr() as usize) }
+    }
+
+    /// Name of the plain constructor of the variant at `index`: its `#[constructor]`
+    /// name, or the snake_case variant name after any `constructor_prefix`
+    fn constructor(&self, index: usize, variant: &Ident) -> Ident {
+        match self.constructor_names.get(index) {
+            Some(Some(name)) => name.clone(),
+            _ if !self.constructor_prefix.is_empty() => {
+                format_ident!("{}{}", self.constructor_prefix, constructor_ident(variant), span = variant.span())
+            }
+            _ => constructor_ident(variant),
+        }
+    }
+
+    /// The visibility of the constructors of the variant at `index`, public by default
+    fn constructor_vis(&self, index: usize) -> TokenStream2 {
+        match self.constructor_vis.get(index) {
+            Some(Some(vis)) => quote! { #vis },
+            _ => quote! { pub },
+        }
+    }
+
+    /// Whether the variant at `index` may only be constructed with restricted visibility
+    fn is_restricted(&self, index: usize) -> bool {
+        matches!(self.constructor_vis.get(index), Some(Some(vis)) if !matches!(vis, syn::Visibility::Public(_)))
+    }
+
+    /// The visibility of constructors taking every variant, restricted like the
+    /// restricted variants if they agree, and private otherwise
+    fn all_variants_vis(&self) -> TokenStream2 {
+        let mut restricted = (0..self.constructor_vis.len())
+            .filter(|&i| self.is_restricted(i))
+            .map(|i| self.constructor_vis(i));
+        let Some(first) = restricted.next() else {
+            return quote! { pub };
+        };
+        if restricted.all(|vis| vis.to_string() == first.to_string()) {
+            first
+        } else {
+            quote! {}
+        }
+    }
+
+    /// The allocator type for the variant at `index`, boxing by default
+    fn allocator(&self, index: usize) -> TokenStream2 {
+        match (self.allocators.get(index), &self.default_allocator) {
+            (Some(Some(path)), _) => quote! { #path },
+            (_, Some(allocator)) => allocator.clone(),
+            _ => quote! { ::tagged_dispatch::BoxAllocator },
+        }
+    }
+}
+
+/// Arguments collected from `#[dispatch(...)]` helper attributes on a derived enum
+struct DeriveArgs {
+    list: TraitListWithFlags,
+    /// Name of the generated handle type (defaults to `Tagged<Enum>`)
+    name: Option<Ident>,
+    /// Generate an arena handle with lifetime `'a` instead of an owned one
+    arena: bool,
+}
 
-                #enum_name(::tagged_dispatch::TaggedPtr::new(ptr, #tag), ::core::marker::PhantomData)
+impl DeriveArgs {
+    /// Parse one `#[dispatch(...)]` attribute body, merging it into `self`
+    fn parse_into(&mut self, input: ParseStream) -> Result<()> {
+        for item in parse_list_items(input)? {
+            match item {
+                syn::Expr::Assign(assign) if is_ident_expr(&assign.left, "name") => {
+                    match &*assign.right {
+                        syn::Expr::Path(expr_path) if expr_path.path.get_ident().is_some() => {
+                            self.name = expr_path.path.get_ident().cloned();
+                        }
+                        other => {
+                            return Err(syn::Error::new_spanned(other, "expected a type name, e.g. `name = Shape`"));
+                        }
+                    }
+                }
+                syn::Expr::Path(expr_path) if expr_path.path.is_ident("arena") => {
+                    self.arena = true;
+                }
+                other => self.list.push_item(other)?,
             }
         }
-    });
 
-    // Generate enum variants
-    let enum_variants = variants.iter().map(|(variant, _)| {
-        quote! { #variant }
-    });
+        Ok(())
+    }
+}
 
-    // Generate variant list for dispatch macros
-    let variant_list: Vec<_> = variants.iter().map(|(variant, ty)| {
-        quote! { (#variant, #ty) }
-    }).collect();
+/// Parse a comma-separated macro argument list. Items are expressions, except that
+/// trait paths may carry generic arguments as in a type (`ops::Mul<f64>`), which
+/// would otherwise parse as a comparison.
+fn parse_list_items(input: ParseStream) -> Result<Vec<syn::Expr>> {
+    let mut items = Vec::new();
+    while !input.is_empty() {
+        let fork = input.fork();
+        let is_path = fork.parse::<Path>().is_ok_and(|path| {
+            (fork.is_empty() || fork.peek(Token![,]))
+                && path.segments.iter().all(|segment| !matches!(segment.arguments, syn::PathArguments::Parenthesized(_)))
+        });
+        if is_path {
+            let path = input.parse::<Path>()?;
+            items.push(syn::Expr::Path(syn::ExprPath { attrs: Vec::new(), qself: None, path }));
+        } else {
+            items.push(input.parse()?);
+        }
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+    Ok(items)
+}
 
-    // Generate dispatch macro invocations for each trait
-    let dispatch_invocations = traits.iter().map(|trait_path| {
-        let trait_name = &trait_path.segments.last().unwrap().ident;
-        let macro_name = format_ident!("__impl_{}_dispatch", trait_name.to_string().to_snake_case());
-        let variant_list = variant_list.clone();
+/// Check whether `expr` is the bare identifier `name`
+fn is_ident_expr(expr: &syn::Expr, name: &str) -> bool {
+    matches!(expr, syn::Expr::Path(expr_path) if expr_path.path.is_ident(name))
+}
 
-        quote! {
-            #macro_name!(#enum_name, #enum_type_name, #lifetime, [#(#variant_list),*]);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(args: &str) -> String {
+        match syn::parse_str::<TraitListWithFlags>(args) {
+            Ok(_) => panic!("expected `{}` to be rejected", args),
+            Err(err) => err.to_string(),
         }
-    });
+    }
 
-    // Generate compile-time trait checks
-    let trait_checks = traits.iter().flat_map(|trait_path| {
-        variants.iter().map(move |(_, ty)| {
-            quote! {
-                const _: fn() = || {
-                    fn assert_impl<T: #trait_path>() {}
-                    assert_impl::<#ty>();
-                };
-            }
-        })
-    });
+    #[test]
+    fn test_known_flags_parse() {
+        let parsed = syn::parse_str::<TraitListWithFlags>("Draw, no_debug, no_cmp, ops::Neg, non_exhaustive, display, bytes, send").unwrap();
+        assert_eq!(parsed.traits.len(), 2);
+        assert!(!parsed.flags.should_generate_debug());
+        assert!(!parsed.flags.should_generate_eq());
+        assert!(parsed.flags.non_exhaustive);
+        assert!(parsed.flags.display);
+        assert!(parsed.flags.bytes);
+        assert!(parsed.flags.send && !parsed.flags.sync);
+    }
 
-    // Generate the arena enum definition based on enabled features
-    // Convert lifetime to TokenStream2
-    let lifetime_tokens = quote! { #lifetime };
-    let arena_enum_definition = generate_arena_enum(&arena_type_name, &lifetime_tokens, &typed_arena_fields);
+    #[test]
+    fn test_shared_allocator() {
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, shared").unwrap().flags;
+        let mut enum_attrs = EnumAttrs { allocators: vec![None, Some(syn::parse_quote!(Pool))], ..EnumAttrs::default() };
+        enum_attrs.apply_flags(&flags);
+        assert_eq!(enum_attrs.allocator(0).to_string(), quote! { ::tagged_dispatch::ArcAllocator }.to_string());
+        assert_eq!(enum_attrs.allocator(1).to_string(), "Pool");
+        assert!(check_shared(&format_ident!("Shape"), &flags, &enum_attrs).is_err());
 
-    // Generate builder new implementation
-    let builder_new_impl = generate_builder_new();
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, rc").unwrap().flags;
+        let mut enum_attrs = EnumAttrs::default();
+        enum_attrs.apply_flags(&flags);
+        assert_eq!(enum_attrs.allocator(0).to_string(), quote! { ::tagged_dispatch::RcAllocator }.to_string());
+        assert!(check_shared(&format_ident!("Shape"), &flags, &enum_attrs).is_ok());
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, rc, send").unwrap().flags;
+        assert!(check_shared(&format_ident!("Shape"), &flags, &enum_attrs).is_err());
+    }
 
-    // Generate builder methods
-    let builder_specific_methods = generate_builder_methods(&builder_name, &arena_type_name, &typed_arena_inits, &lifetime_tokens);
+    #[test]
+    fn test_inline_value_conflicts() {
+        let data: DataEnum = match syn::parse_str::<DeriveInput>("enum E { #[inline_value] Nil, Text }").unwrap().data {
+            Data::Enum(data) => data,
+            _ => unreachable!(),
+        };
+        let variants = process_enum_variants(&mut data.clone()).unwrap();
+        let mut enum_attrs = EnumAttrs::default();
+        enum_attrs.read_variant_attrs(&data).unwrap();
+        assert!(enum_attrs.is_inline(0) && !enum_attrs.is_inline(1));
 
-    // Generate reset implementation
-    let reset_impl = generate_reset_impl(&arena_type_name, &typed_arena_inits2);
+        let flags = syn::parse_str::<TraitListWithFlags>("Value").unwrap().flags;
+        assert!(check_inline_values(&variants, &flags, &enum_attrs).is_ok());
+        let mut conflicting = vec!["Value, tag = \"low\"".to_string(), "Value, cow".to_string()];
+        // `tag_bits` only conflicts once it spills into the low bits
+        if HIGH_TAG_BITS < MAX_TAG_BITS {
+            conflicting.push(format!("Value, tag_bits = {}", MAX_TAG_BITS));
+        }
+        for conflicting in conflicting {
+            let flags = syn::parse_str::<TraitListWithFlags>(&conflicting).unwrap().flags;
+            assert!(check_inline_values(&variants, &flags, &enum_attrs).is_err(), "{}", conflicting);
+        }
+    }
 
-    // Generate stats implementation
-    let stats_impl = generate_stats_impl(&arena_type_name);
+    #[test]
+    fn test_inline_small_inference() {
+        let data: DataEnum = match syn::parse_str::<DeriveInput>(
+            "enum E { A(u32), B(String), C(()), #[alloc_with(Pool)] D(bool), E(u64) }"
+        ).unwrap().data {
+            Data::Enum(data) => data,
+            _ => unreachable!(),
+        };
+        let variants = process_enum_variants(&mut data.clone()).unwrap();
+        let flags = syn::parse_str::<TraitListWithFlags>("Value, inline_small").unwrap().flags;
+        let mut enum_attrs = EnumAttrs::default();
+        enum_attrs.read_variant_attrs(&data).unwrap();
+        enum_attrs.infer_inline_values(&variants, &flags);
+        let inline: Vec<_> = (0..variants.len()).map(|i| enum_attrs.is_inline(i)).collect();
+        assert_eq!(inline, [true, false, true, false, false]);
+    }
 
-    // Conditionally generate trait implementations
-    let debug_impl = if flags.should_generate_debug() {
-        quote! {
-            impl<#lifetime> ::core::fmt::Debug for #enum_name<#lifetime> {
-                fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
-                    write!(f, "{}::{:?}", stringify!(#enum_name), self.tag_type())
-                }
-            }
+    #[test]
+    fn test_default_variant() {
+        let data = |source: &str| match syn::parse_str::<DeriveInput>(source).unwrap().data {
+            Data::Enum(data) => data,
+            _ => unreachable!(),
+        };
+        let mut enum_attrs = EnumAttrs::default();
+        enum_attrs.read_variant_attrs(&data("enum E { A, #[default_variant] B }")).unwrap();
+        assert_eq!(enum_attrs.default_variant, Some(1));
+        enum_attrs.read_variant_attrs(&data("enum E { #[default] A, B }")).unwrap();
+        assert_eq!(enum_attrs.default_variant, Some(0));
+        enum_attrs.read_variant_attrs(&data("enum E { A, B }")).unwrap();
+        assert_eq!(enum_attrs.default_variant, None);
+        assert!(EnumAttrs::default().read_variant_attrs(&data("enum E { #[default] A, #[default_variant] B }")).is_err());
+    }
+
+    #[test]
+    fn test_flattened_conflicts() {
+        let data: DataEnum = match syn::parse_str::<DeriveInput>(
+            "enum E { #[tagged_dispatch_flattened(Flat, Inner)] A(A), B }"
+        ).unwrap().data {
+            Data::Enum(data) => data,
+            _ => unreachable!(),
+        };
+        let mut enum_attrs = EnumAttrs::default();
+        enum_attrs.read_variant_attrs(&data).unwrap();
+        assert!(matches!(&enum_attrs.flattened[..], [Some((outer, _)), None] if outer == "Flat"));
+
+        let name: Ident = syn::parse_quote!(E);
+        let flags = syn::parse_str::<TraitListWithFlags>("Value").unwrap().flags;
+        assert!(check_flattened(&name, &syn::Generics::default(), &flags, &enum_attrs).is_ok());
+        let generics: syn::Generics = syn::parse_quote!(<T>);
+        assert!(check_flattened(&name, &generics, &flags, &enum_attrs).is_err());
+        for conflicting in ["Value, nan_box", "Value, shared", "Value, rc", "Value, cow"] {
+            let flags = syn::parse_str::<TraitListWithFlags>(conflicting).unwrap().flags;
+            assert!(check_flattened(&name, &syn::Generics::default(), &flags, &enum_attrs).is_err(), "{}", conflicting);
         }
-    } else {
-        quote! {}
-    };
+    }
 
-    let eq_impl = if flags.should_generate_eq() {
-        quote! {
-            impl<#lifetime> ::core::cmp::PartialEq for #enum_name<#lifetime> {
-                fn eq(&self, other: &Self) -> bool {
-                    self.0.eq(&other.0)
-                }
-            }
+    #[test]
+    fn test_columns_flag() {
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, columns").unwrap().flags;
+        assert!(flags.columns);
 
-            impl<#lifetime> ::core::cmp::Eq for #enum_name<#lifetime> {}
+        let name: Ident = syn::parse_quote!(Shape);
+        let variants = [(format_ident!("Circle"), syn::parse_quote!(Circle))];
+        let traits = syn::parse_str::<TraitListWithFlags>("Draw").unwrap().traits;
+        let columns = generate_columns(&syn::Visibility::Inherited, &name, &format_ident!("ShapeType"), &variants, &traits).to_string();
+        for item in ["struct ShapeColumns", "trait ShapeColumnPass", "fn push_circle", "fn circle_column_mut", "fn run"] {
+            assert!(columns.contains(item), "{}", item);
         }
-    } else {
-        quote! {}
-    };
+    }
 
-    let ord_impl = if flags.should_generate_ord() {
-        quote! {
-            impl<#lifetime> ::core::cmp::PartialOrd for #enum_name<#lifetime> {
-                fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {
-                    self.0.partial_cmp(&other.0)
-                }
-            }
+    #[test]
+    fn test_pooled_flag() {
+        let name: Ident = syn::parse_quote!(E);
+        let flags = syn::parse_str::<TraitListWithFlags>("Value, pooled").unwrap().flags;
+        assert!(flags.pooled);
+        assert!(check_pooled(&name, &syn::Generics::default(), &flags).is_ok());
+        let generics: syn::Generics = syn::parse_quote!(<T>);
+        assert!(check_pooled(&name, &generics, &flags).is_err());
+        for conflicting in ["Value, pooled, shared", "Value, pooled, rc"] {
+            let flags = syn::parse_str::<TraitListWithFlags>(conflicting).unwrap().flags;
+            assert!(check_pooled(&name, &syn::Generics::default(), &flags).is_err(), "{}", conflicting);
+        }
 
-            impl<#lifetime> ::core::cmp::Ord for #enum_name<#lifetime> {
-                fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
-                    self.0.cmp(&other.0)
-                }
+        let mut enum_attrs = EnumAttrs::default();
+        enum_attrs.apply_flags(&flags);
+        enum_attrs.apply_pool(&name, &flags);
+        assert_eq!(enum_attrs.allocator(0).to_string(), quote! { ::tagged_dispatch::pool::Pooled<__EPool> }.to_string());
+    }
+
+    #[test]
+    fn test_crate_path() {
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, crate = \"framework::td\"").unwrap().flags;
+        let crate_path = flags.crate_path.unwrap();
+        let generated = quote! {
+            impl ::tagged_dispatch::bytes::Encode for Shape {
+                fn tag(&self) -> Result<(), ::tagged_dispatch::TagError> { todo!() }
             }
-        }
-    } else {
-        quote! {}
-    };
+        };
+        let rewritten = with_crate_path(generated, Some(&crate_path)).to_string();
+        assert!(!rewritten.contains("tagged_dispatch"), "{}", rewritten);
+        assert_eq!(rewritten.matches("framework :: td ::").count(), 2);
 
-    let output = quote! {
-        /// Arena-allocated tagged pointer dispatch type
-        #[repr(transparent)]
-        #vis struct #enum_name<#lifetime>(
-            ::tagged_dispatch::TaggedPtr<()>,
-            ::core::marker::PhantomData<&#lifetime ()>
-        );
+        let options = syn::parse_str::<TraitOptions>("ref_impls, crate = \"framework::td\"").unwrap();
+        assert!(options.crate_path.is_some());
+        assert!(syn::parse_str::<TraitListWithFlags>("Draw, crate = framework").is_err());
+    }
 
-        /// Type variants for compile-time checking
-        #[repr(u8)]
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-        #vis enum #enum_type_name {
-            #(#enum_variants,)*
-        }
+    #[test]
+    fn test_bytes_variant_limit() {
+        let name = format_ident!("Packet");
+        let variants = (0..257).map(|i| format!("V{}", i)).collect::<Vec<_>>().join(", ");
+        let data = syn::parse_str::<DeriveInput>(&format!("enum Packet {{ {} }}", variants)).unwrap().data;
+        let flags = syn::parse_str::<TraitListWithFlags>("Encode, bytes").unwrap().flags;
 
-        // Generate arena type enum based on enabled features at macro build time
-        #arena_enum_definition
+        let message = check_variant_limit(&name, &data, 257, &flags).unwrap_err().to_string();
+        // The byte only caps enums whose high tag is wider than it
+        assert_eq!(message.contains("one-byte tag"), HIGH_TAG_BITS > 8, "{}", message);
+        assert!(message.contains("split it"), "{}", message);
+        assert!(!message.contains("tag_bits"), "{}", message);
+    }
 
-        /// Arena builder for creating arena-allocated variants
-        #vis struct #builder_name<#lifetime> {
-            allocator: #arena_type_name<#lifetime>,
-            _phantom: ::core::marker::PhantomData<&#lifetime ()>,
-        }
+    #[test]
+    fn test_constructor_names() {
+        let data: DataEnum = match syn::parse_str::<DeriveInput>("enum E { #[constructor = \"new_circle\"] Circle, BigSquare }").unwrap().data {
+            Data::Enum(data) => data,
+            _ => unreachable!(),
+        };
+        let mut enum_attrs = EnumAttrs::default();
+        enum_attrs.read_variant_attrs(&data).unwrap();
+        assert_eq!(enum_attrs.constructor(0, &format_ident!("Circle")), "new_circle");
+        assert_eq!(enum_attrs.constructor(1, &format_ident!("BigSquare")), "big_square");
 
-        impl<#lifetime> #builder_name<#lifetime> {
-            /// Create a new builder with the default allocator
-            /// (prefers bumpalo if available)
-            pub fn new() -> Self {
-                #builder_new_impl
-            }
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, constructor_prefix = \"make_\"").unwrap().flags;
+        enum_attrs.apply_flags(&flags);
+        assert_eq!(enum_attrs.constructor(0, &format_ident!("Circle")), "new_circle");
+        assert_eq!(enum_attrs.constructor(1, &format_ident!("BigSquare")), "make_big_square");
 
-            #builder_specific_methods
+        assert!(syn::parse_str::<TraitListWithFlags>("Draw, constructor_prefix = make").is_err());
+        let data: DataEnum = match syn::parse_str::<DeriveInput>("enum E { #[constructor(circle)] Circle }").unwrap().data {
+            Data::Enum(data) => data,
+            _ => unreachable!(),
+        };
+        assert!(EnumAttrs::default().read_variant_attrs(&data).is_err());
+    }
 
-            /// Reset all allocations
-            pub fn reset(&mut self) {
-                #reset_impl
-            }
+    #[test]
+    fn test_variant_type() {
+        let variants = |source: &str| match syn::parse_str::<DeriveInput>(source).unwrap().data {
+            Data::Enum(mut data) => process_enum_variants(&mut data),
+            _ => unreachable!(),
+        };
+        let parsed = variants("enum E { #[variant_type = \"geometry::Circle\"] RoundThing, Square }").unwrap();
+        assert_eq!(parsed[0].0, "RoundThing");
+        assert_eq!(parsed[0].1, syn::parse_quote!(geometry::Circle));
+        assert_eq!(parsed[1].1, syn::parse_quote!(Square));
 
-            /// Clear allocations and reclaim memory
-            pub fn clear(&mut self) {
-                self.reset(); // For now, same as reset
-            }
+        assert!(variants("enum E { #[variant_type = \"geometry::Circle\"] Circle(Circle) }").is_err());
+        assert!(variants("enum E { #[variant_type(geometry::Circle)] Circle }").is_err());
+    }
 
-            /// Get memory usage statistics
-            pub fn stats(&self) -> ::tagged_dispatch::ArenaStats {
-                #stats_impl
-            }
+    #[test]
+    fn test_check_generics() {
+        let check = |source: &str, flags: &str| {
+            let generics = syn::parse_str::<DeriveInput>(source).unwrap().generics;
+            let flags = syn::parse_str::<TraitListWithFlags>(flags).unwrap().flags;
+            check_generics(&generics, &flags, &EnumAttrs::default())
+        };
+        assert!(check("enum Store<T: 'static> { Vec(VecStore<T>) }", "Container, shared").is_ok());
+        assert!(check("enum Shape<'a> { Circle }", "Draw").is_ok());
+        assert!(check("enum Store<'a, T> { Vec(VecStore<T>) }", "Container").is_err());
+        assert!(check("enum Store<T> where T: Clone { Vec(VecStore<T>) }", "Container").is_err());
+        assert!(check("enum Store<T> { Vec(VecStore<T>) }", "Container, tag = \"low\"").is_err());
+        assert!(check("enum Store<T> { Vec(VecStore<T>) }", "Container, drop_table").is_err());
+    }
 
-            #(#builder_methods)*
-        }
+    #[test]
+    fn test_shares_type() {
+        let data = |source: &str| match syn::parse_str::<DeriveInput>(source).unwrap().data {
+            Data::Enum(mut data) => process_enum_variants(&mut data).unwrap(),
+            _ => unreachable!(),
+        };
+        let variants = data("enum Message { Request(Packet), Response(Packet), Ping }");
+        assert!(shares_type(&variants, 0) && shares_type(&variants, 1));
+        assert!(!shares_type(&variants, 2));
 
-        impl<#lifetime> #enum_name<#lifetime> {
-            /// Create a new arena builder for this type
-            pub fn arena_builder() -> #builder_name<#lifetime> {
-                #builder_name::new()
-            }
+        let variants = data("enum Shape { Poly(Polygon<f32>), PolyD(Polygon<f64>) }");
+        assert!(!shares_type(&variants, 0) && !shares_type(&variants, 1));
+    }
 
-            #[inline(always)]
-            pub fn tag_type(&self) -> #enum_type_name {
-                unsafe { ::core::mem::transmute(self.0.tag()) }
-            }
-        }
+    #[test]
+    fn test_has_cfg_variants() {
+        let enum_def = |source: &str| syn::parse_str::<DeriveInput>(source).unwrap();
+        assert!(has_cfg_variants(&enum_def("enum Shape { Circle, #[cfg(feature = \"gpu\")] Mesh }")));
+        assert!(!has_cfg_variants(&enum_def("#[cfg(test)] enum Shape { Circle, #[inline_value] Marker(u32) }")));
 
-        // Arena version is Copy
-        impl<#lifetime> Copy for #enum_name<#lifetime> {}
+        let deferred = defer_cfg_variants(quote! { Area }, &enum_def("enum Shape { Circle, #[cfg(any())] Mesh }")).to_string();
+        assert!(deferred.contains("enum __ShapeCfgVariants"));
+        assert!(deferred.contains("TaggedDispatchCfg"));
+    }
 
-        impl<#lifetime> Clone for #enum_name<#lifetime> {
-            #[inline(always)]
-            fn clone(&self) -> Self {
-                *self
-            }
+    #[test]
+    fn test_dyn_compatible() {
+        let compatible = [
+            "trait Draw { fn draw(&self); fn consume(self); }",
+            "trait Draw: Debug { fn draw<'a>(&'a self, label: &'a str) -> &'a str; }",
+            "trait Draw { fn draw(&self); fn new() -> Self where Self: Sized; }",
+        ];
+        for source in compatible {
+            assert!(is_dyn_compatible(&syn::parse_str(source).unwrap()), "{}", source);
         }
+        let incompatible = [
+            "trait Draw { fn draw<T>(&self, value: T); }",
+            "trait Draw { fn draw(&self, other: &Self); }",
+            "trait Draw { fn points(&self) -> impl Iterator<Item = u32>; }",
+            "trait Draw { const LAYER: u32; fn draw(&self); }",
+            "trait Draw { type Output; fn draw(&self) -> Self::Output; }",
+            "trait Draw: Clone { fn draw(&self); }",
+            "trait Draw { fn new() -> u32; }",
+        ];
+        for source in incompatible {
+            assert!(!is_dyn_compatible(&syn::parse_str(source).unwrap()), "{}", source);
+        }
+    }
 
-        #debug_impl
-        #eq_impl
-        #ord_impl
-
-        // No Drop impl needed - arena handles deallocation
+    #[test]
+    fn test_nan_box_number_variant() {
+        let variants = |source: &str| match syn::parse_str::<DeriveInput>(source).unwrap().data {
+            Data::Enum(mut data) => process_enum_variants(&mut data).unwrap(),
+            _ => unreachable!(),
+        };
+        let name = format_ident!("Dynamic");
+        let flags = syn::parse_str::<TraitListWithFlags>("Value, nan_box").unwrap().flags;
+        assert_eq!(flags.max_variants(), 8);
 
-        // Apply dispatch implementations for each trait
-        #(#dispatch_invocations)*
+        let mut enum_attrs = EnumAttrs::default();
+        enum_attrs.read_nan_box(&name, &variants("enum E { Text, Number(f64) }"), &flags).unwrap();
+        assert!(!enum_attrs.is_inline(0) && enum_attrs.is_inline(1));
 
-        // Compile-time trait implementation checks
-        #(#trait_checks)*
+        for invalid in ["enum E { Text }", "enum E { A(f64), B(f64) }"] {
+            assert!(EnumAttrs::default().read_nan_box(&name, &variants(invalid), &flags).is_err(), "{}", invalid);
+        }
+        for conflicting in ["Value, nan_box, tag = \"low\"", "Value, nan_box, tag_bits = 9", "Value, nan_box, cow"] {
+            let flags = syn::parse_str::<TraitListWithFlags>(conflicting).unwrap().flags;
+            let numbers = variants("enum E { Text, Number(f64) }");
+            assert!(EnumAttrs::default().read_nan_box(&name, &numbers, &flags).is_err(), "{}", conflicting);
+        }
+    }
 
-        // Size assertion
-        const _: () = assert!(::core::mem::size_of::<#enum_name<'static>>() == 8);
-    };
+    #[test]
+    fn test_tag_bits_parse() {
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, tag_bits = 10").unwrap().flags;
+        assert_eq!(flags.low_tag_bits(), 10u32.saturating_sub(HIGH_TAG_BITS));
+        assert_eq!(flags.max_variants(), 1 << HIGH_TAG_BITS.max(10));
 
-    TokenStream::from(output)
-}
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, bytes").unwrap().flags;
+        assert_eq!(flags.max_variants(), (1 << HIGH_TAG_BITS).min(256));
 
-/// Generate a single dispatch method implementation
-fn generate_dispatch_method(method: &TraitItemFn) -> proc_macro2::TokenStream {
-    let method_name = &method.sig.ident;
-    let inputs = &method.sig.inputs;
-    let output = &method.sig.output;
-    
-    // Extract arguments (skip &self)
-    let args: Vec<_> = inputs.iter().skip(1).collect();
-    let arg_names: Vec<_> = args.iter().filter_map(|arg| {
-        if let syn::FnArg::Typed(pat_type) = arg {
-            if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
-                Some(&pat_ident.ident)
-            } else {
-                None
-            }
-        } else {
-            None
+        let mut invalid = vec!["tag_bits = 6".to_string(), "tag_bits = 17".to_string(), "tag_bits = x".to_string()];
+        // `bytes` only conflicts with tags that spill into the low bits
+        if HIGH_TAG_BITS < MAX_TAG_BITS {
+            invalid.push(format!("bytes, tag_bits = {}", MAX_TAG_BITS));
+            invalid.push(format!("tag_bits = {}, bytes", MAX_TAG_BITS));
         }
-    }).collect();
-    
-    quote! {
-        #[inline]
-        pub fn #method_name(&self #(, #args)*) #output {
-            unsafe {
-                match self.tag_type() {
-                    $(
-                        $enum_type_name::$variant => {
-                            let ptr = &*(self.0.ptr() as *const $type);
-                            ptr.#method_name(#(#arg_names),*)
-                        }
-                    )*
-                }
-            }
+        for invalid in invalid {
+            assert!(syn::parse_str::<TraitListWithFlags>(&invalid).is_err(), "{}", invalid);
         }
     }
-}
 
-/// Configuration flags for controlling trait generation
-#[derive(Debug, Clone, Default)]
-struct TraitGenerationFlags {
-    no_debug: bool,
-    no_eq: bool,
-    no_ord: bool,
-    no_traits: bool,
-}
+    #[test]
+    fn test_low_tag_parse() {
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, tag = \"low\"").unwrap().flags;
+        assert!(flags.low_tag);
+        assert_eq!(flags.align_tag_bits(2), 1);
+        assert_eq!(flags.align_tag_bits(5), 3);
+        assert_eq!(flags.align_tag_bits(8), 3);
 
-impl TraitGenerationFlags {
-    fn should_generate_debug(&self) -> bool {
-        !self.no_traits && !self.no_debug
+        for invalid in ["tag = \"middle\"", "tag = low", "tag = \"low\", tag_bits = 9", "cow, tag = \"low\""] {
+            assert!(syn::parse_str::<TraitListWithFlags>(invalid).is_err(), "{}", invalid);
+        }
     }
 
-    fn should_generate_eq(&self) -> bool {
-        !self.no_traits && !self.no_eq
+    #[test]
+    fn test_owned_flag_parse() {
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, owned = shapes::Shape").unwrap().flags;
+        let owned = flags.owned.unwrap();
+        assert_eq!(quote!(#owned).to_string(), "shapes :: Shape");
+        assert!(syn::parse_str::<TraitListWithFlags>("Draw, owned = \"Shape\"").is_err());
     }
 
-    fn should_generate_ord(&self) -> bool {
-        !self.no_traits && !self.no_ord && !self.no_eq // Ord requires Eq
+    #[test]
+    fn test_display_flag_delegates_once() {
+        let flags = syn::parse_str::<TraitListWithFlags>("Draw, display").unwrap().flags;
+
+        let mut enum_attrs = EnumAttrs::default();
+        enum_attrs.apply_flags(&flags);
+        assert_eq!(enum_attrs.delegates.len(), 1);
+
+        let attrs: Vec<Attribute> = vec![syn::parse_quote!(#[delegate(fmt::Display)])];
+        let mut enum_attrs = EnumAttrs::from_attrs(&attrs).unwrap();
+        enum_attrs.apply_flags(&flags);
+        assert_eq!(enum_attrs.delegates.len(), 1);
     }
-}
 
-/// Parser for comma-separated trait list and optional flags
-struct TraitListWithFlags {
-    traits: Vec<Path>,
-    flags: TraitGenerationFlags,
-}
+    #[test]
+    fn test_delegate_supported_traits() {
+        let attrs: Vec<Attribute> = vec![syn::parse_quote!(#[delegate(AsRef<str>, std::fmt::Display)])];
+        assert_eq!(EnumAttrs::from_attrs(&attrs).unwrap().delegates.len(), 2);
 
-impl Parse for TraitListWithFlags {
-    fn parse(input: ParseStream) -> Result<Self> {
-        let mut traits = Vec::new();
-        let mut flags = TraitGenerationFlags::default();
+        for unsupported in ["Hash", "AsRef", "Iterator<Item = u8>"] {
+            let attrs: Vec<Attribute> = vec![syn::parse_str::<syn::DeriveInput>(
+                &format!("#[delegate({})] enum E {{}}", unsupported)
+            ).unwrap().attrs.remove(0)];
+            let err = EnumAttrs::from_attrs(&attrs).err().expect("unsupported trait").to_string();
+            assert!(err.starts_with("#[delegate] supports: AsRef<T>"), "{}", err);
+        }
+    }
 
-        if input.is_empty() {
-            return Ok(TraitListWithFlags { traits, flags });
+    #[test]
+    fn test_trait_options_parse() {
+        let options = syn::parse_str::<TraitOptions>("enum_defaults, supertraits(Draw, Update)").unwrap();
+        assert!(options.enum_defaults);
+        assert!(options.supertraits);
+        assert_eq!(options.selected_supertraits, ["Draw", "Update"]);
+
+        assert!(syn::parse_str::<TraitOptions>("supertraits").unwrap().selected_supertraits.is_empty());
+        assert!(syn::parse_str::<TraitOptions>("enum_defaults(Draw)").is_err());
+    }
+
+    #[test]
+    fn test_plugin_trait_support() {
+        let check = |trait_def: ItemTrait| {
+            let methods: Vec<_> = trait_def.items.iter().filter_map(|item| match item {
+                TraitItem::Fn(method) if method.sig.receiver().is_some() => Some(method.clone()),
+                _ => None,
+            }).collect();
+            check_plugin_trait(&trait_def, &methods, &TraitOptions::default())
+        };
+        assert!(check(syn::parse_quote! { trait Draw { fn draw(&self) -> String; fn grow(&mut self, by: f32); } }).is_ok());
+
+        let unsupported: [ItemTrait; 4] = [
+            syn::parse_quote! { trait T { fn into_inner(self) -> u32; } },
+            syn::parse_quote! { trait T { fn longest(&self) -> &str where Self: Sized; } },
+            syn::parse_quote! { trait T { fn scaled(&self) -> Self; } },
+            syn::parse_quote! { trait T { #[no_dispatch] fn name(&self) -> &str; } },
+        ];
+        for trait_def in unsupported {
+            assert!(check(trait_def).is_err());
         }
+    }
 
-        // Parse comma-separated items
-        let items = Punctuated::<syn::Expr, Token![,]>::parse_terminated(input)?;
+    #[test]
+    fn test_plugin_abi_fingerprint() {
+        let name = format_ident!("Shape");
+        let draw: Path = syn::parse_quote! { Draw };
+        let scale: Path = syn::parse_quote! { Scale };
+        let circle = (format_ident!("Circle"), syn::parse_quote! { Circle });
+        let square = (format_ident!("Square"), syn::parse_quote! { Square });
+        let variants = [circle.clone()];
+        let base = plugin_abi_fingerprint(&name, &variants, &[&draw]);
 
-        for item in items {
-            // Try to parse as a path (trait name)
-            if let syn::Expr::Path(expr_path) = item {
-                // Check if it's a known flag
-                if expr_path.path.is_ident("no_debug") {
-                    flags.no_debug = true;
-                } else if expr_path.path.is_ident("no_eq") {
-                    flags.no_eq = true;
-                } else if expr_path.path.is_ident("no_ord") {
-                    flags.no_ord = true;
-                } else if expr_path.path.is_ident("no_cmp") {
-                    flags.no_eq = true;
-                    flags.no_ord = true;
-                } else if expr_path.path.is_ident("no_traits") {
-                    flags.no_traits = true;
-                } else {
-                    // It's a trait path
-                    traits.push(expr_path.path);
-                }
-            } else {
-                return Err(syn::Error::new_spanned(
-                    item,
-                    "Expected trait name or flag (no_debug, no_eq, no_ord, no_cmp, no_traits)"
-                ));
+        assert_eq!(base, plugin_abi_fingerprint(&name, &variants, &[&draw]));
+        assert_ne!(base, plugin_abi_fingerprint(&name, &[circle, square], &[&draw]));
+        assert_ne!(base, plugin_abi_fingerprint(&name, &variants, &[&draw, &scale]));
+        assert_ne!(base, plugin_abi_fingerprint(&format_ident!("Sketch"), &variants, &[&draw]));
+    }
+
+    #[test]
+    fn test_ref_impls_supported_items() {
+        let supported: ItemTrait = syn::parse_quote! {
+            trait Draw {
+                fn draw(&self, scale: u32) -> String;
+                #[no_dispatch]
+                fn name(&self) -> &str { "shape" }
+                const SIDES: u32 = 0;
             }
+        };
+        assert!(check_ref_impls(&supported).is_ok());
+
+        let unsupported: [ItemTrait; 4] = [
+            syn::parse_quote! { trait T { fn new() -> u32; } },
+            syn::parse_quote! { trait T { fn grow(&mut self); } },
+            syn::parse_quote! { trait T { fn scaled(&self) -> Self; } },
+            syn::parse_quote! { trait T { type Output; } },
+        ];
+        for trait_def in &unsupported {
+            let err = check_ref_impls(trait_def).expect_err("unsupported item").to_string();
+            assert!(err.starts_with("`ref_impls` cannot implement this item"), "{}", err);
         }
+    }
 
-        Ok(TraitListWithFlags { traits, flags })
+    #[test]
+    fn test_operator_trait_parse() {
+        let parsed = syn::parse_str::<TraitListWithFlags>("Draw, ops::Mul<f64>, std::ops::Neg, qualified(Audit)").unwrap();
+        let operators: Vec<_> = parsed.traits.iter()
+            .filter_map(|dispatch_trait| operator_trait(&dispatch_trait.path))
+            .map(|(name, method, _)| (name.to_string(), method.to_string()))
+            .collect();
+        assert_eq!(operators, [("Mul".to_string(), "mul".to_string()), ("Neg".to_string(), "neg".to_string())]);
+        assert!(parsed.traits[3].qualified);
+
+        // A bare `Neg` may be a user trait with a dispatch macro
+        assert!(operator_trait(&syn::parse_quote!(Neg)).is_none());
+    }
+
+    #[test]
+    fn test_unknown_flag_suggestion() {
+        assert_eq!(parse_error("Draw, no_debg"), "unknown flag `no_debg`; did you mean `no_debug`?");
+        assert_eq!(parse_error("no_trait"), "unknown flag `no_trait`; did you mean `no_traits`?");
     }
-}
 
+    #[test]
+    fn test_unknown_flag_without_suggestion() {
+        let err = parse_error("Draw, serialize");
+        assert!(err.starts_with("unknown flag `serialize`; expected a trait name or one of:"));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("no_debug", "no_debug"), 0);
+        assert_eq!(edit_distance("no_debg", "no_debug"), 1);
+        assert_eq!(edit_distance("no_eq", "no_ord"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
@@ -0,0 +1,109 @@
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tagged_dispatch::{tagged_dispatch, VariantAllocator};
+
+#[tagged_dispatch]
+trait Volume {
+    fn volume(&self) -> usize;
+    fn into_cells(self) -> Vec<u8>;
+}
+
+#[derive(Clone)]
+struct Voxel;
+
+impl Volume for Voxel {
+    fn volume(&self) -> usize {
+        1
+    }
+
+    fn into_cells(self) -> Vec<u8> {
+        vec![1]
+    }
+}
+
+#[derive(Clone)]
+struct Chunk {
+    cells: Vec<u8>,
+}
+
+impl Volume for Chunk {
+    fn volume(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn into_cells(self) -> Vec<u8> {
+        self.cells
+    }
+}
+
+// Each test uses its own counting allocator, since tests run in parallel
+macro_rules! counting_allocator {
+    ($name:ident, $live:ident) => {
+        static $live: AtomicUsize = AtomicUsize::new(0);
+
+        struct $name;
+
+        unsafe impl<T> VariantAllocator<T> for $name {
+            fn alloc(value: T) -> NonNull<T> {
+                $live.fetch_add(1, Ordering::SeqCst);
+                NonNull::from(Box::leak(Box::new(value)))
+            }
+
+            unsafe fn take(ptr: NonNull<T>) -> T {
+                $live.fetch_sub(1, Ordering::SeqCst);
+                *Box::from_raw(ptr.as_ptr())
+            }
+        }
+    };
+}
+
+counting_allocator!(ChunkPool, CHUNK_POOL_LIVE);
+counting_allocator!(TablePool, TABLE_POOL_LIVE);
+
+#[tagged_dispatch(Volume)]
+enum Block {
+    Voxel,
+    #[alloc_with(ChunkPool)]
+    Chunk,
+}
+
+#[tagged_dispatch(Volume, drop_table)]
+enum TableBlock {
+    Voxel,
+    #[alloc_with(TablePool)]
+    Chunk,
+}
+
+#[test]
+fn test_alloc_with_hooks_one_variant() {
+    let voxel = Block::voxel(Voxel);
+    let chunk = Block::chunk(Chunk { cells: vec![0; 16] });
+    assert_eq!(CHUNK_POOL_LIVE.load(Ordering::SeqCst), 1);
+
+    let copy = chunk.clone();
+    let converted = Block::from(Chunk { cells: vec![0; 2] });
+    assert_eq!(CHUNK_POOL_LIVE.load(Ordering::SeqCst), 3);
+    assert_eq!(voxel.volume() + copy.volume() + converted.volume(), 19);
+
+    // Consuming methods and extraction hand the value back through the pool
+    assert_eq!(copy.into_cells().len(), 16);
+    let extracted = Chunk::try_from(converted).ok().unwrap();
+    assert_eq!(extracted.cells.len(), 2);
+    assert_eq!(CHUNK_POOL_LIVE.load(Ordering::SeqCst), 1);
+
+    drop(chunk);
+    drop(voxel);
+    assert_eq!(CHUNK_POOL_LIVE.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_alloc_with_drop_table() {
+    let blocks = vec![TableBlock::voxel(Voxel), TableBlock::chunk(Chunk { cells: vec![3] })];
+    assert_eq!(TABLE_POOL_LIVE.load(Ordering::SeqCst), 1);
+    assert_eq!(blocks.iter().map(TableBlock::volume).sum::<usize>(), 2);
+
+    let cells: Vec<u8> = blocks.into_iter().flat_map(TableBlock::into_cells).collect();
+    assert_eq!(cells, [1, 3]);
+    assert_eq!(TABLE_POOL_LIVE.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,77 @@
+#![cfg(all(debug_assertions, feature = "std", any(feature = "allocator-bumpalo", feature = "allocator-typed-arena")))]
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Shape {
+    fn area(&self) -> f32;
+}
+
+struct Circle {
+    radius: f32,
+}
+
+impl Shape for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+struct Square {
+    side: f32,
+}
+
+impl Shape for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+#[tagged_dispatch(Shape)]
+enum Shapes<'a> {
+    Circle,
+    Square,
+}
+
+fn panic_message(f: impl FnOnce() -> f32) -> String {
+    let payload = catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+    payload.downcast_ref::<String>().cloned().unwrap_or_default()
+}
+
+#[test]
+fn test_handle_used_after_reset() {
+    let mut builder = ShapesArenaBuilder::new();
+    let raw = builder.circle(Circle { radius: 1.0 }).into_raw();
+    assert_eq!(unsafe { Shapes::from_raw(raw) }.area(), 3.0);
+
+    builder.reset();
+    // Unsound on purpose: the check panics before the value is read
+    let stale = unsafe { Shapes::from_raw(raw) };
+    assert!(panic_message(|| stale.area()).contains("was reset"));
+}
+
+#[test]
+fn test_handle_into_refilled_arena() {
+    let mut builder = ShapesArenaBuilder::new();
+    let raw = builder.circle(Circle { radius: 1.0 }).into_raw();
+    builder.reset();
+    let square = builder.square(Square { side: 2.0 });
+
+    let stale = unsafe { Shapes::from_raw(raw) };
+    assert!(panic_message(|| stale.area()).contains("outlived"));
+    assert_eq!(square.area(), 4.0);
+}
+
+#[test]
+fn test_views_of_reused_memory() {
+    let mut builder = ShapesArenaBuilder::new();
+    builder.circle(Circle { radius: 1.0 });
+    builder.reset();
+
+    // Whatever now lives where the arena was can still be viewed
+    let circles: Vec<_> = (0..64).map(|i| Circle { radius: i as f32 }).collect();
+    let total: f32 = Shapes::view_all(&circles, &[]).iter().map(|shape| shape.area()).sum();
+    assert_eq!(total, circles.iter().map(Circle::area).sum::<f32>());
+}
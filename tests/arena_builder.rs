@@ -75,6 +75,22 @@ fn test_bumpalo_reset() {
     builder.reset();
 }
 
+#[cfg(feature = "allocator-bumpalo")]
+#[test]
+fn test_bumpalo_clear_releases_chunks() {
+    let mut builder = ShapeArenaBuilder::with_bumpalo();
+    for radius in 0..1_000 {
+        builder.circle(Circle { radius: radius as f32 });
+    }
+    builder.reset();
+    assert!(builder.stats().allocated_bytes > 0, "reset keeps a chunk for reuse");
+
+    builder.clear();
+    assert_eq!(builder.stats().allocated_bytes, 0);
+    let circle = builder.circle(Circle { radius: 1.0 });
+    assert_eq!(circle.draw(), "Drawing circle");
+}
+
 #[cfg(feature = "allocator-bumpalo")]
 #[test]
 fn test_external_bumpalo() {
@@ -101,6 +117,35 @@ fn test_typed_arena_builder() {
     assert_eq!(rect.draw(), "Drawing rectangle");
 }
 
+#[cfg(feature = "allocator-bumpalo")]
+#[test]
+fn test_bumpalo_with_capacity() {
+    let bytes = 1 << 16;
+    let builder = ShapeArenaBuilder::with_capacity(bytes);
+    let before = builder.stats();
+    assert!(before.chunk_capacity >= bytes);
+
+    // The working set fits the first chunk, so no more chunks are allocated
+    for radius in 0..1_000 {
+        builder.circle(Circle { radius: radius as f32 });
+    }
+    assert_eq!(builder.stats().allocated_bytes, before.allocated_bytes);
+}
+
+#[cfg(feature = "allocator-typed-arena")]
+#[test]
+fn test_typed_arena_with_capacity() {
+    let builder = ShapeArenaBuilder::with_typed_arena_capacity([128, 16]);
+    let circles: Vec<_> = (0..128).map(|radius| builder.circle(Circle { radius: radius as f32 })).collect();
+    let rect = builder.rectangle(Rectangle {
+        width: 3.0,
+        height: 4.0,
+    });
+
+    assert_eq!(circles[1].draw(), "Drawing circle");
+    assert_eq!(rect.area(), 12.0);
+}
+
 #[cfg(all(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
 #[test]
 fn test_both_allocators() {
@@ -124,4 +169,37 @@ fn test_both_allocators() {
 fn test_size() {
     // Most importantly, the enum should be 8 bytes!
     assert_eq!(std::mem::size_of::<Shape>(), 8);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_scope() {
+    let total = ShapeArenaBuilder::scope(|builder| {
+        let circle = builder.circle(Circle { radius: 1.0 });
+        let rect = builder.rectangle(Rectangle {
+            width: 2.0,
+            height: 5.0,
+        });
+        circle.area() + rect.area()
+    });
+    assert!((total - 13.14).abs() < 0.01);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+#[test]
+fn test_owned_bumpalo_freed_on_drop() {
+    // Each builder frees its arena, so this doesn't grow memory across iterations
+    for i in 0..1_000 {
+        let builder = ShapeArenaBuilder::with_bumpalo();
+        let circles: Vec<_> = (0..64).map(|r| builder.circle(Circle { radius: (i + r) as f32 })).collect();
+        assert_eq!(circles.len(), 64);
+        assert!(builder.stats().allocated_bytes > 0);
+    }
+
+    // Builders over an external arena leave it to its owner
+    let arena = bumpalo::Bump::new();
+    {
+        let builder = ShapeArenaBuilder::with_external_bumpalo(&arena);
+        builder.circle(Circle { radius: 1.0 });
+    }
+    assert!(arena.allocated_bytes() > 0);
+}
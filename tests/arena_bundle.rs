@@ -0,0 +1,91 @@
+#![cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+
+use std::rc::Rc;
+use std::thread;
+
+use static_assertions::{assert_impl_all, assert_not_impl_any};
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Job {
+    fn run(&self) -> u32;
+}
+
+struct Compute {
+    input: u32,
+}
+
+impl Job for Compute {
+    fn run(&self) -> u32 {
+        self.input * 2
+    }
+}
+
+struct Log {
+    message: String,
+}
+
+impl Job for Log {
+    fn run(&self) -> u32 {
+        self.message.len() as u32
+    }
+}
+
+struct Shared {
+    counter: Rc<u32>,
+}
+
+impl Job for Shared {
+    fn run(&self) -> u32 {
+        *self.counter
+    }
+}
+
+#[tagged_dispatch(Job)]
+enum Task<'a> {
+    Compute,
+    Log,
+}
+
+#[tagged_dispatch(Job)]
+enum LocalTask<'a> {
+    Compute,
+    Shared,
+}
+
+assert_impl_all!(TaskArenaBundle: Send);
+assert_not_impl_any!(TaskArenaBundle: Sync);
+assert_not_impl_any!(LocalTaskArenaBundle: Send, Sync);
+
+#[test]
+fn bundles_move_across_threads() {
+    let bundle = TaskArenaBundle::new(|builder| {
+        vec![
+            builder.compute(Compute { input: 4 }),
+            builder.log(Log { message: "done".to_string() }),
+        ]
+    });
+
+    let total = thread::spawn(move || bundle.handles().iter().map(|task| task.run()).sum::<u32>())
+        .join()
+        .unwrap();
+    assert_eq!(total, 12);
+}
+
+#[test]
+fn extend_adds_handles_to_the_same_arena() {
+    let mut bundle = TaskArenaBundle::new(|builder| vec![builder.compute(Compute { input: 1 })]);
+    bundle.extend(|handles, builder| {
+        let again = handles[0];
+        vec![again, builder.compute(Compute { input: handles[0].run() })]
+    });
+
+    let runs: Vec<_> = bundle.handles().iter().map(|task| task.run()).collect();
+    assert_eq!(runs, [2, 2, 4]);
+}
+
+#[test]
+fn local_bundles_stay_usable_in_place() {
+    let bundle = LocalTaskArenaBundle::new(|builder| vec![builder.shared(Shared { counter: Rc::new(3) })]);
+    assert_eq!(bundle.handles()[0].run(), 3);
+}
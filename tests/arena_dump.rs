@@ -0,0 +1,87 @@
+#![cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Node {
+    fn weight(&self) -> u32;
+}
+
+#[derive(Debug)]
+struct Leaf {
+    value: u32,
+}
+
+impl Node for Leaf {
+    fn weight(&self) -> u32 {
+        self.value
+    }
+}
+
+#[derive(Debug)]
+struct Branch {
+    label: &'static str,
+}
+
+impl Node for Branch {
+    fn weight(&self) -> u32 {
+        self.label.len() as u32
+    }
+}
+
+#[tagged_dispatch(Node, dump)]
+enum Graph<'a> {
+    Leaf,
+    Branch,
+}
+
+// Owned enums reject the flag, since there is no builder to record handles:
+// #[tagged_dispatch(Node, dump)]
+// enum OwnedGraph { Leaf, Branch }
+
+const EXPECTED: &str = "\
+GraphArenaBuilder: 3 live values
+Leaf (2):
+    Leaf {
+        value: 1,
+    }
+    Leaf {
+        value: 2,
+    }
+Branch (1):
+    Branch {
+        label: \"root\",
+    }
+";
+
+fn build_and_dump<'a>(builder: &'a GraphArenaBuilder<'a>) {
+    builder.leaf(Leaf { value: 1 });
+    builder.branch(Branch { label: "root" });
+    builder.leaf(Leaf { value: 2 });
+
+    let mut out = String::new();
+    builder.dump(&mut out).unwrap();
+    assert_eq!(out, EXPECTED);
+}
+
+#[test]
+fn test_dump_default_allocator() {
+    build_and_dump(&Graph::arena_builder());
+}
+
+#[cfg(feature = "allocator-typed-arena")]
+#[test]
+fn test_dump_typed_arena() {
+    build_and_dump(&GraphArenaBuilder::with_typed_arena());
+}
+
+#[test]
+fn test_dump_includes_in_place_values() {
+    let builder = Graph::arena_builder();
+    let graph = unsafe { builder.leaf_with(|slot| { slot.write(Leaf { value: 7 }); }) };
+    assert_eq!(graph.weight(), 7);
+
+    let mut out = String::new();
+    builder.dump(&mut out).unwrap();
+    assert!(out.contains("Leaf (1):\n    Leaf {\n        value: 7,\n    }\n"));
+}
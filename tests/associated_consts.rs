@@ -0,0 +1,58 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Job {
+    /// Scheduling priority, higher runs first
+    const PRIORITY: u32;
+    const LABEL: &'static str;
+
+    fn run(&self) -> u32;
+}
+
+#[derive(Clone)]
+struct Render;
+
+impl Job for Render {
+    const PRIORITY: u32 = 10;
+    const LABEL: &'static str = "render";
+
+    fn run(&self) -> u32 {
+        1
+    }
+}
+
+#[derive(Clone)]
+struct Cleanup;
+
+impl Job for Cleanup {
+    const PRIORITY: u32 = 1;
+    const LABEL: &'static str = "cleanup";
+
+    fn run(&self) -> u32 {
+        2
+    }
+}
+
+#[tagged_dispatch(Job)]
+enum Task {
+    Render,
+    Cleanup,
+}
+
+#[test]
+fn test_associated_const_accessors() {
+    let mut tasks = [Task::cleanup(Cleanup), Task::render(Render)];
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.priority()));
+
+    let labels: Vec<_> = tasks.iter().map(|task| task.label()).collect();
+    assert_eq!(labels, ["render", "cleanup"]);
+    assert_eq!(tasks[1].run(), 2);
+}
+
+#[test]
+fn test_associated_const_tables() {
+    const PRIORITIES: &[u32] = Task::PRIORITY_TABLE;
+
+    assert_eq!(PRIORITIES, [10, 1]);
+    assert_eq!(Task::LABEL_TABLE[TaskType::Cleanup as usize], "cleanup");
+}
@@ -0,0 +1,84 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use tagged_dispatch::{tagged_dispatch, AtomicTaggedPtr};
+
+#[tagged_dispatch]
+trait Job {
+    fn cost(&self) -> u32;
+}
+
+#[derive(Clone)]
+struct Render {
+    frames: u32,
+    drops: Arc<AtomicUsize>,
+}
+
+impl Job for Render {
+    fn cost(&self) -> u32 {
+        self.frames
+    }
+}
+
+impl Drop for Render {
+    fn drop(&mut self) {
+        self.drops.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Clone)]
+struct Idle;
+
+impl Job for Idle {
+    fn cost(&self) -> u32 {
+        0
+    }
+}
+
+#[tagged_dispatch(Job, send, sync)]
+enum Task {
+    Render,
+    Idle,
+}
+
+#[test]
+fn test_raw_round_trip() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let task = Task::render(Render { frames: 3, drops: drops.clone() });
+
+    let raw = task.into_raw();
+    assert_eq!(raw.tag(), 0);
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+    let task = unsafe { Task::from_raw(raw) };
+    assert_eq!(task.cost(), 3);
+    drop(task);
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_handles_through_atomic_slot() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let slot = AtomicTaggedPtr::new(Task::idle(Idle).into_raw());
+
+    // Every thread swaps its own handle in and takes ownership of the previous one
+    let total: u32 = thread::scope(|scope| {
+        let workers: Vec<_> = (1..=4)
+            .map(|frames| {
+                let task = Task::render(Render { frames, drops: drops.clone() });
+                let slot = &slot;
+                scope.spawn(move || {
+                    let previous = slot.swap(Some(task.into_raw()), Ordering::AcqRel).unwrap();
+                    unsafe { Task::from_raw(previous) }.cost()
+                })
+            })
+            .collect();
+        workers.into_iter().map(|worker| worker.join().unwrap()).sum()
+    });
+
+    let last = unsafe { Task::from_raw(slot.into_inner().unwrap()) };
+    assert_eq!(total + last.cost(), 1 + 2 + 3 + 4);
+    drop(last);
+    assert_eq!(drops.load(Ordering::SeqCst), 4);
+}
@@ -0,0 +1,117 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Evaluate {
+    fn evaluate(&self) -> i64;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct NodeHeader {
+    id: u32,
+    line: u32,
+}
+
+#[derive(Clone)]
+struct Literal {
+    header: NodeHeader,
+    value: i64,
+}
+
+impl Evaluate for Literal {
+    fn evaluate(&self) -> i64 {
+        self.value
+    }
+}
+
+#[derive(Clone)]
+struct Negate {
+    operand: i64,
+    header: NodeHeader,
+}
+
+impl Evaluate for Negate {
+    fn evaluate(&self) -> i64 {
+        -self.operand
+    }
+}
+
+#[tagged_dispatch(Evaluate)]
+#[base(header: NodeHeader)]
+enum Node {
+    Literal,
+    Negate,
+}
+
+#[test]
+fn test_base_field_accessor() {
+    let nodes = [
+        Node::literal(Literal { header: NodeHeader { id: 1, line: 10 }, value: 4 }),
+        Node::negate(Negate { operand: 2, header: NodeHeader { id: 2, line: 11 } }),
+    ];
+
+    let ids: Vec<_> = nodes.iter().map(|node| node.header().id).collect();
+    assert_eq!(ids, [1, 2]);
+    assert_eq!(nodes[1].header(), &NodeHeader { id: 2, line: 11 });
+    assert_eq!(nodes[1].evaluate(), -2);
+}
+
+#[test]
+fn test_base_field_mut_accessor() {
+    let mut node = Node::negate(Negate { operand: 2, header: NodeHeader { id: 2, line: 11 } });
+    node.header_mut().line = 42;
+
+    assert_eq!(node.header().line, 42);
+    assert_eq!(node.clone().header().line, 42);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Span {
+        start: usize,
+        end: usize,
+    }
+
+    #[derive(Clone)]
+    struct Ident {
+        span: Span,
+        name: &'static str,
+    }
+
+    impl Evaluate for Ident {
+        fn evaluate(&self) -> i64 {
+            self.name.len() as i64
+        }
+    }
+
+    #[derive(Clone)]
+    struct Number {
+        span: Span,
+    }
+
+    impl Evaluate for Number {
+        fn evaluate(&self) -> i64 {
+            0
+        }
+    }
+
+    #[tagged_dispatch(Evaluate)]
+    #[base(span: Span)]
+    enum Token<'a> {
+        Ident,
+        Number,
+    }
+
+    #[test]
+    fn test_arena_base_field() {
+        let builder = Token::arena_builder();
+        let ident = builder.ident(Ident { span: Span { start: 0, end: 3 }, name: "foo" });
+        let number = builder.number(Number { span: Span { start: 4, end: 6 } });
+
+        assert_eq!(ident.span().end - ident.span().start, 3);
+        assert_eq!(number.span().start, 4);
+        assert_eq!(ident.evaluate(), 3);
+    }
+}
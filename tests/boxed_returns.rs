@@ -0,0 +1,97 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Points {
+    #[dispatch_boxed]
+    fn points(&self) -> impl Iterator<Item = u32>;
+
+    #[dispatch_boxed]
+    fn labels(&self, prefix: &'static str) -> impl Iterator<Item = String> + Send + 'static;
+
+    fn count(&self) -> usize;
+}
+
+#[derive(Clone)]
+struct Line {
+    len: u32,
+}
+
+impl Points for Line {
+    fn points(&self) -> impl Iterator<Item = u32> {
+        0..self.len
+    }
+
+    fn labels(&self, prefix: &'static str) -> impl Iterator<Item = String> + Send + 'static {
+        (0..self.len).map(move |i| format!("{prefix}{i}"))
+    }
+
+    fn count(&self) -> usize {
+        self.len as usize
+    }
+}
+
+#[derive(Clone)]
+struct Cloud {
+    points: Vec<u32>,
+}
+
+impl Points for Cloud {
+    fn points(&self) -> impl Iterator<Item = u32> {
+        self.points.iter().copied()
+    }
+
+    fn labels(&self, prefix: &'static str) -> impl Iterator<Item = String> + Send + 'static {
+        std::iter::once(format!("{prefix}cloud"))
+    }
+
+    fn count(&self) -> usize {
+        self.points.len()
+    }
+}
+
+#[tagged_dispatch(Points)]
+enum Geometry {
+    Line,
+    Cloud,
+}
+
+#[test]
+fn test_boxed_iterators() {
+    let line = Geometry::from(Line { len: 3 });
+    let cloud = Geometry::from(Cloud { points: vec![7, 9] });
+
+    assert_eq!(line.points().collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert_eq!(cloud.points().collect::<Vec<_>>(), vec![7, 9]);
+    assert_eq!(line.count() + cloud.count(), 5);
+}
+
+#[test]
+fn test_boxed_iterator_keeps_bounds() {
+    let cloud = Geometry::from(Cloud { points: vec![] });
+
+    let labels: Box<dyn Iterator<Item = String> + Send + 'static> = cloud.labels("p");
+    drop(cloud);
+    assert_eq!(labels.collect::<Vec<_>>(), vec!["pcloud".to_string()]);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Points)]
+    enum GeometryArena<'a> {
+        Line,
+        Cloud,
+    }
+
+    #[test]
+    fn test_arena_boxed_iterators() {
+        let builder = GeometryArena::arena_builder();
+        let shapes = [builder.line(Line { len: 2 }), builder.cloud(Cloud { points: vec![5] })];
+
+        let all: Vec<u32> = shapes.iter().flat_map(|shape| shape.points()).collect();
+        assert_eq!(all, vec![0, 1, 5]);
+        assert_eq!(shapes[1].labels("a").collect::<Vec<_>>(), vec!["acloud".to_string()]);
+        assert_eq!(shapes.iter().map(|shape| shape.count()).sum::<usize>(), 3);
+    }
+}
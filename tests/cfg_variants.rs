@@ -0,0 +1,118 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+// Only exists when enabled, so nothing may name it otherwise
+#[cfg(any())]
+#[derive(Clone)]
+struct Hologram;
+
+#[cfg(any())]
+impl Area for Hologram {
+    fn area(&self) -> f32 {
+        0.0
+    }
+}
+
+#[derive(Clone)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+impl Area for u32 {
+    fn area(&self) -> f32 {
+        *self as f32
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape {
+    Circle,
+    #[cfg(any())]
+    Hologram,
+    #[cfg(not(any()))]
+    Square,
+    #[cfg(not(all()))]
+    Triangle,
+    #[inline_value]
+    Marker(u32),
+}
+
+#[test]
+fn test_cfg_variants() {
+    assert_eq!(Shape::VARIANT_COUNT, 3);
+    assert_eq!(ShapeType::ALL, [ShapeType::Circle, ShapeType::Square, ShapeType::Marker]);
+
+    // Disabled variants leave no gap in the tags
+    assert_eq!(u8::from(ShapeType::Square), 1);
+    assert_eq!(u8::from(ShapeType::Marker), 2);
+
+    let shapes = [Shape::circle(Circle { radius: 1.0 }), Shape::square(Square { side: 2.0 }), Shape::marker(5)];
+    let tags: Vec<_> = shapes.iter().map(Shape::tag_type).collect();
+    assert_eq!(tags, ShapeType::ALL);
+    assert_eq!(shapes.each_ref().map(Shape::area), [3.0, 4.0, 5.0]);
+
+    let cloned = shapes[1].clone();
+    assert!(cloned.is_square());
+    assert!(Square::try_from(cloned).is_ok());
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Area)]
+    enum Sketch<'a> {
+        #[cfg(any())]
+        Hologram,
+        Circle,
+        Square,
+    }
+
+    #[test]
+    fn test_cfg_variants_arena() {
+        let builder = SketchArenaBuilder::new();
+        let sketch = builder.square(Square { side: 3.0 });
+        assert_eq!(sketch.area(), 9.0);
+        assert_eq!(u8::from(sketch.tag_type()), 1);
+        assert_eq!(builder.circle(Circle { radius: 1.0 }).area(), 3.0);
+    }
+}
+
+// The derive entry point only ever sees the enabled variants
+#[allow(dead_code)]
+#[derive(tagged_dispatch::TaggedDispatch)]
+#[dispatch(Area)]
+enum MeasureKind {
+    #[cfg(any())]
+    Hologram,
+    Circle,
+    Square,
+}
+
+#[test]
+fn test_cfg_variants_derive() {
+    let measure = TaggedMeasureKind::square(Square { side: 1.5 });
+    assert_eq!(measure.area(), 2.25);
+    assert_eq!(u8::from(measure.tag_type()), 1);
+}
@@ -0,0 +1,75 @@
+#![cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Polygon {
+    points: Vec<(f32, f32)>,
+}
+
+impl Area for Polygon {
+    fn area(&self) -> f32 {
+        self.points.len() as f32
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape<'a> {
+    Circle,
+    Polygon,
+}
+
+// Not `Clone`, so its enum has no callable `clone_in` but still compiles
+struct Handle;
+
+impl Area for Handle {
+    fn area(&self) -> f32 {
+        0.0
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Resource<'a> {
+    Circle,
+    Handle,
+}
+
+#[test]
+fn test_clone_in_outlives_source_arena() {
+    let level = ShapeArenaBuilder::new();
+    let promoted = {
+        let frame = ShapeArenaBuilder::new();
+        let polygon = frame.polygon(Polygon { points: vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)] });
+        let promoted = polygon.clone_in(&level);
+        // A deep copy, not the same allocation
+        assert_ne!(promoted, polygon);
+        promoted
+    };
+    assert_eq!(promoted.area(), 3.0);
+    assert_eq!(promoted.as_polygon().unwrap().points.len(), 3);
+
+    let circle = level.circle(Circle { radius: 1.0 }).clone_in(&level);
+    assert_eq!(circle.as_circle(), Some(&Circle { radius: 1.0 }));
+}
+
+#[test]
+fn test_non_clone_variants() {
+    let builder = ResourceArenaBuilder::new();
+    assert_eq!(builder.handle(Handle).area(), 0.0);
+}
@@ -0,0 +1,108 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[tagged_dispatch]
+trait Named {
+    fn name(&self) -> &'static str;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+impl Named for Circle {
+    fn name(&self) -> &'static str {
+        "circle"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Rectangle {
+    width: f32,
+    height: f32,
+}
+
+impl Area for Rectangle {
+    fn area(&self) -> f32 {
+        self.width * self.height
+    }
+}
+
+impl Named for Rectangle {
+    fn name(&self) -> &'static str {
+        "rectangle"
+    }
+}
+
+#[tagged_dispatch(Area, Named, columns)]
+enum Shape {
+    Circle,
+    Rectangle,
+}
+
+/// Sums the area of each column in a loop over its concrete type
+#[derive(Default)]
+struct TotalArea {
+    per_column: Vec<(ShapeType, &'static str, usize, f32)>,
+}
+
+impl ShapeColumnPass for TotalArea {
+    fn column<T: Area + Named + 'static>(&mut self, tag: ShapeType, values: &[T]) {
+        let name = values.first().map_or("", |value| value.name());
+        let total = values.iter().map(Area::area).sum();
+        self.per_column.push((tag, name, values.len(), total));
+    }
+}
+
+#[test]
+fn test_run_visits_each_column_in_tag_order() {
+    let mut columns = ShapeColumns::new();
+    assert_eq!(columns.push_circle(Circle { radius: 1.0 }), 0);
+    assert_eq!(columns.push_rectangle(Rectangle { width: 2.0, height: 3.0 }), 0);
+    assert_eq!(columns.push_circle(Circle { radius: 2.0 }), 1);
+    assert_eq!(columns.len(), 3);
+
+    let mut pass = TotalArea::default();
+    columns.run(&mut pass);
+    assert_eq!(
+        pass.per_column,
+        [(ShapeType::Circle, "circle", 2, 15.0), (ShapeType::Rectangle, "rectangle", 1, 6.0)]
+    );
+
+    // Same result as dispatching through handles
+    let shapes = [Shape::circle(Circle { radius: 1.0 }), Shape::circle(Circle { radius: 2.0 })];
+    assert_eq!(shapes.iter().map(Shape::area).sum::<f32>(), pass.per_column[0].3);
+    assert_eq!(shapes[0].name(), pass.per_column[0].1);
+}
+
+#[test]
+fn test_columns_mutate_and_clear() {
+    let mut columns = ShapeColumns::default();
+    assert!(columns.is_empty());
+    columns.push_rectangle(Rectangle { width: 1.0, height: 1.0 });
+
+    for rectangle in columns.rectangle_column_mut() {
+        rectangle.width *= 4.0;
+    }
+    assert_eq!(columns.rectangle_column(), [Rectangle { width: 4.0, height: 1.0 }]);
+    assert!(columns.circle_column().is_empty());
+
+    // Empty columns are still visited
+    let mut pass = TotalArea::default();
+    columns.run(&mut pass);
+    assert_eq!(pass.per_column[0], (ShapeType::Circle, "", 0, 0.0));
+
+    columns.clear();
+    assert!(columns.is_empty());
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "test-util")]
+
+use tagged_dispatch::tagged_dispatch;
+use tagged_dispatch::test_util::{self, DropToken, TaggedEnum};
+
+#[tagged_dispatch]
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+#[derive(Clone)]
+struct Circle {
+    radius: f32,
+}
+
+impl Describe for Circle {
+    fn describe(&self) -> String {
+        format!("circle {}", self.radius)
+    }
+}
+
+#[derive(Clone)]
+struct Label {
+    text: String,
+    _token: Option<DropToken>,
+}
+
+impl Describe for Label {
+    fn describe(&self) -> String {
+        self.text.clone()
+    }
+}
+
+#[tagged_dispatch(Describe)]
+enum Item {
+    Circle,
+    Label,
+}
+
+fn label(text: &str, token: Option<DropToken>) -> Label {
+    Label { text: text.to_string(), _token: token }
+}
+
+#[test]
+fn test_owned_conformance() {
+    test_util::assert_conformance(&[
+        Item::circle(Circle { radius: 1.0 }),
+        Item::label(label("a", None)),
+        Item::label(label("b", None)),
+    ]);
+
+    assert_eq!(Item::VARIANTS, &[ItemType::Circle, ItemType::Label]);
+    assert_eq!(Item::circle(Circle { radius: 1.0 }).describe(), "circle 1");
+    assert_eq!(Item::label(label("c", None)).describe(), "c");
+}
+
+#[test]
+#[should_panic(expected = "no value of variant Label was given")]
+fn test_missing_variant_is_reported() {
+    test_util::assert_tag_round_trip(&[Item::circle(Circle { radius: 1.0 })]);
+}
+
+#[test]
+fn test_drops_once() {
+    test_util::assert_drops_once(|counter| {
+        vec![
+            Item::label(label("a", Some(counter.token()))),
+            Item::label(label("b", Some(counter.token()))),
+        ]
+    });
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Describe)]
+    enum ArenaItem<'a> {
+        Circle,
+        Label,
+    }
+
+    #[test]
+    fn test_arena_conformance() {
+        let builder = ArenaItem::arena_builder();
+        test_util::assert_conformance(&[
+            builder.circle(Circle { radius: 2.0 }),
+            builder.label(label("arena", None)),
+        ]);
+        assert_eq!(<ArenaItem as TaggedEnum>::VARIANTS.len(), 2);
+        assert_eq!(builder.circle(Circle { radius: 2.0 }).describe(), "circle 2");
+    }
+}
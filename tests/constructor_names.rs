@@ -0,0 +1,101 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Shape {
+    fn area(&self) -> f32;
+    fn scaled(&self, factor: f32) -> Self;
+}
+
+#[derive(Clone)]
+struct Circle {
+    radius: f32,
+}
+
+impl Shape for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+
+    fn scaled(&self, factor: f32) -> Self {
+        Circle { radius: self.radius * factor }
+    }
+}
+
+#[derive(Clone)]
+struct Square {
+    side: f32,
+}
+
+impl Shape for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+
+    fn scaled(&self, factor: f32) -> Self {
+        Square { side: self.side * factor }
+    }
+}
+
+#[tagged_dispatch(Shape, fallible)]
+enum Figure {
+    // `circle` is taken by the method below
+    #[constructor = "new_circle"]
+    Circle,
+    Square,
+}
+
+impl Figure {
+    fn circle(radius: f32) -> Self {
+        Self::new_circle(Circle { radius })
+    }
+}
+
+#[tagged_dispatch(Shape, constructor_prefix = "make_")]
+enum Tile {
+    Circle,
+    #[constructor = "square"]
+    Square,
+}
+
+#[test]
+fn test_renamed_constructor() {
+    let figure = Figure::circle(1.0);
+    assert_eq!(figure.area(), 3.0);
+    // Generated code building variants goes through the renamed constructor
+    assert_eq!(figure.scaled(2.0).area(), 12.0);
+    assert_eq!(figure.clone().area(), 3.0);
+    assert_eq!(Figure::from(Circle { radius: 1.0 }).area(), 3.0);
+
+    // Only the plain constructor is renamed
+    assert!(Figure::try_circle(Circle { radius: 1.0 }).is_ok());
+    assert_eq!(Figure::square(Square { side: 2.0 }).area(), 4.0);
+}
+
+#[test]
+fn test_constructor_prefix() {
+    let tile = Tile::make_circle(Circle { radius: 1.0 });
+    assert_eq!(tile.scaled(2.0).area(), 12.0);
+    // `#[constructor]` takes precedence over the prefix
+    assert_eq!(Tile::square(Square { side: 3.0 }).area(), 9.0);
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Shape, constructor_prefix = "alloc_")]
+    enum Sketch<'a> {
+        #[constructor = "new_circle"]
+        Circle,
+        Square,
+    }
+
+    #[test]
+    fn test_arena_constructor_names() {
+        let builder = SketchArenaBuilder::new();
+        let circle = builder.new_circle(Circle { radius: 1.0 });
+        let square = builder.alloc_square(Square { side: 2.0 });
+        assert_eq!(circle.area() + square.area(), 7.0);
+        assert_eq!(circle.scaled(&builder, 2.0).area(), 12.0);
+    }
+}
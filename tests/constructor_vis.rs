@@ -0,0 +1,122 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Token {
+    fn text(&self) -> String;
+    fn doubled(&self) -> Self;
+}
+
+#[derive(Clone)]
+pub struct Word(String);
+
+impl Token for Word {
+    fn text(&self) -> String {
+        self.0.clone()
+    }
+
+    fn doubled(&self) -> Self {
+        Word(format!("{}{}", self.0, self.0))
+    }
+}
+
+#[derive(Clone)]
+pub struct Interned(u32);
+
+impl Token for Interned {
+    fn text(&self) -> String {
+        format!("#{}", self.0)
+    }
+
+    fn doubled(&self) -> Self {
+        Interned(self.0 * 2)
+    }
+}
+
+mod lexer {
+    use super::*;
+
+    // Interned tokens must come from the interner
+    #[tagged_dispatch(Token)]
+    pub enum Lexeme {
+        Word,
+        #[constructor_vis]
+        Interned,
+    }
+
+    pub fn intern(id: u32) -> Lexeme {
+        Lexeme::interned(Interned(id))
+    }
+}
+
+use lexer::Lexeme;
+
+#[test]
+fn test_public_variant_constructors() {
+    let word = Lexeme::word(Word("hi".to_string()));
+    assert_eq!(word.text(), "hi");
+
+    let from: Lexeme = Word("yo".to_string()).into();
+    assert_eq!(from.text(), "yo");
+
+    // The following should not compile, since `Interned` has private constructors
+    // and no `From` impl:
+    // let _ = Lexeme::interned(Interned(1));
+    // let _: Lexeme = Interned(1).into();
+}
+
+#[test]
+fn test_restricted_variant_through_factory() {
+    let interned = lexer::intern(21);
+    assert_eq!(interned.text(), "#21");
+
+    // Methods returning `Self` still re-wrap restricted variants
+    assert_eq!(interned.doubled().text(), "#42");
+
+    let extracted: Result<Interned, _> = interned.try_into();
+    assert_eq!(extracted.ok().map(|interned| interned.0), Some(21));
+}
+
+#[tagged_dispatch(Token)]
+enum CrateLexeme {
+    Word,
+    #[constructor_vis(pub(crate))]
+    Interned,
+}
+
+#[test]
+fn test_crate_visible_constructors() {
+    let interned = CrateLexeme::interned(Interned(3));
+    assert_eq!(interned.doubled().text(), "#6");
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    mod lexer {
+        use super::*;
+
+        #[tagged_dispatch(Token)]
+        pub enum ArenaLexeme<'a> {
+            Word,
+            #[constructor_vis(pub(super))]
+            Interned,
+        }
+    }
+
+    use lexer::ArenaLexeme;
+
+    #[test]
+    fn test_arena_restricted_constructors() {
+        let builder = ArenaLexeme::arena_builder();
+        let interned = builder.interned(Interned(5));
+        let word = builder.word(Word("arena".to_string()));
+        assert_eq!(interned.text(), "#5");
+        assert_eq!(word.text(), "arena");
+
+        let words = [Word("a".to_string())];
+        let ids = [Interned(1)];
+        let views = ArenaLexeme::view_all(&words, &ids);
+        assert_eq!(views.len(), 2);
+    }
+}
@@ -0,0 +1,101 @@
+use std::cell::Cell;
+
+use tagged_dispatch::tagged_dispatch;
+
+thread_local! {
+    static DROPS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn drops() -> usize {
+    DROPS.with(Cell::get)
+}
+
+#[derive(Debug, PartialEq)]
+struct Report {
+    title: String,
+    lines: usize,
+}
+
+#[tagged_dispatch]
+trait Job {
+    fn name(&self) -> String;
+    fn into_report(self, lines: usize) -> Report;
+}
+
+#[derive(Clone)]
+struct Build {
+    target: String,
+}
+
+impl Drop for Build {
+    fn drop(&mut self) {
+        DROPS.with(|drops| drops.set(drops.get() + 1));
+    }
+}
+
+impl Job for Build {
+    fn name(&self) -> String {
+        format!("build {}", self.target)
+    }
+
+    fn into_report(self, lines: usize) -> Report {
+        Report { title: self.name(), lines }
+    }
+}
+
+#[derive(Clone)]
+struct Deploy;
+
+impl Job for Deploy {
+    fn name(&self) -> String {
+        "deploy".to_string()
+    }
+
+    fn into_report(mut self, lines: usize) -> Report {
+        self = Deploy;
+        Report { title: self.name(), lines }
+    }
+}
+
+#[tagged_dispatch(Job)]
+enum Task {
+    Build,
+    Deploy,
+}
+
+#[test]
+fn test_consuming_dispatch_drops_once() {
+    let before = drops();
+    let task = Task::build(Build { target: "app".to_string() });
+    assert_eq!(task.name(), "build app");
+
+    let report = task.into_report(3);
+    assert_eq!(report, Report { title: "build app".to_string(), lines: 3 });
+    assert_eq!(drops() - before, 1);
+
+    let report = Task::deploy(Deploy).into_report(0);
+    assert_eq!(report.title, "deploy");
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Job)]
+    enum TaskRef<'a> {
+        Build,
+        Deploy,
+    }
+
+    #[test]
+    fn test_arena_consuming_dispatch_uses_clone() {
+        let builder = TaskRef::arena_builder();
+        let task = builder.build(Build { target: "lib".to_string() });
+        let copy = task;
+
+        // Arena handles are Copy, so every copy can still be consumed
+        assert_eq!(task.into_report(1).title, "build lib");
+        assert_eq!(copy.into_report(2).title, "build lib");
+        assert_eq!(copy.name(), "build lib");
+    }
+}
@@ -0,0 +1,130 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Node {
+    fn eval(&self) -> i64;
+}
+
+#[derive(Clone, Debug)]
+struct Literal {
+    value: i64,
+}
+
+impl Node for Literal {
+    fn eval(&self) -> i64 {
+        self.value
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Negate {
+    operand: i64,
+    drops: Option<Rc<Cell<u32>>>,
+}
+
+impl Drop for Negate {
+    fn drop(&mut self) {
+        if let Some(drops) = &self.drops {
+            drops.set(drops.get() + 1);
+        }
+    }
+}
+
+impl Node for Negate {
+    fn eval(&self) -> i64 {
+        -self.operand
+    }
+}
+
+#[tagged_dispatch(Node, cow)]
+enum Expr {
+    Literal,
+    Negate,
+}
+
+static ZERO: Literal = Literal { value: 0 };
+
+#[test]
+fn test_mixed_dispatch() {
+    let minus_one = Negate { operand: 1, drops: None };
+    let nodes: Vec<ExprCow> = vec![
+        ExprCow::borrowed_literal(&ZERO),
+        (&minus_one).into(),
+        Expr::literal(Literal { value: 5 }).into(),
+    ];
+
+    assert_eq!(nodes.iter().map(|node| node.eval()).sum::<i64>(), 4);
+    assert!(nodes[0].is_borrowed() && nodes[1].is_borrowed());
+    assert!(nodes[2].is_owned());
+    assert_eq!(nodes[1].tag_type(), ExprType::Negate);
+    assert_eq!(std::mem::size_of::<ExprCow>(), std::mem::size_of::<*const ()>());
+}
+
+#[test]
+fn test_borrowed_values_are_not_dropped() {
+    let drops = Rc::new(Cell::new(0));
+    let local = Negate { operand: 3, drops: Some(drops.clone()) };
+
+    let borrowed = ExprCow::borrowed_negate(&local);
+    let copy = borrowed.clone();
+    assert!(copy.is_borrowed());
+    assert_eq!(copy.eval(), -3);
+    drop(copy);
+    drop(borrowed);
+    assert_eq!(drops.get(), 0);
+
+    let owned: ExprCow = Expr::negate(local.clone()).into();
+    let cloned = owned.clone();
+    assert!(cloned.is_owned());
+    drop(owned);
+    drop(cloned);
+    assert_eq!(drops.get(), 2);
+
+    drop(local);
+    assert_eq!(drops.get(), 3);
+}
+
+#[test]
+fn test_into_owned() {
+    let drops = Rc::new(Cell::new(0));
+    let local = Negate { operand: 2, drops: Some(drops.clone()) };
+
+    let owned = ExprCow::from(&local).into_owned();
+    assert_eq!(owned.eval(), -2);
+    drop(owned);
+    assert_eq!(drops.get(), 1);
+
+    // Owned handles are moved out, not cloned
+    let handle = Expr::negate(local);
+    let address = handle.0.ptr();
+    let owned = ExprCow::from(handle).into_owned();
+    assert_eq!(owned.0.ptr(), address);
+    drop(owned);
+    assert_eq!(drops.get(), 2);
+}
+
+#[test]
+fn test_debug() {
+    let borrowed = ExprCow::borrowed_literal(&ZERO);
+    assert_eq!(format!("{:?}", borrowed), "Borrowed(Expr::Literal)");
+
+    let owned = ExprCow::from(Expr::literal(Literal { value: 1 }));
+    assert_eq!(format!("{:?}", owned), "Owned(Expr::Literal)");
+}
+
+// A single variant keeps its tagged pointer for the spare bit
+#[tagged_dispatch(Node, cow)]
+enum Constant {
+    Literal,
+}
+
+#[test]
+fn test_single_variant_cow() {
+    let nodes = [ConstantCow::from(&ZERO), Constant::literal(Literal { value: 9 }).into()];
+
+    assert!(nodes[0].is_borrowed() && nodes[1].is_owned());
+    assert_eq!(nodes[0].eval() + nodes[1].eval(), 9);
+}
@@ -0,0 +1,64 @@
+// A framework crate re-exporting `tagged_dispatch` under its own path
+mod framework {
+    pub use tagged_dispatch as td;
+}
+
+use framework::td::tagged_dispatch;
+
+#[tagged_dispatch(crate = "crate::framework::td")]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone, Debug)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+#[tagged_dispatch(Area, fallible, crate = "crate::framework::td")]
+enum Shape {
+    Circle,
+    Square,
+}
+
+#[test]
+fn test_reexported_crate_path() {
+    let shapes = [Shape::circle(Circle { radius: 1.0 }), Shape::square(Square { side: 2.0 })];
+    assert_eq!(shapes.iter().map(Shape::area).sum::<f32>(), 7.0);
+    assert_eq!(shapes[1].clone().tag_type(), ShapeType::Square);
+    assert!(Shape::try_circle(Circle { radius: 2.0 }).is_ok());
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Area, crate = "crate::framework::td")]
+    enum Sketch<'a> {
+        Circle,
+        Square,
+    }
+
+    #[test]
+    fn test_arena_crate_path() {
+        let builder = SketchArenaBuilder::new();
+        assert_eq!(builder.square(Square { side: 3.0 }).area(), 9.0);
+    }
+}
@@ -0,0 +1,85 @@
+// The enum is declared before the traits it dispatches, in another module
+mod shapes {
+    use crate::traits::{Area, Name};
+
+    #[derive(Clone)]
+    pub struct Circle {
+        pub radius: f32,
+    }
+
+    impl Area for Circle {
+        fn area(&self) -> f32 {
+            3.0 * self.radius * self.radius
+        }
+    }
+
+    impl Name for Circle {
+        fn name(&self) -> &'static str {
+            "circle"
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct Square {
+        pub side: f32,
+    }
+
+    impl Area for Square {
+        fn area(&self) -> f32 {
+            self.side * self.side
+        }
+    }
+
+    impl Name for Square {
+        fn name(&self) -> &'static str {
+            "square"
+        }
+    }
+
+    #[tagged_dispatch::tagged_dispatch(crate::traits::Area, super::traits::Name)]
+    pub enum Shape {
+        Circle,
+        Square,
+    }
+}
+
+mod traits {
+    use tagged_dispatch::tagged_dispatch;
+
+    #[tagged_dispatch]
+    pub trait Area {
+        fn area(&self) -> f32;
+    }
+
+    // Exported traits can also be used by enums in other crates
+    #[tagged_dispatch(export)]
+    pub trait Name {
+        fn name(&self) -> &'static str;
+    }
+}
+
+use shapes::{Circle, Shape, Square};
+
+#[test]
+fn test_traits_from_another_module() {
+    let shapes = [Shape::circle(Circle { radius: 1.0 }), Shape::square(Square { side: 2.0 })];
+    assert_eq!(shapes.iter().map(Shape::area).sum::<f32>(), 7.0);
+    assert_eq!(shapes[1].name(), "square");
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch::tagged_dispatch(crate::traits::Name)]
+    enum Sketch<'a> {
+        Circle,
+        Square,
+    }
+
+    #[test]
+    fn test_arena_imported_trait() {
+        let builder = SketchArenaBuilder::new();
+        assert_eq!(builder.circle(Circle { radius: 1.0 }).name(), "circle");
+    }
+}
@@ -248,4 +248,117 @@ fn test_defaults_with_arena() {
 #[test]
 fn test_enum_size() {
     assert_eq!(std::mem::size_of::<Pet>(), 8);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_enum_level_defaults() {
+    #[tagged_dispatch]
+    trait Sized2D {
+        fn width(&self) -> u32;
+        fn height(&self) -> u32;
+
+        #[enum_default]
+        fn area(&self) -> u32 {
+            self.width() * self.height()
+        }
+
+        fn perimeter(&self) -> u32 {
+            2 * (self.width() + self.height())
+        }
+    }
+
+    #[derive(Clone)]
+    struct Square(u32);
+
+    impl Sized2D for Square {
+        fn width(&self) -> u32 {
+            self.0
+        }
+
+        fn height(&self) -> u32 {
+            self.0
+        }
+
+        // Bypassed by the enum, which evaluates the default body itself
+        fn area(&self) -> u32 {
+            0
+        }
+    }
+
+    #[derive(Clone)]
+    struct Banner(u32, u32);
+
+    impl Sized2D for Banner {
+        fn width(&self) -> u32 {
+            self.0
+        }
+
+        fn height(&self) -> u32 {
+            self.1
+        }
+    }
+
+    #[tagged_dispatch(Sized2D)]
+    enum Panel {
+        Square,
+        Banner,
+    }
+
+    let square = Panel::square(Square(3));
+    let banner = Panel::banner(Banner(4, 2));
+
+    assert_eq!(square.area(), 9);
+    assert_eq!(banner.area(), 8);
+    assert_eq!(Square(3).area(), 0);
+
+    // Not opted in, so still dispatched per variant
+    assert_eq!(square.perimeter(), 12);
+    assert_eq!(banner.perimeter(), 12);
+}
+
+#[test]
+fn test_trait_wide_enum_defaults() {
+    #[tagged_dispatch(enum_defaults)]
+    trait Greeter {
+        fn name(&self) -> String;
+
+        fn greeting(&self) -> String {
+            format!("Hello, {}!", self.name())
+        }
+
+        fn shout(&self) -> String {
+            self.greeting().to_uppercase()
+        }
+    }
+
+    #[derive(Clone)]
+    struct English;
+
+    impl Greeter for English {
+        fn name(&self) -> String {
+            "world".to_string()
+        }
+    }
+
+    #[derive(Clone)]
+    struct Named(String);
+
+    impl Greeter for Named {
+        fn name(&self) -> String {
+            self.0.clone()
+        }
+    }
+
+    #[tagged_dispatch(Greeter)]
+    enum AnyGreeter {
+        English,
+        Named,
+    }
+
+    let english = AnyGreeter::english(English);
+    let named = AnyGreeter::named(Named("Ada".to_string()));
+
+    assert_eq!(english.greeting(), "Hello, world!");
+    assert_eq!(named.shout(), "HELLO, ADA!");
+    assert_eq!(English.shout(), english.shout());
+}
@@ -0,0 +1,86 @@
+use tagged_dispatch::{tagged_dispatch, TaggedDispatch};
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone, Debug, Default)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * (self.radius + 1.0) * (self.radius + 1.0)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape {
+    Square,
+    #[default]
+    Circle,
+}
+
+#[allow(dead_code)]
+#[derive(TaggedDispatch)]
+#[dispatch(Area)]
+enum ShapeKind {
+    Square,
+    #[default_variant]
+    Circle,
+}
+
+#[derive(Default)]
+struct Scene {
+    shapes: Vec<Shape>,
+    focus: Shape,
+}
+
+#[test]
+fn test_default_impl() {
+    assert_eq!(Shape::default().tag_type(), ShapeType::Circle);
+    assert_eq!(Shape::default().area(), 3.0);
+    assert_eq!(TaggedShapeKind::default().tag_type(), TaggedShapeKindType::Circle);
+    assert_eq!(TaggedShapeKind::default().area(), 3.0);
+
+    // Containers and structs can derive or require `Default`
+    let scene = Scene::default();
+    assert!(scene.shapes.is_empty());
+    assert_eq!(scene.focus.tag_type(), ShapeType::Circle);
+    let mut shapes = vec![Shape::square(Square { side: 2.0 })];
+    shapes.resize_with(3, Default::default);
+    assert_eq!(shapes.iter().map(Shape::area).sum::<f32>(), 10.0);
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Area)]
+    enum Sketch<'a> {
+        Square,
+        #[default]
+        Circle,
+    }
+
+    #[test]
+    fn test_default_builder_method() {
+        let builder = SketchArenaBuilder::new();
+        let circle = builder.default_circle();
+        assert_eq!(circle.tag_type(), SketchType::Circle);
+        assert_eq!(circle.area(), 3.0);
+    }
+}
@@ -0,0 +1,141 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Load {
+    fn size(&self) -> usize;
+}
+
+#[derive(Clone)]
+struct Local {
+    path: String,
+}
+
+impl Load for Local {
+    fn size(&self) -> usize {
+        1
+    }
+}
+
+impl AsRef<str> for Local {
+    fn as_ref(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Borrow<Path> for Local {
+    fn borrow(&self) -> &Path {
+        Path::new(&self.path)
+    }
+}
+
+impl fmt::Display for Local {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "file://{}", self.path)
+    }
+}
+
+#[derive(Clone)]
+struct Remote {
+    url: String,
+}
+
+impl Load for Remote {
+    fn size(&self) -> usize {
+        2
+    }
+}
+
+impl AsRef<str> for Remote {
+    fn as_ref(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Borrow<Path> for Remote {
+    fn borrow(&self) -> &Path {
+        Path::new(self.url.trim_start_matches("https://"))
+    }
+}
+
+impl fmt::Display for Remote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.url)
+    }
+}
+
+impl fmt::Debug for Remote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Remote({})", self.url)
+    }
+}
+
+impl fmt::Debug for Local {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Local({})", self.path)
+    }
+}
+
+#[tagged_dispatch(Load, no_cmp)]
+#[delegate(AsRef<str>, Borrow<Path>, fmt::Display, fmt::Debug)]
+enum Source {
+    Local,
+    Remote,
+}
+
+#[test]
+fn test_delegated_as_ref() {
+    fn name_len(value: impl AsRef<str>) -> usize {
+        value.as_ref().len()
+    }
+
+    let local = Source::local(Local { path: "data.csv".to_string() });
+    assert_eq!(local.as_ref(), "data.csv");
+    assert_eq!(name_len(&local), 8);
+}
+
+#[test]
+fn test_delegated_borrow() {
+    let remote = Source::remote(Remote { url: "https://example.com/data.csv".to_string() });
+    let path: &Path = remote.borrow();
+    assert_eq!(path, Path::new("example.com/data.csv"));
+
+    let mut sizes: HashMap<&Path, usize> = HashMap::new();
+    sizes.insert(remote.borrow(), remote.size());
+    assert_eq!(sizes[Path::new("example.com/data.csv")], 2);
+}
+
+#[test]
+fn test_delegated_formatting() {
+    let local = Source::local(Local { path: "data.csv".to_string() });
+    let remote = Source::remote(Remote { url: "https://example.com".to_string() });
+
+    assert_eq!(local.to_string(), "file://data.csv");
+    assert_eq!(remote.to_string(), "https://example.com");
+    // Delegated Debug replaces the generated `Source::Remote` output
+    assert_eq!(format!("{:?}", remote), "Remote(https://example.com)");
+}
+
+#[tagged_dispatch(Load, display)]
+enum DisplaySource {
+    Local,
+    Remote,
+}
+
+#[test]
+fn test_display_flag() {
+    let sources = [
+        DisplaySource::local(Local { path: "a.txt".to_string() }),
+        DisplaySource::remote(Remote { url: "https://b".to_string() }),
+    ];
+
+    let shown: Vec<String> = sources.iter().map(ToString::to_string).collect();
+    assert_eq!(shown, ["file://a.txt", "https://b"]);
+    assert_eq!(sources.iter().map(DisplaySource::size).sum::<usize>(), 3);
+    // Debug is still the generated one
+    assert_eq!(format!("{:?}", sources[0]), "DisplaySource::Local");
+}
@@ -0,0 +1,123 @@
+use tagged_dispatch::{tagged_dispatch, TaggedDispatch};
+
+#[tagged_dispatch]
+trait Draw {
+    fn draw(&self) -> String;
+}
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone, Debug)]
+struct Circle {
+    radius: f32,
+}
+
+impl Draw for Circle {
+    fn draw(&self) -> String {
+        format!("circle {}", self.radius)
+    }
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Rectangle {
+    width: f32,
+    height: f32,
+}
+
+impl Draw for Rectangle {
+    fn draw(&self) -> String {
+        format!("rectangle {}x{}", self.width, self.height)
+    }
+}
+
+impl Area for Rectangle {
+    fn area(&self) -> f32 {
+        self.width * self.height
+    }
+}
+
+// Unit variants: the derived enum is only a declaration of the variant set
+#[allow(dead_code)]
+#[derive(TaggedDispatch)]
+#[dispatch(Draw)]
+enum ShapeKind {
+    Circle,
+    Rectangle,
+}
+
+// Value-carrying variants, a custom handle name, and several helper attributes
+#[derive(Debug, TaggedDispatch)]
+#[dispatch(Draw, name = Shape)]
+#[dispatch(Area, no_ord)]
+enum ShapeValue {
+    Circle(Circle),
+    Rectangle(Rectangle),
+}
+
+#[test]
+fn test_default_handle_name() {
+    let circle = TaggedShapeKind::circle(Circle { radius: 1.0 });
+    let rect = TaggedShapeKind::rectangle(Rectangle { width: 2.0, height: 3.0 });
+
+    assert_eq!(circle.draw(), "circle 1");
+    assert_eq!(rect.draw(), "rectangle 2x3");
+    assert_eq!(rect.tag_type(), TaggedShapeKindType::Rectangle);
+    assert_eq!(std::mem::size_of::<TaggedShapeKind>(), 8);
+}
+
+#[test]
+fn test_named_handle_with_multiple_attributes() {
+    let shape = Shape::rectangle(Rectangle { width: 2.0, height: 3.0 });
+
+    assert_eq!(shape.draw(), "rectangle 2x3");
+    assert_eq!(shape.area(), 6.0);
+    assert_eq!(format!("{:?}", shape), "Shape::Rectangle");
+    assert_eq!(shape, shape);
+}
+
+#[test]
+fn test_from_derived_enum() {
+    let value = ShapeValue::Circle(Circle { radius: 2.0 });
+    assert_eq!(format!("{:?}", value), "Circle(Circle { radius: 2.0 })");
+
+    let shape: Shape = value.into();
+    assert_eq!(shape.tag_type(), ShapeType::Circle);
+    assert_eq!(shape.draw(), "circle 2");
+    assert_eq!(shape.area(), 12.0);
+
+    let rect = Shape::from(ShapeValue::Rectangle(Rectangle { width: 1.0, height: 4.0 }));
+    assert_eq!(rect.tag_type(), ShapeType::Rectangle);
+    assert_eq!(rect.area(), 4.0);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[allow(dead_code)]
+    #[derive(TaggedDispatch)]
+    #[dispatch(Draw, arena, name = ShapeRef)]
+    enum ShapeArenaKind {
+        Circle,
+        Rectangle,
+    }
+
+    #[test]
+    fn test_arena_handle() {
+        let builder = ShapeRef::arena_builder();
+        let circle = builder.circle(Circle { radius: 1.0 });
+        let copy = circle;
+
+        assert_eq!(copy.draw(), "circle 1");
+        assert_eq!(circle, copy);
+    }
+}
@@ -0,0 +1,75 @@
+#![cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Body {
+    fn position(&self) -> i32;
+}
+
+struct Walker {
+    x: i32,
+}
+
+impl Body for Walker {
+    fn position(&self) -> i32 {
+        self.x
+    }
+}
+
+struct Rock {
+    x: i32,
+}
+
+impl Body for Rock {
+    fn position(&self) -> i32 {
+        self.x
+    }
+}
+
+#[tagged_dispatch(Body)]
+enum Entity<'a> {
+    Walker,
+    Rock,
+}
+
+#[test]
+fn test_flip_reads_previous_frame() {
+    let mut frames = EntityDoubleBufferedBuilder::new();
+    assert!(frames.current().is_empty());
+
+    frames.flip(|previous, builder| {
+        assert!(previous.is_empty());
+        vec![builder.walker(Walker { x: 0 }), builder.rock(Rock { x: 10 })]
+    });
+
+    for _ in 0..5 {
+        frames.flip(|previous, builder| {
+            previous
+                .iter()
+                .map(|entity| match entity.tag_type() {
+                    EntityType::Walker => builder.walker(Walker { x: entity.position() + 1 }),
+                    EntityType::Rock => builder.rock(Rock { x: entity.position() }),
+                })
+                .collect()
+        });
+    }
+
+    let positions: Vec<i32> = frames.current().iter().map(|entity| entity.position()).collect();
+    assert_eq!(positions, vec![5, 10]);
+}
+
+#[test]
+fn test_flip_drops_handles_not_kept() {
+    let mut frames = EntityDoubleBufferedBuilder::new();
+
+    frames.flip(|_, builder| vec![builder.rock(Rock { x: 1 }), builder.rock(Rock { x: 2 })]);
+    frames.flip(|previous, builder| {
+        assert_eq!(previous.len(), 2);
+        Vec::from([builder.walker(Walker { x: previous[1].position() })])
+    });
+
+    assert_eq!(frames.current().len(), 1);
+    assert_eq!(frames.current()[0].tag_type(), EntityType::Walker);
+    assert_eq!(frames.current()[0].position(), 2);
+}
@@ -0,0 +1,71 @@
+use std::cell::Cell;
+
+use tagged_dispatch::tagged_dispatch;
+
+thread_local! {
+    static DROPS: Cell<usize> = const { Cell::new(0) };
+}
+
+#[tagged_dispatch]
+trait Size {
+    fn size(&self) -> usize;
+}
+
+#[derive(Clone)]
+struct Small(u8);
+
+impl Size for Small {
+    fn size(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Drop for Small {
+    fn drop(&mut self) {
+        DROPS.with(|drops| drops.set(drops.get() + 1));
+    }
+}
+
+#[derive(Clone)]
+struct Large(Vec<u8>);
+
+impl Size for Large {
+    fn size(&self) -> usize {
+        self.0.len()
+    }
+}
+
+// Both enums share the drop glue of `Small` and `Large`
+#[tagged_dispatch(Size, drop_table)]
+enum Item {
+    Small,
+    Large,
+}
+
+#[tagged_dispatch(Size, drop_table)]
+enum Entry {
+    Large,
+    Small,
+}
+
+#[test]
+fn test_drop_table_drops_each_variant_once() {
+    let before = DROPS.with(Cell::get);
+    {
+        let items = [Item::small(Small(1)), Item::large(Large(vec![0; 16]))];
+        let entries = [Entry::small(Small(2)), Entry::large(Large(vec![0; 4]))];
+        let copy = items[0].clone();
+
+        assert_eq!(items[1].size() + entries[1].size(), 20);
+        assert_eq!(copy.size(), 1);
+    }
+    assert_eq!(DROPS.with(Cell::get) - before, 3);
+}
+
+#[test]
+fn test_drop_table_explicit_drop() {
+    let before = DROPS.with(Cell::get);
+    let item = Item::small(Small(3));
+    drop(item);
+    assert_eq!(DROPS.with(Cell::get) - before, 1);
+}
@@ -0,0 +1,74 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Packet {
+    id: u32,
+}
+
+impl Describe for Packet {
+    fn describe(&self) -> String {
+        format!("packet {}", self.id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Ping;
+
+impl Describe for Ping {
+    fn describe(&self) -> String {
+        "ping".to_string()
+    }
+}
+
+#[tagged_dispatch(Describe)]
+enum Message {
+    Request(Packet),
+    Response(Packet),
+    Ping,
+}
+
+#[test]
+fn test_duplicate_variant_types() {
+    let request = Message::request(Packet { id: 1 });
+    let response = Message::response(Packet { id: 1 });
+    assert_eq!(request.describe(), response.describe());
+    assert_eq!(request.tag_type(), MessageType::Request);
+    assert_eq!(response.tag_type(), MessageType::Response);
+    assert_ne!(request, response);
+    assert_eq!(response.as_response(), Some(&Packet { id: 1 }));
+    assert_eq!(response.as_request(), None);
+
+    let ping: Message = Ping.into();
+    assert!(ping.is_ping());
+
+    // `From<Packet>` and `TryFrom<Message> for Packet` would be ambiguous, so
+    // variants sharing a type only convert through their own methods
+    let response = response.try_into_request().unwrap_err();
+    assert_eq!(response.try_into_response().unwrap(), Packet { id: 1 });
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Describe)]
+    enum Frame<'a> {
+        Request(Packet),
+        Response(Packet),
+    }
+
+    #[test]
+    fn test_duplicate_variant_types_arena() {
+        let builder = FrameArenaBuilder::new();
+        let frames = [builder.request(Packet { id: 2 }), builder.response(Packet { id: 3 })];
+        assert_eq!(frames.each_ref().map(|frame| frame.describe()), ["packet 2", "packet 3"]);
+        assert_eq!(frames[1].tag_type(), FrameType::Response);
+        assert_eq!(frames[0].try_into_request().unwrap(), &Packet { id: 2 });
+        assert!(frames[0].try_into_response().is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt::Debug;
+
+use tagged_dispatch::{tagged_dispatch, InlineValue};
+
+#[tagged_dispatch]
+trait Draw: Debug {
+    fn draw(&self) -> String;
+
+    // Only callable on sized types, so `dyn Draw` still exists
+    #[no_dispatch]
+    fn boxed(self) -> Box<Self>
+    where
+        Self: Sized,
+    {
+        Box::new(self)
+    }
+}
+
+// Associated consts rule out `dyn Weigh`, so no `as_weigh()` is generated
+#[tagged_dispatch]
+trait Weigh {
+    const GRAMS: u32;
+
+    fn weight(&self) -> u32;
+}
+
+#[derive(Clone, Debug)]
+struct Circle {
+    radius: f32,
+}
+
+impl Draw for Circle {
+    fn draw(&self) -> String {
+        format!("circle {}", self.radius)
+    }
+}
+
+impl Weigh for Circle {
+    const GRAMS: u32 = 10;
+
+    fn weight(&self) -> u32 {
+        Self::GRAMS * self.radius as u32
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Dot(u16);
+
+// SAFETY: a plain `u16`
+unsafe impl InlineValue for Dot {}
+
+impl Draw for Dot {
+    fn draw(&self) -> String {
+        format!("dot {}", self.0)
+    }
+}
+
+impl Weigh for Dot {
+    const GRAMS: u32 = 1;
+
+    fn weight(&self) -> u32 {
+        Self::GRAMS
+    }
+}
+
+#[tagged_dispatch(Draw, Weigh)]
+enum Shape {
+    Circle,
+    #[inline_value]
+    Dot,
+}
+
+// An existing API taking trait objects
+fn draw_all(items: &[&dyn Draw]) -> Vec<String> {
+    items.iter().map(|item| item.draw()).collect()
+}
+
+#[test]
+fn test_as_dyn() {
+    let shapes = [Shape::circle(Circle { radius: 1.5 }), Shape::dot(Dot(7))];
+    let objects: Vec<&dyn Draw> = shapes.iter().map(Shape::as_draw).collect();
+    assert_eq!(draw_all(&objects), ["circle 1.5", "dot 7"]);
+    assert_eq!(format!("{:?}", objects[1]), "Dot(7)");
+
+    // Boxed values still point at the variant's own allocation
+    let circle: &Circle = (&shapes[0]).try_into().unwrap();
+    assert!(std::ptr::addr_eq(shapes[0].as_draw(), circle));
+    assert_eq!(shapes[0].weight(), 10);
+    assert_eq!(Circle { radius: 1.0 }.boxed().draw(), "circle 1");
+}
+
+#[test]
+fn test_with_dyn() {
+    let shape = Shape::dot(Dot(4));
+    assert_eq!(shape.with_draw(|object| object.draw()), shape.draw());
+    assert_eq!(shape.with_draw(|object| object.draw().len()), 5);
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Draw)]
+    enum Sketch<'a> {
+        Circle,
+        #[inline_value]
+        Dot,
+    }
+
+    #[test]
+    fn test_arena_as_dyn() {
+        let builder = SketchArenaBuilder::new();
+        let sketches = [builder.circle(Circle { radius: 2.0 }), builder.dot(Dot(3))];
+        let objects: Vec<&dyn Draw> = sketches.iter().map(Sketch::as_draw).collect();
+        assert_eq!(draw_all(&objects), ["circle 2", "dot 3"]);
+        assert_eq!(sketches[0].with_draw(|object| object.draw()), sketches[0].draw());
+    }
+}
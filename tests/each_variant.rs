@@ -0,0 +1,87 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone, Default)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone, Default)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape {
+    Circle,
+    Square,
+}
+
+#[test]
+fn test_registration_table() {
+    let mut table: Vec<(&str, u8, usize)> = Vec::new();
+
+    macro_rules! register {
+        ($variant:ident, $ty:ty, $tag:expr) => {
+            table.push((stringify!($variant), $tag, std::mem::size_of::<$ty>()));
+        };
+    }
+    each_shape_variant!(register);
+
+    assert_eq!(table, [("Circle", 0, 4), ("Square", 1, 4)]);
+}
+
+#[test]
+fn test_tags_match_constructors() {
+    macro_rules! check_tag {
+        ($variant:ident, $ty:ty, $tag:expr) => {
+            let shape = Shape::from(<$ty>::default());
+            assert_eq!(shape.tag_type(), ShapeType::$variant);
+            assert_eq!(shape.tag_type() as u8, $tag);
+        };
+    }
+    each_shape_variant!(check_tag);
+}
+
+// Generic code can take the variant set as a type
+trait TypeCount {
+    const COUNT: usize;
+}
+
+impl<A, B> TypeCount for (A, B) {
+    const COUNT: usize = 2;
+}
+
+#[test]
+fn test_variant_type_list() {
+    assert_eq!(<ShapeVariants as TypeCount>::COUNT, 2);
+    let variants: ShapeVariants = (Circle { radius: 1.0 }, Square { side: 2.0 });
+    assert_eq!(Shape::from(variants.1).area(), 4.0);
+}
+
+pub mod public {
+    use super::*;
+
+    // A public enum over private variant types
+    #[tagged_dispatch(Area)]
+    pub enum Tile {
+        Circle,
+        Square,
+    }
+}
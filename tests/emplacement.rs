@@ -0,0 +1,161 @@
+use std::mem::MaybeUninit;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Checksum {
+    fn checksum(&self) -> u64;
+}
+
+#[derive(Clone)]
+struct Page {
+    bytes: [u8; 4096],
+}
+
+impl Checksum for Page {
+    fn checksum(&self) -> u64 {
+        self.bytes.iter().map(|&byte| byte as u64).sum()
+    }
+}
+
+/// Fill a page in place without building it on the stack first
+fn fill_page(slot: &mut MaybeUninit<Page>, byte: u8) {
+    let page = slot.as_mut_ptr();
+    unsafe {
+        std::ptr::addr_of_mut!((*page).bytes).write_bytes(byte, 1);
+    }
+}
+
+#[derive(Clone)]
+struct Word(u64);
+
+impl Checksum for Word {
+    fn checksum(&self) -> u64 {
+        self.0
+    }
+}
+
+#[tagged_dispatch(Checksum)]
+enum Block {
+    Page,
+    Word,
+}
+
+#[test]
+fn test_owned_emplacement() {
+    let page = unsafe { Block::page_with(|slot| fill_page(slot, 2)) };
+    let word = unsafe { Block::word_with(|slot| { slot.write(Word(7)); }) };
+
+    assert_eq!(page.tag_type(), BlockType::Page);
+    assert_eq!(page.checksum(), 2 * 4096);
+    assert_eq!(word.checksum(), 7);
+    assert_eq!(page.clone().checksum(), 2 * 4096);
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Checksum)]
+    enum BlockRef<'a> {
+        Page,
+        Word,
+    }
+
+    #[test]
+    fn test_arena_emplacement() {
+        let builder = BlockRef::arena_builder();
+        let page = unsafe { builder.page_with(|slot| fill_page(slot, 1)) };
+        let word = builder.word(Word(3));
+
+        assert_eq!(page.checksum(), 4096);
+        assert_eq!(word.checksum(), 3);
+    }
+
+    #[test]
+    fn test_two_phase_initialization() {
+        let builder = BlockRef::arena_builder();
+        let (slot, pending) = unsafe { builder.page_uninit() };
+        assert_eq!(pending.tag_type(), BlockRefType::Page);
+
+        // Data arrives in chunks and is written straight into the arena slot
+        let page = slot.as_mut_ptr();
+        for (i, chunk) in [3u8, 5].iter().enumerate() {
+            unsafe {
+                let bytes = std::ptr::addr_of_mut!((*page).bytes) as *mut u8;
+                bytes.add(i * 2048).write_bytes(*chunk, 2048);
+            }
+        }
+
+        let page = unsafe { pending.assume_init() };
+        assert_eq!(page.checksum(), 8 * 2048);
+    }
+
+    #[cfg(feature = "allocator-typed-arena")]
+    #[test]
+    fn test_typed_arena_two_phase_initialization() {
+        let builder = BlockRefArenaBuilder::with_typed_arena();
+        let (slot, pending) = unsafe { builder.word_uninit() };
+        slot.write(Word(11));
+
+        assert_eq!(unsafe { pending.assume_init() }.checksum(), 11);
+    }
+
+    #[cfg(feature = "allocator-typed-arena")]
+    #[test]
+    fn test_typed_arena_emplacement() {
+        let builder = BlockRefArenaBuilder::with_typed_arena();
+        let pages: Vec<_> = (0..3u8)
+            .map(|i| unsafe { builder.page_with(|slot| fill_page(slot, i)) })
+            .collect();
+
+        let sums: Vec<_> = pages.iter().map(|page| page.checksum()).collect();
+        assert_eq!(sums, [0, 4096, 2 * 4096]);
+    }
+
+    #[cfg(feature = "allocator-bumpalo")]
+    #[test]
+    fn test_bumpalo_emplacement_allows_nested_allocation() {
+        let builder = BlockRefArenaBuilder::with_bumpalo();
+        let mut inner = None;
+        let outer = unsafe {
+            builder.word_with(|slot| {
+                inner = Some(builder.word(Word(7)));
+                slot.write(Word(42));
+            })
+        };
+
+        assert_eq!(outer.checksum(), 42);
+        assert_eq!(inner.unwrap().checksum(), 7);
+    }
+
+    /// Runs `nested_typed_arena_emplacement` in a child process, which must abort
+    #[cfg(feature = "allocator-typed-arena")]
+    #[test]
+    fn test_typed_arena_nested_emplacement_aborts() {
+        if std::env::var_os("EMPLACEMENT_NESTED_CHILD").is_some() {
+            nested_typed_arena_emplacement();
+            return;
+        }
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "arena_tests::test_typed_arena_nested_emplacement_aborts", "--test-threads=1", "--nocapture"])
+            .env("EMPLACEMENT_NESTED_CHILD", "1")
+            .output()
+            .unwrap();
+
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("in the typed arena it was initializing a slot of"), "{}", stderr);
+    }
+
+    #[cfg(feature = "allocator-typed-arena")]
+    fn nested_typed_arena_emplacement() {
+        let builder = BlockRefArenaBuilder::with_typed_arena();
+        let _outer = unsafe {
+            builder.word_with(|slot| {
+                builder.word(Word(7));
+                slot.write(Word(42));
+            })
+        };
+    }
+}
@@ -0,0 +1,78 @@
+use tagged_dispatch::compat::enum_dispatch;
+
+// The example from `enum_dispatch`'s documentation, unchanged but for the import
+#[enum_dispatch]
+trait KnobControl {
+    fn set_position(&mut self, value: f64);
+    fn get_value(&self) -> f64;
+}
+
+#[derive(Clone, Debug, Default)]
+struct LinearKnob {
+    position: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+struct LogarithmicKnob {
+    position: f64,
+}
+
+impl KnobControl for LinearKnob {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        self.position
+    }
+}
+
+impl KnobControl for LogarithmicKnob {
+    fn set_position(&mut self, value: f64) {
+        self.position = value;
+    }
+
+    fn get_value(&self) -> f64 {
+        (self.position + 1.).log2()
+    }
+}
+
+#[enum_dispatch(KnobControl)]
+enum Knob {
+    LinearKnob,
+    LogarithmicKnob,
+}
+
+#[enum_dispatch(KnobControl)]
+enum Dial {
+    Fine(LinearKnob),
+    Coarse(LogarithmicKnob),
+}
+
+#[test]
+fn test_enum_dispatch_syntax() {
+    let mut knob: Knob = LinearKnob::default().into();
+    knob.set_position(0.5);
+    assert_eq!(knob.get_value(), 0.5);
+
+    let mut knob = Knob::LogarithmicKnob(LogarithmicKnob::default());
+    knob.set_position(1.0);
+    assert_eq!(knob.get_value(), 1.0);
+    assert_eq!(knob.tag_type(), KnobType::LogarithmicKnob);
+}
+
+#[test]
+fn test_variant_constructors_and_conversions() {
+    let dial = Dial::Coarse(LogarithmicKnob { position: 3.0 });
+    assert_eq!(dial.get_value(), 2.0);
+
+    let dial: Result<LinearKnob, _> = dial.try_into();
+    let dial = dial.unwrap_err();
+    let coarse: LogarithmicKnob = dial.try_into().unwrap();
+    assert_eq!(coarse.position, 3.0);
+
+    let mut fine = Dial::Fine(LinearKnob { position: 0.25 });
+    assert_eq!(fine.as_fine().unwrap().position, 0.25);
+    fine.set_position(0.75);
+    assert_eq!(fine.get_value(), 0.75);
+}
@@ -0,0 +1,100 @@
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+use tagged_dispatch::{tagged_dispatch, tagged_dispatch_external};
+
+tagged_dispatch_external! {
+    trait io::Write {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize>;
+        fn flush(&mut self) -> io::Result<()>;
+    }
+}
+
+// Options go in a `#[tagged_dispatch(...)]` attribute, like on a local trait
+tagged_dispatch_external! {
+    #[tagged_dispatch(export)]
+    trait fmt::Display {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+    }
+}
+
+/// Counts the bytes written to it
+#[derive(Clone, Default)]
+struct Counter {
+    bytes: usize,
+}
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tagged_dispatch(Write)]
+enum Sink {
+    Buffer(Vec<u8>),
+    Counter,
+}
+
+#[test]
+fn test_foreign_trait_dispatch() {
+    let mut sinks = [Sink::buffer(Vec::new()), Sink::counter(Counter::default())];
+    for sink in &mut sinks {
+        sink.write(b"hello").unwrap();
+        sink.write(b", world").unwrap();
+        sink.flush().unwrap();
+    }
+    assert_eq!(sinks[0].as_buffer().unwrap(), b"hello, world");
+    assert_eq!(sinks[1].as_counter().unwrap().bytes, 12);
+}
+
+#[tagged_dispatch(Display)]
+enum Label {
+    Name(String),
+    Number(u32),
+}
+
+#[test]
+fn test_foreign_trait_with_options() {
+    let mut text = String::new();
+    for label in [Label::name("x".to_string()), Label::number(7)] {
+        text.push_str(&DisplayLabel(&label).to_string());
+    }
+    assert_eq!(text, "x7");
+}
+
+/// Formats a label through its dispatched `fmt`
+struct DisplayLabel<'a>(&'a Label);
+
+impl Display for DisplayLabel<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[tagged_dispatch(Write, shared)]
+enum SharedSink {
+    Buffer(Vec<u8>),
+    Counter,
+}
+
+#[test]
+fn test_shared_handles_write_when_unique() {
+    let mut sink = SharedSink::buffer(Vec::new());
+    sink.write(b"abc").unwrap();
+    sink.flush().unwrap();
+    assert_eq!(sink.as_buffer().unwrap(), b"abc");
+}
+
+#[test]
+#[should_panic(expected = "shared with another handle")]
+fn test_shared_handles_cannot_write_when_cloned() {
+    let mut sink = SharedSink::counter(Counter::default());
+    let _other = sink.clone();
+    let _ = sink.write(b"abc");
+}
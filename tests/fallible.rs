@@ -0,0 +1,137 @@
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tagged_dispatch::{tagged_dispatch, TagError, VariantAllocator};
+
+#[tagged_dispatch]
+trait Sensor {
+    fn read(&self) -> u32;
+}
+
+#[derive(Clone)]
+struct Thermometer {
+    celsius: u32,
+}
+
+impl Sensor for Thermometer {
+    fn read(&self) -> u32 {
+        self.celsius
+    }
+}
+
+#[derive(Clone)]
+struct Barometer {
+    pascals: u32,
+}
+
+impl Sensor for Barometer {
+    fn read(&self) -> u32 {
+        self.pascals
+    }
+}
+
+const METADATA: usize = 0x3C << 56;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+// Hands out addresses with metadata in the top byte, like a tagging allocator would
+struct TopByteAllocator;
+
+unsafe impl<T> VariantAllocator<T> for TopByteAllocator {
+    fn alloc(value: T) -> NonNull<T> {
+        LIVE.fetch_add(1, Ordering::SeqCst);
+        let addr = Box::into_raw(Box::new(value)) as usize;
+        NonNull::new((addr | METADATA) as *mut T).unwrap()
+    }
+
+    unsafe fn take(ptr: NonNull<T>) -> T {
+        LIVE.fetch_sub(1, Ordering::SeqCst);
+        *Box::from_raw((ptr.as_ptr() as usize & !METADATA) as *mut T)
+    }
+}
+
+// Out of memory for every value, like an exhausted pool
+struct ExhaustedAllocator;
+
+unsafe impl<T> VariantAllocator<T> for ExhaustedAllocator {
+    fn alloc(_value: T) -> NonNull<T> {
+        panic!("out of memory")
+    }
+
+    fn try_alloc(value: T) -> Result<NonNull<T>, T> {
+        Err(value)
+    }
+
+    unsafe fn take(_ptr: NonNull<T>) -> T {
+        unreachable!("nothing was allocated")
+    }
+}
+
+#[tagged_dispatch(Sensor, fallible)]
+enum Probe {
+    Thermometer,
+    #[alloc_with(TopByteAllocator)]
+    Barometer,
+}
+
+#[tagged_dispatch(Sensor, fallible)]
+enum PooledProbe {
+    #[alloc_with(ExhaustedAllocator)]
+    Thermometer,
+    Barometer,
+}
+
+#[test]
+fn test_try_constructor_succeeds() {
+    let probe = Probe::try_thermometer(Thermometer { celsius: 21 }).unwrap();
+    assert_eq!(probe.read(), 21);
+    assert_eq!(probe.tag_type(), ProbeType::Thermometer);
+}
+
+#[test]
+fn test_try_constructor_rejects_tagged_address() {
+    let err = Probe::try_barometer(Barometer { pascals: 101_325 }).unwrap_err();
+    assert!(matches!(err, TagError::AddressInUse { addr } if addr & METADATA == METADATA));
+
+    // The value was handed back to its allocator
+    assert_eq!(LIVE.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_try_constructor_reports_alloc_failure() {
+    let err = PooledProbe::try_thermometer(Thermometer { celsius: 5 }).unwrap_err();
+    assert_eq!(err, TagError::AllocFailed { size: std::mem::size_of::<Thermometer>() });
+    assert_eq!(err.to_string(), "failed to allocate 4 bytes");
+
+    // Variants with other allocators are unaffected
+    let probe = PooledProbe::try_barometer(Barometer { pascals: 90_000 }).unwrap();
+    assert_eq!(probe.read(), 90_000);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Sensor, fallible)]
+    enum ArenaProbe<'a> {
+        Thermometer,
+        Barometer,
+    }
+
+    #[test]
+    fn test_try_builder_method() {
+        let builder = ArenaProbe::arena_builder();
+        let probe = builder.try_barometer(Barometer { pascals: 90_000 }).unwrap();
+        assert_eq!(probe.read(), 90_000);
+        assert_eq!(probe.tag_type(), ArenaProbeType::Barometer);
+    }
+
+    #[test]
+    fn test_try_builder_method_reports_exhausted_arena() {
+        let bump = tagged_dispatch::bumpalo::Bump::new();
+        bump.set_allocation_limit(Some(0));
+        let builder = ArenaProbeArenaBuilder::with_external_bumpalo(&bump);
+        let err = builder.try_thermometer(Thermometer { celsius: 30 }).unwrap_err();
+        assert!(matches!(err, TagError::AllocFailed { size: 4 }));
+    }
+}
@@ -0,0 +1,126 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone, Debug)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Sphere {
+    radius: f32,
+}
+
+impl Area for Sphere {
+    fn area(&self) -> f32 {
+        12.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Point;
+
+impl Area for Point {
+    fn area(&self) -> f32 {
+        0.0
+    }
+}
+
+impl Area for u32 {
+    fn area(&self) -> f32 {
+        *self as f32
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape2D {
+    Circle,
+    Square,
+}
+
+#[tagged_dispatch(Area)]
+enum Shape3D {
+    Sphere,
+    #[inline_value]
+    Voxels(u32),
+}
+
+#[tagged_dispatch(Area)]
+enum AnyShape {
+    #[flatten]
+    TwoD(Shape2D),
+    #[flatten]
+    ThreeD(Shape3D),
+    Point,
+}
+
+#[test]
+fn test_flattened_tag_space() {
+    assert_eq!(AnyShape::VARIANT_COUNT, 5);
+    let names = AnyShapeType::ALL.map(AnyShapeType::name);
+    assert_eq!(names, ["Circle", "Square", "Sphere", "Voxels", "Point"]);
+
+    let shapes = [
+        AnyShape::circle(Circle { radius: 1.0 }),
+        AnyShape::square(Square { side: 2.0 }),
+        AnyShape::sphere(Sphere { radius: 1.0 }),
+        AnyShape::voxels(7),
+        AnyShape::point(Point),
+    ];
+    let areas: Vec<_> = shapes.iter().map(AnyShape::area).collect();
+    assert_eq!(areas, [3.0, 4.0, 12.0, 7.0, 0.0]);
+    assert_eq!(shapes[2].tag_type(), AnyShapeType::Sphere);
+}
+
+#[test]
+fn test_group_checks() {
+    let square = AnyShape::square(Square { side: 1.0 });
+    assert!(square.is_two_d());
+    assert!(!square.is_three_d());
+
+    let point = AnyShape::point(Point);
+    assert!(!point.is_two_d() && !point.is_three_d());
+}
+
+#[test]
+fn test_retagging_conversions() {
+    let shape: AnyShape = Shape3D::sphere(Sphere { radius: 2.0 }).into();
+    assert_eq!(shape.tag_type(), AnyShapeType::Sphere);
+    assert_eq!(shape.area(), 48.0);
+
+    let voxels = AnyShape::from(Shape3D::voxels(3));
+    assert_eq!(voxels.tag_type(), AnyShapeType::Voxels);
+    assert_eq!(voxels.area(), 3.0);
+
+    // Converting back only succeeds for the inner enum's own variants
+    let inner = Shape3D::try_from(shape).unwrap();
+    assert_eq!(inner.tag_type(), Shape3DType::Sphere);
+    assert_eq!(inner.area(), 48.0);
+
+    let square = AnyShape::from(Shape2D::square(Square { side: 3.0 }));
+    let square = Shape3D::try_from(square).unwrap_err();
+    assert_eq!(Shape2D::try_from(square).unwrap().area(), 9.0);
+
+    assert!(Shape2D::try_from(AnyShape::point(Point)).is_err());
+}
@@ -0,0 +1,88 @@
+use std::panic::Location;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch(ref_impls)]
+trait Inspect {
+    #[must_use]
+    fn value(&self) -> i32;
+
+    #[track_caller]
+    fn caller(&self) -> &'static Location<'static>;
+
+    // Compiled out, so neither the variants nor the enum have it
+    #[cfg(any())]
+    fn missing(&self) -> i32;
+
+    #[deprecated(note = "use `value` instead")]
+    fn old_value(&self) -> i32 {
+        self.value()
+    }
+}
+
+#[derive(Clone)]
+struct One;
+
+impl Inspect for One {
+    fn value(&self) -> i32 {
+        1
+    }
+
+    #[track_caller]
+    fn caller(&self) -> &'static Location<'static> {
+        Location::caller()
+    }
+}
+
+#[derive(Clone)]
+struct Two;
+
+impl Inspect for Two {
+    fn value(&self) -> i32 {
+        2
+    }
+
+    #[track_caller]
+    fn caller(&self) -> &'static Location<'static> {
+        Location::caller()
+    }
+}
+
+#[tagged_dispatch(Inspect)]
+enum Number {
+    /// The number one
+    One,
+    Two,
+}
+
+#[test]
+fn test_track_caller_reports_call_site() {
+    let number = Number::two(Two);
+    let expected_line = line!() + 1;
+    let location = number.caller();
+
+    assert_eq!(location.file(), file!());
+    assert_eq!(location.line(), expected_line);
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_method_still_dispatches() {
+    assert_eq!(Number::one(One).old_value(), 1);
+    assert_eq!(Number::two(Two).old_value(), 2);
+}
+
+#[test]
+fn test_must_use_method() {
+    let value = Number::one(One).value();
+    assert_eq!(value, 1);
+}
+
+#[test]
+fn test_cfg_disabled_method() {
+    fn value(inspect: impl Inspect) -> i32 {
+        #[allow(deprecated)]
+        inspect.old_value()
+    }
+    assert_eq!(value(&Number::two(Two)), 2);
+}
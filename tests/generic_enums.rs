@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Container {
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+struct VecStore<T>(Vec<T>);
+
+#[derive(Clone, Debug, PartialEq, Hash)]
+struct MapStore<T>(BTreeMap<u32, T>);
+
+impl<T> Container for VecStore<T> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl<T> Container for MapStore<T> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+#[tagged_dispatch(Container)]
+enum Store<T: Clone + 'static> {
+    Vec(VecStore<T>),
+    Map(MapStore<T>),
+}
+
+#[test]
+fn test_generic_enum() {
+    let stores: [Store<String>; 2] = [
+        Store::vec(VecStore(vec!["a".to_string(), "b".to_string()])),
+        Store::map(MapStore(BTreeMap::new())),
+    ];
+    assert_eq!(stores.each_ref().map(|store| store.len()), [2, 0]);
+    assert!(stores[1].is_empty());
+    assert_eq!(stores[0].tag_type(), StoreType::Vec);
+    assert_eq!(stores[0].clone().as_vec().unwrap().0, ["a", "b"]);
+    assert!(stores[0].type_name().ends_with("VecStore<alloc::string::String>"));
+
+    let converted: Store<u8> = VecStore(vec![1]).into();
+    assert_eq!(VecStore::try_from(converted).unwrap(), VecStore(vec![1]));
+    assert_eq!(std::mem::size_of::<Store<u64>>(), 8);
+}
+
+#[tagged_dispatch(ref_impls)]
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+#[tagged_dispatch(map_errors)]
+trait Parse {
+    type Error;
+
+    fn first(&self) -> Result<String, Self::Error>;
+}
+
+impl<T: std::fmt::Debug> Describe for VecStore<T> {
+    fn describe(&self) -> String {
+        format!("vec {:?}", self.0)
+    }
+}
+
+impl<T: std::fmt::Debug> Describe for MapStore<T> {
+    fn describe(&self) -> String {
+        format!("map {:?}", self.0)
+    }
+}
+
+impl<T: std::fmt::Debug> Parse for VecStore<T> {
+    type Error = &'static str;
+
+    fn first(&self) -> Result<String, Self::Error> {
+        self.0.first().map(|item| format!("{:?}", item)).ok_or("empty vec")
+    }
+}
+
+impl<T: std::fmt::Debug> Parse for MapStore<T> {
+    type Error = u32;
+
+    fn first(&self) -> Result<String, Self::Error> {
+        self.0.values().next().map(|item| format!("{:?}", item)).ok_or(0)
+    }
+}
+
+// Every impl carries the bounds, including the dispatched traits and options
+#[tagged_dispatch(Container, Describe, Parse, send, sync, shared, hash)]
+enum Shared<T: Clone + std::fmt::Debug + std::hash::Hash + Send + Sync + 'static> {
+    Vec(VecStore<T>),
+    Map(MapStore<T>),
+}
+
+fn describe_all(items: impl IntoIterator<Item = impl Describe>) -> Vec<String> {
+    items.into_iter().map(|item| item.describe()).collect()
+}
+
+#[test]
+fn test_generic_enum_options() {
+    let stores = [Shared::vec(VecStore(vec![1u8])), Shared::map(MapStore(BTreeMap::new()))];
+    assert_eq!(describe_all(&stores), ["vec [1]", "map {}"]);
+    assert_eq!(stores[0].first().unwrap(), "1");
+    assert_eq!(stores[1].first().unwrap_err().tag_type(), SharedType::Map);
+
+    let copy = stores[0].clone();
+    std::thread::spawn(move || assert_eq!(copy.len(), 1)).join().unwrap();
+    assert!(stores[1].is_empty());
+
+    let lens = stores.each_ref().map(|store| store.map_concrete().vec(|vec| vec.0.len()).map(|map| map.0.len()).finish());
+    assert_eq!(lens, [Some(1), Some(0)]);
+}
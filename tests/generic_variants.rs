@@ -0,0 +1,64 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Perimeter {
+    fn perimeter(&self) -> f64;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Polygon<T> {
+    sides: Vec<T>,
+}
+
+impl Perimeter for Polygon<f32> {
+    fn perimeter(&self) -> f64 {
+        self.sides.iter().sum::<f32>() as f64
+    }
+}
+
+impl Perimeter for Polygon<f64> {
+    fn perimeter(&self) -> f64 {
+        self.sides.iter().sum()
+    }
+}
+
+// Variants of the same generic type differ only in their arguments
+#[tagged_dispatch(Perimeter)]
+enum Shape {
+    Poly(Polygon<f32>),
+    PolyD(Polygon<f64>),
+}
+
+#[test]
+fn test_generic_variant_types() {
+    let shapes = [
+        Shape::poly(Polygon { sides: vec![1.0, 2.0] }),
+        Shape::poly_d(Polygon { sides: vec![0.5, 0.25] }),
+    ];
+    assert_eq!(shapes.each_ref().map(Shape::perimeter), [3.0, 0.75]);
+    assert_eq!(shapes[0].clone().as_poly().unwrap().sides, [1.0f32, 2.0]);
+    assert_eq!(shapes[1].tag_type(), ShapeType::PolyD);
+
+    let converted: Shape = Polygon { sides: vec![4.0f64] }.into();
+    assert!(converted.is_poly_d());
+    assert_eq!(Polygon::<f64>::try_from(converted).unwrap().sides, [4.0]);
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Perimeter)]
+    enum Sketch<'a> {
+        Poly(Polygon<f32>),
+        PolyD(Polygon<f64>),
+    }
+
+    #[test]
+    fn test_generic_variant_types_arena() {
+        let builder = SketchArenaBuilder::new();
+        let sketches = [builder.poly(Polygon { sides: vec![1.0] }), builder.poly_d(Polygon { sides: vec![2.0] })];
+        assert_eq!(sketches.each_ref().map(|sketch| sketch.perimeter()), [1.0, 2.0]);
+        assert_eq!(sketches[1].as_poly_d().unwrap().sides, [2.0]);
+    }
+}
@@ -0,0 +1,54 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+impl Describe for u32 {
+    fn describe(&self) -> String {
+        format!("count {}", self)
+    }
+}
+
+impl Describe for bool {
+    fn describe(&self) -> String {
+        format!("flag {}", self)
+    }
+}
+
+impl Describe for () {
+    fn describe(&self) -> String {
+        "nothing".to_string()
+    }
+}
+
+impl Describe for String {
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[tagged_dispatch(Describe, inline_small)]
+enum Metric {
+    Count(u32),
+    Flag(bool),
+    Missing(()),
+    Label(String),
+}
+
+#[test]
+fn test_small_variants_inline() {
+    let metrics = [Metric::count(3), Metric::flag(true), Metric::missing(()), Metric::label("cpu".to_string())];
+    let described: Vec<_> = metrics.iter().map(Metric::describe).collect();
+    assert_eq!(described, ["count 3", "flag true", "nothing", "\"cpu\""]);
+
+    // Handles compare by address, so equal inline values compare equal
+    // while equal boxed values don't
+    assert_eq!(Metric::count(3), Metric::count(3));
+    assert_ne!(Metric::count(3), Metric::count(4));
+    assert_ne!(Metric::label("cpu".to_string()), Metric::label("cpu".to_string()));
+
+    assert_eq!(u32::try_from(Metric::count(9)).unwrap(), 9);
+    assert_eq!(std::mem::size_of::<Option<Metric>>(), 8);
+}
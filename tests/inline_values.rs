@@ -0,0 +1,183 @@
+use tagged_dispatch::{tagged_dispatch, InlineValue};
+
+#[tagged_dispatch]
+trait Value {
+    fn truthy(&self) -> bool;
+    fn describe(&self) -> String;
+    fn into_number(self) -> f64;
+}
+
+#[tagged_dispatch]
+trait Negate {
+    fn negate(&mut self);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Nil;
+
+// SAFETY: zero-sized
+unsafe impl InlineValue for Nil {}
+
+impl Value for Nil {
+    fn truthy(&self) -> bool {
+        false
+    }
+
+    fn describe(&self) -> String {
+        "nil".to_string()
+    }
+
+    fn into_number(self) -> f64 {
+        0.0
+    }
+}
+
+impl Value for bool {
+    fn truthy(&self) -> bool {
+        *self
+    }
+
+    fn describe(&self) -> String {
+        self.to_string()
+    }
+
+    fn into_number(self) -> f64 {
+        self as u8 as f64
+    }
+}
+
+impl Value for i32 {
+    fn truthy(&self) -> bool {
+        *self != 0
+    }
+
+    fn describe(&self) -> String {
+        format!("int {}", self)
+    }
+
+    fn into_number(self) -> f64 {
+        self as f64
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Text(String);
+
+impl Value for Text {
+    fn truthy(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self.0)
+    }
+
+    fn into_number(self) -> f64 {
+        self.0.parse().unwrap_or(f64::NAN)
+    }
+}
+
+impl Negate for Nil {
+    fn negate(&mut self) {}
+}
+
+impl Negate for bool {
+    fn negate(&mut self) {
+        *self = !*self;
+    }
+}
+
+impl Negate for i32 {
+    fn negate(&mut self) {
+        *self = -*self;
+    }
+}
+
+impl Negate for Text {
+    fn negate(&mut self) {
+        self.0.insert(0, '-');
+    }
+}
+
+#[tagged_dispatch(Value, Negate)]
+enum Dynamic {
+    #[inline_value]
+    Nil,
+    #[inline_value]
+    Bool(bool),
+    #[inline_value]
+    Int(i32),
+    Text,
+}
+
+// Inline values must fit below the tag and implement `InlineValue`:
+// #[tagged_dispatch(Value)]
+// enum TooBig { #[inline_value] Wide(u64), Text }
+
+#[test]
+fn test_inline_dispatch() {
+    let values = [
+        Dynamic::nil(Nil),
+        Dynamic::bool(true),
+        Dynamic::bool(false),
+        Dynamic::int(-7),
+        Dynamic::text(Text("hi".to_string())),
+    ];
+    let descriptions: Vec<_> = values.iter().map(Dynamic::describe).collect();
+    assert_eq!(descriptions, ["nil", "true", "false", "int -7", "\"hi\""]);
+    assert_eq!(values.iter().filter(|value| value.truthy()).count(), 3);
+    assert_eq!(values[3].tag_type(), DynamicType::Int);
+}
+
+#[test]
+fn test_inline_values_round_trip() {
+    let int = Dynamic::int(i32::MIN);
+    let copy = int.clone();
+    assert_eq!(copy, int);
+    assert_eq!(copy.into_number(), i32::MIN as f64);
+    assert_eq!(i32::try_from(int).unwrap(), i32::MIN);
+
+    // A zero-sized value with tag 0 still isn't mistaken for `None`
+    let nil = Dynamic::nil(Nil);
+    assert_eq!(std::mem::size_of::<Option<Dynamic>>(), 8);
+    assert!(Some(nil.clone()).is_some());
+    assert_eq!(Nil::try_from(nil.clone()).unwrap(), Nil);
+    assert!(bool::try_from(nil).is_err());
+
+    let text = Dynamic::text(Text("2.5".to_string()));
+    assert_eq!(text.clone().into_number(), 2.5);
+    assert_eq!(<&Text>::try_from(&text).unwrap(), &Text("2.5".to_string()));
+}
+
+#[test]
+fn test_inline_values_mutate_in_place() {
+    let mut values = [Dynamic::nil(Nil), Dynamic::bool(true), Dynamic::int(i32::MAX), Dynamic::text(Text("1".to_string()))];
+    for value in &mut values {
+        value.negate();
+    }
+    let descriptions: Vec<_> = values.iter().map(Dynamic::describe).collect();
+    assert_eq!(descriptions, ["nil", "false", "int -2147483647", "\"-1\""]);
+    assert_eq!(values[2].tag_type(), DynamicType::Int);
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Value)]
+    enum Constant<'a> {
+        #[inline_value]
+        Bool(bool),
+        Text,
+    }
+
+    #[test]
+    fn test_inline_arena_values() {
+        let builder = ConstantArenaBuilder::new();
+        let values = [builder.bool(true), builder.text(Text(String::new())), Constant::view_bool(&false)];
+        assert_eq!(values.map(|value| value.truthy()), [true, false, false]);
+        assert_eq!(values.map(|value| value.describe()), ["true", "\"\"", "false"]);
+        assert_eq!(values[0].into_number(), 1.0);
+        assert!(bool::try_from(values[0]).unwrap());
+    }
+}
@@ -0,0 +1,107 @@
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+
+use tagged_dispatch::{tagged_dispatch, TaggedPtr};
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+#[derive(Clone)]
+struct Dot;
+
+impl Area for Dot {
+    fn area(&self) -> f32 {
+        0.0
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape {
+    Square,
+    Dot,
+}
+
+#[tagged_dispatch(Area, invalid_tag = "unchecked")]
+enum FastShape {
+    Square,
+    Dot,
+}
+
+fn report_tag(enum_name: &'static str, tag: usize) -> ! {
+    panic!("hook saw tag {} in {}", tag, enum_name)
+}
+
+#[tagged_dispatch(Area, invalid_tag = report_tag)]
+enum HookedShape {
+    Square,
+    Dot,
+}
+
+static SQUARE: Square = Square { side: 2.0 };
+
+// Simulates a corrupted handle: a tag past the last variant
+fn corrupt_ptr() -> TaggedPtr<()> {
+    TaggedPtr::new(&SQUARE as *const Square as *mut (), 5)
+}
+
+#[test]
+fn test_valid_tags_dispatch_under_every_policy() {
+    assert_eq!(Shape::square(Square { side: 2.0 }).area(), 4.0);
+    assert_eq!(FastShape::square(Square { side: 3.0 }).area(), 9.0);
+    assert_eq!(FastShape::dot(Dot).tag_type(), FastShapeType::Dot);
+    assert_eq!(HookedShape::dot(Dot).area(), 0.0);
+    assert_eq!(TabledShape::square(Square { side: 1.0 }).area(), 1.0);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "invalid tag 5 in a `Shape` handle")]
+fn test_default_policy_checks_tag_type_in_debug() {
+    let shape = ManuallyDrop::new(Shape(corrupt_ptr(), PhantomData));
+    shape.tag_type();
+}
+
+#[test]
+#[should_panic(expected = "invalid tag 5 in a `Shape` handle")]
+fn test_default_policy_panics_on_clone() {
+    let shape = ManuallyDrop::new(Shape(corrupt_ptr(), PhantomData));
+    let _ = (*shape).clone();
+}
+
+#[test]
+#[should_panic(expected = "hook saw tag 5 in HookedShape")]
+fn test_hook_checks_tag_type() {
+    let shape = ManuallyDrop::new(HookedShape(corrupt_ptr(), PhantomData));
+    shape.area();
+}
+
+#[test]
+#[should_panic(expected = "hook saw tag 5 in HookedShape")]
+fn test_hook_runs_on_drop() {
+    drop(HookedShape(corrupt_ptr(), PhantomData));
+}
+
+#[tagged_dispatch(Area, drop_table, invalid_tag = report_tag)]
+enum TabledShape {
+    Square,
+    Dot,
+}
+
+#[test]
+#[should_panic(expected = "hook saw tag 5 in TabledShape")]
+fn test_hook_runs_on_drop_table_drop() {
+    drop(TabledShape(corrupt_ptr(), PhantomData));
+}
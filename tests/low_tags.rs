@@ -0,0 +1,129 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Instr {
+    fn cost(&self) -> u32;
+}
+
+#[derive(Clone)]
+struct Load {
+    slot: u32,
+}
+
+impl Instr for Load {
+    fn cost(&self) -> u32 {
+        self.slot + 1
+    }
+}
+
+#[derive(Clone)]
+struct Store {
+    slot: u32,
+    drops: Rc<Cell<u32>>,
+}
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+impl Instr for Store {
+    fn cost(&self) -> u32 {
+        self.slot + 2
+    }
+}
+
+#[derive(Clone)]
+struct Halt;
+
+impl Instr for Halt {
+    fn cost(&self) -> u32 {
+        0
+    }
+}
+
+// Three variants need a 2-bit tag, so every variant type needs 4-byte alignment
+#[derive(Clone)]
+#[repr(align(4))]
+struct Jump;
+
+impl Instr for Jump {
+    fn cost(&self) -> u32 {
+        3
+    }
+}
+
+#[tagged_dispatch(Instr, tag = "low")]
+enum Op {
+    Load,
+    Store,
+    Jump,
+}
+
+// `tag = "high"` is the default layout
+#[tagged_dispatch(Instr, tag = "high")]
+enum HighOp {
+    Load,
+    Halt,
+}
+
+#[test]
+fn test_low_tag_dispatch() {
+    let drops = Rc::new(Cell::new(0));
+    let ops = [
+        Op::load(Load { slot: 1 }),
+        Op::store(Store { slot: 2, drops: drops.clone() }),
+        Op::jump(Jump),
+    ];
+
+    assert_eq!(ops.iter().map(|op| op.cost()).sum::<u32>(), 9);
+    assert_eq!(ops[1].tag_type(), OpType::Store);
+    assert_eq!(ops[2].tag_type(), OpType::Jump);
+    assert_eq!(std::mem::size_of::<Op>(), std::mem::size_of::<*const ()>());
+
+    let cloned = ops.clone();
+    assert!(ops.iter().zip(&cloned).all(|(op, clone)| op.tag_type() == clone.tag_type()));
+    drop(cloned);
+    drop(ops);
+    assert_eq!(drops.get(), 2);
+}
+
+#[test]
+fn test_low_tag_conversions() {
+    let op = Op::load(Load { slot: 4 });
+    let load: &Load = (&op).try_into().unwrap();
+    assert_eq!(load.slot, 4);
+
+    let load: Load = op.try_into().ok().unwrap();
+    assert_eq!(load.cost(), 5);
+}
+
+#[test]
+fn test_high_tag_is_default() {
+    assert_eq!(HighOp::halt(Halt).cost(), 0);
+    assert_eq!(HighOp::load(Load { slot: 0 }).tag_type(), HighOpType::Load);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Instr, tag = "low")]
+    enum ArenaOp<'a> {
+        Load,
+        Jump,
+    }
+
+    #[test]
+    fn test_arena_low_tags() {
+        let builder = ArenaOp::arena_builder();
+        let ops = [builder.load(Load { slot: 2 }), builder.jump(Jump), builder.load(Load { slot: 5 })];
+
+        assert_eq!(ops.iter().map(|op| op.cost()).sum::<u32>(), 12);
+        assert_eq!(ops[1].tag_type(), ArenaOpType::Jump);
+    }
+}
@@ -0,0 +1,120 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch(map_errors)]
+trait Parse {
+    type Error: std::fmt::Debug;
+
+    fn parse(&self, input: &str) -> Result<u32, Self::Error>;
+    fn name(&self) -> &'static str;
+}
+
+#[derive(Debug, PartialEq)]
+enum DigitError {
+    Empty,
+    NotADigit(char),
+}
+
+#[derive(Debug, PartialEq)]
+struct TooLong(usize);
+
+#[derive(Clone)]
+struct Digit;
+
+impl Parse for Digit {
+    type Error = DigitError;
+
+    fn parse(&self, input: &str) -> Result<u32, DigitError> {
+        let c = input.chars().next().ok_or(DigitError::Empty)?;
+        c.to_digit(10).ok_or(DigitError::NotADigit(c))
+    }
+
+    fn name(&self) -> &'static str {
+        "digit"
+    }
+}
+
+#[derive(Clone)]
+struct Length {
+    max: usize,
+}
+
+impl Parse for Length {
+    type Error = TooLong;
+
+    fn parse(&self, input: &str) -> Result<u32, TooLong> {
+        if input.len() > self.max {
+            return Err(TooLong(input.len()));
+        }
+        Ok(input.len() as u32)
+    }
+
+    fn name(&self) -> &'static str {
+        "length"
+    }
+}
+
+#[tagged_dispatch(Parse)]
+enum Parser {
+    Digit,
+    Length,
+}
+
+#[test]
+fn test_ok_passes_through() {
+    assert_eq!(Parser::digit(Digit).parse("7").unwrap(), 7);
+    assert_eq!(Parser::length(Length { max: 4 }).parse("abc").unwrap(), 3);
+}
+
+#[test]
+fn test_errors_are_wrapped_per_variant() {
+    let digit = Parser::digit(Digit);
+    match digit.parse("x") {
+        Err(ParserError::Digit(err)) => assert_eq!(err, DigitError::NotADigit('x')),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let err = Parser::length(Length { max: 2 }).parse("abcd").unwrap_err();
+    assert_eq!(err.tag_type(), ParserType::Length);
+    assert!(matches!(err, ParserError::Length(TooLong(4))));
+}
+
+#[test]
+fn test_other_methods_unchanged() {
+    assert_eq!(Parser::digit(Digit).name(), "digit");
+}
+
+#[test]
+fn test_question_mark_into_enum_error() {
+    fn total(parsers: &[Parser], input: &str) -> Result<u32, ParserError> {
+        let mut total = 0;
+        for parser in parsers {
+            total += parser.parse(input)?;
+        }
+        Ok(total)
+    }
+
+    let parsers = [Parser::digit(Digit), Parser::length(Length { max: 8 })];
+    assert_eq!(total(&parsers, "5").unwrap(), 6);
+    assert!(matches!(total(&parsers, ""), Err(ParserError::Digit(DigitError::Empty))));
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Parse)]
+    enum ArenaParser<'a> {
+        Digit,
+        Length,
+    }
+
+    #[test]
+    fn test_arena_errors_are_wrapped() {
+        let builder = ArenaParser::arena_builder();
+        let parser = builder.length(Length { max: 1 });
+        assert_eq!(parser.name(), "length");
+
+        assert_eq!(parser.parse("a").unwrap(), 1);
+        assert!(matches!(parser.parse("ab"), Err(ArenaParserError::Length(TooLong(2)))));
+    }
+}
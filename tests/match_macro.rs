@@ -0,0 +1,97 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone)]
+struct Rectangle {
+    width: f32,
+    height: f32,
+}
+
+impl Area for Rectangle {
+    fn area(&self) -> f32 {
+        self.width * self.height
+    }
+}
+
+impl Area for u32 {
+    fn area(&self) -> f32 {
+        0.0
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape {
+    Circle,
+    Rectangle,
+    #[inline_value]
+    Marker(u32),
+}
+
+fn width(shape: &Shape) -> f32 {
+    match_shape!(shape, {
+        Circle(c) => c.radius * 2.0,
+        Rectangle(r) => r.width,
+        Marker(_) => 0.0,
+    })
+}
+
+#[test]
+fn test_match_every_variant() {
+    assert_eq!(width(&Shape::circle(Circle { radius: 1.5 })), 3.0);
+    assert_eq!(width(&Shape::rectangle(Rectangle { width: 4.0, height: 2.0 })), 4.0);
+    assert_eq!(width(&Shape::marker(7)), 0.0);
+}
+
+#[test]
+fn test_match_with_default() {
+    let shape = Shape::marker(7);
+    let marker = match_shape!(shape, {
+        Marker(id) => *id,
+        _ => 0,
+    });
+    assert_eq!(marker, 7);
+
+    // The handle is only borrowed, so it is still usable afterwards
+    let shape = Shape::rectangle(Rectangle { width: 4.0, height: 2.0 });
+    let height = match_shape!(shape, { Rectangle(Rectangle { height, .. }) => *height, _ => 0.0 });
+    assert_eq!(height, 2.0);
+    assert_eq!(shape.area(), 8.0);
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Area)]
+    enum Sketch<'a> {
+        Circle,
+        Rectangle,
+    }
+
+    #[test]
+    fn test_arena_match() {
+        let builder = SketchArenaBuilder::new();
+        let sketch = builder.circle(Circle { radius: 2.0 });
+        let radius = match_sketch!(sketch, {
+            Circle(c) => c.radius,
+            Rectangle(r) => r.width.min(r.height),
+        });
+        assert_eq!(radius, 2.0);
+        assert_eq!(sketch.area(), 12.0);
+    }
+}
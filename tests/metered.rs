@@ -0,0 +1,186 @@
+use tagged_dispatch::metered::Usage;
+use tagged_dispatch::{tagged_dispatch, BoxAllocator, Meter, Metered, TaggedAllocator};
+
+#[tagged_dispatch]
+trait Mass {
+    fn mass(&self) -> u64;
+    fn into_mass(self) -> u64;
+}
+
+#[derive(Clone)]
+struct Pebble(u8);
+
+impl Mass for Pebble {
+    fn mass(&self) -> u64 {
+        self.0 as u64
+    }
+
+    fn into_mass(self) -> u64 {
+        self.0 as u64
+    }
+}
+
+#[derive(Clone)]
+struct Boulder([u64; 4]);
+
+impl Mass for Boulder {
+    fn mass(&self) -> u64 {
+        self.0.iter().sum()
+    }
+
+    fn into_mass(self) -> u64 {
+        self.mass()
+    }
+}
+
+// The global meter is shared by all tests, so each owned enum meters its own types
+#[derive(Clone)]
+struct Ore([u64; 2]);
+
+impl Mass for Ore {
+    fn mass(&self) -> u64 {
+        self.0.iter().sum()
+    }
+
+    fn into_mass(self) -> u64 {
+        self.mass()
+    }
+}
+
+#[tagged_dispatch(Mass)]
+enum Haul {
+    #[alloc_with(Metered<BoxAllocator>)]
+    Ore,
+    Pebble,
+}
+
+#[test]
+fn test_owned_variants_record_in_global_meter() {
+    let ore = Haul::ore(Ore([2, 3]));
+    let pebble = Haul::pebble(Pebble(1));
+
+    assert_eq!(
+        Meter::global().usage::<Ore>(),
+        Usage { allocations: 1, frees: 0, bytes: 16, live_bytes: 16 }
+    );
+    // Variants without `alloc_with` are not metered
+    assert_eq!(Meter::global().usage::<Pebble>(), Usage::default());
+
+    assert_eq!(ore.into_mass(), 5);
+    assert_eq!(pebble.mass(), 1);
+    drop(pebble);
+
+    let usage = Meter::global().usage::<Ore>();
+    assert_eq!((usage.frees, usage.live_bytes), (1, 0));
+}
+
+#[derive(Clone)]
+struct Slag(u32);
+
+impl Mass for Slag {
+    fn mass(&self) -> u64 {
+        self.0 as u64
+    }
+
+    fn into_mass(self) -> u64 {
+        self.0 as u64
+    }
+}
+
+#[tagged_dispatch(Mass)]
+enum Waste {
+    #[alloc_with(Metered<BoxAllocator>)]
+    Slag,
+}
+
+#[test]
+#[should_panic(expected = "allocation budget of 8 bytes exceeded")]
+fn test_owned_budget_panics() {
+    Meter::global().set_budget::<Slag>(8);
+
+    let first = Waste::slag(Slag(1));
+    assert_eq!(first.mass(), 1);
+    // Freed values no longer count against the budget
+    assert_eq!(Waste::slag(Slag(4)).into_mass(), 4);
+    let _second = Waste::slag(Slag(2));
+    let _third = Waste::slag(Slag(3));
+}
+
+#[test]
+fn test_budget_counts_live_bytes() {
+    let meter = Meter::new();
+    meter.set_budget::<u32>(8);
+
+    meter.record_alloc::<u32>();
+    meter.record_alloc::<u32>();
+    meter.record_free::<u32>();
+    meter.record_alloc::<u32>();
+
+    assert_eq!(meter.usage::<u32>().allocations, 3);
+    assert_eq!(meter.usage::<u32>().live_bytes, 8);
+
+    meter.clear_budget::<u32>();
+    meter.record_alloc::<u32>();
+    assert_eq!(meter.usage::<u32>().live_bytes, 12);
+}
+
+#[test]
+fn test_tagged_allocator_decorator() {
+    let metered = Metered::new(BoxAllocator);
+
+    let pebble = metered.alloc(Pebble(7));
+    let boulder = metered.alloc(Boulder([1; 4]));
+
+    assert_eq!(metered.meter().usage::<Pebble>().bytes, 1);
+    assert_eq!(metered.meter().usage::<Boulder>().bytes, 32);
+    assert_eq!(metered.meter().total().allocations, 2);
+
+    metered.meter().reset();
+    assert_eq!(metered.meter().total(), Usage::default());
+
+    unsafe {
+        drop(Box::from_raw(pebble));
+        drop(Box::from_raw(boulder));
+    }
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+    use tagged_dispatch::bumpalo::Bump;
+
+    #[tagged_dispatch(Mass)]
+    enum Pile<'a> {
+        Pebble,
+        Boulder,
+    }
+
+    #[test]
+    fn test_metered_builder() {
+        let arena = Metered::new(Bump::new());
+        let builder = PileArenaBuilder::with_metered_bumpalo(&arena);
+
+        let pile = [
+            builder.pebble(Pebble(1)),
+            builder.pebble(Pebble(2)),
+            builder.boulder(Boulder([3; 4])),
+        ];
+        assert_eq!(pile.iter().map(|item| item.mass()).sum::<u64>(), 15);
+        assert_eq!(pile[2].into_mass(), 12);
+
+        assert_eq!(arena.meter().usage::<Pebble>().allocations, 2);
+        assert_eq!(arena.meter().usage::<Boulder>().live_bytes, 32);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeded for")]
+    fn test_metered_builder_budget() {
+        let arena = Metered::new(Bump::new());
+        arena.meter().set_budget::<Boulder>(64);
+        let builder = PileArenaBuilder::with_metered_bumpalo(&arena);
+
+        for _ in 0..3 {
+            builder.boulder(Boulder([0; 4]));
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Render {
+    fn name(&self) -> String;
+    fn draw(&self) -> String;
+}
+
+#[tagged_dispatch]
+trait Audit {
+    fn name(&self) -> String;
+    fn label() -> &'static str;
+}
+
+#[derive(Clone)]
+struct Button;
+
+impl Render for Button {
+    fn name(&self) -> String {
+        "button".to_string()
+    }
+
+    fn draw(&self) -> String {
+        "[ ok ]".to_string()
+    }
+}
+
+impl Audit for Button {
+    fn name(&self) -> String {
+        "audit:button".to_string()
+    }
+
+    fn label() -> &'static str {
+        "Button"
+    }
+}
+
+#[derive(Clone)]
+struct Slider;
+
+impl Render for Slider {
+    fn name(&self) -> String {
+        "slider".to_string()
+    }
+
+    fn draw(&self) -> String {
+        "--o--".to_string()
+    }
+}
+
+impl Audit for Slider {
+    fn name(&self) -> String {
+        "audit:slider".to_string()
+    }
+
+    fn label() -> &'static str {
+        "Slider"
+    }
+}
+
+// `Render` keeps the plain method names, `Audit` gets `_as_audit` suffixes
+#[tagged_dispatch(Render, qualified(Audit))]
+enum Widget {
+    Button,
+    Slider,
+}
+
+// Both traits qualified
+#[tagged_dispatch(qualified(Render), qualified(Audit))]
+enum Control {
+    Button,
+    Slider,
+}
+
+#[test]
+fn test_qualified_trait_methods() {
+    let widget = Widget::slider(Slider);
+
+    assert_eq!(widget.name(), "slider");
+    assert_eq!(widget.draw(), "--o--");
+    assert_eq!(widget.name_as_audit(), "audit:slider");
+    assert_eq!(widget.label_as_audit_for_variant(), "Slider");
+}
+
+#[test]
+fn test_all_traits_qualified() {
+    let control = Control::button(Button);
+
+    assert_eq!(control.name_as_render(), "button");
+    assert_eq!(control.draw_as_render(), "[ ok ]");
+    assert_eq!(control.name_as_audit(), "audit:button");
+}
@@ -0,0 +1,105 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Label {
+    fn longest<'b>(&'b self, other: &'b str) -> &'b str where Self: Sized;
+    fn render_with<F>(&self, render: F) -> String
+    where
+        F: Fn(&str) -> String;
+    fn rename<S>(&mut self, name: S)
+    where
+        S: Into<String>;
+}
+
+#[derive(Clone)]
+struct Name(String);
+
+#[derive(Clone)]
+struct Code(u32, String);
+
+impl Label for Name {
+    fn longest<'b>(&'b self, other: &'b str) -> &'b str where Self: Sized {
+        if self.0.len() >= other.len() { &self.0 } else { other }
+    }
+
+    fn render_with<F>(&self, render: F) -> String
+    where
+        F: Fn(&str) -> String,
+    {
+        render(&self.0)
+    }
+
+    fn rename<S>(&mut self, name: S)
+    where
+        S: Into<String>,
+    {
+        self.0 = name.into();
+    }
+}
+
+impl Label for Code {
+    fn longest<'b>(&'b self, other: &'b str) -> &'b str where Self: Sized {
+        if self.1.len() >= other.len() { &self.1 } else { other }
+    }
+
+    fn render_with<F>(&self, render: F) -> String
+    where
+        F: Fn(&str) -> String,
+    {
+        render(&format!("#{}", self.0))
+    }
+
+    fn rename<S>(&mut self, name: S)
+    where
+        S: Into<String>,
+    {
+        self.1 = name.into();
+    }
+}
+
+#[tagged_dispatch(Label)]
+enum Tag {
+    Name,
+    Code,
+}
+
+#[test]
+fn test_method_generics_and_lifetimes() {
+    let other = String::from("medium");
+    let mut tags = [
+        Tag::name(Name("long enough name".into())),
+        Tag::code(Code(7, "ab".into())),
+    ];
+
+    // The returned borrow may come from either the handle or the argument
+    assert_eq!(tags[0].longest(&other), "long enough name");
+    assert_eq!(tags[1].longest(&other), "medium");
+
+    assert_eq!(tags[0].render_with(str::to_uppercase), "LONG ENOUGH NAME");
+    assert_eq!(tags[1].render_with(|text| format!("[{text}]")), "[#7]");
+
+    tags[0].rename("n");
+    tags[1].rename(String::from("renamed"));
+    assert_eq!(tags[0].longest(&other), "medium");
+    assert_eq!(tags[1].longest(&other), "renamed");
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Label)]
+    enum TagRef<'a> {
+        Name,
+        Code,
+    }
+
+    #[test]
+    fn test_method_generics_and_lifetimes_arena() {
+        let builder = TagRefArenaBuilder::new();
+        let tag = builder.code(Code(42, "answer".into()));
+        let other = String::from("short");
+        assert_eq!(tag.longest(&other), "answer");
+        assert_eq!(tag.render_with(|text| text.repeat(2)), "#42#42");
+    }
+}
@@ -162,6 +162,8 @@ fn test_multiple_traits_dispatch() {
 #[test]
 fn test_enum_size_with_multiple_traits() {
     assert_eq!(std::mem::size_of::<Shape>(), 8);
+    // The handle is never all zero bits, so `None` fits in the same word
+    assert_eq!(std::mem::size_of::<Option<Shape>>(), 8);
 }
 
 #[cfg(feature = "allocator-bumpalo")]
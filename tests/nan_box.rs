@@ -0,0 +1,184 @@
+use tagged_dispatch::{tagged_dispatch, NanBoxedPtr, TagError};
+
+#[tagged_dispatch]
+trait Value {
+    fn truthy(&self) -> bool;
+    fn describe(&self) -> String;
+    fn into_number(self) -> f64;
+}
+
+#[tagged_dispatch]
+trait Scale {
+    fn scale(&mut self, by: f64);
+}
+
+impl Value for f64 {
+    fn truthy(&self) -> bool {
+        *self != 0.0 && !self.is_nan()
+    }
+
+    fn describe(&self) -> String {
+        format!("number {}", self)
+    }
+
+    fn into_number(self) -> f64 {
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Text(String);
+
+impl Value for Text {
+    fn truthy(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self.0)
+    }
+
+    fn into_number(self) -> f64 {
+        self.0.parse().unwrap_or(f64::NAN)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct List(Vec<f64>);
+
+impl Value for List {
+    fn truthy(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    fn describe(&self) -> String {
+        format!("list of {}", self.0.len())
+    }
+
+    fn into_number(self) -> f64 {
+        self.0.iter().sum()
+    }
+}
+
+impl Scale for f64 {
+    fn scale(&mut self, by: f64) {
+        *self *= by;
+    }
+}
+
+impl Scale for Text {
+    fn scale(&mut self, by: f64) {
+        self.0 = self.0.repeat(by as usize);
+    }
+}
+
+impl Scale for List {
+    fn scale(&mut self, by: f64) {
+        self.0.iter_mut().for_each(|item| *item *= by);
+    }
+}
+
+// The number variant doesn't have to come first
+#[tagged_dispatch(Value, Scale, nan_box)]
+enum Dynamic {
+    Text,
+    Number(f64),
+    List,
+}
+
+#[test]
+fn test_nan_box_dispatch() {
+    let values = [
+        Dynamic::number(1.5),
+        Dynamic::number(-0.0),
+        Dynamic::text(Text("hi".to_string())),
+        Dynamic::list(List(vec![1.0, 2.0])),
+    ];
+    let descriptions: Vec<_> = values.iter().map(Dynamic::describe).collect();
+    assert_eq!(descriptions, ["number 1.5", "number -0", "\"hi\"", "list of 2"]);
+    assert_eq!(values.iter().filter(|value| value.truthy()).count(), 3);
+    assert_eq!(values[0].tag_type(), DynamicType::Number);
+    assert_eq!(values[3].tag_type(), DynamicType::List);
+    assert_eq!(std::mem::size_of::<Dynamic>(), 8);
+}
+
+#[test]
+fn test_nan_box_special_numbers() {
+    for number in [0.0, f64::INFINITY, f64::NEG_INFINITY, f64::MIN_POSITIVE, f64::MAX, -1e-300] {
+        let value = Dynamic::number(number);
+        assert_eq!(value.tag_type(), DynamicType::Number);
+        assert_eq!(f64::try_from(value).unwrap(), number);
+    }
+
+    // Every NaN, payload and sign included, is stored as the canonical quiet NaN
+    let negative_nan = f64::from_bits(0xFFFF_0000_1234_5678);
+    let value = Dynamic::number(negative_nan);
+    assert_eq!(value.tag_type(), DynamicType::Number);
+    assert!(value.clone().into_number().is_nan());
+    assert_eq!(value, Dynamic::number(f64::NAN));
+}
+
+#[test]
+fn test_nan_boxed_ptr_non_canonical_nan() {
+    // A NaN with the boxed prefix handed straight to `new` must still read back as a number
+    type Boxed = NanBoxedPtr<u64, 0>;
+    let bits = 0xFFF9_0000_0000_1000u64;
+    let ptr = Boxed::new(bits as usize as *mut u64, 0);
+    assert!(!ptr.is_boxed());
+    assert_eq!(ptr.tag(), 0);
+    assert!(ptr.number().unwrap().is_nan());
+
+    let err = Boxed::try_new(bits as usize as *mut u64, 0).unwrap_err();
+    assert_eq!(err, TagError::NonCanonicalNan { bits });
+    assert!(Boxed::try_new(Boxed::number_bits(f64::NAN), 0).is_ok());
+}
+
+#[test]
+fn test_nan_box_mutation() {
+    let mut values = [Dynamic::number(1.5), Dynamic::text(Text("ab".to_string())), Dynamic::list(List(vec![1.0]))];
+    for value in &mut values {
+        value.scale(2.0);
+    }
+    let descriptions: Vec<_> = values.iter().map(Dynamic::describe).collect();
+    assert_eq!(descriptions, ["number 3", "\"abab\"", "list of 1"]);
+
+    // A NaN written through `&mut f64` is stored canonically too
+    let mut number = Dynamic::number(1.0);
+    number.scale(f64::from_bits(0xFFFF_0000_1234_5678));
+    assert_eq!(number.tag_type(), DynamicType::Number);
+    assert_eq!(number, Dynamic::number(f64::NAN));
+}
+
+#[test]
+fn test_nan_box_conversions() {
+    let text = Dynamic::text(Text("2.5".to_string()));
+    assert_eq!(<&Text>::try_from(&text).unwrap(), &Text("2.5".to_string()));
+    assert!(f64::try_from(text.clone()).is_err());
+    assert_eq!(text.into_number(), 2.5);
+
+    let list = Dynamic::list(List(vec![3.0, 4.0]));
+    assert_eq!(list.clone().into_number(), 7.0);
+    assert_eq!(List::try_from(list).unwrap(), List(vec![3.0, 4.0]));
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Value, nan_box)]
+    enum Constant<'a> {
+        Number(f64),
+        Text,
+    }
+
+    #[test]
+    fn test_nan_box_arena() {
+        let builder = ConstantArenaBuilder::new();
+        let values = [builder.number(4.0), builder.text(Text("x".to_string())), Constant::view_number(&f64::NAN)];
+        assert_eq!(values.each_ref().map(|value| value.truthy()), [true, true, false]);
+        assert_eq!(values[1].describe(), "\"x\"");
+        assert_eq!(values[0].into_number(), 4.0);
+        assert_eq!(f64::try_from(values[0]).unwrap(), 4.0);
+        assert_eq!(values[1].tag_type(), ConstantType::Text);
+    }
+}
@@ -0,0 +1,61 @@
+use std::io::{Cursor, Read};
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Source {
+    fn read_all(&mut self) -> String;
+    fn name(&self) -> &'static str;
+}
+
+// Neither variant can be cloned, like a file or socket handle
+struct Memory {
+    data: Cursor<Vec<u8>>,
+}
+
+impl Source for Memory {
+    fn read_all(&mut self) -> String {
+        let mut out = String::new();
+        self.data.read_to_string(&mut out).unwrap();
+        out
+    }
+
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+}
+
+struct Pipe {
+    chunks: Vec<&'static str>,
+}
+
+impl Source for Pipe {
+    fn read_all(&mut self) -> String {
+        self.chunks.drain(..).collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "pipe"
+    }
+}
+
+#[tagged_dispatch(Source, no_clone)]
+enum Input {
+    Memory,
+    Pipe,
+}
+
+#[test]
+fn test_non_clone_variants() {
+    let mut inputs = vec![
+        Input::memory(Memory { data: Cursor::new(b"abc".to_vec()) }),
+        Input::pipe(Pipe { chunks: vec!["d", "ef"] }),
+    ];
+    let read: Vec<_> = inputs.iter_mut().map(Input::read_all).collect();
+    assert_eq!(read, ["abc", "def"]);
+    assert_eq!(inputs[1].name(), "pipe");
+    assert_eq!(inputs[1].read_all(), "");
+
+    let pipe = Pipe::try_from(inputs.pop().unwrap()).ok().unwrap();
+    assert!(pipe.chunks.is_empty());
+}
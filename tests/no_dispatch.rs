@@ -181,4 +181,86 @@ fn test_mixed_dispatch() {
 
     assert_eq!(concrete_a.also_not_dispatched(), "default");
     assert_eq!(concrete_b.also_not_dispatched(), "default");
-}
\ No newline at end of file
+}
+
+#[macro_use]
+mod rules {
+    use tagged_dispatch::tagged_dispatch;
+
+    #[tagged_dispatch]
+    pub trait Validate {
+        fn check(&self, input: &str) -> bool;
+
+        #[no_dispatch]
+        fn validation_rules() -> &'static [&'static str];
+
+        fn max_len(scale: usize) -> usize {
+            scale * 8
+        }
+
+        #[no_dispatch]
+        fn create() -> Self where Self: Sized;
+    }
+}
+
+#[derive(Clone)]
+struct Email;
+
+impl rules::Validate for Email {
+    fn check(&self, input: &str) -> bool {
+        input.contains('@')
+    }
+
+    fn validation_rules() -> &'static [&'static str] {
+        &["contains @"]
+    }
+
+    fn create() -> Self {
+        Email
+    }
+}
+
+#[derive(Clone)]
+struct Zip;
+
+impl rules::Validate for Zip {
+    fn check(&self, input: &str) -> bool {
+        input.len() == 5
+    }
+
+    fn validation_rules() -> &'static [&'static str] {
+        &["five digits", "numeric"]
+    }
+
+    fn max_len(_scale: usize) -> usize {
+        5
+    }
+
+    fn create() -> Self {
+        Zip
+    }
+}
+
+#[tagged_dispatch(rules::Validate)]
+enum Field {
+    Email,
+    Zip,
+}
+
+#[test]
+fn test_static_lookup_for_variant() {
+    let email = Field::email(Email);
+    let zip = Field::zip(Zip);
+
+    assert!(email.check("a@b"));
+    assert_eq!(email.validation_rules_for_variant(), &["contains @"]);
+    assert_eq!(zip.validation_rules_for_variant().len(), 2);
+
+    // Defaulted statics and arguments are forwarded as well
+    assert_eq!(email.max_len_for_variant(2), 16);
+    assert_eq!(zip.max_len_for_variant(2), 5);
+
+    // Statics mentioning `Self` get no lookup but still work on the concrete type
+    let created = Field::zip(<Zip as rules::Validate>::create());
+    assert_eq!(created.tag_type(), FieldType::Zip);
+}
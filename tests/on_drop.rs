@@ -0,0 +1,82 @@
+use std::cell::RefCell;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Resource {
+    fn handle(&self) -> u32;
+    fn release(self) -> u32;
+}
+
+#[derive(Clone)]
+struct Texture {
+    id: u32,
+}
+
+impl Resource for Texture {
+    fn handle(&self) -> u32 {
+        self.id
+    }
+
+    fn release(self) -> u32 {
+        self.id
+    }
+}
+
+#[derive(Clone)]
+struct Buffer {
+    id: u32,
+}
+
+impl Resource for Buffer {
+    fn handle(&self) -> u32 {
+        self.id
+    }
+
+    fn release(self) -> u32 {
+        self.id
+    }
+}
+
+thread_local! {
+    static DROPPED: RefCell<Vec<(GpuResourceType, u32)>> = const { RefCell::new(Vec::new()) };
+}
+
+fn track_drop(tag: GpuResourceType, ptr: *const ()) {
+    let id = match tag {
+        GpuResourceType::Texture => unsafe { (*(ptr as *const Texture)).id },
+        GpuResourceType::Buffer => unsafe { (*(ptr as *const Buffer)).id },
+    };
+    DROPPED.with(|dropped| dropped.borrow_mut().push((tag, id)));
+}
+
+#[tagged_dispatch(Resource)]
+#[on_drop(track_drop)]
+enum GpuResource {
+    Texture,
+    Buffer,
+}
+
+fn take_dropped() -> Vec<(GpuResourceType, u32)> {
+    DROPPED.with(|dropped| dropped.borrow_mut().drain(..).collect())
+}
+
+#[test]
+fn test_on_drop_sees_value() {
+    let texture = GpuResource::texture(Texture { id: 7 });
+    let buffer = GpuResource::buffer(Buffer { id: 9 });
+    assert_eq!(texture.handle() + buffer.handle(), 16);
+
+    drop(buffer);
+    drop(texture);
+    assert_eq!(take_dropped(), vec![(GpuResourceType::Buffer, 9), (GpuResourceType::Texture, 7)]);
+}
+
+#[test]
+fn test_on_drop_skipped_when_value_moves_out() {
+    let texture = GpuResource::texture(Texture { id: 3 });
+
+    // The value lives on, so it hasn't been dropped by the handle
+    assert_eq!(texture.release(), 3);
+    assert!(take_dropped().is_empty());
+}
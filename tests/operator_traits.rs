@@ -0,0 +1,110 @@
+use std::ops;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[derive(Clone)]
+struct Constant {
+    value: f64,
+}
+
+impl ops::Neg for Constant {
+    type Output = f64;
+
+    fn neg(self) -> f64 {
+        -self.value
+    }
+}
+
+impl ops::Mul<f64> for Constant {
+    type Output = f64;
+
+    fn mul(self, rhs: f64) -> f64 {
+        self.value * rhs
+    }
+}
+
+#[derive(Clone)]
+struct Sum {
+    terms: Vec<f64>,
+}
+
+impl ops::Neg for Sum {
+    type Output = f64;
+
+    fn neg(self) -> f64 {
+        -self.terms.iter().sum::<f64>()
+    }
+}
+
+impl ops::Mul<f64> for Sum {
+    type Output = f64;
+
+    fn mul(self, rhs: f64) -> f64 {
+        self.terms.iter().sum::<f64>() * rhs
+    }
+}
+
+impl ops::Index<usize> for Constant {
+    type Output = f64;
+
+    fn index(&self, _index: usize) -> &f64 {
+        &self.value
+    }
+}
+
+impl ops::Index<usize> for Sum {
+    type Output = f64;
+
+    fn index(&self, index: usize) -> &f64 {
+        &self.terms[index]
+    }
+}
+
+#[tagged_dispatch(ops::Neg, ops::Mul<f64>, std::ops::Index<usize>)]
+enum Expr {
+    Constant,
+    Sum,
+}
+
+#[test]
+fn test_unary_operator() {
+    assert_eq!(-Expr::constant(Constant { value: 2.0 }), -2.0);
+    assert_eq!(-Expr::sum(Sum { terms: vec![1.0, 2.0] }), -3.0);
+}
+
+#[test]
+fn test_binary_operator() {
+    let sum = Expr::sum(Sum { terms: vec![1.0, 2.0] });
+
+    assert_eq!(sum * 2.0, 6.0);
+}
+
+#[test]
+fn test_index_operator() {
+    let constant = Expr::constant(Constant { value: 4.0 });
+    let sum = Expr::sum(Sum { terms: vec![1.0, 5.0] });
+
+    assert_eq!(constant[3], 4.0);
+    assert_eq!(sum[1], 5.0);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(ops::Neg, ops::Mul<f64>)]
+    enum ExprRef<'a> {
+        Constant,
+        Sum,
+    }
+
+    #[test]
+    fn test_arena_operators() {
+        let builder = ExprRef::arena_builder();
+        let sum = builder.sum(Sum { terms: vec![2.0, 3.0] });
+
+        // Handles are Copy, so the operators work on clones of the value
+        assert_eq!(-sum, -5.0);
+        assert_eq!(sum * 2.0, 10.0);
+    }
+}
@@ -0,0 +1,81 @@
+#![cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+impl Area for u32 {
+    fn area(&self) -> f32 {
+        *self as f32
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape {
+    Circle,
+    Square,
+    #[inline_value]
+    Tiles(u32),
+}
+
+// Constructor names may differ between the two; variants pair up by name
+#[tagged_dispatch(Area, owned = Shape, constructor_prefix = "new_")]
+enum FrameShape<'a> {
+    Circle,
+    Square,
+    #[inline_value]
+    Tiles(u32),
+}
+
+#[test]
+fn test_clone_into_arena() {
+    let builder = FrameShapeArenaBuilder::new();
+    let shapes = [Shape::circle(Circle { radius: 1.0 }), Shape::square(Square { side: 2.0 }), Shape::tiles(5)];
+
+    let framed: Vec<_> = shapes.iter().map(|shape| shape.clone_into_arena(&builder)).collect();
+    let areas: Vec<_> = framed.iter().map(FrameShape::area).collect();
+    assert_eq!(areas, [3.0, 4.0, 5.0]);
+    assert_eq!(framed[1].as_square(), Some(&Square { side: 2.0 }));
+    assert_eq!(framed[2].tag_type(), FrameShapeType::Tiles);
+}
+
+#[test]
+fn test_to_owned_outlives_arena() {
+    let owned = {
+        let builder = FrameShapeArenaBuilder::new();
+        let framed = builder.new_square(Square { side: 3.0 });
+        framed.to_owned()
+    };
+    assert_eq!(owned.tag_type(), ShapeType::Square);
+    assert_eq!(owned.as_square(), Some(&Square { side: 3.0 }));
+    assert_eq!(owned.area(), 9.0);
+
+    let builder = FrameShapeArenaBuilder::new();
+    let tiles = builder.new_tiles(7).to_owned();
+    assert_eq!(tiles.as_tiles(), Some(&7));
+}
@@ -0,0 +1,77 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+mod geometry {
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Circle {
+        pub radius: f32,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Rectangle {
+        pub width: f32,
+        pub height: f32,
+    }
+
+    impl super::Area for Circle {
+        fn area(&self) -> f32 {
+            3.0 * self.radius * self.radius
+        }
+    }
+
+    impl super::Area for Rectangle {
+        fn area(&self) -> f32 {
+            self.width * self.height
+        }
+    }
+}
+
+// Shorthand variants name their type in `#[variant_type]`; everything generated
+// for the variant is named after the variant, not the type
+#[tagged_dispatch(Area)]
+enum Shape {
+    #[variant_type = "geometry::Circle"]
+    Circle,
+    #[variant_type = "geometry::Rectangle"]
+    WideRectangle,
+}
+
+#[test]
+fn test_path_variant_types() {
+    let shapes = [
+        Shape::circle(geometry::Circle { radius: 1.0 }),
+        Shape::wide_rectangle(geometry::Rectangle { width: 4.0, height: 2.0 }),
+    ];
+    assert_eq!(shapes.each_ref().map(Shape::area), [3.0, 8.0]);
+    assert!(shapes[1].is_wide_rectangle());
+    assert_eq!(shapes[0].as_circle(), Some(&geometry::Circle { radius: 1.0 }));
+    assert_eq!(shapes[1].tag_type(), ShapeType::WideRectangle);
+
+    let circle: Shape = geometry::Circle { radius: 2.0 }.into();
+    assert_eq!(geometry::Circle::try_from(circle).unwrap().radius, 2.0);
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Area)]
+    enum Sketch<'a> {
+        #[variant_type = "geometry::Circle"]
+        Circle,
+        #[variant_type = "geometry::Rectangle"]
+        WideRectangle,
+    }
+
+    #[test]
+    fn test_path_variant_types_arena() {
+        let builder = SketchArenaBuilder::new();
+        let sketch = builder.wide_rectangle(geometry::Rectangle { width: 1.0, height: 5.0 });
+        assert_eq!(sketch.area(), 5.0);
+        assert!(sketch.is_wide_rectangle());
+    }
+}
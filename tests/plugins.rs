@@ -0,0 +1,200 @@
+use tagged_dispatch::plugin::{Abi, PluginRegistry, RegisterError, ABI_VERSION};
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Draw {
+    fn draw(&self) -> String;
+}
+
+#[tagged_dispatch]
+trait Scale {
+    fn scale(&mut self, factor: f32);
+    fn size(&self) -> f32;
+}
+
+#[derive(Clone)]
+struct Circle {
+    radius: f32,
+}
+
+impl Draw for Circle {
+    fn draw(&self) -> String {
+        format!("circle {}", self.radius)
+    }
+}
+
+impl Scale for Circle {
+    fn scale(&mut self, factor: f32) {
+        self.radius *= factor;
+    }
+
+    fn size(&self) -> f32 {
+        self.radius
+    }
+}
+
+#[tagged_dispatch(Draw, Scale, open)]
+enum Shape {
+    Circle,
+}
+
+// Defined where `Shape` was never told about it, as a plugin would be
+mod hexagon {
+    use super::{Draw, Scale};
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Hexagon {
+        pub side: f32,
+    }
+
+    impl Draw for Hexagon {
+        fn draw(&self) -> String {
+            format!("hexagon {}", self.side)
+        }
+    }
+
+    impl Scale for Hexagon {
+        fn scale(&mut self, factor: f32) {
+            self.side *= factor;
+        }
+
+        fn size(&self) -> f32 {
+            self.side
+        }
+    }
+}
+
+use hexagon::Hexagon;
+
+#[derive(Clone)]
+struct Unregistered;
+
+#[test]
+fn test_registered_plugin() {
+    ShapeRegistry::register::<Hexagon>().unwrap();
+    // Registering again has no effect
+    ShapeRegistry::register::<Hexagon>().unwrap();
+    assert!(ShapeRegistry::is_registered::<Hexagon>());
+    assert_eq!(ShapeRegistry::type_names().filter(|name| name.ends_with("::Hexagon")).count(), 1);
+
+    let plugin = ShapePlugin::new(Hexagon { side: 2.0 }).ok().unwrap();
+    assert!(plugin.type_name().ends_with("::Hexagon"));
+    let mut shapes = vec![Shape::circle(Circle { radius: 1.0 }), Shape::plugin(plugin)];
+    assert_eq!(shapes[1].tag_type(), ShapeType::Plugin);
+    assert!(shapes[1].is_plugin());
+
+    let drawn: Vec<_> = shapes.iter().map(Shape::draw).collect();
+    assert_eq!(drawn, ["circle 1", "hexagon 2"]);
+
+    for shape in &mut shapes {
+        shape.scale(1.5);
+    }
+    assert_eq!(shapes[1].size(), 3.0);
+    assert_eq!(shapes[1].as_plugin().and_then(ShapePlugin::downcast_ref), Some(&Hexagon { side: 3.0 }));
+
+    // Cloning the handle clones the plugin's value through its table
+    let copy = shapes[1].clone();
+    shapes[1].scale(2.0);
+    assert_eq!((copy.size(), shapes[1].size()), (3.0, 6.0));
+    assert_eq!(shapes[1].as_draw().draw(), "hexagon 6");
+}
+
+#[test]
+fn test_resolved_plugin() {
+    ShapeRegistry::register::<Hexagon>().unwrap();
+    let mut shape = Shape::plugin(ShapePlugin::new(Hexagon { side: 2.0 }).ok().unwrap());
+
+    // The trait object is the registered value's own, not the plugin type's forwarding impl
+    let plugin = shape.as_plugin().unwrap();
+    let draw: &dyn Draw = plugin.as_dyn();
+    assert!(std::ptr::addr_eq(draw, plugin.downcast_ref::<Hexagon>().unwrap()));
+    assert_eq!(draw.draw(), "hexagon 2");
+
+    let scale = shape.as_plugin_mut().unwrap().as_dyn_mut::<dyn Scale>();
+    scale.scale(2.0);
+    scale.scale(1.5);
+    assert_eq!(shape.draw(), "hexagon 6");
+}
+
+#[test]
+fn test_unregistered_plugin() {
+    assert!(!ShapeRegistry::is_registered::<Unregistered>());
+    assert!(ShapePlugin::new(Unregistered).is_err());
+}
+
+#[tagged_dispatch]
+trait Update {
+    fn update(&mut self) -> u32;
+}
+
+#[tagged_dispatch]
+trait Entity: Draw + Update {}
+
+#[derive(Clone)]
+struct Counter(u32);
+
+impl Draw for Counter {
+    fn draw(&self) -> String {
+        format!("counter {}", self.0)
+    }
+}
+
+impl Update for Counter {
+    fn update(&mut self) -> u32 {
+        self.0 += 1;
+        self.0
+    }
+}
+
+impl Entity for Counter {}
+
+#[derive(Clone)]
+struct Timer(u32);
+
+impl Draw for Timer {
+    fn draw(&self) -> String {
+        format!("timer {}", self.0)
+    }
+}
+
+impl Update for Timer {
+    fn update(&mut self) -> u32 {
+        self.0 = self.0.saturating_sub(1);
+        self.0
+    }
+}
+
+impl Entity for Timer {}
+
+// Plugins of an enum dispatching a supertrait-only trait reach its supertraits' methods
+#[tagged_dispatch(Entity, open)]
+enum World {
+    Counter,
+}
+
+#[test]
+fn test_plugin_supertraits() {
+    WorldRegistry::register::<Timer>().unwrap();
+    let mut world = [World::counter(Counter(0)), World::plugin(WorldPlugin::new(Timer(3)).ok().unwrap())];
+    let updated: Vec<_> = world.iter_mut().map(World::update).collect();
+    assert_eq!(updated, [1, 2]);
+    assert_eq!(world[1].draw(), "timer 2");
+}
+
+#[test]
+fn test_abi_handshake() {
+    assert_eq!(ShapeRegistry::ABI.version, ABI_VERSION);
+    // The fingerprint covers the variants and traits, so enums differ
+    assert_ne!(ShapeRegistry::ABI, WorldRegistry::ABI);
+
+    // A plugin built against another definition of the enum is turned away
+    let registry = PluginRegistry::<()>::new(ShapeRegistry::ABI);
+    let stale = Abi { version: ABI_VERSION, fingerprint: ShapeRegistry::ABI.fingerprint ^ 1 };
+    let err = registry.register::<Hexagon>(stale, ()).unwrap_err();
+    assert_eq!(err, RegisterError::AbiMismatch { expected: ShapeRegistry::ABI, found: stale });
+    assert!(err.to_string().starts_with("plugin built for ABI 1/"));
+    assert!(registry.get::<Hexagon>().is_none());
+
+    assert!(registry.register::<Hexagon>(ShapeRegistry::ABI, ()).is_ok());
+    assert_eq!(registry.iter().count(), 1);
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "bytemuck")]
+
+use tagged_dispatch::bytemuck::{Pod, Zeroable};
+use tagged_dispatch::bytes::DecodeError;
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Sample {
+    fn value(&self) -> f64;
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Temperature {
+    celsius: f32,
+}
+
+unsafe impl Zeroable for Temperature {}
+unsafe impl Pod for Temperature {}
+
+impl Sample for Temperature {
+    fn value(&self) -> f64 {
+        self.celsius as f64
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Pressure {
+    sensor: u64,
+    pascals: u64,
+}
+
+unsafe impl Zeroable for Pressure {}
+unsafe impl Pod for Pressure {}
+
+impl Sample for Pressure {
+    fn value(&self) -> f64 {
+        self.pascals as f64
+    }
+}
+
+#[tagged_dispatch(Sample, bytes)]
+enum Reading {
+    Temperature,
+    Pressure,
+}
+
+#[test]
+fn test_encode_layout() {
+    let reading = Reading::temperature(Temperature { celsius: 1.5 });
+    let bytes = reading.to_bytes();
+
+    assert_eq!(bytes.len(), reading.encoded_len());
+    assert_eq!(bytes[0], 0);
+    assert_eq!(&bytes[1..], 1.5f32.to_ne_bytes());
+}
+
+#[test]
+fn test_round_trip() {
+    let pressure = Pressure { sensor: 7, pascals: 101_325 };
+    let bytes = Reading::pressure(pressure).to_bytes();
+    assert_eq!(bytes.len(), 1 + std::mem::size_of::<Pressure>());
+
+    let decoded = Reading::try_from(bytes.as_slice()).unwrap();
+    assert_eq!(decoded.tag_type(), ReadingType::Pressure);
+    assert_eq!(decoded.value(), 101_325.0);
+
+    let value: Pressure = decoded.try_into().ok().unwrap();
+    assert_eq!(value, pressure);
+}
+
+#[test]
+fn test_decode_errors() {
+    assert_eq!(Reading::try_from(&[][..]).err(), Some(DecodeError::Empty));
+    assert_eq!(Reading::try_from(&[9, 0, 0, 0, 0][..]).err(), Some(DecodeError::InvalidTag(9)));
+    assert_eq!(
+        Reading::try_from(&[0, 1, 2][..]).err(),
+        Some(DecodeError::Length { expected: 4, found: 2 })
+    );
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Sample, bytes)]
+    enum ArenaReading<'a> {
+        Temperature,
+        Pressure,
+    }
+
+    #[test]
+    fn test_builder_decodes() {
+        let builder = ArenaReading::arena_builder();
+        let bytes = builder.temperature(Temperature { celsius: -4.0 }).to_bytes();
+
+        // Owned and arena enums with the same variant order share the encoding
+        let owned = Reading::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(owned.value(), -4.0);
+
+        let decoded = builder.from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.value(), -4.0);
+        assert_eq!(builder.from_bytes(&[5]).err(), Some(DecodeError::InvalidTag(5)));
+    }
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "std")]
+
+use std::cell::Cell;
+
+use tagged_dispatch::tagged_dispatch;
+
+thread_local! {
+    static DROPS: Cell<usize> = const { Cell::new(0) };
+}
+
+#[tagged_dispatch]
+trait Draw {
+    fn draw(&self) -> String;
+}
+
+#[derive(Clone, Debug)]
+struct Spark {
+    heat: u32,
+}
+
+impl Draw for Spark {
+    fn draw(&self) -> String {
+        format!("spark {}", self.heat)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Smoke {
+    puffs: Vec<u8>,
+}
+
+impl Draw for Smoke {
+    fn draw(&self) -> String {
+        format!("smoke {}", self.puffs.len())
+    }
+}
+
+impl Drop for Smoke {
+    fn drop(&mut self) {
+        DROPS.with(|drops| drops.set(drops.get() + 1));
+    }
+}
+
+#[tagged_dispatch(Draw, pooled, send)]
+enum Particle {
+    Spark,
+    Smoke,
+    Ember(Spark),
+}
+
+fn spark_address(particle: &Particle) -> *const Spark {
+    particle.as_spark().or(particle.as_ember()).unwrap() as *const Spark
+}
+
+#[test]
+fn dropped_blocks_are_reused() {
+    let first = Particle::spark(Spark { heat: 1 });
+    let address = spark_address(&first);
+    drop(first);
+
+    let second = Particle::spark(Spark { heat: 2 });
+    assert_eq!(spark_address(&second), address);
+    assert_eq!(second.draw(), "spark 2");
+}
+
+#[test]
+fn variants_of_the_same_type_share_a_pool() {
+    let spark = Particle::spark(Spark { heat: 3 });
+    let address = spark_address(&spark);
+    drop(spark);
+
+    let ember = Particle::ember(Spark { heat: 4 });
+    assert_eq!(spark_address(&ember), address);
+    assert_eq!(ember.draw(), "spark 4");
+}
+
+#[test]
+fn pooled_values_are_still_dropped() {
+    DROPS.with(|drops| drops.set(0));
+    let smoke = Particle::smoke(Smoke { puffs: vec![1, 2, 3] });
+    assert_eq!(smoke.draw(), "smoke 3");
+    drop(smoke);
+    assert_eq!(DROPS.with(Cell::get), 1);
+
+    // Clones are allocated from the pool as well
+    let smoke = Particle::smoke(Smoke { puffs: vec![4] });
+    let copy = smoke.clone();
+    assert_eq!(copy.draw(), "smoke 1");
+    drop(copy);
+    assert_eq!(DROPS.with(Cell::get), 2);
+    drop(smoke);
+    assert_eq!(DROPS.with(Cell::get), 3);
+}
+
+#[test]
+fn pools_are_per_thread() {
+    let spark = Particle::spark(Spark { heat: 5 });
+    let handle = std::thread::spawn(move || {
+        // Dropped on another thread, so the block joins that thread's pool
+        drop(spark);
+        let again = Particle::spark(Spark { heat: 6 });
+        again.draw()
+    });
+    assert_eq!(handle.join().unwrap(), "spark 6");
+}
@@ -0,0 +1,97 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Widget {
+    fn width(&self) -> u32;
+    fn into_label(self) -> String;
+}
+
+// Counts drops of the widget itself, not of its handles
+#[derive(Clone)]
+struct Button {
+    width: u32,
+    drops: Rc<Cell<usize>>,
+}
+
+impl Drop for Button {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+impl Widget for Button {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn into_label(self) -> String {
+        format!("button {}", self.width)
+    }
+}
+
+#[derive(Clone)]
+struct Spacer {
+    width: u32,
+}
+
+impl Widget for Spacer {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn into_label(self) -> String {
+        format!("spacer {}", self.width)
+    }
+}
+
+#[tagged_dispatch(Widget, rc)]
+enum Node {
+    Button,
+    Spacer,
+}
+
+// `rc` handles can't cross threads:
+// #[tagged_dispatch(Widget, rc, send)]
+// enum SendNode { Button, Spacer }
+
+#[test]
+fn test_clone_shares_value() {
+    let drops = Rc::new(Cell::new(0));
+    let node = Node::button(Button { width: 40, drops: drops.clone() });
+    let copy = node.clone();
+    assert_eq!(copy, node);
+
+    drop(node);
+    assert_eq!(drops.get(), 0);
+    assert_eq!(copy.width(), 40);
+    drop(copy);
+    assert_eq!(drops.get(), 1);
+}
+
+#[test]
+fn test_take_clones_only_while_shared() {
+    let drops = Rc::new(Cell::new(0));
+    let node = Node::button(Button { width: 8, drops: drops.clone() });
+    let copy = node.clone();
+
+    // The clone taken out is dropped after formatting, the shared value stays
+    assert_eq!(node.into_label(), "button 8");
+    assert_eq!(drops.get(), 1);
+    assert_eq!(copy.width(), 8);
+
+    let button = Button::try_from(copy).unwrap();
+    assert_eq!(drops.get(), 1);
+    drop(button);
+    assert_eq!(drops.get(), 2);
+}
+
+#[test]
+fn test_mixed_variants() {
+    let row = vec![Node::spacer(Spacer { width: 4 }), Node::spacer(Spacer { width: 6 })];
+    let copy = row.to_vec();
+    drop(row);
+    assert_eq!(copy.iter().map(Node::width).sum::<u32>(), 10);
+}
@@ -0,0 +1,104 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch(ref_impls)]
+trait Draw {
+    fn draw(&self, scale: u32) -> String;
+
+    fn outline(&self) -> String {
+        format!("[{}]", self.draw(1))
+    }
+}
+
+#[tagged_dispatch(ref_impls)]
+trait Named {
+    fn name(&self) -> &'static str;
+}
+
+#[derive(Clone)]
+struct Circle;
+
+impl Draw for Circle {
+    fn draw(&self, scale: u32) -> String {
+        "o".repeat(scale as usize)
+    }
+}
+
+impl Named for Circle {
+    fn name(&self) -> &'static str {
+        "circle"
+    }
+}
+
+#[derive(Clone)]
+struct Square;
+
+impl Draw for Square {
+    fn draw(&self, scale: u32) -> String {
+        "#".repeat(scale as usize)
+    }
+
+    fn outline(&self) -> String {
+        "square outline".to_string()
+    }
+}
+
+impl Named for Square {
+    fn name(&self) -> &'static str {
+        "square"
+    }
+}
+
+#[tagged_dispatch(Draw, qualified(Named))]
+enum Shape {
+    Circle,
+    Square,
+}
+
+fn render(drawable: impl Draw) -> String {
+    drawable.draw(2)
+}
+
+fn render_all<'s>(drawables: impl IntoIterator<Item = &'s Shape>) -> Vec<String> {
+    drawables.into_iter().map(|drawable| drawable.outline()).collect()
+}
+
+fn name_of(named: impl Named) -> &'static str {
+    named.name()
+}
+
+#[test]
+fn test_shared_ref_impl() {
+    let shapes = vec![Shape::circle(Circle), Shape::square(Square)];
+
+    assert_eq!(render(&shapes[0]), "oo");
+    // Forwarded methods still reach variant overrides of default methods
+    assert_eq!(render_all(&shapes), vec!["[o]", "square outline"]);
+    // Qualified traits forward to the `_as_<trait>` methods
+    assert_eq!(name_of(&shapes[1]), "square");
+}
+
+#[test]
+fn test_mut_ref_impl() {
+    let mut shape = Shape::square(Square);
+
+    assert_eq!(render(&mut shape), "##");
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Draw)]
+    enum ShapeRef<'a> {
+        Circle,
+        Square,
+    }
+
+    #[test]
+    fn test_arena_ref_impl() {
+        let builder = ShapeRef::arena_builder();
+        let circle = builder.circle(Circle);
+
+        assert_eq!(render(&circle), "oo");
+    }
+}
@@ -0,0 +1,112 @@
+use tagged_dispatch::{tagged_dispatch, TagError};
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+impl Area for u32 {
+    fn area(&self) -> f32 {
+        0.0
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape {
+    Circle,
+    Square,
+    #[inline_value]
+    Marker(u32),
+}
+
+#[test]
+fn test_variant_metadata() {
+    assert_eq!(Shape::VARIANT_COUNT, 3);
+    assert_eq!(ShapeType::ALL, [ShapeType::Circle, ShapeType::Square, ShapeType::Marker]);
+
+    let names: Vec<_> = ShapeType::ALL.iter().map(|ty| ty.name()).collect();
+    assert_eq!(names, ["Circle", "Square", "Marker"]);
+    assert_eq!(ShapeType::Marker.type_name(), "u32");
+    assert!(ShapeType::Circle.type_name().ends_with("::Circle"));
+
+    // Usable in const contexts, e.g. static tables for tooling
+    const SQUARE: &str = ShapeType::Square.name();
+    assert_eq!(SQUARE, "Square");
+}
+
+#[test]
+fn test_display_and_parse() {
+    assert_eq!(ShapeType::Square.to_string(), "Square");
+    assert_eq!(format!("[{:>8}]", ShapeType::Circle), "[  Circle]");
+    for ty in ShapeType::ALL {
+        assert_eq!(ty.to_string().parse::<ShapeType>(), Ok(ty));
+    }
+
+    let err = "Triangle".parse::<ShapeType>().unwrap_err();
+    assert_eq!(err.enum_name(), "Shape");
+    assert_eq!(err.to_string(), "not a `Shape` variant name");
+    // Names are matched exactly
+    assert!("circle".parse::<ShapeType>().is_err());
+}
+
+#[test]
+fn test_numeric_tags() {
+    assert_eq!(u8::from(ShapeType::Circle), 0);
+    assert_eq!(u8::from(ShapeType::Marker), 2);
+    for ty in ShapeType::ALL {
+        assert_eq!(ShapeType::try_from(u8::from(ty)), Ok(ty));
+    }
+    assert_eq!(ShapeType::try_from(3u8), Err(TagError::TagOutOfRange { tag: 3, max_variants: 3 }));
+}
+
+#[test]
+fn test_handle_metadata() {
+    let shapes = [Shape::circle(Circle { radius: 1.0 }), Shape::square(Square { side: 2.0 }), Shape::marker(3)];
+    let names: Vec<_> = shapes.iter().map(Shape::variant_name).collect();
+    assert_eq!(names, ["Circle", "Square", "Marker"]);
+    assert_eq!(shapes[2].type_name(), "u32");
+    assert_eq!(shapes[1].area(), 4.0);
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Area)]
+    enum Sketch<'a> {
+        Circle,
+        Square,
+    }
+
+    #[test]
+    fn test_arena_metadata() {
+        let builder = SketchArenaBuilder::new();
+        let sketch = builder.square(Square { side: 3.0 });
+        assert_eq!(Sketch::VARIANT_COUNT, 2);
+        assert_eq!(sketch.variant_name(), "Square");
+        assert_eq!(sketch.area(), 9.0);
+        assert_eq!(SketchType::ALL.map(SketchType::name), ["Circle", "Square"]);
+    }
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "registry")]
+
+use tagged_dispatch::registry::{self, TagLayout};
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Weigh {
+    fn weight(&self) -> usize;
+}
+
+#[derive(Clone)]
+struct Pebble;
+
+impl Weigh for Pebble {
+    fn weight(&self) -> usize {
+        1
+    }
+}
+
+#[derive(Clone)]
+#[repr(align(8))]
+struct Boulder {
+    mass: [u64; 4],
+}
+
+impl Weigh for Boulder {
+    fn weight(&self) -> usize {
+        self.mass.len()
+    }
+}
+
+#[tagged_dispatch(Weigh)]
+enum Rock {
+    Pebble,
+    Boulder,
+}
+
+#[derive(Clone)]
+struct Slab(u64);
+
+impl Weigh for Slab {
+    fn weight(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[tagged_dispatch(Weigh, tag = "low")]
+enum LowRock {
+    Boulder,
+    Flat(Slab),
+}
+
+mod nested {
+    use super::*;
+
+    #[tagged_dispatch(Weigh)]
+    pub enum Stone {
+        Pebble,
+    }
+}
+
+fn find(name: &str) -> &'static registry::EnumInfo {
+    tagged_dispatch::registry()
+        .iter()
+        .find(|info| info.name == name)
+        .unwrap_or_else(|| panic!("{} is not registered", name))
+}
+
+#[test]
+fn test_enums_are_registered() {
+    let rock = find("Rock");
+    assert!(rock.owned);
+    assert_eq!(rock.module_path, "registry");
+    assert_eq!(rock.layout, TagLayout::High);
+    assert_eq!(rock.handle_size, std::mem::size_of::<Rock>());
+    assert_eq!(rock.variants.len(), 2);
+    assert_eq!(rock.variants[1].name, "Boulder");
+    assert_eq!(rock.variants[1].size, 32);
+    assert_eq!(rock.max_variant_size(), 32);
+
+    assert_eq!(find("LowRock").layout, TagLayout::Low { bits: 1 });
+    assert_eq!(find("LowRock").variants[1].name, "Flat");
+    assert_eq!(find("LowRock").variants[1].type_name, "Slab");
+
+    let stone = find("Stone");
+    assert_eq!(stone.module_path, "registry::nested");
+    assert_eq!(stone.layout, TagLayout::Untagged);
+
+    // Registered enums dispatch as usual
+    let weights = [Rock::pebble(Pebble).weight(), LowRock::flat(Slab(3)).weight(), nested::Stone::pebble(Pebble).weight()];
+    assert_eq!(weights, [1, 3, 1]);
+}
+
+#[test]
+fn test_report() {
+    let mut report = String::new();
+    registry::write_report(&mut report).unwrap();
+
+    assert!(report.contains("registry::Rock (owned, high 7-bit tag, 2 variants, largest 32 bytes)"));
+    assert!(report.contains("    Boulder(Boulder): 32 bytes, align 8"));
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Weigh)]
+    enum ArenaRock<'a> {
+        Pebble,
+        Boulder,
+    }
+
+    #[test]
+    fn test_arena_enum_registered() {
+        let info = find("ArenaRock");
+        assert!(!info.owned);
+        assert_eq!(info.handle_size, std::mem::size_of::<ArenaRock>());
+        let _ = ArenaRock::arena_builder().pebble(Pebble).weight();
+    }
+}
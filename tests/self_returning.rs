@@ -0,0 +1,96 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Transform {
+    fn area(&self) -> f32;
+    fn scaled(&self, factor: f32) -> Self;
+    fn into_unit(self) -> Self;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Circle {
+    radius: f32,
+}
+
+impl Transform for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+
+    fn scaled(&self, factor: f32) -> Self {
+        Circle { radius: self.radius * factor }
+    }
+
+    fn into_unit(self) -> Self {
+        Circle { radius: 1.0 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Square {
+    side: f32,
+}
+
+impl Transform for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+
+    fn scaled(&self, factor: f32) -> Self {
+        Square { side: self.side * factor }
+    }
+
+    fn into_unit(self) -> Self {
+        Square { side: 1.0 }
+    }
+}
+
+#[tagged_dispatch(Transform)]
+enum Shape {
+    Circle,
+    Square,
+}
+
+#[test]
+fn test_self_result_is_rewrapped() {
+    let square = Shape::square(Square { side: 2.0 });
+    let bigger = square.scaled(3.0);
+
+    assert_eq!(bigger.tag_type(), ShapeType::Square);
+    assert_eq!(bigger.area(), 36.0);
+    assert_ne!(bigger, square);
+    assert_eq!(square.area(), 4.0);
+}
+
+#[test]
+fn test_consuming_self_result() {
+    let circle = Shape::circle(Circle { radius: 5.0 }).into_unit();
+
+    assert_eq!(circle.tag_type(), ShapeType::Circle);
+    assert_eq!(circle.area(), 3.0);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Transform)]
+    enum ShapeRef<'a> {
+        Circle,
+        Square,
+    }
+
+    #[test]
+    fn test_arena_self_result_uses_builder() {
+        let builder = ShapeRef::arena_builder();
+        let circle = builder.circle(Circle { radius: 1.0 });
+
+        let bigger = circle.scaled(&builder, 2.0);
+        assert_eq!(bigger.tag_type(), ShapeRefType::Circle);
+        assert_eq!(bigger.area(), 12.0);
+        assert_eq!(circle.area(), 3.0);
+
+        let unit = bigger.into_unit(&builder);
+        assert_eq!(unit.area(), 3.0);
+    }
+}
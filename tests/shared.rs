@@ -0,0 +1,118 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tagged_dispatch::tagged_dispatch;
+
+static CLONES: AtomicUsize = AtomicUsize::new(0);
+static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+#[tagged_dispatch]
+trait Draw {
+    fn area(&self) -> f32;
+    fn into_label(self) -> String;
+}
+
+#[derive(Debug, PartialEq)]
+struct Circle {
+    radius: f32,
+}
+
+impl Clone for Circle {
+    fn clone(&self) -> Self {
+        CLONES.fetch_add(1, Ordering::SeqCst);
+        Circle { radius: self.radius }
+    }
+}
+
+impl Drop for Circle {
+    fn drop(&mut self) {
+        DROPS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Draw for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+
+    fn into_label(self) -> String {
+        format!("circle {}", self.radius)
+    }
+}
+
+#[derive(Clone)]
+struct Square {
+    side: f32,
+}
+
+impl Draw for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+
+    fn into_label(self) -> String {
+        format!("square {}", self.side)
+    }
+}
+
+#[tagged_dispatch(Draw, shared, send, sync)]
+enum Shape {
+    Circle,
+    Square,
+}
+
+// `shared` conflicts with options that assume one handle per value:
+// #[tagged_dispatch(Draw, shared, cow)]
+// enum CowShape { Circle, Square }
+
+// Tests share the clone and drop counters, so they run one at a time
+static SERIAL: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[test]
+fn test_clone_shares_value() {
+    let _serial = SERIAL.lock().unwrap();
+    let clones = CLONES.load(Ordering::SeqCst);
+    let drops = DROPS.load(Ordering::SeqCst);
+
+    let shape = Shape::circle(Circle { radius: 2.0 });
+    let copy = shape.clone();
+    assert_eq!(copy, shape);
+    assert_eq!(copy.area(), 12.0);
+    assert_eq!(CLONES.load(Ordering::SeqCst), clones);
+
+    drop(shape);
+    assert_eq!(DROPS.load(Ordering::SeqCst), drops);
+    assert_eq!(copy.area(), 12.0);
+    drop(copy);
+    assert_eq!(DROPS.load(Ordering::SeqCst), drops + 1);
+}
+
+#[test]
+fn test_take_clones_only_while_shared() {
+    let _serial = SERIAL.lock().unwrap();
+    let clones = CLONES.load(Ordering::SeqCst);
+
+    let shape = Shape::circle(Circle { radius: 1.0 });
+    let copy = shape.clone();
+    assert_eq!(shape.into_label(), "circle 1");
+    assert_eq!(CLONES.load(Ordering::SeqCst), clones + 1);
+
+    // The last handle gives the value up without copying it
+    assert_eq!(Circle::try_from(copy).unwrap(), Circle { radius: 1.0 });
+    assert_eq!(CLONES.load(Ordering::SeqCst), clones + 1);
+}
+
+#[test]
+fn test_shared_across_threads() {
+    let _serial = SERIAL.lock().unwrap();
+    let drops = DROPS.load(Ordering::SeqCst);
+
+    let shapes = vec![Shape::circle(Circle { radius: 1.0 }), Shape::square(Square { side: 2.0 })];
+    let render = shapes.to_vec();
+    let total = std::thread::spawn(move || render.iter().map(Shape::area).sum::<f32>())
+        .join()
+        .unwrap();
+    assert_eq!(total, 7.0);
+
+    drop(shapes);
+    assert_eq!(DROPS.load(Ordering::SeqCst), drops + 1);
+}
@@ -0,0 +1,108 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Backend {
+    fn name(&self) -> &'static str;
+    fn pending(&self, jobs: u32) -> u32;
+    fn into_total(self) -> u32;
+}
+
+#[derive(Clone)]
+struct Vulkan {
+    submitted: u32,
+    drops: Rc<Cell<u32>>,
+}
+
+impl Drop for Vulkan {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+impl Backend for Vulkan {
+    fn name(&self) -> &'static str {
+        "vulkan"
+    }
+
+    fn pending(&self, jobs: u32) -> u32 {
+        self.submitted + jobs
+    }
+
+    fn into_total(self) -> u32 {
+        self.submitted
+    }
+}
+
+// Typically the other backends are behind cfg gates
+#[tagged_dispatch(Backend)]
+enum Renderer {
+    Vulkan,
+}
+
+fn vulkan(drops: &Rc<Cell<u32>>) -> Vulkan {
+    Vulkan { submitted: 0, drops: drops.clone() }
+}
+
+#[test]
+fn test_single_variant_layout() {
+    assert_eq!(std::mem::size_of::<Renderer>(), std::mem::size_of::<*const ()>());
+    assert_eq!(std::mem::size_of::<Option<Renderer>>(), std::mem::size_of::<*const ()>());
+}
+
+#[test]
+fn test_single_variant_dispatch() {
+    let drops = Rc::new(Cell::new(0));
+    let renderer = Renderer::vulkan(Vulkan { submitted: 3, drops: drops.clone() });
+
+    assert_eq!(renderer.tag_type(), RendererType::Vulkan);
+    assert_eq!(renderer.name(), "vulkan");
+    assert_eq!(renderer.pending(4), 7);
+
+    let copy = renderer.clone();
+    assert_ne!(renderer, copy);
+    drop(copy);
+    assert_eq!(drops.get(), 1);
+
+    assert_eq!(renderer.into_total(), 3);
+    assert_eq!(drops.get(), 2);
+}
+
+#[test]
+fn test_single_variant_conversions() {
+    let drops = Rc::new(Cell::new(0));
+    let renderer: Renderer = vulkan(&drops).into();
+
+    let borrowed: &Vulkan = (&renderer).try_into().unwrap();
+    assert_eq!(borrowed.name(), "vulkan");
+
+    let extracted: Vulkan = renderer.try_into().ok().unwrap();
+    assert_eq!(drops.get(), 0);
+    drop(extracted);
+    assert_eq!(drops.get(), 1);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Backend)]
+    enum ArenaRenderer<'a> {
+        Vulkan,
+    }
+
+    #[test]
+    fn test_single_variant_arena() {
+        let drops = Rc::new(Cell::new(0));
+        let builder = ArenaRenderer::arena_builder();
+        let renderer = builder.vulkan(vulkan(&drops));
+
+        assert_eq!(renderer.name(), "vulkan");
+        assert_eq!(renderer.pending(1), 1);
+        assert_eq!(renderer.into_total(), 0);
+        assert_eq!(renderer.tag_type(), ArenaRendererType::Vulkan);
+        assert_eq!(std::mem::size_of::<ArenaRenderer>(), std::mem::size_of::<*const ()>());
+    }
+}
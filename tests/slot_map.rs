@@ -0,0 +1,83 @@
+#![cfg(feature = "slotmap")]
+
+use tagged_dispatch::slot_map::{SecondaryMapExt, SlotMapExt};
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Draw {
+    fn draw(&self) -> String;
+}
+
+#[derive(Clone)]
+struct Circle {
+    radius: u32,
+}
+
+impl Draw for Circle {
+    fn draw(&self) -> String {
+        format!("circle {}", self.radius)
+    }
+}
+
+#[derive(Clone)]
+struct Line;
+
+impl Draw for Line {
+    fn draw(&self) -> String {
+        "line".to_string()
+    }
+}
+
+#[tagged_dispatch(Draw)]
+enum Shape {
+    Circle,
+    Line,
+}
+
+#[test]
+fn test_shape_map_dispatch() {
+    let mut shapes = ShapeMap::new();
+    let circle = shapes.insert_variant(Circle { radius: 2 });
+    let line = shapes.insert(Shape::line(Line));
+
+    assert_eq!(shapes[circle].draw(), "circle 2");
+    assert_eq!(shapes[line].draw(), "line");
+
+    shapes.remove(circle);
+    assert!(shapes.get(circle).is_none());
+}
+
+#[test]
+fn test_secondary_map() {
+    let mut shapes = ShapeMap::new();
+    let key = shapes.insert_variant(Line);
+
+    let mut outlines = ShapeSecondaryMap::new();
+    assert!(outlines.insert_variant(key, Circle { radius: 5 }).is_none());
+    assert_eq!(outlines[key].draw(), "circle 5");
+
+    let replaced = outlines.insert_variant(key, Line).unwrap();
+    assert_eq!(replaced.draw(), "circle 5");
+    assert_eq!(outlines[key].draw(), "line");
+    assert_eq!(shapes[key].draw(), "line");
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Draw)]
+    enum ShapeRef<'a> {
+        Circle,
+        Line,
+    }
+
+    #[test]
+    fn test_arena_shape_map() {
+        let builder = ShapeRef::arena_builder();
+        let mut shapes: ShapeRefMap<'_> = ShapeRefMap::new();
+        let key = shapes.insert(builder.circle(Circle { radius: 1 }));
+
+        assert_eq!(shapes[key].draw(), "circle 1");
+    }
+}
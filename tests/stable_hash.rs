@@ -0,0 +1,107 @@
+use std::hash::{Hash, Hasher};
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Key {
+    fn len(&self) -> usize;
+}
+
+#[derive(Clone, Hash, PartialEq)]
+struct Path {
+    segments: Vec<String>,
+}
+
+impl Key for Path {
+    fn len(&self) -> usize {
+        self.segments.len()
+    }
+}
+
+#[derive(Clone, Hash, PartialEq)]
+struct Id {
+    value: u64,
+}
+
+impl Key for Id {
+    fn len(&self) -> usize {
+        1
+    }
+}
+
+#[tagged_dispatch(Key, stable_hash)]
+enum CacheKey {
+    Path,
+    Id,
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is fixed across processes and releases
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+fn fnv(key: &CacheKey) -> u64 {
+    let mut hasher = Fnv(0xcbf29ce484222325);
+    key.stable_hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn test_stable_hash_is_structural() {
+    let a = CacheKey::path(Path { segments: vec!["a".into(), "b".into()] });
+    let b = CacheKey::path(Path { segments: vec!["a".into(), "b".into()] });
+
+    // Different allocations, same value
+    assert_ne!(a, b);
+    assert_eq!(fnv(&a), fnv(&b));
+    assert_eq!(a.len(), 2);
+}
+
+#[test]
+fn test_stable_hash_includes_tag() {
+    let id = CacheKey::id(Id { value: 7 });
+
+    let mut expected = Fnv(0xcbf29ce484222325);
+    expected.write_u8(1);
+    Id { value: 7 }.hash(&mut expected);
+    assert_eq!(fnv(&id), expected.finish());
+}
+
+#[tagged_dispatch(Key, hash, no_eq)]
+enum MapKey {
+    Path,
+    Id,
+}
+
+// Value equality, to match the value-based `Hash`
+impl PartialEq for MapKey {
+    fn eq(&self, other: &Self) -> bool {
+        match (<&Path>::try_from(self), <&Path>::try_from(other)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => matches!((<&Id>::try_from(self), <&Id>::try_from(other)), (Ok(a), Ok(b)) if a == b),
+        }
+    }
+}
+
+impl Eq for MapKey {}
+
+#[test]
+fn test_hash_flag_hashes_value() {
+    let mut sizes = std::collections::HashMap::new();
+    sizes.insert(MapKey::id(Id { value: 1 }), 10);
+    sizes.insert(MapKey::path(Path { segments: vec!["x".into()] }), 20);
+
+    assert_eq!(sizes[&MapKey::id(Id { value: 1 })], 10);
+    assert_eq!(sizes.get(&MapKey::id(Id { value: 2 })), None);
+    assert_eq!(sizes.keys().map(|key| key.len()).sum::<usize>(), 2);
+}
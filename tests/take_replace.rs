@@ -0,0 +1,64 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone, Debug, Default)]
+struct Empty;
+
+impl Area for Empty {
+    fn area(&self) -> f32 {
+        0.0
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape {
+    Square,
+    #[default]
+    Empty,
+}
+
+#[tagged_dispatch(Area)]
+enum Solid {
+    Square,
+}
+
+struct Slot {
+    shape: Shape,
+}
+
+#[test]
+fn test_take_leaves_default() {
+    let mut slot = Slot { shape: Shape::square(Square { side: 2.0 }) };
+    let taken = slot.shape.take();
+    assert_eq!(taken.area(), 4.0);
+    assert_eq!(slot.shape.tag_type(), ShapeType::Empty);
+    assert_eq!(Shape::default().tag_type(), ShapeType::Empty);
+}
+
+#[test]
+fn test_replace() {
+    let mut slot = Slot { shape: Shape::default() };
+    let old = slot.shape.replace(Shape::square(Square { side: 3.0 }));
+    assert_eq!(old.tag_type(), ShapeType::Empty);
+    assert_eq!(slot.shape.area(), 9.0);
+
+    // Enums without a default variant still get `replace`
+    let mut solid = Solid::square(Square { side: 1.0 });
+    let old = solid.replace(Solid::square(Square { side: 5.0 }));
+    assert_eq!((old.area(), solid.area()), (1.0, 25.0));
+}
@@ -0,0 +1,132 @@
+use std::rc::Rc;
+use std::thread;
+
+use static_assertions::{assert_impl_all, assert_not_impl_any};
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Job {
+    fn run(&self) -> u32;
+}
+
+#[derive(Clone)]
+struct Compute {
+    input: u32,
+}
+
+impl Job for Compute {
+    fn run(&self) -> u32 {
+        self.input * 2
+    }
+}
+
+#[derive(Clone)]
+struct Log {
+    message: String,
+}
+
+impl Job for Log {
+    fn run(&self) -> u32 {
+        self.message.len() as u32
+    }
+}
+
+#[derive(Clone)]
+struct Shared {
+    counter: Rc<u32>,
+}
+
+impl Job for Shared {
+    fn run(&self) -> u32 {
+        *self.counter
+    }
+}
+
+#[tagged_dispatch(Job, send, sync)]
+enum Task {
+    Compute,
+    Log,
+}
+
+#[tagged_dispatch(Job, send)]
+enum SendOnlyTask {
+    Compute,
+}
+
+// Without the flags handles are neither, whatever their variants are
+#[tagged_dispatch(Job)]
+enum LocalTask {
+    Compute,
+    Shared,
+}
+
+assert_impl_all!(Task: Send, Sync);
+assert_impl_all!(SendOnlyTask: Send);
+assert_not_impl_any!(SendOnlyTask: Sync);
+assert_not_impl_any!(LocalTask: Send, Sync);
+
+// Adding `send` to `LocalTask` should not compile, since `Shared` holds an `Rc`:
+// #[tagged_dispatch(Job, send)]
+// enum BadTask { Compute, Shared }
+
+#[test]
+fn test_send_across_threads() {
+    let tasks = [Task::compute(Compute { input: 21 }), Task::log(Log { message: "hello".to_string() })];
+
+    let total = thread::spawn(move || tasks.iter().map(Task::run).sum::<u32>()).join().unwrap();
+    assert_eq!(total, 47);
+
+    let task = SendOnlyTask::compute(Compute { input: 2 });
+    assert_eq!(thread::spawn(move || task.run()).join().unwrap(), 4);
+}
+
+#[test]
+fn test_share_across_threads() {
+    let task = Task::compute(Compute { input: 5 });
+
+    let results: Vec<u32> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..4).map(|_| scope.spawn(|| task.run())).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    assert_eq!(results, [10; 4]);
+}
+
+#[test]
+fn test_local_task_still_dispatches() {
+    let task = LocalTask::shared(Shared { counter: Rc::new(3) });
+    assert_eq!(task.run(), 3);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    // Arena handles share their value, so `send` requires `Sync` variants
+    #[tagged_dispatch(Job, send, sync)]
+    enum ArenaTask<'a> {
+        Compute,
+        Log,
+    }
+
+    #[tagged_dispatch(Job)]
+    enum LocalArenaTask<'a> {
+        Compute,
+    }
+
+    assert_impl_all!(ArenaTask<'static>: Send, Sync);
+    assert_not_impl_any!(LocalArenaTask<'static>: Send, Sync);
+
+    #[test]
+    fn test_arena_handles_cross_scoped_threads() {
+        let builder = ArenaTask::arena_builder();
+        let tasks = [builder.compute(Compute { input: 1 }), builder.log(Log { message: "abc".to_string() })];
+
+        let total = thread::scope(|scope| {
+            scope.spawn(move || tasks.iter().map(|task| task.run()).sum::<u32>()).join().unwrap()
+        });
+        assert_eq!(total, 5);
+
+        let local = LocalArenaTask::arena_builder();
+        assert_eq!(local.compute(Compute { input: 3 }).run(), 6);
+    }
+}
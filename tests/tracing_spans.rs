@@ -0,0 +1,109 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use tagged_dispatch::tagged_dispatch;
+use tagged_dispatch::tracing::{
+    self,
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+#[tagged_dispatch]
+trait Draw {
+    fn draw(&self) -> &str;
+}
+
+#[derive(Clone)]
+struct Circle;
+
+impl Draw for Circle {
+    fn draw(&self) -> &str {
+        "circle"
+    }
+}
+
+#[derive(Clone)]
+struct Rectangle;
+
+impl Draw for Rectangle {
+    fn draw(&self) -> &str {
+        "rectangle"
+    }
+}
+
+#[tagged_dispatch(Draw)]
+enum Shape {
+    Circle,
+    Rectangle,
+}
+
+/// Span name plus its recorded `(field, value)` pairs
+type RecordedSpan = (String, Vec<(String, String)>);
+
+/// Subscriber that records the fields of every span it sees
+#[derive(Clone, Default)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<RecordedSpan>>>,
+}
+
+struct FieldCollector(Vec<(String, String)>);
+
+impl Visit for FieldCollector {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push((field.name().to_string(), format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+        let mut fields = FieldCollector(Vec::new());
+        attrs.record(&mut fields);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push((attrs.metadata().name().to_string(), fields.0));
+        span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+    fn event(&self, _: &Event<'_>) {}
+    fn enter(&self, _: &span::Id) {}
+    fn exit(&self, _: &span::Id) {}
+}
+
+#[test]
+fn test_dispatch_emits_span() {
+    let recorder = Recorder::default();
+    let shapes = [Shape::circle(Circle), Shape::rectangle(Rectangle)];
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        assert_eq!(shapes[0].draw(), "circle");
+        assert_eq!(shapes[1].draw(), "rectangle");
+    });
+
+    let spans = recorder.spans.lock().unwrap();
+    assert_eq!(spans.len(), 2);
+
+    let field = |i: usize, name: &str| {
+        spans[i]
+            .1
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.clone())
+    };
+
+    assert_eq!(spans[0].0, "dispatch");
+    assert_eq!(field(0, "enum_name").as_deref(), Some("Shape"));
+    assert_eq!(field(0, "variant").as_deref(), Some("Circle"));
+    assert_eq!(field(0, "method").as_deref(), Some("draw"));
+    assert_eq!(field(1, "variant").as_deref(), Some("Rectangle"));
+}
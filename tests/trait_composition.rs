@@ -0,0 +1,171 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Draw {
+    fn draw(&self) -> String;
+}
+
+#[tagged_dispatch]
+trait Update {
+    fn speed(&self) -> u32;
+}
+
+// Alias-like trait: dispatches everything its supertraits declare
+#[tagged_dispatch]
+trait GameObject: Draw + Update + Clone {}
+
+// A trait with methods of its own only dispatches supertraits when asked to
+#[tagged_dispatch(supertraits)]
+trait Entity: Draw {
+    fn id(&self) -> u32;
+}
+
+// Not a `#[tagged_dispatch]` trait, so it can't be dispatched through
+trait Collide {
+    fn solid(&self) -> bool;
+}
+
+// Inherits `Entity` and, through it, `Draw`
+#[tagged_dispatch(supertraits(Entity))]
+trait Sprite: Entity + Collide {
+    fn layer(&self) -> u8;
+}
+
+#[derive(Clone)]
+struct Player;
+
+impl Draw for Player {
+    fn draw(&self) -> String {
+        "@".to_string()
+    }
+}
+
+impl Update for Player {
+    fn speed(&self) -> u32 {
+        2
+    }
+}
+
+impl GameObject for Player {}
+
+impl Collide for Player {
+    fn solid(&self) -> bool {
+        false
+    }
+}
+
+impl Sprite for Player {
+    fn layer(&self) -> u8 {
+        2
+    }
+}
+
+impl Entity for Player {
+    fn id(&self) -> u32 {
+        1
+    }
+}
+
+#[derive(Clone)]
+struct Wall;
+
+impl Draw for Wall {
+    fn draw(&self) -> String {
+        "#".to_string()
+    }
+}
+
+impl Update for Wall {
+    fn speed(&self) -> u32 {
+        0
+    }
+}
+
+impl GameObject for Wall {}
+
+impl Collide for Wall {
+    fn solid(&self) -> bool {
+        true
+    }
+}
+
+impl Sprite for Wall {
+    fn layer(&self) -> u8 {
+        0
+    }
+}
+
+impl Entity for Wall {
+    fn id(&self) -> u32 {
+        2
+    }
+}
+
+#[tagged_dispatch(GameObject)]
+enum Object {
+    Player,
+    Wall,
+}
+
+#[tagged_dispatch(Entity, qualified(Update))]
+enum Tile {
+    Player,
+    Wall,
+}
+
+#[tagged_dispatch(Sprite)]
+enum Scenery {
+    Player,
+    Wall,
+}
+
+#[test]
+fn test_alias_trait_dispatches_supertraits() {
+    let objects = [Object::player(Player), Object::wall(Wall)];
+
+    let drawn: String = objects.iter().map(|object| object.draw()).collect();
+    assert_eq!(drawn, "@#");
+    assert_eq!(objects[0].speed(), 2);
+    assert_eq!(objects[1].speed(), 0);
+}
+
+#[test]
+fn test_supertraits_option() {
+    let tile = Tile::wall(Wall);
+
+    assert_eq!(tile.id(), 2);
+    assert_eq!(tile.draw(), "#");
+    assert_eq!(tile.speed_as_update(), 0);
+}
+
+#[test]
+fn test_selected_supertraits_are_transitive() {
+    let scenery = Scenery::player(Player);
+
+    assert_eq!(scenery.layer(), 2);
+    assert_eq!(scenery.id(), 1);
+    assert_eq!(scenery.draw(), "@");
+
+    // Only reachable through the variant types
+    assert!(Wall.solid() && !Player.solid());
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(GameObject)]
+    enum ObjectRef<'a> {
+        Player,
+        Wall,
+    }
+
+    #[test]
+    fn test_arena_alias_dispatch() {
+        let builder = ObjectRef::arena_builder();
+        let player = builder.player(Player);
+
+        assert_eq!(player.draw(), "@");
+        assert_eq!(player.speed(), 2);
+    }
+}
@@ -143,6 +143,45 @@ fn test_no_traits() {
     assert_ne!(circle1, rect);
 }
 
+// Test opting out of the size assertion
+#[tagged_dispatch(Draw, no_size_assert)]
+enum ShapeNoSizeAssert {
+    Circle,
+    Rectangle,
+}
+
+#[test]
+fn test_no_size_assert() {
+    let rect = ShapeNoSizeAssert::rectangle(Rectangle { width: 1.0, height: 1.0 });
+
+    assert_eq!(rect.tag_type(), ShapeNoSizeAssertType::Rectangle);
+    assert_eq!(
+        std::mem::size_of::<ShapeNoSizeAssert>(),
+        std::mem::size_of::<tagged_dispatch::TaggedPtr<()>>()
+    );
+}
+
+// Test marking the Type enum non-exhaustive
+#[tagged_dispatch(Draw, non_exhaustive)]
+pub enum ShapeNonExhaustive {
+    Circle,
+    Rectangle,
+}
+
+#[test]
+fn test_non_exhaustive_type() {
+    let circle = ShapeNonExhaustive::circle(Circle { radius: 1.0 });
+
+    // Downstream crates must keep a wildcard arm; within this crate it is unreachable
+    #[allow(unreachable_patterns)]
+    let name = match circle.tag_type() {
+        ShapeNonExhaustiveType::Circle => "circle",
+        ShapeNonExhaustiveType::Rectangle => "rectangle",
+        _ => "unknown",
+    };
+    assert_eq!(name, "circle");
+}
+
 // Test arena version with flags
 #[cfg(feature = "allocator-bumpalo")]
 mod arena_tests {
@@ -0,0 +1,104 @@
+use std::cell::RefCell;
+
+use tagged_dispatch::tagged_dispatch;
+use tagged_dispatch::traverse::{self, Mark, Node};
+
+#[tagged_dispatch]
+trait SceneNode {
+    fn name(&self) -> &'static str;
+    fn children(&self) -> Vec<Scene>;
+}
+
+#[derive(Clone)]
+struct Group {
+    name: &'static str,
+    children: RefCell<Vec<Scene>>,
+}
+
+impl SceneNode for Group {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn children(&self) -> Vec<Scene> {
+        self.children.borrow().clone()
+    }
+}
+
+#[derive(Clone)]
+struct Mesh {
+    name: &'static str,
+}
+
+impl SceneNode for Mesh {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn children(&self) -> Vec<Scene> {
+        Vec::new()
+    }
+}
+
+#[tagged_dispatch(SceneNode, shared)]
+enum Scene {
+    Group,
+    Mesh,
+}
+
+fn group(name: &'static str, children: Vec<Scene>) -> Scene {
+    Scene::group(Group { name, children: RefCell::new(children) })
+}
+
+fn mesh(name: &'static str) -> Scene {
+    Scene::mesh(Mesh { name })
+}
+
+fn names(nodes: impl Iterator<Item = Scene>) -> Vec<&'static str> {
+    nodes.map(|node| node.name()).collect()
+}
+
+#[test]
+fn test_traversal_order() {
+    let shared = mesh("shared");
+    let root = group("root", vec![
+        group("left", vec![mesh("a"), shared.clone()]),
+        group("right", vec![shared.clone()]),
+    ]);
+
+    assert_eq!(names(traverse::dfs(root.clone(), Scene::children)), ["root", "left", "a", "shared", "right"]);
+    assert_eq!(names(traverse::bfs(root.clone(), Scene::children)), ["root", "left", "right", "a", "shared"]);
+    assert!(!traverse::has_cycle(root, Scene::children));
+}
+
+#[test]
+fn test_cycle_detection() {
+    let root = group("root", vec![]);
+    let child = group("child", vec![mesh("leaf"), root.clone()]);
+    <&Group>::try_from(&root).unwrap().children.borrow_mut().push(child.clone());
+
+    assert_eq!(names(traverse::dfs(root.clone(), Scene::children)), ["root", "child", "leaf"]);
+    let cycle = traverse::find_cycle(root.clone(), Scene::children).unwrap();
+    assert_eq!(names(cycle.into_iter()), ["root", "child"]);
+
+    // Break the cycle so both groups are freed
+    <&Group>::try_from(&root).unwrap().children.borrow_mut().clear();
+}
+
+#[test]
+fn test_marks() {
+    let mut node = mesh("marked");
+    let id = node.node_id();
+    assert!(!node.is_marked());
+
+    node.set_marked(true);
+    assert!(node.is_marked());
+    assert_eq!(node.node_id(), id);
+    assert_eq!(node.name(), "marked");
+    // Clones of a shared handle point at the same value, mark included
+    assert!(node.clone().is_marked());
+
+    node.set_marked(false);
+    assert!(!node.is_marked());
+    assert_eq!(node.name(), "marked");
+}
@@ -0,0 +1,102 @@
+use std::convert::TryFrom;
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape {
+    Circle,
+    Square,
+}
+
+#[derive(Debug)]
+struct WrongShape;
+
+fn largest_circle(shapes: Vec<Shape>) -> Result<Circle, WrongShape> {
+    let mut best: Option<Circle> = None;
+    for shape in shapes {
+        let circle = Circle::try_from(shape).map_err(|_| WrongShape)?;
+        if best.as_ref().is_none_or(|best| circle.radius > best.radius) {
+            best = Some(circle);
+        }
+    }
+    best.ok_or(WrongShape)
+}
+
+#[test]
+fn test_owned_try_from() {
+    let circle = Circle::try_from(Shape::circle(Circle { radius: 2.0 }));
+    assert_eq!(circle.unwrap(), Circle { radius: 2.0 });
+
+    // The handle comes back untouched on mismatch
+    let square = Circle::try_from(Shape::square(Square { side: 3.0 })).unwrap_err();
+    assert_eq!(square.area(), 9.0);
+    assert_eq!(Square::try_from(square).unwrap(), Square { side: 3.0 });
+}
+
+#[test]
+fn test_try_from_with_question_mark() {
+    let circles = vec![Shape::circle(Circle { radius: 1.0 }), Shape::circle(Circle { radius: 4.0 })];
+    assert_eq!(largest_circle(circles).unwrap().radius, 4.0);
+
+    let mixed = vec![Shape::circle(Circle { radius: 1.0 }), Shape::square(Square { side: 1.0 })];
+    assert!(largest_circle(mixed).is_err());
+}
+
+#[test]
+fn test_borrowed_try_from() {
+    let shape = Shape::square(Square { side: 2.0 });
+
+    let square: &Square = <&Square>::try_from(&shape).unwrap();
+    assert_eq!(square.side, 2.0);
+    assert!(<&Circle>::try_from(&shape).is_err());
+    assert_eq!(shape.area(), 4.0);
+}
+
+#[cfg(feature = "allocator-bumpalo")]
+mod arena_tests {
+    use super::*;
+
+    #[tagged_dispatch(Area)]
+    enum ShapeRef<'a> {
+        Circle,
+        Square,
+    }
+
+    #[test]
+    fn test_arena_try_from() {
+        let builder = ShapeRef::arena_builder();
+        let shape = builder.circle(Circle { radius: 1.5 });
+
+        let circle: &Circle = shape.try_into().unwrap();
+        assert_eq!(circle.radius, 1.5);
+        assert!(<&Square>::try_from(shape).is_err());
+        assert_eq!(shape.area(), circle.area());
+    }
+}
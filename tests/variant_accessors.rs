@@ -0,0 +1,108 @@
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Square {
+    side: f32,
+}
+
+impl Area for Square {
+    fn area(&self) -> f32 {
+        self.side * self.side
+    }
+}
+
+impl Area for u32 {
+    fn area(&self) -> f32 {
+        0.0
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape {
+    Circle,
+    Square,
+    #[inline_value]
+    Marker(u32),
+}
+
+#[test]
+fn test_variant_checks() {
+    let shapes = [Shape::circle(Circle { radius: 1.0 }), Shape::square(Square { side: 2.0 }), Shape::marker(9)];
+    assert!(shapes[0].is_circle() && !shapes[0].is_square());
+    assert_eq!(shapes.iter().filter(|shape| shape.is_square()).count(), 1);
+
+    assert_eq!(shapes[0].as_circle(), Some(&Circle { radius: 1.0 }));
+    assert_eq!(shapes[0].as_square(), None);
+    assert_eq!(shapes[2].as_marker(), Some(&9));
+}
+
+#[test]
+fn test_variant_mutation() {
+    let mut shape = Shape::square(Square { side: 2.0 });
+    assert!(shape.as_circle_mut().is_none());
+    shape.as_square_mut().unwrap().side = 5.0;
+    assert_eq!(shape.area(), 25.0);
+}
+
+#[test]
+fn test_try_into_variant() {
+    let shape = Shape::circle(Circle { radius: 2.0 });
+    // The handle comes back untouched on mismatch
+    let shape = shape.try_into_square().unwrap_err();
+    assert_eq!(shape.try_into_circle().unwrap(), Circle { radius: 2.0 });
+    assert_eq!(Shape::marker(4).try_into_marker().unwrap(), 4);
+}
+
+#[test]
+fn test_map_concrete() {
+    let size = |shape: &Shape| shape.map_concrete().circle(|c| c.radius).square(|s| s.side / 2.0).finish();
+    assert_eq!(size(&Shape::circle(Circle { radius: 1.0 })), Some(1.0));
+    assert_eq!(size(&Shape::square(Square { side: 3.0 })), Some(1.5));
+    // No closure was given for markers
+    assert_eq!(size(&Shape::marker(2)), None);
+    assert_eq!(Shape::marker(2).map_concrete().marker(|id| id * 2).finish(), Some(4));
+}
+
+#[cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+mod arena {
+    use super::*;
+
+    #[tagged_dispatch(Area)]
+    enum Sketch<'a> {
+        Circle,
+        Square,
+    }
+
+    #[test]
+    fn test_arena_accessors() {
+        let builder = SketchArenaBuilder::new();
+        let sketch = builder.square(Square { side: 3.0 });
+        assert!(sketch.is_square());
+        assert_eq!(sketch.as_square().map(|square| square.side), Some(3.0));
+
+        // Arena values stay in the arena, so only a reference comes out
+        let square: &Square = sketch.try_into_square().unwrap();
+        assert_eq!(square.area(), 9.0);
+        assert_eq!(sketch.area(), 9.0);
+        assert!(sketch.try_into_circle().is_err());
+
+        let side = sketch.map_concrete().circle(|c| c.radius).square(|s| s.side).finish();
+        assert_eq!(side, Some(3.0));
+    }
+}
@@ -0,0 +1,72 @@
+#![cfg(any(feature = "allocator-bumpalo", feature = "allocator-typed-arena"))]
+
+use tagged_dispatch::tagged_dispatch;
+
+#[tagged_dispatch]
+trait Area {
+    fn area(&self) -> f32;
+}
+
+struct Circle {
+    radius: f32,
+}
+
+impl Area for Circle {
+    fn area(&self) -> f32 {
+        3.0 * self.radius * self.radius
+    }
+}
+
+struct Rectangle {
+    width: f32,
+    height: f32,
+}
+
+impl Area for Rectangle {
+    fn area(&self) -> f32 {
+        self.width * self.height
+    }
+}
+
+#[tagged_dispatch(Area)]
+enum Shape<'a> {
+    Circle,
+    Rectangle,
+}
+
+#[test]
+fn test_view_all_borrows_existing_vecs() {
+    let circles = vec![Circle { radius: 1.0 }, Circle { radius: 2.0 }];
+    let rects = vec![Rectangle { width: 2.0, height: 3.0 }];
+
+    let shapes = Shape::view_all(&circles, &rects);
+    assert_eq!(shapes.len(), 3);
+
+    let tags: Vec<_> = shapes.iter().map(|shape| shape.tag_type()).collect();
+    assert_eq!(tags, [ShapeType::Circle, ShapeType::Circle, ShapeType::Rectangle]);
+
+    let total: f32 = shapes.iter().map(|shape| shape.area()).sum();
+    assert_eq!(total, 21.0);
+}
+
+#[test]
+fn test_view_points_at_original_value() {
+    let rect = Rectangle { width: 4.0, height: 5.0 };
+    let shape = Shape::view_rectangle(&rect);
+
+    assert_eq!(shape.area(), 20.0);
+    assert_eq!(shape, Shape::view_rectangle(&rect));
+    assert_ne!(shape, Shape::view_rectangle(&Rectangle { width: 4.0, height: 5.0 }));
+}
+
+#[test]
+fn test_views_mix_with_arena_handles() {
+    let builder = Shape::arena_builder();
+    let circles = [Circle { radius: 1.0 }];
+
+    let mut shapes = Shape::view_all(&circles, &[]);
+    shapes.push(builder.rectangle(Rectangle { width: 1.0, height: 1.0 }));
+
+    let total: f32 = shapes.iter().map(|shape| shape.area()).sum();
+    assert_eq!(total, 4.0);
+}